@@ -0,0 +1,15 @@
+//! Passes the app's identifier from `tauri.conf.json` to the CLI, which keeps its
+//! data in the folder Tauri names after it.
+
+fn main() {
+    let config_path = "../tauri.conf.json";
+    println!("cargo:rerun-if-changed={}", config_path);
+    let config = std::fs::read_to_string(config_path)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", config_path, e));
+    let config: serde_json::Value =
+        serde_json::from_str(&config).unwrap_or_else(|e| panic!("invalid {}: {}", config_path, e));
+    let identifier = config["identifier"]
+        .as_str()
+        .unwrap_or_else(|| panic!("{} has no identifier", config_path));
+    println!("cargo:rustc-env=APP_IDENTIFIER={}", identifier);
+}
@@ -0,0 +1,382 @@
+//! The `insighttube-cli` command-line tool, for using the app without its window.
+//!
+//! It is built on `insighttube-core`, like the app, and works on the same data: the
+//! transcript cache, settings, cookies and API keys in the app's data directory.
+//! Transcripts fetched from the command line are therefore cached and searchable
+//! in the app too.
+//!
+//! ```text
+//! insighttube-cli fetch <url-or-id> [--format text|srt|vtt|markdown|json|ndjson|csv] [--output FILE]
+//! insighttube-cli search <query> [--limit N]
+//! insighttube-cli summarize <url-or-id> [--provider P] [--model M] [--language L]
+//! ```
+
+use insighttube_core::db::Database;
+use insighttube_core::error::TranscriptError;
+use insighttube_core::export::{self, short_timestamp, ExportFormat, ExportOptions};
+use insighttube_core::llm::{self, ProviderKind};
+use insighttube_core::summarize::{summarize, SummarizeOptions};
+use insighttube_core::transcript::{load_transcript, TranscriptSegment};
+use insighttube_core::{cookies, search, secrets, settings, video_id};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Folder the app keeps its data in, under the platform's data directory; the
+/// identifier in `tauri.conf.json`.
+const APP_IDENTIFIER: &str = env!("APP_IDENTIFIER");
+
+const USAGE: &str = "Usage:
+  insighttube-cli fetch <url-or-id> [--format FORMAT] [--output FILE]
+      Prints the transcript, or writes it to FILE. FORMAT is text (the default),
+      srt, vtt, markdown, json, ndjson or csv.
+  insighttube-cli search <query> [--limit N]
+      Searches every transcript fetched so far.
+  insighttube-cli summarize <url-or-id> [--provider P] [--model M] [--language L]
+      Summarizes the transcript with openai, anthropic, gemini or ollama; the
+      provider chosen in the app by default.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// The text of every segment, one per line.
+    Text,
+    Export(ExportFormat),
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Fetch {
+        video: String,
+        format: Format,
+        output: Option<PathBuf>,
+    },
+    Search {
+        query: String,
+        limit: Option<usize>,
+    },
+    Summarize {
+        video: String,
+        provider: Option<ProviderKind>,
+        model: Option<String>,
+        language: Option<String>,
+    },
+    Help,
+    Version,
+}
+
+/// Words and `--name value` (or `--name=value`) options of the command line.
+struct Args {
+    words: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn split(args: &[String]) -> Result<Self, String> {
+        let mut words = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => match arg.as_str() {
+                    "-f" => "format",
+                    "-o" => "output",
+                    "-n" => "limit",
+                    "-p" => "provider",
+                    "-m" => "model",
+                    _ => {
+                        words.push(arg.clone());
+                        continue;
+                    }
+                },
+            };
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None if matches!(name, "help" | "version") => (name.to_string(), String::new()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("--{} needs a value", name))?;
+                    (name.to_string(), value.clone())
+                }
+            };
+            options.push((name, value));
+        }
+        Ok(Self { words, options })
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    /// Takes the value of option `name`, last one winning.
+    fn take(&mut self, name: &str) -> Option<String> {
+        let mut value = None;
+        self.options.retain(|(n, v)| {
+            if n == name {
+                value = Some(v.clone());
+                false
+            } else {
+                true
+            }
+        });
+        value
+    }
+
+    /// Fails on options the command does not take.
+    fn done(self) -> Result<(), String> {
+        match self.options.first() {
+            Some((name, _)) => Err(format!("unknown option --{}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a value the way the app's settings spell it, such as `srt` or `openai`.
+fn parse_named<T: serde::de::DeserializeOwned>(what: &str, value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown {} \"{}\"", what, value))
+}
+
+fn parse(args: &[String]) -> Result<Command, String> {
+    let mut args = Args::split(args)?;
+    if args.has("version") {
+        return Ok(Command::Version);
+    }
+    if args.has("help") || args.words.is_empty() {
+        return Ok(Command::Help);
+    }
+    let words = std::mem::take(&mut args.words);
+    let command = match (words[0].as_str(), &words[1..]) {
+        ("help", _) => Command::Help,
+        ("fetch", [video]) => Command::Fetch {
+            video: video.clone(),
+            format: match args.take("format").as_deref() {
+                None | Some("text") | Some("txt") => Format::Text,
+                Some(format) => Format::Export(parse_named("format", format)?),
+            },
+            output: args.take("output").map(PathBuf::from),
+        },
+        ("search", [_, ..]) => Command::Search {
+            query: words[1..].join(" "),
+            limit: args
+                .take("limit")
+                .map(|n| {
+                    n.parse()
+                        .map_err(|_| format!("--limit takes a number, not \"{}\"", n))
+                })
+                .transpose()?,
+        },
+        ("summarize", [video]) => Command::Summarize {
+            video: video.clone(),
+            provider: args
+                .take("provider")
+                .map(|p| parse_named("provider", &p))
+                .transpose()?,
+            model: args.take("model"),
+            language: args.take("language"),
+        },
+        ("fetch" | "summarize", _) => {
+            return Err(format!("{} takes one video URL or ID", words[0]))
+        }
+        ("search", _) => return Err("search needs something to search for".into()),
+        (other, _) => return Err(format!("unknown command \"{}\"", other)),
+    };
+    args.done()?;
+    Ok(command)
+}
+
+/// The app's data directory, where Tauri puts it on each platform.
+fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        var("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        var("HOME")?.join("Library/Application Support")
+    } else {
+        var("XDG_DATA_HOME")
+            .filter(|p| p.is_absolute())
+            .or_else(|| var("HOME").map(|home| home.join(".local/share")))?
+    };
+    Some(base.join(APP_IDENTIFIER))
+}
+
+fn plain_text(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|s| s.text.trim())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("{}\n", t))
+        .collect()
+}
+
+async fn execute(db: &Database, command: Command) -> Result<(), TranscriptError> {
+    match command {
+        Command::Fetch {
+            video,
+            format,
+            output,
+        } => {
+            let video_id = video_id::parse(&video)?;
+            let segments = load_transcript(db, &video_id, None).await?;
+            let text = match format {
+                Format::Text => plain_text(&segments),
+                Format::Export(format) => {
+                    export::render(format, &video_id, &segments, &[], &ExportOptions::default())
+                }
+            };
+            match output {
+                Some(path) => std::fs::write(&path, text).map_err(|e| {
+                    TranscriptError::FileError(format!(
+                        "Could not write \"{}\": {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+                None => print!("{}", text),
+            }
+        }
+        Command::Search { query, limit } => {
+            for result in search::search(db, &query, limit)? {
+                for hit in result.matches {
+                    let snippet = hit.snippet.replace("<mark>", "").replace("</mark>", "");
+                    println!(
+                        "https://youtu.be/{}?t={}\t{}\t{}",
+                        result.video_id,
+                        hit.offset.floor() as u64,
+                        short_timestamp(hit.offset),
+                        snippet
+                    );
+                }
+            }
+        }
+        Command::Summarize {
+            video,
+            provider,
+            model,
+            language,
+        } => {
+            let video_id = video_id::parse(&video)?;
+            let settings = settings::current().llm;
+            let model = model.or(if provider.is_none() {
+                settings.model
+            } else {
+                None
+            });
+            let provider = provider.or(settings.provider).ok_or_else(|| {
+                TranscriptError::InvalidInput(
+                    "Name a provider with --provider, or choose one in the app.".into(),
+                )
+            })?;
+            let llm = llm::provider(db, provider, model.clone(), "summary")?;
+            let segments = load_transcript(db, &video_id, None).await?;
+            let options = SummarizeOptions {
+                model,
+                language,
+                max_key_points: None,
+            };
+            let (tldr, key_points) = summarize(llm.as_ref(), &segments, &options, None).await?;
+            println!("{}\n", tldr);
+            for point in key_points {
+                println!("[{}] {}", short_timestamp(point.timestamp), point.text);
+            }
+        }
+        Command::Help | Command::Version => {}
+    }
+    Ok(())
+}
+
+/// Runs the command line in `std::env::args`.
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("insighttube-cli {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        _ => {}
+    }
+
+    let Some(data_dir) = data_dir() else {
+        eprintln!("error: could not locate the app's data directory");
+        return ExitCode::FAILURE;
+    };
+    let db = std::fs::create_dir_all(&data_dir)
+        .map_err(|e| TranscriptError::FileError(format!("Could not create the data folder: {}", e)))
+        .and_then(|_| Database::open(&data_dir.join("insighttube.db")));
+    let result = match db {
+        Ok(db) => {
+            settings::load_headless(&data_dir);
+            cookies::load(&data_dir);
+            secrets::load(&data_dir);
+            execute(&db, command).await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_options() {
+        assert_eq!(
+            parse(&args("fetch dQw4w9WgXcQ --format srt -o out.srt")),
+            Ok(Command::Fetch {
+                video: "dQw4w9WgXcQ".into(),
+                format: Format::Export(ExportFormat::Srt),
+                output: Some(PathBuf::from("out.srt")),
+            })
+        );
+        assert_eq!(
+            parse(&args("search onion soup --limit=5")),
+            Ok(Command::Search {
+                query: "onion soup".into(),
+                limit: Some(5),
+            })
+        );
+        assert_eq!(
+            parse(&args("summarize dQw4w9WgXcQ -p openai")),
+            Ok(Command::Summarize {
+                video: "dQw4w9WgXcQ".into(),
+                provider: Some(ProviderKind::OpenAi),
+                model: None,
+                language: None,
+            })
+        );
+        assert_eq!(parse(&args("")), Ok(Command::Help));
+    }
+
+    #[test]
+    fn rejects_malformed_command_lines() {
+        assert!(parse(&args("fetch")).is_err());
+        assert!(parse(&args("fetch a b")).is_err());
+        assert!(parse(&args("fetch dQw4w9WgXcQ --format docx")).is_err());
+        assert!(parse(&args("fetch dQw4w9WgXcQ --limit 3")).is_err());
+        assert!(parse(&args("search onion --limit")).is_err());
+        assert!(parse(&args("transcribe dQw4w9WgXcQ")).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Action items and key takeaways of a video, as a checklist.
+//!
+//! The model reads the transcript in the same timestamped chunks as the summarizer
+//! and lists what the video tells viewers to do (steps, recommendations, things to
+//! try) apart from what it wants them to remember. Every item cites the moment it
+//! comes up, and the list can be written out as a Markdown checklist with links
+//! back to those moments.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{self, CompletionRequest, Provider, ProviderKind};
+use crate::operations::Operations;
+use crate::summarize::{chunks, parse_timestamp};
+use crate::transcript::load_transcript;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Chunks worked on at the same time.
+const CONCURRENCY: usize = 3;
+const MAX_TOKENS: u32 = 2_000;
+/// Items of each kind asked for per chunk.
+const MAX_ITEMS_PER_CHUNK: usize = 10;
+
+const SYSTEM_PROMPT: &str = "You extract action items and key takeaways from YouTube video \
+transcripts. Every transcript line starts with its [m:ss] timestamp. Reply with a JSON object \
+only, shaped as {\"actions\": [{\"text\": string, \"timestamp\": \"m:ss\"}], \"takeaways\": \
+[{\"text\": string, \"timestamp\": \"m:ss\"}]}. Actions are what the video tells viewers to \
+do: steps, recommendations and things to try, each written as a short imperative sentence. \
+Takeaways are the points it wants viewers to remember, one sentence each. Every timestamp is \
+that of the line where the item comes up. Leave out calls to like, subscribe or buy from \
+sponsors. Either list may be empty.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActionItemOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Language to write the items in; the transcript's language by default.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionItemKind {
+    /// Something to do.
+    Action,
+    /// Something to remember.
+    Takeaway,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionItem {
+    pub kind: ActionItemKind,
+    pub text: String,
+    /// Seconds into the video.
+    pub timestamp: f64,
+    /// Ticked off in the checklist; always false as extracted.
+    #[serde(default)]
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionItems {
+    /// Actions first, then takeaways, each in the order of the video.
+    pub items: Vec<ActionItem>,
+    pub provider: ProviderKind,
+    pub model: String,
+}
+
+/// The JSON requested from the model.
+#[derive(Debug, Deserialize)]
+struct RawItems {
+    #[serde(default)]
+    actions: Vec<RawItem>,
+    #[serde(default)]
+    takeaways: Vec<RawItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawItem {
+    text: String,
+    #[serde(default)]
+    timestamp: serde_json::Value,
+}
+
+impl RawItems {
+    fn into_items(self) -> Vec<ActionItem> {
+        let of_kind = |kind: ActionItemKind, raw: Vec<RawItem>| {
+            raw.into_iter()
+                .filter(|item| !item.text.trim().is_empty())
+                .map(move |item| ActionItem {
+                    kind,
+                    text: item.text.trim().to_string(),
+                    timestamp: parse_timestamp(&item.timestamp).unwrap_or(0.0).max(0.0),
+                    done: false,
+                })
+        };
+        of_kind(ActionItemKind::Action, self.actions)
+            .chain(of_kind(ActionItemKind::Takeaway, self.takeaways))
+            .collect()
+    }
+}
+
+async fn complete(
+    provider: &dyn Provider,
+    prompt: String,
+) -> Result<Vec<ActionItem>, TranscriptError> {
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt,
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    let raw: RawItems = llm::parse_json(&provider.complete(&request, None).await?.text)?;
+    Ok(raw.into_items())
+}
+
+/// Actions before takeaways, each in the order of the video, without repeats.
+fn checklist(found: impl IntoIterator<Item = ActionItem>) -> Vec<ActionItem> {
+    let mut items: Vec<ActionItem> = Vec::new();
+    for item in found {
+        let text = item.text.to_lowercase();
+        let repeated = items
+            .iter()
+            .any(|i| i.kind == item.kind && i.text.to_lowercase() == text);
+        if !repeated {
+            items.push(item);
+        }
+    }
+    items.sort_by(|a, b| {
+        (a.kind == ActionItemKind::Takeaway)
+            .cmp(&(b.kind == ActionItemKind::Takeaway))
+            .then(a.timestamp.total_cmp(&b.timestamp))
+    });
+    items
+}
+
+/// Renders `items` as Markdown: the actions as a task list, the takeaways as
+/// bullets, each linking to its moment of the video.
+fn render_markdown(video_id: &str, items: &[ActionItem]) -> String {
+    let mut out = format!(
+        "# Action items\n\nSource: <https://youtu.be/{}>\n\n",
+        video_id
+    );
+    let sections = [
+        (ActionItemKind::Action, "To do"),
+        (ActionItemKind::Takeaway, "Key takeaways"),
+    ];
+    for (kind, heading) in sections {
+        let mut of_kind = items.iter().filter(|i| i.kind == kind).peekable();
+        if of_kind.peek().is_none() {
+            continue;
+        }
+        let _ = write!(out, "## {}\n\n", heading);
+        for item in of_kind {
+            let marker = match (kind, item.done) {
+                (ActionItemKind::Takeaway, _) => "-",
+                (ActionItemKind::Action, false) => "- [ ]",
+                (ActionItemKind::Action, true) => "- [x]",
+            };
+            let _ = writeln!(
+                out,
+                "{} {} ([{}](https://youtu.be/{}?t={}))",
+                marker,
+                item.text.replace('\n', " "),
+                short_timestamp(item.timestamp),
+                video_id,
+                item.timestamp.floor() as u64
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Extracts the action items and key takeaways of the transcript of `video_id`
+/// with `provider`. Can be stopped with `cancel_operation(operation_id)`.
+pub async fn extract_action_items(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<ActionItemOptions>,
+    operation_id: Option<String>,
+) -> Result<ActionItems, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    let llm = llm::provider(db, provider, options.model.clone(), "actionItems")?;
+    let language = match options.language.as_deref().map(str::trim) {
+        Some(lang) if !lang.is_empty() => format!(" Write them in {}.", lang),
+        _ => " Write them in the language of the transcript.".into(),
+    };
+
+    let segments = load_transcript(db, &video_id, None).await?;
+    // Prompts are built up front: a lazily mapped stream of borrowing futures
+    // trips up the `Send` check of the command's future
+    let requests: Vec<_> = chunks(&segments)
+        .into_iter()
+        .map(|chunk| {
+            let prompt = format!(
+                "List at most {} actions and {} takeaways.{}\n\nTranscript:\n{}",
+                MAX_ITEMS_PER_CHUNK, MAX_ITEMS_PER_CHUNK, language, chunk
+            );
+            complete(llm.as_ref(), prompt)
+        })
+        .collect();
+    let operation = operations.start(operation_id.as_deref());
+    let found: Vec<Vec<ActionItem>> = operation
+        .run(stream::iter(requests).buffered(CONCURRENCY).try_collect())
+        .await?;
+
+    Ok(ActionItems {
+        items: checklist(found.into_iter().flatten()),
+        provider,
+        model: llm.model().to_string(),
+    })
+}
+
+/// Writes `items` of `video_id` to `path` as a Markdown checklist.
+pub fn export_action_items(
+    video_id: String,
+    items: Vec<ActionItem>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    std::fs::write(&path, render_markdown(&video_id, &items))
+        .map_err(|e| TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: ActionItemKind, text: &str, timestamp: f64) -> ActionItem {
+        ActionItem {
+            kind,
+            text: text.into(),
+            timestamp,
+            done: false,
+        }
+    }
+
+    #[test]
+    fn orders_actions_before_takeaways_without_repeats() {
+        let items = checklist([
+            item(ActionItemKind::Takeaway, "Sleep matters.", 30.0),
+            item(ActionItemKind::Action, "Stretch daily.", 90.0),
+            item(ActionItemKind::Action, "Drink water.", 10.0),
+            item(ActionItemKind::Action, "drink water.", 200.0),
+        ]);
+        let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, ["Drink water.", "Stretch daily.", "Sleep matters."]);
+    }
+
+    #[test]
+    fn renders_a_markdown_checklist() {
+        let mut done = item(ActionItemKind::Action, "Drink water.", 10.0);
+        done.done = true;
+        let items = [
+            done,
+            item(ActionItemKind::Action, "Stretch daily.", 90.0),
+            item(ActionItemKind::Takeaway, "Sleep matters.", 30.0),
+        ];
+        assert_eq!(
+            render_markdown("dQw4w9WgXcQ", &items),
+            "# Action items\n\nSource: <https://youtu.be/dQw4w9WgXcQ>\n\n\
+             ## To do\n\n\
+             - [x] Drink water. ([0:10](https://youtu.be/dQw4w9WgXcQ?t=10))\n\
+             - [ ] Stretch daily. ([1:30](https://youtu.be/dQw4w9WgXcQ?t=90))\n\n\
+             ## Key takeaways\n\n\
+             - Sleep matters. ([0:30](https://youtu.be/dQw4w9WgXcQ?t=30))\n\n"
+        );
+    }
+}
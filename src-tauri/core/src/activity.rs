@@ -0,0 +1,278 @@
+//! A log of what the app did, behind the in-app activity feed.
+//!
+//! Videos ingested by background jobs, summaries made, exports written and the
+//! failures of any of them are each recorded as an [`Activity`]: a kind, the video
+//! concerned, a line to show and details to go with it. Only the most recent
+//! [`MAX_ENTRIES`] are kept. Failing to record one only costs a warning in the
+//! logs; it never fails the work it describes.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const PAGE_SIZE: usize = 50;
+/// Entries beyond this many are dropped, oldest first.
+const MAX_ENTRIES: i64 = 10_000;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS activity (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind       TEXT    NOT NULL,
+    video_id   TEXT,
+    message    TEXT    NOT NULL,
+    details    TEXT    NOT NULL DEFAULT '{}',
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS activity_kind ON activity (kind, id);
+CREATE INDEX IF NOT EXISTS activity_video ON activity (video_id, id);
+";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityKind {
+    /// A background job fetched a transcript.
+    VideoIngested,
+    SummaryGenerated,
+    /// A transcript or note was written to a file, Obsidian, Notion or Readwise.
+    ExportCompleted,
+    /// Any of the above failed.
+    Error,
+}
+
+impl ActivityKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::VideoIngested => "videoIngested",
+            Self::SummaryGenerated => "summaryGenerated",
+            Self::ExportCompleted => "exportCompleted",
+            Self::Error => "error",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            Self::VideoIngested,
+            Self::SummaryGenerated,
+            Self::ExportCompleted,
+            Self::Error,
+        ]
+        .into_iter()
+        .find(|k| k.as_str() == value)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    pub id: i64,
+    pub kind: ActivityKind,
+    pub video_id: Option<String>,
+    pub message: String,
+    /// Facts particular to the kind, such as the path of an export or the kind of
+    /// an error.
+    pub details: Value,
+    pub created_at: i64,
+}
+
+/// Which activity `get_activity` lists; everything when empty.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActivityFilter {
+    /// Only these kinds; all of them when empty.
+    pub kinds: Vec<ActivityKind>,
+    pub video_id: Option<String>,
+    /// Only activity at or after this time, in seconds since the epoch.
+    pub since: Option<i64>,
+    /// Only activity before this time.
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityPage {
+    /// Newest first.
+    pub entries: Vec<Activity>,
+    pub page: usize,
+    /// Whether a next page has entries.
+    pub has_more: bool,
+}
+
+fn activity_from_row(row: &Row) -> rusqlite::Result<Activity> {
+    let kind: String = row.get(1)?;
+    let details: String = row.get(4)?;
+    Ok(Activity {
+        id: row.get(0)?,
+        kind: ActivityKind::parse(&kind).unwrap_or(ActivityKind::Error),
+        video_id: row.get(2)?,
+        message: row.get(3)?,
+        details: serde_json::from_str(&details).unwrap_or(Value::Null),
+        created_at: row.get(5)?,
+    })
+}
+
+fn insert(
+    conn: &Connection,
+    kind: ActivityKind,
+    video_id: Option<&str>,
+    message: &str,
+    details: &Value,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO activity (kind, video_id, message, details, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            kind.as_str(),
+            video_id,
+            message,
+            details.to_string(),
+            unix_now()
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM activity WHERE id <= (SELECT id FROM activity ORDER BY id DESC
+                                           LIMIT 1 OFFSET ?1)",
+        params![MAX_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// Records an activity of `kind` on `video_id`, if any.
+pub(crate) fn record(
+    db: &Database,
+    kind: ActivityKind,
+    video_id: Option<&str>,
+    message: &str,
+    details: Value,
+) {
+    if let Err(e) = db.with_conn(|conn| insert(conn, kind, video_id, message, &details)) {
+        tracing::warn!(kind = kind.as_str(), error = %e, "activity not recorded");
+    }
+}
+
+/// Records the outcome of `task` on `video_id`: an activity of `kind` with the
+/// message and details `success` gives for a result, or the error. Cancelled work
+/// is not recorded, since the user stopped it.
+pub fn finished<T>(
+    db: &Database,
+    kind: ActivityKind,
+    video_id: Option<&str>,
+    task: &str,
+    result: &Result<T, TranscriptError>,
+    success: impl FnOnce(&T) -> (String, Value),
+) {
+    match result {
+        Ok(value) => {
+            let (message, details) = success(value);
+            record(db, kind, video_id, &message, details);
+        }
+        Err(TranscriptError::Cancelled) => {}
+        Err(e) => failed(db, video_id, task, e),
+    }
+}
+
+/// Records that `task` on `video_id` failed with `error`.
+pub fn failed(db: &Database, video_id: Option<&str>, task: &str, error: &TranscriptError) {
+    record(
+        db,
+        ActivityKind::Error,
+        video_id,
+        &format!("{} failed: {}", task, error),
+        json!({ "task": task, "error": error.kind() }),
+    );
+}
+
+fn query(
+    conn: &Connection,
+    filter: &ActivityFilter,
+    page: usize,
+) -> rusqlite::Result<ActivityPage> {
+    let kinds = (!filter.kinds.is_empty()).then(|| {
+        Value::from(filter.kinds.iter().map(|k| k.as_str()).collect::<Vec<_>>()).to_string()
+    });
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, video_id, message, details, created_at FROM activity
+         WHERE (?1 IS NULL OR kind IN (SELECT value FROM json_each(?1)))
+           AND (?2 IS NULL OR video_id = ?2)
+           AND (?3 IS NULL OR created_at >= ?3)
+           AND (?4 IS NULL OR created_at < ?4)
+         ORDER BY id DESC
+         LIMIT ?5 OFFSET ?6",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            kinds,
+            filter.video_id,
+            filter.since,
+            filter.until,
+            PAGE_SIZE as i64 + 1,
+            (page * PAGE_SIZE) as i64
+        ],
+        activity_from_row,
+    )?;
+    let mut entries = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+    let has_more = entries.len() > PAGE_SIZE;
+    entries.truncate(PAGE_SIZE);
+    Ok(ActivityPage {
+        entries,
+        page,
+        has_more,
+    })
+}
+
+/// Lists the recorded activity matching `filter`, newest first, a page of 50 at a
+/// time; `page` counts from 0.
+pub fn get_activity(
+    db: &Database,
+    filter: Option<ActivityFilter>,
+    page: Option<usize>,
+) -> Result<ActivityPage, TranscriptError> {
+    let mut filter = filter.unwrap_or_default();
+    filter.video_id = filter
+        .video_id
+        .map(|id| crate::video_id::parse(&id))
+        .transpose()?;
+    db.with_conn(|conn| query(conn, &filter, page.unwrap_or(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_filtered_pages_newest_first() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        for i in 0..60 {
+            let (kind, video_id) = if i % 2 == 0 {
+                (ActivityKind::VideoIngested, "dQw4w9WgXcQ")
+            } else {
+                (ActivityKind::Error, "9bZkp7q19f0")
+            };
+            insert(&conn, kind, Some(video_id), &i.to_string(), &json!({})).unwrap();
+        }
+
+        let first = query(&conn, &ActivityFilter::default(), 0).unwrap();
+        assert_eq!(first.entries.len(), PAGE_SIZE);
+        assert_eq!(first.entries[0].message, "59");
+        assert!(first.has_more);
+        let second = query(&conn, &ActivityFilter::default(), 1).unwrap();
+        assert_eq!(second.entries.len(), 10);
+        assert!(!second.has_more);
+
+        let errors = ActivityFilter {
+            kinds: vec![ActivityKind::Error],
+            ..ActivityFilter::default()
+        };
+        let errors = query(&conn, &errors, 0).unwrap();
+        assert_eq!(errors.entries.len(), 30);
+        assert!(errors.entries.iter().all(|a| a.kind == ActivityKind::Error));
+        let ingested = ActivityFilter {
+            video_id: Some("dQw4w9WgXcQ".into()),
+            ..ActivityFilter::default()
+        };
+        assert_eq!(query(&conn, &ingested, 0).unwrap().entries[0].message, "58");
+    }
+}
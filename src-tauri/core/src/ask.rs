@@ -0,0 +1,261 @@
+//! Answering questions about a video from its transcript.
+//!
+//! The transcript is cut into paragraph passages, the passages closest to the
+//! question are retrieved, and only those are given to the model, which cites them
+//! by number. Retrieval uses the provider's embeddings (stored by
+//! [`embeddings`](crate::embeddings)) when it has them, and keyword overlap otherwise.
+
+use crate::db::Database;
+use crate::embeddings::{self, cosine_similarity};
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{CompletionRequest, OnChunk, Provider, ProviderKind};
+use crate::transcript::{load_transcript, segmenter, TranscriptParagraph, TranscriptSegment};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const DEFAULT_TOP_K: usize = 6;
+pub const MAX_TOP_K: usize = 20;
+const MAX_TOKENS: u32 = 1_000;
+
+const SYSTEM_PROMPT: &str = "You answer questions about a YouTube video using only the \
+numbered transcript passages you are given. Cite the passages that support each statement \
+with their numbers in square brackets, like [2]. If the passages do not contain the answer, \
+say so instead of guessing.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AskOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Passages given to the model.
+    pub top_k: Option<usize>,
+}
+
+/// A passage the answer cites.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    /// The number the answer refers to it by, as in `[2]`.
+    pub index: usize,
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptAnswer {
+    pub answer: String,
+    pub citations: Vec<Citation>,
+    pub provider: ProviderKind,
+    pub model: String,
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Scores passages by the question terms they contain, rarer terms weighing more.
+fn keyword_scores(question: &str, passages: &[TranscriptParagraph]) -> Vec<f32> {
+    let passage_terms: Vec<Vec<String>> = passages.iter().map(|p| terms(&p.text)).collect();
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for passage in &passage_terms {
+        for term in passage.iter().collect::<HashSet<_>>() {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+
+    let question: HashSet<String> = terms(question).into_iter().collect();
+    let n = passages.len() as f32;
+    passage_terms
+        .iter()
+        .map(|passage| {
+            question
+                .iter()
+                .map(|term| {
+                    let tf = passage.iter().filter(|t| *t == term).count() as f32;
+                    let df = document_frequency.get(term.as_str()).copied().unwrap_or(0) as f32;
+                    (1.0 + tf).ln() * (1.0 + n / (1.0 + df)).ln()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Indices of the `k` best-scoring passages, in playback order.
+fn top_k(scores: &[f32], k: usize) -> Vec<usize> {
+    let mut ranked: Vec<usize> = (0..scores.len()).collect();
+    ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    ranked.truncate(k);
+    ranked.sort_unstable();
+    ranked
+}
+
+/// The `k` passages of the transcript most relevant to `question`, in playback order.
+async fn retrieve(
+    db: &Database,
+    provider: &dyn Provider,
+    video_id: &str,
+    segments: &[TranscriptSegment],
+    question: &str,
+    k: usize,
+) -> Result<Vec<TranscriptParagraph>, TranscriptError> {
+    let passages = segmenter::paragraphs(segments);
+    if passages.len() <= k {
+        return Ok(passages);
+    }
+    if provider.embedding_model().is_none() {
+        let best = top_k(&keyword_scores(question, &passages), k);
+        return Ok(best.into_iter().map(|i| passages[i].clone()).collect());
+    }
+
+    // Passage vectors are stored, so only the question is embedded on later calls
+    let embedded = embeddings::ensure(db, provider, video_id, segments).await?;
+    let query = provider
+        .embed(&[question.to_string()])
+        .await?
+        .pop()
+        .unwrap_or_default();
+    let scores: Vec<f32> = embedded
+        .iter()
+        .map(|e| cosine_similarity(&e.vector, &query))
+        .collect();
+    Ok(top_k(&scores, k)
+        .into_iter()
+        .map(|i| embedded[i].passage.clone())
+        .collect())
+}
+
+/// Passage numbers cited as `[n]` or `[n, m]` in `answer`, among `1..=count`.
+fn cited_numbers(answer: &str, count: usize) -> Vec<usize> {
+    static CITATION_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap());
+    let mut numbers: Vec<usize> = CITATION_RE
+        .captures_iter(answer)
+        .flat_map(|c| {
+            c[1].split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect::<Vec<usize>>()
+        })
+        .filter(|n| (1..=count).contains(n))
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// Answers `question` from the `k` most relevant passages of a transcript,
+/// streaming the answer to `on_chunk`.
+pub async fn answer(
+    db: &Database,
+    provider: &dyn Provider,
+    video_id: &str,
+    segments: &[TranscriptSegment],
+    question: &str,
+    k: usize,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<(String, Vec<Citation>), TranscriptError> {
+    let selected = retrieve(db, provider, video_id, segments, question, k).await?;
+    let context: String = selected
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("[{}] ({}) {}\n", i + 1, short_timestamp(p.start), p.text))
+        .collect();
+
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt: format!("Passages:\n{}\nQuestion: {}", context, question),
+        max_tokens: MAX_TOKENS,
+        json: false,
+    };
+    let answer = provider.complete(&request, on_chunk).await?.text;
+
+    let citations = cited_numbers(&answer, selected.len())
+        .into_iter()
+        .map(|n| Citation {
+            index: n,
+            text: selected[n - 1].text.clone(),
+            start: selected[n - 1].start,
+            end: selected[n - 1].end,
+        })
+        .collect();
+    Ok((answer.trim().to_string(), citations))
+}
+
+/// Answers `question` about `video_id` with `provider`, passing the answer to
+/// `on_chunk` as it is generated.
+pub async fn ask_transcript(
+    db: &Database,
+    video_id: String,
+    question: String,
+    provider: ProviderKind,
+    options: Option<AskOptions>,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<TranscriptAnswer, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let question = question.trim().to_string();
+    if question.is_empty() {
+        return Err(TranscriptError::InvalidInput("Enter a question.".into()));
+    }
+    let options = options.unwrap_or_default();
+    let k = options.top_k.unwrap_or(DEFAULT_TOP_K).clamp(1, MAX_TOP_K);
+    let llm = crate::llm::provider(db, provider, options.model, "question")?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    let (answer, citations) = answer(
+        db,
+        llm.as_ref(),
+        &video_id,
+        &segments,
+        &question,
+        k,
+        on_chunk,
+    )
+    .await?;
+    Ok(TranscriptAnswer {
+        answer,
+        citations,
+        provider,
+        model: llm.model().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passage(text: &str, start: f64) -> TranscriptParagraph {
+        TranscriptParagraph {
+            text: text.into(),
+            start,
+            end: start + 10.0,
+        }
+    }
+
+    #[test]
+    fn ranks_passages_by_rare_question_terms() {
+        let passages = [
+            passage("Today we talk about the video and the channel.", 0.0),
+            passage("Sourdough needs a starter fed with flour and water.", 10.0),
+            passage("The video is sponsored by the channel members.", 20.0),
+        ];
+
+        let scores = keyword_scores(
+            "How do I feed my sourdough starter in this video?",
+            &passages,
+        );
+        assert_eq!(top_k(&scores, 1), [1]);
+        assert_eq!(top_k(&scores, 2), [0, 1]);
+    }
+
+    #[test]
+    fn extracts_cited_passage_numbers() {
+        let answer = "Feed it daily [2]. Use equal parts [2, 3] but not [9] or [x].";
+        assert_eq!(cited_numbers(answer, 4), [2, 3]);
+    }
+}
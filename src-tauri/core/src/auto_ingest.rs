@@ -0,0 +1,194 @@
+//! Automatic ingestion of the new videos of subscriptions.
+//!
+//! A scheduler checks the subscription feeds every `subscriptionPollMinutes` while
+//! the app runs. When auto-ingestion is on, each new video of a subscription that
+//! has it enabled becomes a background job: a summary job, which runs the
+//! [`ingest`](crate::ingest) pipeline with its summarize stage, or a transcript job
+//! when summaries are turned off. Going through the job queue means pending videos
+//! survive a restart and failed ones can be retried like any other job. Nobody is
+//! waiting on these summaries when they are made, so they are stored for later.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::jobs::{self, JobKind, JobQueue};
+use crate::llm::{self, Provider, ProviderKind};
+use crate::subscriptions::SubscriptionVideo;
+use crate::summarize::Summary;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+/// Shortest interval between checks, whatever the settings say.
+const MIN_POLL_MINUTES: u32 = 5;
+const DEFAULT_LIMIT: usize = 50;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS ingested_summaries (
+    video_id      TEXT    PRIMARY KEY,
+    summary       TEXT    NOT NULL,
+    summarized_at INTEGER NOT NULL
+);
+";
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoIngestSettings {
+    /// Whether new subscription videos are ingested at all.
+    pub enabled: bool,
+    /// Summarize the transcripts as well as fetching them.
+    pub summarize: bool,
+    /// Provider of the summaries; the preselected one when unset.
+    pub provider: Option<ProviderKind>,
+    /// Model of `provider`, or of the preselected provider when that is unset.
+    pub model: Option<String>,
+}
+
+impl Default for AutoIngestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            summarize: true,
+            provider: None,
+            model: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestedSummary {
+    pub video_id: String,
+    pub summary: Summary,
+    pub summarized_at: i64,
+}
+
+const SUMMARY_COLUMNS: &str = "video_id, summary, summarized_at";
+
+fn summary_from_row(row: &Row) -> rusqlite::Result<IngestedSummary> {
+    let summary: String = row.get(1)?;
+    Ok(IngestedSummary {
+        video_id: row.get(0)?,
+        summary: serde_json::from_str(&summary).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, e.into())
+        })?,
+        summarized_at: row.get(2)?,
+    })
+}
+
+pub(crate) fn store(
+    db: &Database,
+    video_id: &str,
+    summary: &Summary,
+) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string(summary)
+        .map_err(|e| TranscriptError::ParseError(format!("Failed to encode summary: {}", e)))?;
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO ingested_summaries (video_id, summary, summarized_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (video_id) DO UPDATE SET
+                 summary = excluded.summary, summarized_at = excluded.summarized_at",
+            params![video_id, json, unix_now()],
+        )
+        .map(|_| ())
+    })
+}
+
+/// The provider automatic summaries are made with: the auto-ingestion one, or the
+/// preselected one. Its calls are recorded against `operation`.
+pub(crate) fn provider<'a>(
+    db: &'a Database,
+    operation: &'static str,
+) -> Result<(ProviderKind, Box<dyn Provider + 'a>), TranscriptError> {
+    let settings = crate::settings::current();
+    let (provider, model) = match settings.auto_ingest.provider {
+        Some(provider) => (Some(provider), settings.auto_ingest.model),
+        None => (settings.llm.provider, settings.llm.model),
+    };
+    let provider = provider.ok_or_else(|| {
+        TranscriptError::InvalidInput(
+            "Choose a provider for automatic summaries in the settings.".into(),
+        )
+    })?;
+    Ok((provider, llm::provider(db, provider, model, operation)?))
+}
+
+pub fn poll_minutes() -> u32 {
+    crate::settings::current()
+        .subscription_poll_minutes
+        .max(MIN_POLL_MINUTES)
+}
+
+/// Ticks every `minutes`, starting at `first`. Checks that overrun the interval
+/// (a slow network, a suspended laptop) push the next one back rather than
+/// bunching up.
+pub fn schedule(minutes: u32, first: Instant) -> Interval {
+    let mut ticks = tokio::time::interval_at(first, Duration::from_secs(minutes as u64 * 60));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticks
+}
+
+pub(crate) fn stored(
+    db: &Database,
+    video_id: &str,
+) -> Result<Option<IngestedSummary>, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM ingested_summaries WHERE video_id = ?1",
+                SUMMARY_COLUMNS
+            ),
+            [video_id],
+            summary_from_row,
+        )
+        .optional()
+    })
+}
+
+/// The stored summary of `video_id`, if it was auto-ingested with summaries on.
+pub fn get_ingested_summary(
+    db: &Database,
+    video_id: String,
+) -> Result<Option<IngestedSummary>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    stored(db, &video_id)
+}
+
+/// Lists the stored summaries of auto-ingested videos, newest first.
+pub fn list_ingested_summaries(
+    db: &Database,
+    limit: Option<usize>,
+) -> Result<Vec<IngestedSummary>, TranscriptError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1) as i64;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM ingested_summaries ORDER BY summarized_at DESC LIMIT ?1",
+            SUMMARY_COLUMNS
+        ))?;
+        let rows = stmt.query_map([limit], summary_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Queues ingestion jobs for new subscription `videos`, if auto-ingestion is on.
+pub fn enqueue(
+    db: &Database,
+    queue: &JobQueue,
+    videos: &[SubscriptionVideo],
+) -> Result<(), TranscriptError> {
+    let settings = crate::settings::current().auto_ingest;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let kind = if settings.summarize {
+        JobKind::Summary
+    } else {
+        JobKind::Transcript
+    };
+    for video in videos {
+        jobs::enqueue(db, kind, &video.video_id)?;
+    }
+    queue.wake();
+    Ok(())
+}
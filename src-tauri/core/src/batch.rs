@@ -0,0 +1,177 @@
+//! Fetching transcripts for many videos at once.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::fetch_player_response;
+use crate::metadata::parse_video_metadata;
+use crate::operations;
+use crate::playlist::{fetch_all_videos, parse_playlist_id};
+use crate::progress::{Progress, Stage};
+use crate::transcript::{caption_tracks, load_transcript, CaptionTrack, TranscriptSegment};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 16;
+
+/// Outcome for one entry of a batch; exactly one of `segments` and `error` is set.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    /// The input as given, so results can be matched even when it was invalid.
+    pub input: String,
+    pub video_id: Option<String>,
+    pub segments: Option<Vec<TranscriptSegment>>,
+    pub error: Option<TranscriptError>,
+}
+
+impl BatchItemResult {
+    fn new(
+        input: String,
+        video_id: Option<String>,
+        result: Result<Vec<TranscriptSegment>, TranscriptError>,
+    ) -> Self {
+        let (segments, error) = match result {
+            Ok(segments) => (Some(segments), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self {
+            input,
+            video_id,
+            segments,
+            error,
+        }
+    }
+}
+
+/// Fetches one batch entry; invalid input becomes a failed result, not an error, and
+/// so does cancellation.
+async fn fetch_one(db: &Database, input: String, cancel: &CancellationToken) -> BatchItemResult {
+    match crate::video_id::parse(&input) {
+        Ok(video_id) => {
+            let result =
+                operations::cancellable(cancel, load_transcript(db, &video_id, None)).await;
+            BatchItemResult::new(input, Some(video_id), result)
+        }
+        Err(e) => BatchItemResult::new(input, None, Err(e)),
+    }
+}
+
+/// Fetches every input with at most `concurrency` requests in flight, in input order.
+/// Once `cancel` fires, the entries not fetched yet fail as cancelled.
+pub async fn fetch_all(
+    db: &Database,
+    inputs: Vec<String>,
+    concurrency: Option<usize>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Vec<BatchItemResult> {
+    let concurrency = concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY);
+    let total = inputs.len();
+    progress.report_count(Stage::Fetching, 0, total, "videos");
+
+    let mut done = 0;
+    stream::iter(inputs)
+        .map(|input| fetch_one(db, input, cancel))
+        .buffered(concurrency)
+        .map(|result| {
+            done += 1;
+            progress.report_count(Stage::Fetching, done, total, "videos");
+            result
+        })
+        .collect()
+        .await
+}
+
+/// What a batch would find for one entry, without downloading any captions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptAvailability {
+    pub input: String,
+    pub video_id: Option<String>,
+    /// Whether the video has a transcript to fetch.
+    pub available: bool,
+    /// Its caption tracks, in every language offered.
+    pub tracks: Vec<CaptionTrack>,
+    pub duration_seconds: Option<u64>,
+    /// Why the transcript cannot be fetched, or the video was not checked.
+    pub error: Option<TranscriptError>,
+}
+
+async fn check(availability: &mut TranscriptAvailability) -> Result<(), TranscriptError> {
+    let video_id = crate::video_id::parse(&availability.input)?;
+    availability.video_id = Some(video_id.clone());
+    let player_json = fetch_player_response(&build_client()?, &video_id).await?;
+    availability.duration_seconds = parse_video_metadata(&video_id, &player_json)
+        .ok()
+        .map(|m| m.duration_seconds)
+        .filter(|&seconds| seconds > 0);
+    availability.tracks = caption_tracks(&player_json)?;
+    availability.available = true;
+    Ok(())
+}
+
+/// Checks one entry with a player call; invalid input and failures become an
+/// unavailable result, not an error.
+async fn check_one(input: String) -> TranscriptAvailability {
+    let mut availability = TranscriptAvailability {
+        input,
+        video_id: None,
+        available: false,
+        tracks: Vec::new(),
+        duration_seconds: None,
+        error: None,
+    };
+    if let Err(e) = check(&mut availability).await {
+        availability.error = Some(e);
+    }
+    availability
+}
+
+/// Checks whether each video has a transcript, in which languages, and how long
+/// it is, so a batch can be sized up before running it. Only the player is asked,
+/// with at most `concurrency` requests in flight; no captions are downloaded and
+/// nothing is cached. Results are in input order, one per input.
+pub async fn check_transcript_availability(
+    video_ids: Vec<String>,
+    concurrency: Option<usize>,
+) -> Result<Vec<TranscriptAvailability>, TranscriptError> {
+    let concurrency = concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY);
+    Ok(stream::iter(video_ids)
+        .map(check_one)
+        .buffered(concurrency)
+        .collect()
+        .await)
+}
+
+/// Fetches the transcripts of every video of a playlist, listing all its pages
+/// first. Results are in playlist order, one per video.
+pub async fn fetch_playlist(
+    db: &Database,
+    playlist_id: &str,
+    concurrency: Option<usize>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<Vec<BatchItemResult>, TranscriptError> {
+    let playlist_id = parse_playlist_id(playlist_id)?;
+    let client = build_client()?;
+
+    progress.report(Stage::Listing, None, None);
+    let videos = operations::cancellable(
+        cancel,
+        fetch_all_videos(&client, &playlist_id, |found| {
+            let message = format!("{} videos found", found);
+            progress.report(Stage::Listing, None, Some(&message));
+        }),
+    )
+    .await?;
+    let video_ids = videos.into_iter().map(|v| v.video_id).collect();
+
+    Ok(fetch_all(db, video_ids, concurrency, progress, cancel).await)
+}
@@ -0,0 +1,204 @@
+//! Persistent cache of fetched transcripts, keyed by (video_id, lang).
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::transcript::{CaptionKind, TrackInfo, Transcript};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transcript_cache (
+    video_id   TEXT    NOT NULL,
+    lang       TEXT    NOT NULL,
+    is_default INTEGER NOT NULL DEFAULT 0,
+    has_words  INTEGER NOT NULL DEFAULT 0,
+    segments   TEXT    NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (video_id, lang)
+);
+";
+
+/// Records which caption kind was preferred when the default track was chosen.
+pub(crate) const ADD_CAPTION_KIND: &str = "
+ALTER TABLE transcript_cache ADD COLUMN caption_kind TEXT;
+";
+
+/// Keeps the [`TrackInfo`] of each cached transcript as JSON.
+pub(crate) const ADD_TRACK: &str = "
+ALTER TABLE transcript_cache ADD COLUMN track TEXT;
+";
+
+/// Records when each cached transcript was last used and how large it is, for
+/// [`crate::storage`] to evict the least recently used ones.
+pub(crate) const ADD_USAGE: &str = "
+ALTER TABLE transcript_cache ADD COLUMN used_at INTEGER;
+ALTER TABLE transcript_cache ADD COLUMN size INTEGER;
+UPDATE transcript_cache SET used_at = fetched_at, size = LENGTH(segments);
+";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub entries: u64,
+    pub videos: u64,
+    pub expired_entries: u64,
+    pub total_bytes: u64,
+    pub oldest_fetched_at: Option<i64>,
+    pub newest_fetched_at: Option<i64>,
+}
+
+/// Which cached track a lookup refers to.
+pub enum CacheKey<'a> {
+    /// The track `fetch_transcript` picks when no language is requested, preferring
+    /// captions of the given kind.
+    Default(CaptionKind),
+    /// A specific (possibly translated) language.
+    Lang(&'a str),
+}
+
+fn track_and_segments(row: &Row) -> rusqlite::Result<(String, String)> {
+    Ok((row.get(0)?, row.get(1)?))
+}
+
+/// Returns the cached transcript if present, fresh, and (when `need_words` is set)
+/// fetched with word timing. Rows cached before tracks were recorded are misses.
+pub fn get(
+    db: &Database,
+    video_id: &str,
+    key: CacheKey,
+    need_words: bool,
+) -> Result<Option<Transcript>, TranscriptError> {
+    let min_fetched_at = unix_now() - crate::settings::current().cache_ttl_secs();
+
+    let cached = db.with_conn(|conn| match key {
+        CacheKey::Default(kind) => conn
+            .query_row(
+                "SELECT track, segments FROM transcript_cache
+                 WHERE video_id = ?1 AND is_default = 1 AND fetched_at >= ?2
+                   AND (has_words = 1 OR ?3 = 0) AND caption_kind = ?4
+                   AND track IS NOT NULL",
+                params![video_id, min_fetched_at, need_words, kind.as_str()],
+                track_and_segments,
+            )
+            .optional(),
+        CacheKey::Lang(lang) => conn
+            .query_row(
+                "SELECT track, segments FROM transcript_cache
+                 WHERE video_id = ?1 AND lang = ?2 AND fetched_at >= ?3
+                   AND (has_words = 1 OR ?4 = 0) AND track IS NOT NULL",
+                params![video_id, lang, min_fetched_at, need_words],
+                track_and_segments,
+            )
+            .optional(),
+    })?;
+
+    // A row that no longer deserializes is treated as a miss and overwritten later
+    let transcript = cached.and_then(|(track, segments)| {
+        let track: TrackInfo = serde_json::from_str(&track).ok()?;
+        let segments = serde_json::from_str(&segments).ok()?;
+        Some(Transcript::new(video_id, track, segments))
+    });
+    if transcript.is_some() {
+        let _ = db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE transcript_cache SET used_at = ?2 WHERE video_id = ?1",
+                params![video_id, unix_now()],
+            )
+        });
+    }
+    Ok(transcript)
+}
+
+/// Stores a fetched transcript. `default_kind` marks the track chosen without a
+/// language preference, and the caption kind preferred when choosing it; the mark
+/// is kept once set for a (video_id, lang) pair.
+pub fn put(
+    db: &Database,
+    transcript: &Transcript,
+    default_kind: Option<CaptionKind>,
+) -> Result<(), TranscriptError> {
+    let video_id = transcript.video_id.as_str();
+    let segments = &transcript.segments;
+    let is_default = default_kind.is_some();
+    let Some(lang) = segments.first().map(|s| s.lang.as_str()) else {
+        return Ok(());
+    };
+    let has_words = segments.iter().any(|s| !s.words.is_empty());
+    let encode = |e: serde_json::Error| {
+        TranscriptError::ParseError(format!("Failed to encode transcript: {}", e))
+    };
+    let json = serde_json::to_string(segments).map_err(encode)?;
+    let track = serde_json::to_string(&transcript.track).map_err(encode)?;
+
+    db.with_conn(|conn| {
+        if is_default {
+            conn.execute(
+                "UPDATE transcript_cache SET is_default = 0 WHERE video_id = ?1 AND lang != ?2",
+                params![video_id, lang],
+            )?;
+        }
+        conn.execute(
+            "INSERT INTO transcript_cache
+                 (video_id, lang, is_default, has_words, segments, fetched_at, caption_kind,
+                  track, used_at, size)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?6, ?9)
+             ON CONFLICT (video_id, lang) DO UPDATE SET
+                 is_default   = MAX(is_default, excluded.is_default),
+                 has_words    = excluded.has_words,
+                 segments     = excluded.segments,
+                 fetched_at   = excluded.fetched_at,
+                 caption_kind = COALESCE(excluded.caption_kind, caption_kind),
+                 track        = excluded.track,
+                 used_at      = excluded.used_at,
+                 size         = excluded.size",
+            params![
+                video_id,
+                lang,
+                is_default,
+                has_words,
+                json,
+                unix_now(),
+                default_kind.map(CaptionKind::as_str),
+                track,
+                json.len() as i64
+            ],
+        )
+        .map(|_| ())
+    })?;
+    if let Err(e) = crate::storage::enforce(db, Some(video_id)) {
+        tracing::warn!(error = %e, "cache limits not enforced");
+    }
+    Ok(())
+}
+
+pub fn clear_transcript_cache(db: &Database) -> Result<u64, TranscriptError> {
+    db.with_conn(|conn| conn.execute("DELETE FROM transcript_cache", []))
+        .map(|n| n as u64)
+}
+
+pub fn get_cache_stats(db: &Database) -> Result<CacheStats, TranscriptError> {
+    let min_fetched_at = unix_now() - crate::settings::current().cache_ttl_secs();
+
+    db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT COUNT(*),
+                    COUNT(DISTINCT video_id),
+                    COALESCE(SUM(fetched_at < ?1), 0),
+                    COALESCE(SUM(LENGTH(segments)), 0),
+                    MIN(fetched_at),
+                    MAX(fetched_at)
+             FROM transcript_cache",
+            params![min_fetched_at],
+            |row| {
+                Ok(CacheStats {
+                    entries: row.get(0)?,
+                    videos: row.get(1)?,
+                    expired_entries: row.get(2)?,
+                    total_bytes: row.get(3)?,
+                    oldest_fetched_at: row.get(4)?,
+                    newest_fetched_at: row.get(5)?,
+                })
+            },
+        )
+    })
+}
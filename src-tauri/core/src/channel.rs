@@ -0,0 +1,110 @@
+//! Listing a channel's uploads.
+//!
+//! Every channel `UC…` has an uploads playlist `UU…`, so uploads are walked with the
+//! playlist pager once the channel ID is known.
+
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::post_endpoint;
+use crate::playlist::{fetch_playlist_page, PlaylistVideo};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelVideosPage {
+    pub channel_id: String,
+    pub videos: Vec<PlaylistVideo>,
+    pub continuation: Option<String>,
+}
+
+fn is_channel_id(s: &str) -> bool {
+    s.len() == 24
+        && s.starts_with("UC")
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Resolves a channel ID, `@handle`, or channel URL (`/channel/`, `/@`, `/c/`, `/user/`)
+/// into a `UC…` channel ID.
+pub async fn resolve_channel_id(
+    client: &reqwest::Client,
+    input: &str,
+) -> Result<String, TranscriptError> {
+    let input = input.trim().trim_end_matches('/');
+    if is_channel_id(input) {
+        return Ok(input.to_string());
+    }
+    if let Some(id) = input
+        .rsplit_once("/channel/")
+        .map(|(_, rest)| rest.split(['/', '?']).next().unwrap_or(""))
+        .filter(|id| is_channel_id(id))
+    {
+        return Ok(id.to_string());
+    }
+
+    let url = if input.starts_with('@') {
+        format!("https://www.youtube.com/{}", input)
+    } else if input.contains("youtube.com/") {
+        if input.contains("://") {
+            input.to_string()
+        } else {
+            format!("https://{}", input)
+        }
+    } else {
+        return Err(TranscriptError::InvalidInput(format!(
+            "\"{}\" is not a channel ID, @handle, or channel URL.",
+            input
+        )));
+    };
+
+    let response = post_endpoint(
+        client,
+        "navigation/resolve_url",
+        serde_json::json!({ "url": url }),
+    )
+    .await?;
+
+    response
+        .get("endpoint")
+        .and_then(|e| e.get("browseEndpoint"))
+        .and_then(|b| b.get("browseId"))
+        .and_then(|id| id.as_str())
+        .filter(|id| is_channel_id(id))
+        .map(String::from)
+        .ok_or_else(|| {
+            TranscriptError::VideoUnavailable(format!("Could not find the channel \"{}\".", input))
+        })
+}
+
+/// Lists a channel's uploads, newest first. Whole pages are fetched until at least
+/// `limit` videos are collected; pass `continuation` back to keep walking.
+pub async fn fetch_channel_videos(
+    channel_id_or_handle: String,
+    limit: Option<usize>,
+    continuation: Option<String>,
+) -> Result<ChannelVideosPage, TranscriptError> {
+    let client = build_client()?;
+    let channel_id = resolve_channel_id(&client, &channel_id_or_handle).await?;
+    let uploads_id = format!("UU{}", &channel_id[2..]);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+    let mut videos = Vec::new();
+    let mut continuation = continuation;
+    loop {
+        let page = fetch_playlist_page(&client, &uploads_id, continuation).await?;
+        videos.extend(page.videos);
+        continuation = page.continuation;
+
+        if videos.len() >= limit || continuation.is_none() {
+            break;
+        }
+    }
+
+    Ok(ChannelVideosPage {
+        channel_id,
+        videos,
+        continuation,
+    })
+}
@@ -0,0 +1,227 @@
+//! Summaries of a video chapter by chapter, as a navigable outline.
+//!
+//! The transcript is split at the chapters (from the player response or the
+//! description, or at the shifts of topic found by [`outline`] for videos without
+//! any) and each part summarized on its own, with the same prompts as a
+//! whole-video summary. Outlines are cached per video, as long lectures take many
+//! completions to go through.
+
+use crate::chapters::{extract_chapters, Chapter};
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::fetch_player_response;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+use crate::outline::{self, OutlineOptions};
+use crate::progress::{Progress, Stage};
+use crate::summarize::{summarize, KeyPoint, SummarizeOptions};
+use crate::transcript::{load_transcript, TranscriptSegment};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS chapter_summaries (
+    video_id      TEXT    PRIMARY KEY,
+    summary       TEXT    NOT NULL,
+    summarized_at INTEGER NOT NULL
+);
+";
+
+/// Key points per chapter unless the options say otherwise.
+const DEFAULT_KEY_POINTS: usize = 3;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChapterSummaryOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Language to write the summaries in; the transcript's language by default.
+    pub language: Option<String>,
+    /// Key points per chapter.
+    pub max_key_points: Option<usize>,
+    /// Summarize again even if an outline is cached.
+    pub refresh: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterSummary {
+    pub title: String,
+    /// Seconds into the video.
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Empty for a chapter without speech.
+    pub tldr: String,
+    pub key_points: Vec<KeyPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterOutline {
+    pub video_id: String,
+    pub chapters: Vec<ChapterSummary>,
+    pub provider: ProviderKind,
+    pub model: String,
+    pub summarized_at: i64,
+}
+
+/// The segments of each of `chapters`, in order. A segment belongs to the last
+/// chapter starting at or before it, so speech running past the reported duration
+/// still lands in the final chapter.
+fn split(segments: Vec<TranscriptSegment>, chapters: &[Chapter]) -> Vec<Vec<TranscriptSegment>> {
+    let mut parts: Vec<Vec<TranscriptSegment>> = vec![Vec::new(); chapters.len()];
+    for segment in segments {
+        let i = chapters
+            .iter()
+            .rposition(|c| c.start_time <= segment.offset)
+            .unwrap_or(0);
+        if let Some(part) = parts.get_mut(i) {
+            part.push(segment);
+        }
+    }
+    parts
+}
+
+fn cached(db: &Database, video_id: &str) -> Result<Option<ChapterOutline>, TranscriptError> {
+    let min_summarized_at = unix_now() - crate::settings::current().cache_ttl_secs();
+    let outline: Option<String> = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT summary FROM chapter_summaries WHERE video_id = ?1 AND summarized_at >= ?2",
+            params![video_id, min_summarized_at],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    // An outline that no longer deserializes is redone
+    Ok(outline.and_then(|o| serde_json::from_str(&o).ok()))
+}
+
+fn store(db: &Database, outline: &ChapterOutline) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string(outline).map_err(|e| {
+        TranscriptError::ParseError(format!("Failed to encode chapter summaries: {}", e))
+    })?;
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO chapter_summaries (video_id, summary, summarized_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (video_id) DO UPDATE SET
+                 summary = excluded.summary, summarized_at = excluded.summarized_at",
+            params![outline.video_id, json, outline.summarized_at],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Summarizes each chapter of `video_id` with `provider`, one at a time. A cached
+/// outline is returned unless it has expired or `refresh` is set. With
+/// `operation_id`, progress events count the chapters done and `cancel_operation`
+/// stops the run.
+pub async fn summarize_by_chapter(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<ChapterSummaryOptions>,
+    operation_id: Option<String>,
+    progress: &Progress,
+) -> Result<ChapterOutline, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    if !options.refresh {
+        if let Some(outline) = cached(db, &video_id)? {
+            return Ok(outline);
+        }
+    }
+
+    let llm = llm::provider(db, provider, options.model.clone(), "chapterSummaries")?;
+    let operation = operations.start(operation_id.as_deref());
+
+    progress.report(Stage::Fetching, None, Some("Fetching chapters"));
+    let client = build_client()?;
+    let player_json = operation
+        .run(fetch_player_response(&client, &video_id))
+        .await?;
+    let mut chapters = extract_chapters(&video_id, &player_json)?;
+    let segments = operation.run(load_transcript(db, &video_id, None)).await?;
+    if chapters.is_empty() {
+        chapters = outline::sections(&segments, &OutlineOptions::default());
+    }
+    if chapters.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "This video has no transcript to outline.".into(),
+        ));
+    }
+
+    let summarize_options = SummarizeOptions {
+        model: options.model,
+        language: options.language,
+        max_key_points: Some(options.max_key_points.unwrap_or(DEFAULT_KEY_POINTS)),
+    };
+    let parts = split(segments, &chapters);
+    let total = chapters.len();
+    progress.report_count(Stage::Summarizing, 0, total, "chapters");
+    let mut summaries = Vec::with_capacity(total);
+    for (done, (chapter, part)) in chapters.into_iter().zip(parts).enumerate() {
+        let (tldr, key_points) = if part.is_empty() {
+            (String::new(), Vec::new())
+        } else {
+            operation
+                .run(summarize(llm.as_ref(), &part, &summarize_options, None))
+                .await?
+        };
+        summaries.push(ChapterSummary {
+            title: chapter.title,
+            start_time: chapter.start_time,
+            end_time: chapter.end_time,
+            tldr,
+            key_points,
+        });
+        progress.report_count(Stage::Summarizing, done + 1, total, "chapters");
+    }
+
+    let outline = ChapterOutline {
+        video_id,
+        chapters: summaries,
+        provider,
+        model: llm.model().to_string(),
+        summarized_at: unix_now(),
+    };
+    store(db, &outline)?;
+    Ok(outline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: format!("at {}", offset),
+            duration: 1.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    fn chapter(start_time: f64, end_time: f64) -> Chapter {
+        Chapter {
+            title: format!("From {}", start_time),
+            start_time,
+            end_time,
+        }
+    }
+
+    #[test]
+    fn splits_segments_at_chapter_starts() {
+        let chapters = [chapter(0.0, 10.0), chapter(10.0, 20.0), chapter(20.0, 30.0)];
+        let segments = [0.0, 9.5, 10.0, 31.0].map(segment).to_vec();
+        let offsets: Vec<Vec<f64>> = split(segments, &chapters)
+            .iter()
+            .map(|part| part.iter().map(|s| s.offset).collect())
+            .collect();
+        assert_eq!(offsets, [vec![0.0, 9.5], vec![10.0], vec![31.0]]);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{fetch_player_response, text_of};
+use crate::metadata::parse_video_metadata;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A description line starting with a timestamp: `0:00 Intro`, `[1:02:03] - Outro`.
+static LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*[\[(]?(?:(\d{1,2}):)?(\d{1,2}):(\d{2})[\])]?\s*(?:[-–—:|]\s*)?(.+?)\s*$")
+        .unwrap()
+});
+
+/// A titled time range of a video, in seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+/// Collects `chapterRenderer` markers anywhere in the response as (start, title).
+fn collect_chapter_markers(value: &Value, out: &mut Vec<(f64, String)>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(renderer) = map.get("chapterRenderer") {
+                let start = renderer
+                    .get("timeRangeStartMillis")
+                    .and_then(|v| v.as_f64())
+                    .map(|ms| ms / 1000.0);
+                let title = renderer.get("title").and_then(text_of);
+                if let (Some(start), Some(title)) = (start, title) {
+                    out.push((start, title));
+                }
+            }
+            for child in map.values() {
+                collect_chapter_markers(child, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_chapter_markers(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parses `0:00 Intro` / `1:02:03 - Outro` style lines from a video description.
+///
+/// Like YouTube itself, this only treats the timestamps as chapters when there are
+/// at least two of them and the first one starts at zero.
+pub(crate) fn parse_description_chapters(description: &str) -> Vec<(f64, String)> {
+    let mut markers: Vec<(f64, String)> = description
+        .lines()
+        .filter_map(|line| {
+            let cap = LINE_RE.captures(line)?;
+            let hours: f64 = cap
+                .get(1)
+                .map_or(0.0, |h| h.as_str().parse().unwrap_or(0.0));
+            let minutes: f64 = cap[2].parse().ok()?;
+            let seconds: f64 = cap[3].parse().ok()?;
+            let title = cap[4].trim_matches(|c: char| c == '-' || c.is_whitespace());
+            if title.is_empty() {
+                return None;
+            }
+            Some((hours * 3600.0 + minutes * 60.0 + seconds, title.to_string()))
+        })
+        .collect();
+
+    markers.dedup_by(|a, b| a.0 == b.0);
+    let ascending = markers.windows(2).all(|w| w[0].0 < w[1].0);
+
+    if markers.len() < 2 || markers[0].0 != 0.0 || !ascending {
+        return Vec::new();
+    }
+    markers
+}
+
+/// Turns sorted start markers into chapters ending where the next one begins.
+fn to_chapters(mut markers: Vec<(f64, String)>, duration: f64) -> Vec<Chapter> {
+    markers.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let starts: Vec<f64> = markers.iter().map(|(start, _)| *start).collect();
+    markers
+        .into_iter()
+        .enumerate()
+        .map(|(i, (start_time, title))| Chapter {
+            title,
+            start_time,
+            end_time: starts
+                .get(i + 1)
+                .copied()
+                .unwrap_or(duration.max(start_time)),
+        })
+        .collect()
+}
+
+/// Extracts chapters from the player response, falling back to timestamps in the
+/// video description.
+pub fn extract_chapters(
+    video_id: &str,
+    player_json: &Value,
+) -> Result<Vec<Chapter>, TranscriptError> {
+    let metadata = parse_video_metadata(video_id, player_json)?;
+
+    let mut markers = Vec::new();
+    collect_chapter_markers(player_json, &mut markers);
+    markers.dedup_by(|a, b| a.0 == b.0);
+
+    if markers.is_empty() {
+        markers = parse_description_chapters(&metadata.description);
+    }
+
+    Ok(to_chapters(markers, metadata.duration_seconds as f64))
+}
+
+pub async fn fetch_chapters(video_id: String) -> Result<Vec<Chapter>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, &video_id).await?;
+    extract_chapters(&video_id, &player_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_description_timestamps() {
+        let description =
+            "Great talk!\n\n0:00 Intro\n02:15 - The problem\n1:05:30 | Q&A\nThanks for watching";
+        let markers = parse_description_chapters(description);
+
+        assert_eq!(
+            markers,
+            vec![
+                (0.0, "Intro".to_string()),
+                (135.0, "The problem".to_string()),
+                (3930.0, "Q&A".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_descriptions_without_a_zero_chapter() {
+        assert!(parse_description_chapters("1:00 One\n2:00 Two").is_empty());
+        assert!(parse_description_chapters("0:00 Only one").is_empty());
+    }
+
+    #[test]
+    fn chapters_end_at_next_start_or_duration() {
+        let chapters = to_chapters(vec![(0.0, "A".into()), (60.0, "B".into())], 100.0);
+
+        assert_eq!(chapters[0].end_time, 60.0);
+        assert_eq!(chapters[1].start_time, 60.0);
+        assert_eq!(chapters[1].end_time, 100.0);
+    }
+
+    /// Chapters of a batch of descriptions, with the shared line pattern against one
+    /// compiled for every description as it used to be. Run with
+    /// `cargo test --release -p insighttube-core bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_description_chapters() {
+        let descriptions: Vec<String> = (0..2_000)
+            .map(|i| {
+                format!(
+                    "Episode {}!\n\n0:00 Intro\n1:30 Onions\n4:05 - Stock\n12:40 | Simmering\n\
+                     1:18:00 Serving\nThanks for watching",
+                    i
+                )
+            })
+            .collect();
+        let per_call = |description: &str| -> Vec<String> {
+            let line_re = Regex::new(LINE_RE.as_str()).unwrap();
+            description
+                .lines()
+                .filter_map(|line| line_re.captures(line).map(|c| c[4].to_string()))
+                .collect()
+        };
+
+        let started = std::time::Instant::now();
+        let shared: Vec<_> = descriptions
+            .iter()
+            .map(|d| parse_description_chapters(d))
+            .collect();
+        let shared_time = started.elapsed();
+        let started = std::time::Instant::now();
+        let compiled: Vec<_> = descriptions.iter().map(|d| per_call(d)).collect();
+        let compiled_time = started.elapsed();
+
+        for (shared, compiled) in shared.iter().zip(&compiled) {
+            let titles: Vec<&String> = shared.iter().map(|(_, title)| title).collect();
+            assert_eq!(titles, compiled.iter().collect::<Vec<_>>());
+        }
+        println!(
+            "{} descriptions: {:?} with the shared pattern, {:?} compiling it per call",
+            descriptions.len(),
+            shared_time,
+            compiled_time
+        );
+    }
+}
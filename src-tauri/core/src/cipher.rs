@@ -0,0 +1,340 @@
+//! Deciphering stream URLs with the transforms in YouTube's player JavaScript.
+//!
+//! `signatureCipher` formats carry a scrambled signature, and every stream URL has
+//! an `n` parameter that googlevideo throttles to ~50 KB/s unless it has been
+//! transformed. Both transforms are functions in the player's `base.js`. They are
+//! located with regular expressions, cut out of the script and evaluated with an
+//! embedded JavaScript engine. The extracted code is cached per player version.
+
+use crate::error::TranscriptError;
+use crate::http;
+use boa_engine::{Context, Source};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+use std::sync::{Arc, Mutex};
+
+/// Transforms extracted from one version of the player script.
+#[derive(Debug)]
+pub(crate) struct Player {
+    pub version: String,
+    /// `signatureTimestamp` that the player API must be given for deciphered
+    /// signatures to be accepted.
+    pub signature_timestamp: Option<u32>,
+    signature: Option<JsFunction>,
+    n: Option<JsFunction>,
+}
+
+/// Self-contained JavaScript that defines `name`, a function of one string.
+#[derive(Debug, Clone, PartialEq)]
+struct JsFunction {
+    code: String,
+    name: String,
+}
+
+static PLAYER: Lazy<Mutex<Option<Arc<Player>>>> = Lazy::new(|| Mutex::new(None));
+
+static SIGNATURE_FN_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r#"\b[a-zA-Z0-9]+\s*&&\s*[a-zA-Z0-9]+\.set\([^,]+\s*,\s*encodeURIComponent\s*\(\s*([a-zA-Z0-9$]+)\("#,
+        r#"\bm=([a-zA-Z0-9$]{2,})\(decodeURIComponent\(h\.s\)\)"#,
+        r#"(?:^|[^a-zA-Z0-9$])([a-zA-Z0-9$]{2,})\s*=\s*function\(\s*a\s*\)\s*\{\s*a\s*=\s*a\.split\(\s*""\s*\)"#,
+    ]
+    .iter()
+    .map(|p| Regex::new(p).unwrap())
+    .collect()
+});
+
+static N_FN_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r#"\.get\("n"\)\)&&\(b=([a-zA-Z0-9$]+)(?:\[(\d+)\])?\([a-zA-Z0-9]\)"#,
+        r#"\(b=String\.fromCharCode\(110\),c=a\.get\(b\)\)&&\(c=([a-zA-Z0-9$]+)(?:\[(\d+)\])?\([a-zA-Z0-9]\)"#,
+    ]
+    .iter()
+    .map(|p| Regex::new(p).unwrap())
+    .collect()
+});
+
+/// Version directory of a player URL: `/s/player/<version>/player_ias.vflset/…`.
+fn player_version(js_url: &str) -> Option<&str> {
+    let rest = js_url.split("/player/").nth(1)?;
+    rest.split('/').next().filter(|v| !v.is_empty())
+}
+
+/// Index just past the brace that closes the one at `open`, skipping string literals.
+fn matching_brace(js: &str, open: usize) -> Option<usize> {
+    let bytes = js.as_bytes();
+    let mut depth = 0usize;
+    let mut quote: Option<u8> = None;
+    let mut i = open;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(_) if b == b'\\' => i += 1,
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None => match b {
+                b'"' | b'\'' | b'`' => quote = Some(b),
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Source of `name=function(…){…}` or `function name(…){…}`, as a `var` statement.
+fn function_source(js: &str, name: &str) -> Option<String> {
+    let name = regex::escape(name);
+    let re = Regex::new(&format!(
+        r#"(?:^|[^a-zA-Z0-9$.])(?:{name}\s*=\s*function|function\s+{name})\s*\(([^)]*)\)\s*\{{"#
+    ))
+    .ok()?;
+    let caps = re.captures(js)?;
+    let whole = caps.get(0)?;
+    let open = whole.end() - 1;
+    let end = matching_brace(js, open)?;
+    Some(format!(
+        "var {}=function({}){};",
+        name.replace('\\', ""),
+        &caps[1],
+        &js[open..end]
+    ))
+}
+
+/// Source of the object literal `var name={…};` holding signature helper methods.
+fn object_source(js: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(r#"var {}\s*=\s*\{{"#, regex::escape(name))).ok()?;
+    let whole = re.find(js)?;
+    let open = whole.end() - 1;
+    let end = matching_brace(js, open)?;
+    Some(format!("var {}={};", name, &js[open..end]))
+}
+
+/// Recent players route every identifier through one global lookup array defined
+/// at the top of the script; transforms need it to run on their own.
+fn global_array_source(js: &str) -> Option<String> {
+    static RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r#"(?:'use strict'|"use strict");\s*(var\s+[a-zA-Z0-9_$]+\s*=\s*(?:"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*')\.split\((?:"[^"]*"|'[^']*')\))"#,
+        )
+        .unwrap()
+    });
+    RE.captures(js).map(|c| format!("{};", &c[1]))
+}
+
+fn extract_signature(js: &str, globals: &str) -> Option<JsFunction> {
+    let name = SIGNATURE_FN_PATTERNS
+        .iter()
+        .find_map(|re| re.captures(js).map(|c| c[1].to_string()))?;
+    let function = function_source(js, &name)?;
+
+    // The body calls helpers such as `Xy.ab(a,3)` on one object
+    static HELPER_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"[;,]\s*([a-zA-Z0-9$]{2,})\.[a-zA-Z0-9$]{2,}\(a\s*,"#).unwrap());
+    let helper = HELPER_RE
+        .captures(&function)
+        .and_then(|c| object_source(js, &c[1]))
+        .unwrap_or_default();
+
+    Some(JsFunction {
+        code: format!("{}{}{}", globals, helper, function),
+        name,
+    })
+}
+
+fn extract_n(js: &str, globals: &str) -> Option<JsFunction> {
+    let caps = N_FN_PATTERNS.iter().find_map(|re| re.captures(js))?;
+    let mut name = caps[1].to_string();
+
+    // `b=Xy[0](b)`: the function is an element of an array variable
+    if let Some(idx) = caps.get(2).and_then(|i| i.as_str().parse::<usize>().ok()) {
+        let array_re = Regex::new(&format!(
+            r#"var {}\s*=\s*\[([^\]]+)\]"#,
+            regex::escape(&name)
+        ))
+        .ok()?;
+        let items = array_re.captures(js)?[1].to_string();
+        name = items.split(',').nth(idx)?.trim().to_string();
+    }
+
+    // The function bails out early when a global it expects is missing; with
+    // only the function evaluated, that check must go
+    static GUARD_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r#";\s*if\s*\(\s*typeof\s+[a-zA-Z0-9_$]+\s*===?\s*(?:"undefined"|'undefined'|[a-zA-Z0-9_$]+\[\d+\])\s*\)\s*return\s+[a-zA-Z0-9_$]+;"#,
+        )
+        .unwrap()
+    });
+    let function = function_source(js, &name)?;
+    let function = GUARD_RE.replace_all(&function, ";").into_owned();
+
+    Some(JsFunction {
+        code: format!("{}{}", globals, function),
+        name,
+    })
+}
+
+pub(crate) fn parse_player(version: &str, js: &str) -> Player {
+    static STS_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(?:signatureTimestamp|sts)\s*:\s*(\d{5})"#).unwrap());
+
+    let globals = global_array_source(js).unwrap_or_default();
+    Player {
+        version: version.to_string(),
+        signature_timestamp: STS_RE.captures(js).and_then(|c| c[1].parse().ok()),
+        signature: extract_signature(js, &globals),
+        n: extract_n(js, &globals),
+    }
+}
+
+/// Calls `function` with `arg` in a fresh JavaScript context.
+fn call(function: &JsFunction, arg: &str) -> Result<String, TranscriptError> {
+    let literal = serde_json::to_string(arg).unwrap_or_default();
+    let script = format!("{}\n{}({});", function.code, function.name, literal);
+
+    let mut context = Context::default();
+    let value = context
+        .eval(Source::from_bytes(&script))
+        .map_err(|e| TranscriptError::ParseError(format!("Player script failed: {}", e)))?;
+    value
+        .as_string()
+        .map(|s| s.to_std_string_escaped())
+        .ok_or_else(|| TranscriptError::ParseError("Player script returned no string.".into()))
+}
+
+impl Player {
+    /// Turns a stream URL into one that plays at full speed: the deciphered
+    /// signature is appended as `sp`, and the `n` parameter is transformed.
+    pub(crate) fn decipher(
+        &self,
+        url: &str,
+        signature: Option<(&str, &str)>,
+    ) -> Result<String, TranscriptError> {
+        let mut url = Url::parse(url)
+            .map_err(|e| TranscriptError::ParseError(format!("Invalid stream URL: {}", e)))?;
+
+        let signature = match signature {
+            Some((s, sp)) => {
+                let function = self.signature.as_ref().ok_or_else(|| {
+                    TranscriptError::ParseError(
+                        "Could not find the signature function in the player script.".into(),
+                    )
+                })?;
+                Some((sp.to_string(), call(function, s)?))
+            }
+            None => None,
+        };
+
+        // A failed n transform only costs download speed, so the URL is still used
+        let n = url
+            .query_pairs()
+            .find(|(k, _)| k == "n")
+            .map(|(_, v)| v.into_owned());
+        let n = match (n, &self.n) {
+            (Some(n), Some(function)) => call(function, &n).ok(),
+            _ => None,
+        };
+
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| match (&*k, &n) {
+                ("n", Some(n)) => (k.into_owned(), n.clone()),
+                _ => (k.into_owned(), v.into_owned()),
+            })
+            .chain(signature)
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+        Ok(url.into())
+    }
+}
+
+/// The current player, downloading and parsing `js_url` when its version has not
+/// been seen yet.
+pub(crate) async fn player(
+    client: &reqwest::Client,
+    js_url: &str,
+) -> Result<Arc<Player>, TranscriptError> {
+    let version = player_version(js_url).unwrap_or(js_url);
+    let cached = PLAYER.lock().ok().and_then(|p| p.clone());
+    if let Some(player) = cached.filter(|p| p.version == version) {
+        return Ok(player);
+    }
+
+    let url = if js_url.starts_with("http") {
+        js_url.to_string()
+    } else {
+        format!("https://www.youtube.com{}", js_url)
+    };
+    let js = http::send(client.get(&url))
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| TranscriptError::NetworkError(format!("Failed to load player script: {}", e)))?
+        .text()
+        .await
+        .map_err(|e| {
+            TranscriptError::NetworkError(format!("Failed to load player script: {}", e))
+        })?;
+
+    let player = Arc::new(parse_player(version, &js));
+    if let Ok(mut cached) = PLAYER.lock() {
+        *cached = Some(player.clone());
+    }
+    Ok(player)
+}
+
+/// `signatureTimestamp` of the last player parsed, if any.
+pub(crate) fn signature_timestamp() -> Option<u32> {
+    PLAYER
+        .lock()
+        .ok()
+        .and_then(|p| p.as_ref().and_then(|p| p.signature_timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER_JS: &str = r#"'use strict';var XY="split;reverse;join".split(";");
+        var Ab={rv:function(a){a.reverse()},sp:function(a,b){a.splice(0,b)},
+        sw:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c}};
+        Sg=function(a){a=a.split("");Ab.sw(a,1);Ab.rv(a,0);Ab.sp(a,2);return a.join("")};
+        var qP=[nF];nF=function(a){var b=a[XY[0]]("");if(typeof Qz==="undefined")return a;b[XY[1]]();return b[XY[2]]("")};
+        x.get("n"))&&(b=qP[0](b),a.set("n",b));var cfg={signatureTimestamp:20123};"#;
+
+    #[test]
+    fn extracts_and_runs_player_transforms() {
+        let player = parse_player("abc123", PLAYER_JS);
+        assert_eq!(player.signature_timestamp, Some(20123));
+
+        // "abcdef" → swap(1): "bacdef" → reverse: "fedcab" → splice(2): "dcab"
+        let url = player
+            .decipher(
+                "https://rr.googlevideo.com/videoplayback?n=xyz&id=1",
+                Some(("abcdef", "sig")),
+            )
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://rr.googlevideo.com/videoplayback?n=zyx&id=1&sig=dcab"
+        );
+    }
+
+    #[test]
+    fn finds_matching_braces_outside_strings() {
+        let js = r#"f=function(a){var b="}{";return {x:1}}rest"#;
+        let open = js.find('{').unwrap();
+        assert_eq!(&js[matching_brace(js, open).unwrap()..], "rest");
+        assert_eq!(
+            player_version("/s/player/3bb1f723/player_ias.vflset/en_US/base.js"),
+            Some("3bb1f723")
+        );
+    }
+}
@@ -0,0 +1,452 @@
+//! User-defined collections ("Rust talks", "Lectures") grouping saved videos.
+//!
+//! A collection only holds video IDs; transcripts come from the cache like
+//! everywhere else, and titles from the history when the video is in it. Bulk
+//! commands work through every video of a collection in the order it was added.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations;
+use crate::progress::{Progress, Stage};
+use crate::summarize::{self, KeyPoint, SummarizeOptions};
+use crate::transcript::load_transcript;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS collections (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    name        TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+    description TEXT    NOT NULL DEFAULT '',
+    created_at  INTEGER NOT NULL,
+    updated_at  INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS collection_videos (
+    collection_id INTEGER NOT NULL,
+    video_id      TEXT    NOT NULL,
+    added_at      INTEGER NOT NULL,
+    PRIMARY KEY (collection_id, video_id)
+);
+
+CREATE INDEX IF NOT EXISTS collection_videos_video ON collection_videos (video_id);
+";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub video_count: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionVideo {
+    pub video_id: String,
+    /// From the history; `None` for videos never fetched or removed from it.
+    pub title: Option<String>,
+    pub channel_name: Option<String>,
+    pub added_at: i64,
+}
+
+/// Summary of one video of a collection; exactly one of `summary` and `error` is set.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSummaryItem {
+    pub video_id: String,
+    pub summary: Option<summarize::Summary>,
+    pub error: Option<TranscriptError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSummary {
+    pub collection_id: i64,
+    /// A few sentences on the collection as a whole, from the video summaries;
+    /// `None` when no video could be summarized.
+    pub overview: Option<String>,
+    pub videos: Vec<CollectionSummaryItem>,
+}
+
+const COLLECTION_COLUMNS: &str = "c.id, c.name, c.description,
+     (SELECT COUNT(*) FROM collection_videos v WHERE v.collection_id = c.id),
+     c.created_at, c.updated_at";
+
+fn collection_from_row(row: &Row) -> rusqlite::Result<Collection> {
+    Ok(Collection {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        video_count: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+pub fn get(db: &Database, id: i64) -> Result<Collection, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM collections c WHERE c.id = ?1",
+                COLLECTION_COLUMNS
+            ),
+            [id],
+            collection_from_row,
+        )
+        .optional()
+    })?
+    .ok_or_else(not_found)
+}
+
+fn not_found() -> TranscriptError {
+    TranscriptError::InvalidInput("This collection no longer exists.".into())
+}
+
+fn valid_name(name: &str) -> Result<&str, TranscriptError> {
+    match name.trim() {
+        "" => Err(TranscriptError::InvalidInput(
+            "Collection names cannot be empty.".into(),
+        )),
+        name => Ok(name),
+    }
+}
+
+/// Maps a clash with the unique name index to a message the user can act on.
+fn name_taken(name: &str, e: TranscriptError) -> TranscriptError {
+    match e {
+        TranscriptError::DatabaseError(message) if message.contains("UNIQUE") => {
+            TranscriptError::InvalidInput(format!(
+                "There is already a collection named \"{}\".",
+                name
+            ))
+        }
+        e => e,
+    }
+}
+
+/// Video IDs of collection `id`, in the order they were added.
+pub fn video_ids(db: &Database, id: i64) -> Result<Vec<String>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT video_id FROM collection_videos WHERE collection_id = ?1
+             ORDER BY added_at, rowid",
+        )?;
+        let rows = stmt.query_map([id], |row| row.get(0))?;
+        rows.collect()
+    })
+}
+
+fn parse_video_ids(video_ids: &[String]) -> Result<Vec<String>, TranscriptError> {
+    video_ids
+        .iter()
+        .map(|id| crate::video_id::parse(id))
+        .collect()
+}
+
+fn touch(db: &Database, id: i64) -> Result<(), TranscriptError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE collections SET updated_at = ?2 WHERE id = ?1",
+            params![id, unix_now()],
+        )
+        .map(|_| ())
+    })
+}
+
+pub const OVERVIEW_SYSTEM_PROMPT: &str =
+    "You write overviews of collections of YouTube videos from summaries of each \
+video. Reply with a JSON object only, shaped as {\"overview\": string}. The overview is \
+one paragraph of three to five sentences on the themes the videos share and how they differ.";
+
+#[derive(Debug, Deserialize)]
+pub struct RawOverview {
+    pub overview: String,
+}
+
+pub fn overview_prompt(collection: &Collection, summaries: &[(&str, &str, &[KeyPoint])]) -> String {
+    let mut prompt = format!("Collection: {}\n", collection.name);
+    if !collection.description.is_empty() {
+        prompt.push_str(&format!("Description: {}\n", collection.description));
+    }
+    for (i, (video_id, tldr, key_points)) in summaries.iter().enumerate() {
+        prompt.push_str(&format!("\nVideo {} ({}): {}\n", i + 1, video_id, tldr));
+        for point in key_points.iter() {
+            prompt.push_str(&format!("- {}\n", point.text));
+        }
+    }
+    prompt
+}
+
+pub fn create_collection(
+    db: &Database,
+    name: String,
+    description: Option<String>,
+) -> Result<Collection, TranscriptError> {
+    let name = valid_name(&name)?;
+    let description = description.unwrap_or_default();
+    let id = db
+        .with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO collections (name, description, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?3)",
+                params![name, description.trim(), unix_now()],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+        .map_err(|e| name_taken(name, e))?;
+    get(db, id)
+}
+
+/// Lists every collection by name.
+pub fn list_collections(db: &Database) -> Result<Vec<Collection>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM collections c ORDER BY c.name",
+            COLLECTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], collection_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Renames a collection or changes its description; omitted fields are kept.
+pub fn update_collection(
+    db: &Database,
+    id: i64,
+    name: Option<String>,
+    description: Option<String>,
+) -> Result<Collection, TranscriptError> {
+    let name = name.as_deref().map(valid_name).transpose()?;
+    let updated = db
+        .with_conn(|conn| {
+            conn.execute(
+                "UPDATE collections SET name = COALESCE(?2, name),
+                                        description = COALESCE(?3, description),
+                                        updated_at = ?4
+                 WHERE id = ?1",
+                params![id, name, description.as_deref().map(str::trim), unix_now()],
+            )
+        })
+        .map_err(|e| name_taken(name.unwrap_or_default(), e))?;
+    if updated == 0 {
+        return Err(not_found());
+    }
+    get(db, id)
+}
+
+/// Deletes a collection. Its videos stay in the cache and the history. Returns
+/// whether there was one to delete.
+pub fn delete_collection(db: &Database, id: i64) -> Result<bool, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM collection_videos WHERE collection_id = ?1",
+            [id],
+        )?;
+        conn.execute("DELETE FROM collections WHERE id = ?1", [id])
+    })
+    .map(|n| n > 0)
+}
+
+/// Adds videos, given as IDs or URLs, to a collection. Returns how many were not
+/// in it yet.
+pub fn add_to_collection(
+    db: &Database,
+    id: i64,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    let video_ids = parse_video_ids(&video_ids)?;
+    get(db, id)?;
+    let now = unix_now();
+    let added = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "INSERT OR IGNORE INTO collection_videos (collection_id, video_id, added_at)
+             VALUES (?1, ?2, ?3)",
+        )?;
+        video_ids.iter().try_fold(0, |added, video_id| {
+            Ok(added + stmt.execute(params![id, video_id, now])? as u64)
+        })
+    })?;
+    touch(db, id)?;
+    Ok(added)
+}
+
+/// Removes videos from a collection and returns how many were removed.
+pub fn remove_from_collection(
+    db: &Database,
+    id: i64,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    let video_ids = parse_video_ids(&video_ids)?;
+    let removed = db.with_conn(|conn| {
+        let mut stmt = conn
+            .prepare("DELETE FROM collection_videos WHERE collection_id = ?1 AND video_id = ?2")?;
+        video_ids.iter().try_fold(0, |removed, video_id| {
+            Ok(removed + stmt.execute(params![id, video_id])? as u64)
+        })
+    })?;
+    if removed > 0 {
+        touch(db, id)?;
+    }
+    Ok(removed)
+}
+
+/// Lists the videos of a collection in the order they were added.
+pub fn list_collection_videos(
+    db: &Database,
+    id: i64,
+) -> Result<Vec<CollectionVideo>, TranscriptError> {
+    get(db, id)?;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT v.video_id, h.title, h.channel_name, v.added_at
+             FROM collection_videos v LEFT JOIN history h ON h.video_id = v.video_id
+             WHERE v.collection_id = ?1
+             ORDER BY v.added_at, v.rowid",
+        )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok(CollectionVideo {
+                video_id: row.get(0)?,
+                title: row.get(1)?,
+                channel_name: row.get(2)?,
+                added_at: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Collections that contain `video_id`, by name.
+pub fn collections_of_video(
+    db: &Database,
+    video_id: String,
+) -> Result<Vec<Collection>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM collections c
+             JOIN collection_videos v ON v.collection_id = c.id AND v.video_id = ?1
+             ORDER BY c.name",
+            COLLECTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([video_id], collection_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Summarizes every video of collection `id`, then the collection as a whole from
+/// those summaries. A video that fails is reported in its entry and left out of the
+/// overview.
+pub async fn summarize_collection(
+    db: &Database,
+    id: i64,
+    provider: ProviderKind,
+    options: Option<SummarizeOptions>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<CollectionSummary, TranscriptError> {
+    let collection = get(db, id)?;
+    let video_ids = video_ids(db, id)?;
+    let options = options.unwrap_or_default();
+    let llm = llm::provider(db, provider, options.model.clone(), "collectionSummary")?;
+
+    let total = video_ids.len();
+    progress.report_count(Stage::Summarizing, 0, total, "videos");
+    let mut videos = Vec::with_capacity(total);
+    for (done, video_id) in video_ids.into_iter().enumerate() {
+        let result = operations::cancellable(cancel, async {
+            let segments = load_transcript(db, &video_id, None).await?;
+            summarize::summarize(llm.as_ref(), &segments, &options, None).await
+        })
+        .await;
+        let (summary, error) = match result {
+            Ok((tldr, key_points)) => (
+                Some(summarize::Summary {
+                    tldr,
+                    key_points,
+                    provider,
+                    model: llm.model().to_string(),
+                }),
+                None,
+            ),
+            Err(e) => (None, Some(e)),
+        };
+        videos.push(CollectionSummaryItem {
+            video_id,
+            summary,
+            error,
+        });
+        progress.report_count(Stage::Summarizing, done + 1, total, "videos");
+    }
+
+    let summaries: Vec<_> = videos
+        .iter()
+        .filter_map(|v| {
+            v.summary.as_ref().map(|s| {
+                (
+                    v.video_id.as_str(),
+                    s.tldr.as_str(),
+                    s.key_points.as_slice(),
+                )
+            })
+        })
+        .collect();
+    let overview = if summaries.is_empty() {
+        Ok(None)
+    } else {
+        let request = llm::CompletionRequest {
+            system: OVERVIEW_SYSTEM_PROMPT.into(),
+            prompt: overview_prompt(&collection, &summaries),
+            max_tokens: 600,
+            json: true,
+        };
+        operations::cancellable(cancel, llm.complete(&request, None))
+            .await
+            .and_then(|completion| llm::parse_json(&completion.text))
+            .map(|raw: RawOverview| Some(raw.overview.trim().to_string()))
+    };
+
+    overview.map(|overview| CollectionSummary {
+        collection_id: id,
+        overview,
+        videos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_video_summary_in_the_overview_prompt() {
+        let collection = Collection {
+            id: 1,
+            name: "Rust talks".into(),
+            description: "Conference talks".into(),
+            video_count: 2,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let points = [KeyPoint {
+            text: "Ownership replaces a GC.".into(),
+            timestamp: 12.0,
+        }];
+        let summaries: [(&str, &str, &[KeyPoint]); 2] = [
+            ("dQw4w9WgXcQ", "A talk on ownership.", &points),
+            ("jNQXAC9IVRw", "A talk on async.", &[]),
+        ];
+        let prompt = overview_prompt(&collection, &summaries);
+
+        assert_eq!(
+            prompt,
+            "Collection: Rust talks\nDescription: Conference talks\n\
+             \nVideo 1 (dQw4w9WgXcQ): A talk on ownership.\n- Ownership replaces a GC.\n\
+             \nVideo 2 (jNQXAC9IVRw): A talk on async.\n"
+        );
+    }
+}
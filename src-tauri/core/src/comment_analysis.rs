@@ -0,0 +1,361 @@
+//! Sentiment and recurring themes of a video's comments, from a language model.
+//!
+//! Comments are sent in prompt-sized batches; each batch is classified and its
+//! themes listed. The sentiment counts of the batches are added up, and when there
+//! are several batches their themes are merged by one more completion. Analyses
+//! are cached per video, as comments change slowly and the batches are costly.
+
+use crate::comments::{self, Comment, CommentSort};
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::llm::{self, CompletionRequest, Provider, ProviderKind};
+use crate::operations::Operations;
+use crate::progress::{Progress, Stage};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS comment_analyses (
+    video_id    TEXT    PRIMARY KEY,
+    analysis    TEXT    NOT NULL,
+    analyzed_at INTEGER NOT NULL
+);
+";
+
+/// Comment threads analyzed by default.
+const DEFAULT_LIMIT: usize = 300;
+/// Comment characters per prompt.
+const BATCH_CHARS: usize = 12_000;
+/// Longest comment kept whole; the rest of it rarely changes its sentiment.
+const MAX_COMMENT_CHARS: usize = 500;
+const MAX_THEMES: usize = 8;
+const MAX_TOKENS: u32 = 1_500;
+
+const SYSTEM_PROMPT: &str = "You analyze the comments of a YouTube video. Every comment \
+is on its own line, starting with its number. Reply with a JSON object only, shaped as \
+{\"positive\": number, \"neutral\": number, \"negative\": number, \
+\"themes\": [{\"theme\": string, \"description\": string, \"comments\": number}]}. \
+The three numbers count the comments of each sentiment and add up to the number of \
+comments. A theme is a subject several comments bring up, named in a few words and \
+described in one sentence; \"comments\" counts the comments that bring it up. \
+Themes are ordered by that count, most discussed first.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CommentAnalysisOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Comment threads to analyze, replies included.
+    pub limit: Option<usize>,
+    /// Analyze again even if an analysis is cached.
+    pub refresh: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SentimentDistribution {
+    pub positive: u64,
+    pub neutral: u64,
+    pub negative: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentTheme {
+    pub theme: String,
+    pub description: String,
+    /// Comments that bring the theme up, as counted by the model.
+    pub comment_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAnalysis {
+    pub video_id: String,
+    /// Comments analyzed, replies included.
+    pub comment_count: usize,
+    pub sentiment: SentimentDistribution,
+    pub themes: Vec<CommentTheme>,
+    pub provider: ProviderKind,
+    pub model: String,
+    pub analyzed_at: i64,
+}
+
+/// The JSON requested from the model.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct RawAnalysis {
+    positive: u64,
+    neutral: u64,
+    negative: u64,
+    themes: Vec<RawTheme>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTheme {
+    theme: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    comments: u64,
+}
+
+impl RawTheme {
+    fn into_theme(self) -> Option<CommentTheme> {
+        let theme = self.theme.trim();
+        (!theme.is_empty()).then(|| CommentTheme {
+            theme: theme.to_string(),
+            description: self.description.trim().to_string(),
+            comment_count: self.comments,
+        })
+    }
+}
+
+/// The text of every comment and reply, each on one line and shortened to
+/// [`MAX_COMMENT_CHARS`].
+fn comment_lines(comments: &[Comment]) -> Vec<String> {
+    comments
+        .iter()
+        .flat_map(|thread| std::iter::once(thread).chain(&thread.replies))
+        .map(|c| c.text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .map(|text| match text.char_indices().nth(MAX_COMMENT_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        })
+        .collect()
+}
+
+/// Splits comment lines into prompt-sized batches, numbered from one in each, with
+/// how many comments each batch holds.
+fn batches(lines: &[String]) -> Vec<(String, usize)> {
+    let mut batches: Vec<Vec<&str>> = Vec::new();
+    let mut size = 0;
+    for line in lines {
+        match batches.last_mut() {
+            Some(batch) if size + line.len() <= BATCH_CHARS => batch.push(line),
+            _ => {
+                batches.push(vec![line]);
+                size = 0;
+            }
+        }
+        size += line.len();
+    }
+    batches
+        .into_iter()
+        .map(|batch| {
+            let text = batch
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format!("{}. {}\n", i + 1, line))
+                .collect();
+            (text, batch.len())
+        })
+        .collect()
+}
+
+async fn complete(provider: &dyn Provider, prompt: String) -> Result<RawAnalysis, TranscriptError> {
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt,
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    llm::parse_json(&provider.complete(&request, None).await?.text)
+}
+
+/// The prompt merging the themes found in separate batches.
+fn merge_prompt(themes: &[CommentTheme], total: usize) -> String {
+    let listed: String = themes
+        .iter()
+        .map(|t| {
+            format!(
+                "- {} ({} comments): {}\n",
+                t.theme, t.comment_count, t.description
+            )
+        })
+        .collect();
+    format!(
+        "These themes were found in separate batches of the {} comments of one video. \
+         Merge themes about the same subject, adding up their comment counts, and keep at \
+         most {} of the most discussed. Set every sentiment count to 0.\n\n{}",
+        total, MAX_THEMES, listed
+    )
+}
+
+/// Keeps the sentiment counts consistent with the comments analyzed: the model's
+/// counts are scaled to add up to `total`.
+fn normalize(sentiment: SentimentDistribution, total: usize) -> SentimentDistribution {
+    let counted = sentiment.positive + sentiment.neutral + sentiment.negative;
+    if counted == 0 {
+        return SentimentDistribution {
+            neutral: total as u64,
+            ..Default::default()
+        };
+    }
+    let scale = |n: u64| (n as f64 * total as f64 / counted as f64).round() as u64;
+    let positive = scale(sentiment.positive);
+    let negative = scale(sentiment.negative).min(total as u64 - positive.min(total as u64));
+    SentimentDistribution {
+        positive,
+        negative,
+        neutral: (total as u64).saturating_sub(positive + negative),
+    }
+}
+
+fn cached(db: &Database, video_id: &str) -> Result<Option<CommentAnalysis>, TranscriptError> {
+    let min_analyzed_at = unix_now() - crate::settings::current().cache_ttl_secs();
+    let analysis: Option<String> = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT analysis FROM comment_analyses WHERE video_id = ?1 AND analyzed_at >= ?2",
+            params![video_id, min_analyzed_at],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    // An analysis that no longer deserializes is redone
+    Ok(analysis.and_then(|a| serde_json::from_str(&a).ok()))
+}
+
+fn store(db: &Database, analysis: &CommentAnalysis) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string(analysis).map_err(|e| {
+        TranscriptError::ParseError(format!("Failed to encode comment analysis: {}", e))
+    })?;
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO comment_analyses (video_id, analysis, analyzed_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (video_id) DO UPDATE SET
+                 analysis = excluded.analysis, analyzed_at = excluded.analyzed_at",
+            params![analysis.video_id, json, analysis.analyzed_at],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Classifies the sentiment of the comments of `video_id` and finds their recurring
+/// themes. A cached analysis is returned unless it has expired or `refresh` is set.
+pub async fn analyze_comments(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<CommentAnalysisOptions>,
+    operation_id: Option<String>,
+    progress: &Progress,
+) -> Result<CommentAnalysis, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    if !options.refresh {
+        if let Some(analysis) = cached(db, &video_id)? {
+            return Ok(analysis);
+        }
+    }
+
+    let llm = llm::provider(db, provider, options.model.clone(), "commentAnalysis")?;
+    let operation = operations.start(operation_id.as_deref());
+
+    progress.report(Stage::Fetching, None, Some("Fetching comments"));
+    let client = build_client()?;
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let comments = operation
+        .run(comments::fetch(&client, &video_id, CommentSort::Top, limit))
+        .await?;
+    let lines = comment_lines(&comments);
+    if lines.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "This video has no comments to analyze.".into(),
+        ));
+    }
+
+    let batches = batches(&lines);
+    let total = batches.len();
+    let mut sentiment = SentimentDistribution::default();
+    let mut themes = Vec::new();
+    progress.report_count(Stage::Analyzing, 0, total, "batches");
+    for (done, (batch, count)) in batches.into_iter().enumerate() {
+        let prompt = format!(
+            "List at most {} themes of these {} comments.\n\nComments:\n{}",
+            MAX_THEMES, count, batch
+        );
+        let raw = operation.run(complete(llm.as_ref(), prompt)).await?;
+        let batch_sentiment = normalize(
+            SentimentDistribution {
+                positive: raw.positive,
+                neutral: raw.neutral,
+                negative: raw.negative,
+            },
+            count,
+        );
+        sentiment.positive += batch_sentiment.positive;
+        sentiment.neutral += batch_sentiment.neutral;
+        sentiment.negative += batch_sentiment.negative;
+        themes.extend(raw.themes.into_iter().filter_map(RawTheme::into_theme));
+        progress.report_count(Stage::Analyzing, done + 1, total, "batches");
+    }
+
+    if total > 1 && !themes.is_empty() {
+        let prompt = merge_prompt(&themes, lines.len());
+        let merged = operation.run(complete(llm.as_ref(), prompt)).await?;
+        themes = merged
+            .themes
+            .into_iter()
+            .filter_map(RawTheme::into_theme)
+            .collect();
+    }
+    themes.sort_by_key(|t| std::cmp::Reverse(t.comment_count));
+    themes.truncate(MAX_THEMES);
+
+    let analysis = CommentAnalysis {
+        video_id,
+        comment_count: lines.len(),
+        sentiment,
+        themes,
+        provider,
+        model: llm.model().to_string(),
+        analyzed_at: unix_now(),
+    };
+    store(db, &analysis)?;
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_comments_from_one_in_every_batch() {
+        let lines: Vec<String> = (0..3).map(|i| format!("{}", i).repeat(7_000)).collect();
+        let batches = batches(&lines);
+
+        assert_eq!(batches.len(), 3);
+        assert!(batches
+            .iter()
+            .all(|(batch, count)| batch.starts_with("1. ") && *count == 1));
+    }
+
+    #[test]
+    fn scales_sentiment_counts_to_the_comments_analyzed() {
+        let counted = SentimentDistribution {
+            positive: 6,
+            neutral: 2,
+            negative: 2,
+        };
+        assert_eq!(
+            normalize(counted, 5),
+            SentimentDistribution {
+                positive: 3,
+                neutral: 1,
+                negative: 1,
+            }
+        );
+        assert_eq!(
+            normalize(SentimentDistribution::default(), 4),
+            SentimentDistribution {
+                positive: 0,
+                neutral: 4,
+                negative: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! Fetching a video's comments through the Innertube `next` endpoint.
+//!
+//! The watch-next response only carries a continuation token for the comment
+//! section; the threads themselves, the sort menu and each thread's replies are
+//! all further continuations. YouTube serves comments in two shapes: the older
+//! `commentRenderer`, and `commentViewModel` whose data lives in
+//! `commentEntityPayload` mutations next to the items. Both are read.
+
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{continuation_token, find_all, post_endpoint, text_of};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Replies fetched per thread at most; long threads are cut short.
+const MAX_REPLIES_PER_THREAD: usize = 50;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CommentSort {
+    #[default]
+    Top,
+    Newest,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub comment_id: String,
+    pub author: String,
+    pub author_channel_id: Option<String>,
+    pub text: String,
+    pub like_count: u64,
+    /// As YouTube shows it, such as "2 days ago".
+    pub published_time: String,
+    pub is_pinned: bool,
+    /// Whether the author is the video's uploader.
+    pub is_creator: bool,
+    pub reply_count: u64,
+    /// Empty for replies themselves.
+    pub replies: Vec<Comment>,
+}
+
+/// Reads an abbreviated count such as "1.2K", "3M" or "1,024".
+pub fn parse_count(text: &str) -> u64 {
+    let text: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | 'K' | 'M' | 'B'))
+        .collect();
+    let (number, multiplier) = match text.chars().last() {
+        Some('K') => (&text[..text.len() - 1], 1e3),
+        Some('M') => (&text[..text.len() - 1], 1e6),
+        Some('B') => (&text[..text.len() - 1], 1e9),
+        _ => (text.as_str(), 1.0),
+    };
+    number
+        .parse::<f64>()
+        .map_or(0, |n| (n * multiplier).round() as u64)
+}
+
+/// `commentEntityPayload`s of a response, by comment ID.
+fn entity_payloads(response: &Value) -> HashMap<&str, &Value> {
+    find_all(response, "commentEntityPayload")
+        .into_iter()
+        .filter_map(|payload| {
+            let id = payload.pointer("/properties/commentId")?.as_str()?;
+            Some((id, payload))
+        })
+        .collect()
+}
+
+fn str_at(value: &Value, pointer: &str) -> Option<String> {
+    value.pointer(pointer)?.as_str().map(String::from)
+}
+
+/// Reads a `commentRenderer` or a `commentViewModel`, whose data is looked up
+/// in `payloads`.
+fn parse_comment(item: &Value, payloads: &HashMap<&str, &Value>) -> Option<Comment> {
+    if let Some(renderer) = item.get("commentRenderer") {
+        return Some(Comment {
+            comment_id: str_at(renderer, "/commentId")?,
+            author: renderer
+                .get("authorText")
+                .and_then(text_of)
+                .unwrap_or_default(),
+            author_channel_id: str_at(renderer, "/authorEndpoint/browseEndpoint/browseId"),
+            text: renderer
+                .get("contentText")
+                .and_then(text_of)
+                .unwrap_or_default(),
+            like_count: renderer
+                .get("voteCount")
+                .and_then(text_of)
+                .map_or(0, |c| parse_count(&c)),
+            published_time: renderer
+                .get("publishedTimeText")
+                .and_then(text_of)
+                .unwrap_or_default(),
+            is_pinned: renderer.get("pinnedCommentBadge").is_some(),
+            is_creator: renderer
+                .get("authorIsChannelOwner")
+                .and_then(|o| o.as_bool())
+                .unwrap_or(false),
+            reply_count: renderer
+                .get("replyCount")
+                .and_then(|c| c.as_u64())
+                .unwrap_or(0),
+            replies: Vec::new(),
+        });
+    }
+
+    // Threads wrap the view model once more than replies do
+    let view_model = item.get("commentViewModel")?;
+    let view_model = view_model.get("commentViewModel").unwrap_or(view_model);
+    let payload = payloads.get(view_model.get("commentId")?.as_str()?)?;
+    let count = |pointer| str_at(payload, pointer).map_or(0, |c| parse_count(&c));
+    Some(Comment {
+        comment_id: str_at(payload, "/properties/commentId")?,
+        author: str_at(payload, "/author/displayName").unwrap_or_default(),
+        author_channel_id: str_at(payload, "/author/channelId"),
+        text: str_at(payload, "/properties/content/content").unwrap_or_default(),
+        like_count: count("/toolbar/likeCountNotliked"),
+        published_time: str_at(payload, "/properties/publishedTime").unwrap_or_default(),
+        is_pinned: view_model.get("pinnedText").is_some(),
+        is_creator: payload
+            .pointer("/author/isCreator")
+            .and_then(|c| c.as_bool())
+            .unwrap_or(false),
+        reply_count: count("/toolbar/replyCount"),
+        replies: Vec::new(),
+    })
+}
+
+/// The items of a continuation response and the token of its next page.
+fn continuation_items(response: &Value) -> (Vec<&Value>, Option<String>) {
+    let items: Vec<&Value> = find_all(response, "continuationItems")
+        .into_iter()
+        .filter_map(|items| items.as_array())
+        .flatten()
+        .collect();
+    let next = items
+        .iter()
+        .filter_map(|item| item.get("continuationItemRenderer"))
+        .find_map(continuation_token);
+    (items, next)
+}
+
+/// The threads of a page of comments, each with the token of its replies, and the
+/// token of the next page.
+fn parse_threads(response: &Value) -> (Vec<(Comment, Option<String>)>, Option<String>) {
+    let payloads = entity_payloads(response);
+    let (items, next) = continuation_items(response);
+    let threads = items
+        .into_iter()
+        .filter_map(|item| item.get("commentThreadRenderer"))
+        .filter_map(|thread| {
+            let comment = thread
+                .get("comment")
+                .and_then(|c| parse_comment(c, &payloads))
+                .or_else(|| parse_comment(thread, &payloads))?;
+            let replies = thread
+                .pointer("/replies/commentRepliesRenderer")
+                .and_then(continuation_token);
+            Some((comment, replies))
+        })
+        .collect();
+    (threads, next)
+}
+
+/// The replies of a page of a thread, and the token of the next page.
+fn parse_replies(response: &Value) -> (Vec<Comment>, Option<String>) {
+    let payloads = entity_payloads(response);
+    let (items, next) = continuation_items(response);
+    let replies = items
+        .into_iter()
+        .filter_map(|item| parse_comment(item, &payloads))
+        .collect();
+    (replies, next)
+}
+
+/// The continuation that loads the comment section, from the watch-next response.
+fn comment_section_token(next: &Value) -> Option<String> {
+    find_all(next, "itemSectionRenderer")
+        .into_iter()
+        .find(|section| {
+            section.get("sectionIdentifier").and_then(|s| s.as_str())
+                == Some("comment-item-section")
+        })
+        .and_then(continuation_token)
+}
+
+/// The continuation that reloads the comment section sorted by `sort`.
+fn sort_token(response: &Value, sort: CommentSort) -> Option<String> {
+    let index = match sort {
+        CommentSort::Top => 0,
+        CommentSort::Newest => 1,
+    };
+    find_all(response, "sortFilterSubMenuRenderer")
+        .into_iter()
+        .find_map(|menu| menu.get("subMenuItems")?.get(index))
+        .and_then(continuation_token)
+}
+
+async fn continuation(client: &reqwest::Client, token: &str) -> Result<Value, TranscriptError> {
+    post_endpoint(client, "next", serde_json::json!({ "continuation": token })).await
+}
+
+async fn fetch_replies(
+    client: &reqwest::Client,
+    token: String,
+) -> Result<Vec<Comment>, TranscriptError> {
+    let mut replies = Vec::new();
+    let mut token = Some(token);
+    while let Some(current) = token.filter(|_| replies.len() < MAX_REPLIES_PER_THREAD) {
+        let (page, next) = parse_replies(&continuation(client, &current).await?);
+        if page.is_empty() {
+            break;
+        }
+        replies.extend(page);
+        token = next;
+    }
+    replies.truncate(MAX_REPLIES_PER_THREAD);
+    Ok(replies)
+}
+
+/// Fetches up to `limit` comment threads of `video_id` in the order of `sort`,
+/// each with its replies.
+pub(crate) async fn fetch(
+    client: &reqwest::Client,
+    video_id: &str,
+    sort: CommentSort,
+    limit: usize,
+) -> Result<Vec<Comment>, TranscriptError> {
+    let next = post_endpoint(client, "next", serde_json::json!({ "videoId": video_id })).await?;
+    let section = comment_section_token(&next).ok_or(TranscriptError::CommentsDisabled)?;
+
+    let mut response = continuation(client, &section).await?;
+    if sort != CommentSort::Top {
+        if let Some(sorted) = sort_token(&response, sort) {
+            response = continuation(client, &sorted).await?;
+        }
+    }
+
+    let mut threads = Vec::new();
+    loop {
+        let (page, next) = parse_threads(&response);
+        let empty = page.is_empty();
+        threads.extend(page);
+        let Some(next) = next.filter(|_| threads.len() < limit && !empty) else {
+            break;
+        };
+        response = continuation(client, &next).await?;
+    }
+    threads.truncate(limit);
+
+    let mut comments = Vec::with_capacity(threads.len());
+    for (mut comment, replies) in threads {
+        if let Some(token) = replies {
+            comment.replies = fetch_replies(client, token).await?;
+        }
+        comments.push(comment);
+    }
+    Ok(comments)
+}
+
+/// Fetches the comment threads of a video with their replies, top comments first
+/// unless `sort` asks for the newest. `limit` caps the number of threads.
+pub async fn fetch_comments(
+    video_id: String,
+    sort: Option<CommentSort>,
+    limit: Option<usize>,
+) -> Result<Vec<Comment>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+    fetch(
+        &client,
+        &video_id,
+        sort.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_LIMIT).max(1),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_abbreviated_counts() {
+        assert_eq!(parse_count("1.2K"), 1200);
+        assert_eq!(parse_count("3M"), 3_000_000);
+        assert_eq!(parse_count("1,024"), 1024);
+        assert_eq!(parse_count(""), 0);
+    }
+
+    #[test]
+    fn reads_both_comment_shapes() {
+        let page = json!({
+            "onResponseReceivedEndpoints": [{ "appendContinuationItemsAction": {
+                "continuationItems": [
+                    { "commentThreadRenderer": {
+                        "comment": { "commentRenderer": {
+                            "commentId": "old",
+                            "authorText": { "simpleText": "@cook" },
+                            "authorEndpoint": { "browseEndpoint": { "browseId": "UC1" } },
+                            "contentText": { "runs": [{ "text": "Great " }, { "text": "video" }] },
+                            "publishedTimeText": { "runs": [{ "text": "2 days ago" }] },
+                            "voteCount": { "simpleText": "1.2K" },
+                            "replyCount": 3,
+                            "pinnedCommentBadge": {}
+                        } },
+                        "replies": { "commentRepliesRenderer": { "contents": [
+                            { "continuationItemRenderer": { "continuationEndpoint": {
+                                "continuationCommand": { "token": "replies-old" }
+                            } } }
+                        ] } }
+                    } },
+                    { "commentThreadRenderer": {
+                        "commentViewModel": { "commentViewModel": { "commentId": "new" } }
+                    } },
+                    { "continuationItemRenderer": { "continuationEndpoint": {
+                        "continuationCommand": { "token": "page-2" }
+                    } } }
+                ]
+            } }],
+            "frameworkUpdates": { "entityBatchUpdate": { "mutations": [
+                { "payload": { "commentEntityPayload": {
+                    "properties": {
+                        "commentId": "new",
+                        "content": { "content": "Thanks!" },
+                        "publishedTime": "1 hour ago"
+                    },
+                    "author": { "displayName": "@host", "channelId": "UC2", "isCreator": true },
+                    "toolbar": { "likeCountNotliked": "5", "replyCount": "" }
+                } } }
+            ] } }
+        });
+
+        let (threads, next) = parse_threads(&page);
+        assert_eq!(next.as_deref(), Some("page-2"));
+        assert_eq!(threads.len(), 2);
+
+        let (old, replies) = &threads[0];
+        assert_eq!(replies.as_deref(), Some("replies-old"));
+        assert_eq!(old.author, "@cook");
+        assert_eq!(old.author_channel_id.as_deref(), Some("UC1"));
+        assert_eq!(old.text, "Great video");
+        assert_eq!(old.like_count, 1200);
+        assert_eq!(old.reply_count, 3);
+        assert!(old.is_pinned);
+
+        let (new, replies) = &threads[1];
+        assert_eq!(*replies, None);
+        assert_eq!(new.text, "Thanks!");
+        assert_eq!(new.published_time, "1 hour ago");
+        assert_eq!(new.like_count, 5);
+        assert!(new.is_creator && !new.is_pinned);
+    }
+}
@@ -0,0 +1,286 @@
+//! Comparing what several videos say on one question.
+//!
+//! Every video is summarized on its own first, with timestamped key points, and a
+//! last completion reads those summaries side by side to answer the question:
+//! where the videos agree, where they disagree, and which moment of which video
+//! backs each point. Citations the model makes up (a video that is not in the
+//! comparison, a timestamp it cannot give) are dropped rather than shown.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{self, CompletionRequest, ProviderKind};
+use crate::operations::Operations;
+use crate::progress::{Progress, Stage};
+use crate::summarize::{parse_timestamp, summarize, KeyPoint, SummarizeOptions};
+use crate::transcript::load_transcript;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const MAX_VIDEOS: usize = 8;
+/// Key points of each video's summary, the material the comparison works from.
+const KEY_POINTS_PER_VIDEO: usize = 12;
+const MAX_TOKENS: u32 = 3_000;
+
+const SYSTEM_PROMPT: &str = "You compare what several YouTube videos say about a question, \
+from a summary of each. Every video is introduced as \"Video n\", and its key points start \
+with their [m:ss] timestamp. Reply with a JSON object only, shaped as {\"overview\": string, \
+\"agreements\": [{\"point\": string, \"citations\": [{\"video\": number, \"timestamp\": \
+\"m:ss\", \"claim\": string}]}], \"disagreements\": [same shape]}. The overview answers the \
+question in a few sentences, across the videos. An agreement is a point two or more videos \
+share; a disagreement is one where they differ, with a citation per video giving what that \
+video says. Cite only the videos and timestamps given, and leave out points the summaries \
+do not support.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompareOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Language to write the comparison in; the transcripts' language by default.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    pub video_id: String,
+    /// Seconds into the video.
+    pub timestamp: f64,
+    /// What the video says on the point.
+    pub claim: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedPoint {
+    pub point: String,
+    pub citations: Vec<Citation>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedVideo {
+    pub video_id: String,
+    pub tldr: String,
+    pub key_points: Vec<KeyPoint>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub question: String,
+    pub overview: String,
+    pub agreements: Vec<ComparedPoint>,
+    pub disagreements: Vec<ComparedPoint>,
+    /// The summary each video was compared by, in the order given.
+    pub videos: Vec<ComparedVideo>,
+    pub provider: ProviderKind,
+    pub model: String,
+}
+
+/// The JSON requested from the model.
+#[derive(Debug, Deserialize)]
+struct RawComparison {
+    #[serde(default)]
+    overview: String,
+    #[serde(default)]
+    agreements: Vec<RawPoint>,
+    #[serde(default)]
+    disagreements: Vec<RawPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPoint {
+    point: String,
+    #[serde(default)]
+    citations: Vec<RawCitation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawCitation {
+    /// Number of the video, from 1.
+    video: usize,
+    #[serde(default)]
+    timestamp: serde_json::Value,
+    #[serde(default)]
+    claim: String,
+}
+
+/// The points with their citations resolved to video IDs. Citations of videos
+/// outside the comparison are dropped, and so are points left without any.
+fn resolve(raw: Vec<RawPoint>, videos: &[ComparedVideo]) -> Vec<ComparedPoint> {
+    raw.into_iter()
+        .filter(|p| !p.point.trim().is_empty())
+        .filter_map(|p| {
+            let citations: Vec<Citation> = p
+                .citations
+                .into_iter()
+                .filter_map(|c| {
+                    let video = videos.get(c.video.checked_sub(1)?)?;
+                    Some(Citation {
+                        video_id: video.video_id.clone(),
+                        timestamp: parse_timestamp(&c.timestamp)?.max(0.0),
+                        claim: c.claim.trim().to_string(),
+                    })
+                })
+                .collect();
+            (!citations.is_empty()).then(|| ComparedPoint {
+                point: p.point.trim().to_string(),
+                citations,
+            })
+        })
+        .collect()
+}
+
+fn synthesis_prompt(question: &str, videos: &[ComparedVideo], language: &str) -> String {
+    let mut prompt = format!("Question: {}{}\n", question, language);
+    for (i, video) in videos.iter().enumerate() {
+        let _ = write!(prompt, "\nVideo {}: {}\n", i + 1, video.tldr);
+        for point in &video.key_points {
+            let _ = writeln!(
+                prompt,
+                "[{}] {}",
+                short_timestamp(point.timestamp),
+                point.text
+            );
+        }
+    }
+    prompt
+}
+
+/// Compares what the videos `video_ids` (two to eight) say about `question`, from
+/// a summary of each made with `provider`. With `operation_id`, progress events
+/// count the videos summarized and `cancel_operation` stops the run.
+#[allow(clippy::too_many_arguments)]
+pub async fn compare_videos(
+    db: &Database,
+    operations: &Operations,
+    video_ids: Vec<String>,
+    question: String,
+    provider: ProviderKind,
+    options: Option<CompareOptions>,
+    operation_id: Option<String>,
+    progress: &Progress,
+) -> Result<Comparison, TranscriptError> {
+    let mut ids: Vec<String> = Vec::with_capacity(video_ids.len());
+    for video_id in &video_ids {
+        let video_id = crate::video_id::parse(video_id)?;
+        if !ids.contains(&video_id) {
+            ids.push(video_id);
+        }
+    }
+    if !(2..=MAX_VIDEOS).contains(&ids.len()) {
+        return Err(TranscriptError::InvalidInput(format!(
+            "Compare between 2 and {} different videos.",
+            MAX_VIDEOS
+        )));
+    }
+    let question = question.trim().to_string();
+    if question.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "Ask a question to compare the videos on.".into(),
+        ));
+    }
+
+    let options = options.unwrap_or_default();
+    let llm = llm::provider(db, provider, options.model.clone(), "comparison")?;
+    let operation = operations.start(operation_id.as_deref());
+    let summarize_options = SummarizeOptions {
+        model: options.model.clone(),
+        language: options.language.clone(),
+        max_key_points: Some(KEY_POINTS_PER_VIDEO),
+    };
+
+    let total = ids.len();
+    progress.report_count(Stage::Summarizing, 0, total, "videos");
+    let mut videos = Vec::with_capacity(total);
+    for (done, video_id) in ids.into_iter().enumerate() {
+        let (tldr, key_points) = operation
+            .run(async {
+                let segments = load_transcript(db, &video_id, None).await?;
+                summarize(llm.as_ref(), &segments, &summarize_options, None).await
+            })
+            .await?;
+        videos.push(ComparedVideo {
+            video_id,
+            tldr,
+            key_points,
+        });
+        progress.report_count(Stage::Summarizing, done + 1, total, "videos");
+    }
+
+    progress.report(Stage::Summarizing, None, Some("Comparing the videos"));
+    let language = match options.language.as_deref().map(str::trim) {
+        Some(lang) if !lang.is_empty() => format!("\nWrite the comparison in {}.", lang),
+        _ => String::new(),
+    };
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt: synthesis_prompt(&question, &videos, &language),
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    let raw: RawComparison =
+        llm::parse_json(&operation.run(llm.complete(&request, None)).await?.text)?;
+
+    Ok(Comparison {
+        question,
+        overview: raw.overview.trim().to_string(),
+        agreements: resolve(raw.agreements, &videos),
+        disagreements: resolve(raw.disagreements, &videos),
+        videos,
+        provider,
+        model: llm.model().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(video_id: &str) -> ComparedVideo {
+        ComparedVideo {
+            video_id: video_id.into(),
+            tldr: String::new(),
+            key_points: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn resolves_citations_to_the_compared_videos() {
+        let raw: RawComparison = serde_json::from_str(
+            r#"{"agreements": [
+                {"point": "Sleep matters", "citations": [
+                    {"video": 1, "timestamp": "1:05", "claim": "Eight hours"},
+                    {"video": 3, "timestamp": "0:10", "claim": "Made up"},
+                    {"video": 2, "timestamp": 42, "claim": "Seven at least"}
+                ]},
+                {"point": "Uncited", "citations": [{"video": 0, "timestamp": "0:01"}]}
+            ]}"#,
+        )
+        .unwrap();
+        let points = resolve(
+            raw.agreements,
+            &[video("aaaaaaaaaaa"), video("bbbbbbbbbbb")],
+        );
+        assert_eq!(
+            points,
+            [ComparedPoint {
+                point: "Sleep matters".into(),
+                citations: vec![
+                    Citation {
+                        video_id: "aaaaaaaaaaa".into(),
+                        timestamp: 65.0,
+                        claim: "Eight hours".into(),
+                    },
+                    Citation {
+                        video_id: "bbbbbbbbbbb".into(),
+                        timestamp: 42.0,
+                        claim: "Seven at least".into(),
+                    },
+                ],
+            }]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Signed-in requests from an imported browser cookie jar.
+//!
+//! Age-restricted and members-only videos need a logged-in session. Users export
+//! their YouTube cookies (cookies.txt or a browser extension's JSON), and the
+//! Google cookies are sent with every Innertube request together with the
+//! `SAPISIDHASH` authorization header derived from them.
+
+use crate::error::TranscriptError;
+use crate::secrets::write_private;
+use once_cell::sync::Lazy;
+use reqwest::header::{AUTHORIZATION, COOKIE};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::Path;
+use std::sync::RwLock;
+
+const COOKIE_FILE: &str = "cookies.txt";
+const ORIGIN: &str = "https://www.youtube.com";
+const DOMAINS: &[&str] = &["youtube.com", "google.com"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cookie {
+    pub domain: String,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CookieStatus {
+    pub cookie_count: usize,
+    /// Whether a `SAPISID` cookie is present, i.e. requests can be authorized.
+    pub signed_in: bool,
+}
+
+/// Entry of a browser-extension JSON export.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonCookie {
+    domain: String,
+    name: String,
+    value: String,
+    expiration_date: Option<f64>,
+}
+
+static JAR: Lazy<RwLock<Vec<Cookie>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+fn is_relevant(domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.');
+    DOMAINS
+        .iter()
+        .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+}
+
+/// Parses a Netscape cookies.txt or a JSON array export, keeping unexpired
+/// YouTube and Google cookies.
+pub(crate) fn parse_cookies(contents: &str, now: i64) -> Result<Vec<Cookie>, TranscriptError> {
+    let not_expired = |expiry: i64| expiry <= 0 || expiry > now;
+
+    let cookies: Vec<Cookie> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<JsonCookie>>(contents)
+            .map_err(|e| TranscriptError::InvalidInput(format!("Invalid cookie JSON: {}", e)))?
+            .into_iter()
+            .filter(|c| not_expired(c.expiration_date.unwrap_or(0.0) as i64))
+            .map(|c| Cookie {
+                domain: c.domain,
+                name: c.name,
+                value: c.value,
+            })
+            .collect()
+    } else {
+        contents
+            .lines()
+            .filter_map(|line| {
+                // curl and yt-dlp mark HttpOnly cookies with a prefix on otherwise valid lines
+                let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+                if line.starts_with('#') {
+                    return None;
+                }
+                let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+                if fields.len() != 7 {
+                    return None;
+                }
+                let expiry = fields[4].parse::<i64>().unwrap_or(0);
+                not_expired(expiry).then(|| Cookie {
+                    domain: fields[0].to_string(),
+                    name: fields[5].to_string(),
+                    value: fields[6].to_string(),
+                })
+            })
+            .collect()
+    };
+
+    let cookies: Vec<Cookie> = cookies
+        .into_iter()
+        .filter(|c| is_relevant(&c.domain) && !c.name.is_empty())
+        .collect();
+    if cookies.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "No YouTube cookies found. Export them while signed in to youtube.com.".into(),
+        ));
+    }
+    Ok(cookies)
+}
+
+/// `SAPISIDHASH <ts>_<sha1("<ts> <SAPISID> <origin>")>`, as sent by youtube.com.
+pub(crate) fn sapisid_hash(sapisid: &str, origin: &str, timestamp: i64) -> String {
+    let digest = Sha1::digest(format!("{} {} {}", timestamp, sapisid, origin));
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("SAPISIDHASH {}_{}", timestamp, hex)
+}
+
+fn sapisid(cookies: &[Cookie]) -> Option<&str> {
+    ["SAPISID", "__Secure-3PAPISID"]
+        .iter()
+        .find_map(|name| cookies.iter().find(|c| c.name == *name))
+        .map(|c| c.value.as_str())
+}
+
+fn status(cookies: &[Cookie]) -> CookieStatus {
+    CookieStatus {
+        cookie_count: cookies.len(),
+        signed_in: sapisid(cookies).is_some(),
+    }
+}
+
+/// Whether requests are sent as a signed-in user.
+pub(crate) fn is_signed_in() -> bool {
+    JAR.read()
+        .map(|jar| sapisid(&jar).is_some())
+        .unwrap_or(false)
+}
+
+/// Adds the imported cookies and, when possible, the SAPISIDHASH authorization.
+pub(crate) fn authorize(request: RequestBuilder) -> RequestBuilder {
+    let Ok(jar) = JAR.read() else {
+        return request;
+    };
+    if jar.is_empty() {
+        return request;
+    }
+
+    let cookie_header = jar
+        .iter()
+        .map(|c| format!("{}={}", c.name, c.value))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let request = request.header(COOKIE, cookie_header);
+
+    match sapisid(&jar) {
+        Some(sapisid) => request
+            .header(
+                AUTHORIZATION,
+                sapisid_hash(sapisid, ORIGIN, crate::db::unix_now()),
+            )
+            .header("X-Origin", ORIGIN)
+            .header("X-Goog-AuthUser", "0"),
+        None => request,
+    }
+}
+
+/// Loads a previously imported jar from the app data directory, if any.
+pub fn load(data_dir: &Path) {
+    let Ok(contents) = std::fs::read_to_string(data_dir.join(COOKIE_FILE)) else {
+        return;
+    };
+    if let (Ok(cookies), Ok(mut jar)) =
+        (parse_cookies(&contents, crate::db::unix_now()), JAR.write())
+    {
+        *jar = cookies;
+    }
+}
+
+/// Imports a cookies.txt or JSON cookie export from `path`, replacing the current
+/// jar and the copy kept in `data_dir`.
+pub fn import_cookies(data_dir: &Path, path: &str) -> Result<CookieStatus, TranscriptError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| TranscriptError::FileError(format!("Could not read \"{}\": {}", path, e)))?;
+    let cookies = parse_cookies(&contents, crate::db::unix_now())?;
+
+    // The jar grants access to the account
+    write_private(&data_dir.join(COOKIE_FILE), &contents)
+        .map_err(|e| TranscriptError::FileError(format!("Could not save cookies: {}", e)))?;
+
+    let status = status(&cookies);
+    if let Ok(mut jar) = JAR.write() {
+        *jar = cookies;
+    }
+    Ok(status)
+}
+
+/// Empties the jar and deletes the copy kept in `data_dir`.
+pub fn clear_cookies(data_dir: &Path) -> Result<(), TranscriptError> {
+    if let Ok(mut jar) = JAR.write() {
+        jar.clear();
+    }
+    match std::fs::remove_file(data_dir.join(COOKIE_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(TranscriptError::FileError(
+            format!("Could not delete cookies: {}", e),
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub fn get_cookie_status() -> CookieStatus {
+    JAR.read().map(|jar| status(&jar)).unwrap_or(CookieStatus {
+        cookie_count: 0,
+        signed_in: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_netscape_cookies_txt() {
+        let txt = "# Netscape HTTP Cookie File\n\
+                   .youtube.com\tTRUE\t/\tTRUE\t1900000000\tSAPISID\tabc/def\n\
+                   #HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t1900000000\tSID\tsid-value\n\
+                   .youtube.com\tTRUE\t/\tTRUE\t1000\tEXPIRED\tx\n\
+                   .example.com\tTRUE\t/\tFALSE\t0\tOTHER\ty\n";
+
+        let cookies = parse_cookies(txt, 1_700_000_000).unwrap();
+        let names: Vec<&str> = cookies.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["SAPISID", "SID"]);
+        assert_eq!(sapisid(&cookies), Some("abc/def"));
+    }
+
+    #[test]
+    fn parses_json_export() {
+        let json = r#"[{"domain": ".google.com", "name": "NID", "value": "1"},
+                       {"domain": ".youtube.com", "name": "__Secure-3PAPISID", "value": "2", "expirationDate": 1900000000.5}]"#;
+
+        let cookies = parse_cookies(json, 1_700_000_000).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(sapisid(&cookies), Some("2"));
+    }
+
+    #[test]
+    fn rejects_jar_without_youtube_cookies() {
+        let txt = ".example.com\tTRUE\t/\tFALSE\t0\tOTHER\ty\n";
+        assert!(parse_cookies(txt, 0).is_err());
+    }
+
+    #[test]
+    fn computes_sapisid_hash() {
+        assert_eq!(
+            sapisid_hash("abcDEF/ghi", "https://www.youtube.com", 1_700_000_000),
+            "SAPISIDHASH 1700000000_f09245f47c3c8f151971b883e625628368e1e5f9"
+        );
+    }
+}
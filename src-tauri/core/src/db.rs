@@ -0,0 +1,374 @@
+use crate::error::TranscriptError;
+use rusqlite::{params, Connection, DatabaseName};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Table definitions of every module that persists data, applied on open.
+const SCHEMAS: &[&str] = &[
+    crate::cache::SCHEMA,
+    crate::search::SCHEMA,
+    crate::embeddings::SCHEMA,
+    crate::jobs::SCHEMA,
+    crate::history::SCHEMA,
+    crate::collections::SCHEMA,
+    crate::notes::SCHEMA,
+    crate::highlights::SCHEMA,
+    crate::comment_analysis::SCHEMA,
+    crate::subscriptions::SCHEMA,
+    crate::auto_ingest::SCHEMA,
+    crate::chapter_summaries::SCHEMA,
+    crate::readwise::SCHEMA,
+    crate::activity::SCHEMA,
+    crate::llm::usage::SCHEMA,
+];
+
+/// Changes to tables created by earlier versions, applied once each and in order.
+/// The database's `user_version` counts the ones already applied, so entries are
+/// only ever appended.
+const MIGRATIONS: &[&str] = &[
+    crate::cache::ADD_CAPTION_KIND,
+    crate::cache::ADD_TRACK,
+    crate::subscriptions::ADD_PLAYLISTS,
+    crate::subscriptions::ADD_AUTO_INGEST,
+    crate::cache::ADD_USAGE,
+];
+
+/// The local SQLite store, kept in Tauri managed state.
+pub struct Database {
+    conn: Mutex<Connection>,
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TableInfo {
+    pub name: String,
+    pub rows: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DbInfo {
+    pub path: String,
+    /// Migrations applied to the database.
+    pub schema_version: u32,
+    /// Migrations this version of the app knows.
+    pub latest_version: u32,
+    pub size_bytes: u64,
+    pub sqlite_version: String,
+    /// Whether this build can encrypt the database.
+    pub encryption_available: bool,
+    pub encrypted: bool,
+    pub tables: Vec<TableInfo>,
+}
+
+fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Applies the migrations `conn` is missing, each in a transaction with the
+/// version it brings the database to, so one cut short leaves the database as it
+/// was. Returns the version the database was at.
+fn migrate(conn: &mut Connection) -> Result<u32, TranscriptError> {
+    let applied = schema_version(conn)?;
+    if applied as usize > MIGRATIONS.len() {
+        return Err(TranscriptError::DatabaseError(format!(
+            "The database was upgraded by a newer version of InsightTube (schema {}, this \
+             version knows {}). Update the app to open it.",
+            applied,
+            MIGRATIONS.len()
+        )));
+    }
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(applied)
+}
+
+/// Copies the database at `path`, at schema `version`, next to it before it is
+/// migrated, for going back if a migration turns out wrong. The copy is of the file,
+/// so an encrypted database stays encrypted.
+fn back_up(conn: &Connection, path: &Path, version: u32) -> Result<(), TranscriptError> {
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    std::fs::copy(path, sibling(path, &format!(".v{}.bak", version))).map_err(|e| {
+        TranscriptError::FileError(format!("Could not back up the database: {}", e))
+    })?;
+    Ok(())
+}
+
+/// Removes the copies `back_up` made of the database at `path`, which would keep
+/// its data readable once it is encrypted.
+fn remove_backups(path: &Path) {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}.v", name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if file_name.starts_with(&prefix) && file_name.ends_with(".bak") {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// `path` with `suffix` added to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// `key` as SQLCipher takes a raw key, which it uses without deriving one.
+fn raw_key(key: &str) -> String {
+    format!("x'{}'", key)
+}
+
+fn random_key() -> String {
+    let bytes: [u8; 32] = rand::random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The key of the database in the keychain, in builds that can encrypt it.
+fn stored_key() -> Option<String> {
+    if !cfg!(feature = "encryption") {
+        return None;
+    }
+    crate::secrets::database_key().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "database key not read");
+        None
+    })
+}
+
+/// Opens the file at `path`, unlocked with `key` when it is encrypted.
+fn connect(path: &Path, key: Option<&str>) -> Result<Connection, TranscriptError> {
+    let conn = Connection::open(path)?;
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", raw_key(key))?;
+    }
+    // The first read of the file, where a missing or wrong key shows
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::NotADatabase) => TranscriptError::DatabaseError(
+                "The database is encrypted, and the key in the keychain does not open it.".into(),
+            ),
+            _ => e.into(),
+        })?;
+    Ok(conn)
+}
+
+impl Database {
+    /// Opens the database at `path`, creating missing tables and upgrading the
+    /// schema of one written by an earlier version.
+    pub fn open(path: &Path) -> Result<Self, TranscriptError> {
+        let existed = path.exists();
+        let mut conn = connect(path, stored_key().as_deref())?;
+        let version = schema_version(&conn)?;
+        if existed && (version as usize) < MIGRATIONS.len() {
+            back_up(&conn, path, version)?;
+        }
+        for schema in SCHEMAS {
+            conn.execute_batch(schema)?;
+        }
+        let from = migrate(&mut conn)?;
+        if from as usize != MIGRATIONS.len() {
+            tracing::info!(from, to = MIGRATIONS.len(), "database schema upgraded");
+        }
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// An empty database in memory, for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for schema in SCHEMAS {
+            conn.execute_batch(schema).unwrap();
+        }
+        migrate(&mut conn).unwrap();
+        Self {
+            conn: Mutex::new(conn),
+            path: PathBuf::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrites the database encrypted with a new key kept in the keychain, or
+    /// decrypted. The rewritten copy replaces the file once the keychain holds its
+    /// key, so a failure at any step leaves a database that opens.
+    fn set_encryption(&self, encrypt: bool) -> Result<(), TranscriptError> {
+        let current = crate::secrets::database_key()?;
+        if current.is_some() == encrypt {
+            return Ok(());
+        }
+        let key = encrypt.then(random_key);
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| TranscriptError::DatabaseError("Database lock was poisoned.".into()))?;
+
+        let copy = sibling(&self.path, ".rekey");
+        let _ = std::fs::remove_file(&copy);
+        let version = schema_version(&conn)?;
+        let exported = conn
+            .execute(
+                "ATTACH DATABASE ?1 AS rekeyed KEY ?2",
+                params![
+                    copy.to_string_lossy(),
+                    key.as_deref().map(raw_key).unwrap_or_default()
+                ],
+            )
+            .and_then(|_| conn.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(())))
+            .and_then(|_| {
+                conn.pragma_update(
+                    Some(DatabaseName::Attached("rekeyed")),
+                    "user_version",
+                    version,
+                )
+            });
+        let _ = conn.execute("DETACH DATABASE rekeyed", []);
+        let stored = exported
+            .map_err(TranscriptError::from)
+            .and_then(|_| crate::secrets::set_database_key(key.as_deref()));
+        if let Err(e) = stored {
+            let _ = std::fs::remove_file(&copy);
+            return Err(e);
+        }
+
+        // The old connection is closed so its file can be replaced
+        *conn = Connection::open_in_memory()?;
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(sibling(&self.path, suffix));
+        }
+        if let Err(e) = std::fs::rename(&copy, &self.path) {
+            let _ = crate::secrets::set_database_key(current.as_deref());
+            *conn = connect(&self.path, current.as_deref())?;
+            return Err(TranscriptError::FileError(format!(
+                "Could not replace the database: {}",
+                e
+            )));
+        }
+        *conn = connect(&self.path, key.as_deref())?;
+        if encrypt {
+            remove_backups(&self.path);
+        }
+        tracing::info!(encrypted = encrypt, "database rewritten");
+        Ok(())
+    }
+
+    /// Runs `f` with exclusive access to the connection.
+    pub fn with_conn<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, TranscriptError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| TranscriptError::DatabaseError("Database lock was poisoned.".into()))?;
+        Ok(f(&conn)?)
+    }
+}
+
+/// Current time as Unix seconds, the timestamp format used in every table.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Where the database is, its schema version, and the size of each table.
+pub fn get_db_info(db: &Database) -> Result<DbInfo, TranscriptError> {
+    let (schema_version, size_bytes, sqlite_version, tables) = db.with_conn(|conn| {
+        let page_count: u64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        let page_size: u64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+        let sqlite_version: String =
+            conn.query_row("SELECT sqlite_version()", [], |row| row.get(0))?;
+        let names = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                 ORDER BY name",
+            )?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut tables = Vec::new();
+        for name in names {
+            let rows = conn.query_row(
+                &format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")),
+                [],
+                |row| row.get(0),
+            )?;
+            tables.push(TableInfo { name, rows });
+        }
+        Ok((
+            schema_version(conn)?,
+            page_count * page_size,
+            sqlite_version,
+            tables,
+        ))
+    })?;
+    Ok(DbInfo {
+        path: db.path().display().to_string(),
+        schema_version,
+        latest_version: MIGRATIONS.len() as u32,
+        size_bytes,
+        sqlite_version,
+        encryption_available: cfg!(feature = "encryption"),
+        encrypted: stored_key().is_some(),
+        tables,
+    })
+}
+
+/// Encrypts the database with a key kept in the keychain, or decrypts it. Builds
+/// without the `encryption` feature cannot.
+pub fn set_database_encryption(db: &Database, enabled: bool) -> Result<(), TranscriptError> {
+    if !cfg!(feature = "encryption") {
+        return Err(TranscriptError::InvalidInput(
+            "This build of InsightTube cannot encrypt its database.".into(),
+        ));
+    }
+    db.set_encryption(enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for schema in SCHEMAS {
+            conn.execute_batch(schema).unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn applies_each_migration_once() {
+        let mut conn = fresh();
+        assert_eq!(migrate(&mut conn).unwrap(), 0);
+        assert_eq!(schema_version(&conn).unwrap() as usize, MIGRATIONS.len());
+        assert_eq!(migrate(&mut conn).unwrap() as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn refuses_databases_of_newer_versions() {
+        let mut conn = fresh();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
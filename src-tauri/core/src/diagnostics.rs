@@ -0,0 +1,262 @@
+//! A diagnostics bundle to attach to bug reports.
+//!
+//! `export_diagnostics` writes a zip of the recent logs, the settings, the app and
+//! OS versions, and the last requests that failed. Nothing in it identifies the
+//! user: settings are [redacted](crate::settings::Settings::redacted), cookies and
+//! API keys are never part of it, and the query parameters that carry keys, tokens
+//! or signatures are blanked in URLs and log lines alike. Failed requests to hosts
+//! other than YouTube and Google keep no path, which for a webhook can be a secret.
+
+use crate::db::unix_now;
+use crate::error::TranscriptError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Mutex;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Failed requests remembered for the bundle.
+const MAX_FAILURES: usize = 50;
+pub const LOG_LINES: usize = 5_000;
+/// Query parameters whose values are kept; every other value is blanked.
+const SAFE_PARAMS: &[&str] = &[
+    "v",
+    "lang",
+    "tlang",
+    "fmt",
+    "kind",
+    "hl",
+    "channel_id",
+    "playlist_id",
+    "list",
+];
+/// Hosts whose URL paths are kept. Elsewhere, such as a webhook, the path may be a
+/// secret and is dropped.
+const SAFE_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "google.com",
+    "googleapis.com",
+    "googlevideo.com",
+];
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedRequest {
+    at: i64,
+    url: String,
+    /// The response status, when there was a response.
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+pub static FAILURES: Lazy<Mutex<VecDeque<FailedRequest>>> = Lazy::new(Default::default);
+
+/// `url` with the values of all but [`SAFE_PARAMS`] blanked, and without its path
+/// unless it is one of the [`SAFE_HOSTS`].
+fn sanitize_url(url: &reqwest::Url) -> String {
+    let mut sanitized = url.clone();
+    let host = url.host_str().unwrap_or_default();
+    let safe_host = SAFE_HOSTS
+        .iter()
+        .any(|safe| host == *safe || host.ends_with(&format!(".{}", safe)));
+    if !safe_host {
+        sanitized.set_path("");
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if SAFE_PARAMS.contains(&name.as_ref()) {
+                value.into_owned()
+            } else {
+                "…".to_string()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    sanitized.set_query(None);
+    if !pairs.is_empty() {
+        sanitized.query_pairs_mut().extend_pairs(pairs);
+    }
+    sanitized.set_fragment(None);
+    sanitized.to_string()
+}
+
+/// Blanks the keys, tokens and signatures of URLs quoted in `text`, such as a log
+/// line or an error message.
+pub fn scrub(text: &str) -> String {
+    static SECRET_PARAM_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"([?&](?:key|pot|sig|signature|sp|n|ei|ip|expire|lsig)=)[^&\s)\x22]+").unwrap()
+    });
+    SECRET_PARAM_RE.replace_all(text, "${1}…").into_owned()
+}
+
+/// Remembers a request that ended in an error status or failed outright.
+pub(crate) fn record_outcome(result: &Result<reqwest::Response, reqwest::Error>) {
+    let failure = match result {
+        Ok(res) if res.status().is_client_error() || res.status().is_server_error() => {
+            FailedRequest {
+                at: unix_now(),
+                url: sanitize_url(res.url()),
+                status: Some(res.status().as_u16()),
+                error: None,
+            }
+        }
+        Ok(_) => return,
+        Err(e) => {
+            let url = e.url().map(sanitize_url).unwrap_or_default();
+            // The message quotes the URL in full
+            let error = match e.url() {
+                Some(raw) => e.to_string().replace(raw.as_str(), &url),
+                None => e.to_string(),
+            };
+            FailedRequest {
+                at: unix_now(),
+                url,
+                status: e.status().map(|s| s.as_u16()),
+                error: Some(scrub(&error)),
+            }
+        }
+    };
+    if let Ok(mut failures) = FAILURES.lock() {
+        if failures.len() == MAX_FAILURES {
+            failures.pop_front();
+        }
+        failures.push_back(failure);
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    app_version: String,
+    tauri_version: &'static str,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    local_transcription: bool,
+    exported_at: i64,
+}
+
+pub fn zip_error(path: &str, e: impl std::fmt::Display) -> TranscriptError {
+    TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e))
+}
+
+pub fn to_json(value: &impl Serialize) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
+}
+
+/// Writes a zip to `path` with the recent logs, the redacted settings, the app,
+/// Tauri and OS versions, and the last failed requests, to attach to a bug report.
+pub fn export_diagnostics(
+    path: &str,
+    app_version: String,
+    tauri_version: &'static str,
+) -> Result<(), TranscriptError> {
+    let system = SystemInfo {
+        app_version,
+        tauri_version,
+        os: std::env::consts::OS,
+        os_family: std::env::consts::FAMILY,
+        arch: std::env::consts::ARCH,
+        local_transcription: cfg!(feature = "whisper"),
+        exported_at: unix_now(),
+    };
+    let failures: Vec<FailedRequest> = FAILURES
+        .lock()
+        .map(|f| f.iter().cloned().collect())
+        .unwrap_or_default();
+    let logs: String = crate::logging::recent_lines(LOG_LINES)?
+        .iter()
+        .map(|line| scrub(line) + "\n")
+        .collect();
+
+    let files = [
+        ("system.json", to_json(&system)),
+        (
+            "settings.json",
+            to_json(&crate::settings::current().redacted()),
+        ),
+        ("failed_requests.json", to_json(&failures)),
+        ("logs.txt", logs.into_bytes()),
+    ];
+    let file = std::fs::File::create(path).map_err(|e| zip_error(path, e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in files {
+        zip.start_file(name, options)
+            .map_err(|e| zip_error(path, e))?;
+        zip.write_all(&contents).map_err(|e| zip_error(path, e))?;
+    }
+    zip.finish().map_err(|e| zip_error(path, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_secrets_in_urls_and_messages() {
+        let url = reqwest::Url::parse(
+            "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&signature=abc&pot=xyz",
+        )
+        .unwrap();
+        assert_eq!(
+            sanitize_url(&url),
+            "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&signature=%E2%80%A6&pot=%E2%80%A6"
+        );
+        let webhook = reqwest::Url::parse("https://discord.com/api/webhooks/123/s3cret").unwrap();
+        assert_eq!(sanitize_url(&webhook), "https://discord.com/");
+
+        let message = "error sending request for url \
+            (https://www.youtube.com/youtubei/v1/player?key=AIzaSy123&prettyPrint=false)";
+        assert_eq!(
+            scrub(message),
+            "error sending request for url \
+            (https://www.youtube.com/youtubei/v1/player?key=…&prettyPrint=false)"
+        );
+    }
+
+    /// Scrubbing the log lines of a batch of transcript fetches, with the shared
+    /// pattern against one compiled for every line. Run with
+    /// `cargo test --release -p insighttube-core bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_scrubbing_transcript_fetches() {
+        let lines: Vec<String> = (0..2_000)
+            .map(|i| {
+                format!(
+                    "WARN captions: error sending request for url \
+                     (https://www.youtube.com/api/timedtext?v=video{:05}&lang=en\
+                     &signature=ABC{}&expire=1700000000&pot=MnQ{}&fmt=srv3)",
+                    i, i, i
+                )
+            })
+            .collect();
+        let per_call = |line: &str| -> String {
+            let re =
+                Regex::new(r"([?&](?:key|pot|sig|signature|sp|n|ei|ip|expire|lsig)=)[^&\s)\x22]+")
+                    .unwrap();
+            re.replace_all(line, "${1}…").into_owned()
+        };
+
+        let started = std::time::Instant::now();
+        let shared: Vec<String> = lines.iter().map(|l| scrub(l)).collect();
+        let shared_time = started.elapsed();
+        let started = std::time::Instant::now();
+        let compiled: Vec<String> = lines.iter().map(|l| per_call(l)).collect();
+        let compiled_time = started.elapsed();
+
+        assert_eq!(shared, compiled);
+        println!(
+            "{} transcript fetches: {:?} with the shared pattern, {:?} compiling it per call",
+            lines.len(),
+            shared_time,
+            compiled_time
+        );
+    }
+}
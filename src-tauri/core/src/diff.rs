@@ -0,0 +1,274 @@
+//! Comparing the transcripts of two videos, such as a re-upload and its original,
+//! an edited version, or two talks on the same topic.
+//!
+//! Both transcripts are rebuilt into sentences, which are compared by their words
+//! alone, ignoring case and punctuation, so caption fragments cut differently do
+//! not show up as changes. The longest common run of sentences is found with a
+//! dynamic program after trimming the start and end the videos share; the
+//! sentences around it become added, removed and changed blocks, each with its
+//! place in both videos.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::transcript::{load_transcript, segmenter, TranscriptParagraph};
+use serde::Serialize;
+
+/// Largest table the alignment fills, in sentence pairs. Past it, the part the
+/// videos do not share at the start or end is reported as a single change.
+const MAX_CELLS: usize = 16_000_000;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    /// Only in the second video.
+    Added,
+    /// Only in the first video.
+    Removed,
+    /// Different in the two videos.
+    Changed,
+}
+
+/// One video's side of a block. The side a block is missing from is empty and
+/// starts and ends where the other side's text would go.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSide {
+    pub text: String,
+    /// Seconds from the start of the video.
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffBlock {
+    pub kind: DiffKind,
+    pub a: DiffSide,
+    pub b: DiffSide,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptDiff {
+    pub video_id_a: String,
+    pub video_id_b: String,
+    /// Share of the sentences the videos have in common, from 0 to 1.
+    pub similarity: f64,
+    pub unchanged_sentences: usize,
+    pub blocks: Vec<DiffBlock>,
+}
+
+/// The words of a sentence, lowercased, for comparison.
+fn comparison_key(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same,
+    Remove,
+    Add,
+}
+
+/// An edit script turning `a` into `b`, from their longest common subsequence.
+fn edit_script(a: &[String], b: &[String]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops = vec![Op::Same; prefix];
+    if a_mid.len() * b_mid.len() > MAX_CELLS {
+        ops.extend(std::iter::repeat_n(Op::Remove, a_mid.len()));
+        ops.extend(std::iter::repeat_n(Op::Add, b_mid.len()));
+    } else {
+        // lengths[i][j]: longest common subsequence of a_mid[i..] and b_mid[j..]
+        let width = b_mid.len() + 1;
+        let mut lengths = vec![0u32; (a_mid.len() + 1) * width];
+        for i in (0..a_mid.len()).rev() {
+            for j in (0..b_mid.len()).rev() {
+                lengths[i * width + j] = if a_mid[i] == b_mid[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a_mid.len() || j < b_mid.len() {
+            if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+                ops.push(Op::Same);
+                i += 1;
+                j += 1;
+            } else if j == b_mid.len()
+                || (i < a_mid.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+            {
+                ops.push(Op::Remove);
+                i += 1;
+            } else {
+                ops.push(Op::Add);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Same, suffix));
+    ops
+}
+
+/// `sentences[range]` as one side of a block, or an empty side at `range.start`.
+fn side(sentences: &[TranscriptParagraph], range: std::ops::Range<usize>) -> DiffSide {
+    match sentences.get(range.clone()).filter(|s| !s.is_empty()) {
+        Some(run) => DiffSide {
+            text: run
+                .iter()
+                .map(|s| s.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            start: run[0].start,
+            end: run[run.len() - 1].end,
+        },
+        None => {
+            let at = sentences
+                .get(range.start)
+                .map(|s| s.start)
+                .or_else(|| sentences.last().map(|s| s.end))
+                .unwrap_or(0.0);
+            DiffSide {
+                text: String::new(),
+                start: at,
+                end: at,
+            }
+        }
+    }
+}
+
+/// Aligns the sentences of two transcripts and collects where they differ.
+fn diff(a: &[TranscriptParagraph], b: &[TranscriptParagraph]) -> (Vec<DiffBlock>, usize) {
+    let keys = |sentences: &[TranscriptParagraph]| -> Vec<String> {
+        sentences.iter().map(|s| comparison_key(&s.text)).collect()
+    };
+    let ops = edit_script(&keys(a), &keys(b));
+
+    let mut blocks = Vec::new();
+    let mut unchanged = 0;
+    let (mut i, mut j) = (0, 0);
+    let mut ops = ops.into_iter().peekable();
+    while let Some(op) = ops.next() {
+        if op == Op::Same {
+            unchanged += 1;
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (start_i, start_j) = (i, j);
+        let mut next = Some(op);
+        while let Some(op) = next.filter(|op| *op != Op::Same) {
+            match op {
+                Op::Remove => i += 1,
+                _ => j += 1,
+            }
+            next = ops.next_if(|op| *op != Op::Same);
+        }
+        let kind = match (i > start_i, j > start_j) {
+            (true, true) => DiffKind::Changed,
+            (true, false) => DiffKind::Removed,
+            _ => DiffKind::Added,
+        };
+        blocks.push(DiffBlock {
+            kind,
+            a: side(a, start_i..i),
+            b: side(b, start_j..j),
+        });
+    }
+    (blocks, unchanged)
+}
+
+/// Compares the transcripts of two videos sentence by sentence, returning the
+/// added, removed and changed blocks with their timestamps in both videos.
+pub async fn diff_transcripts(
+    db: &Database,
+    video_id_a: String,
+    video_id_b: String,
+) -> Result<TranscriptDiff, TranscriptError> {
+    let video_id_a = crate::video_id::parse(&video_id_a)?;
+    let video_id_b = crate::video_id::parse(&video_id_b)?;
+    let a = segmenter::sentences(&load_transcript(db, &video_id_a, None).await?);
+    let b = segmenter::sentences(&load_transcript(db, &video_id_b, None).await?);
+
+    let (blocks, unchanged_sentences) = diff(&a, &b);
+    let total = a.len() + b.len();
+    Ok(TranscriptDiff {
+        video_id_a,
+        video_id_b,
+        similarity: if total == 0 {
+            1.0
+        } else {
+            (2 * unchanged_sentences) as f64 / total as f64
+        },
+        unchanged_sentences,
+        blocks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(texts: &[&str]) -> Vec<TranscriptParagraph> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TranscriptParagraph {
+                text: text.to_string(),
+                start: i as f64 * 10.0,
+                end: i as f64 * 10.0 + 8.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aligns_added_removed_and_changed_sentences() {
+        let a = sentences(&[
+            "Welcome back.",
+            "Today we talk about Rust.",
+            "First, ownership.",
+            "Then borrowing.",
+            "Thanks for watching.",
+        ]);
+        let b = sentences(&[
+            "welcome back",
+            "Quick word from our sponsor.",
+            "Today we talk about Rust!",
+            "First, lifetimes.",
+            "Then borrowing.",
+        ]);
+
+        let (blocks, unchanged) = diff(&a, &b);
+        assert_eq!(unchanged, 3);
+        let kinds: Vec<_> = blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            [DiffKind::Added, DiffKind::Changed, DiffKind::Removed]
+        );
+
+        let added = &blocks[0];
+        assert_eq!(added.b.text, "Quick word from our sponsor.");
+        assert_eq!((added.b.start, added.b.end), (10.0, 18.0));
+        assert_eq!((added.a.text.as_str(), added.a.start), ("", 10.0));
+
+        assert_eq!(blocks[1].a.text, "First, ownership.");
+        assert_eq!(blocks[1].b.text, "First, lifetimes.");
+
+        let removed = &blocks[2];
+        assert_eq!(removed.a.text, "Thanks for watching.");
+        assert_eq!((removed.b.start, removed.b.end), (48.0, 48.0));
+    }
+}
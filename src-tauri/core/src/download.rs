@@ -0,0 +1,286 @@
+//! Downloading a video's audio-only stream.
+//!
+//! Streams are fetched in ranged chunks: googlevideo throttles long single
+//! responses, and chunking lets progress events report how far along the
+//! download is.
+
+use crate::cipher;
+use crate::error::TranscriptError;
+use crate::http::{self, build_client};
+use crate::innertube::{self, fetch_player_response};
+use crate::operations::{self, Operations};
+use crate::progress::{self, Progress, Stage};
+use reqwest::header::RANGE;
+use reqwest::Url;
+use serde::Serialize;
+use std::io::Write;
+use tokio_util::sync::CancellationToken;
+
+const CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Where a stream can be fetched from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum StreamUrl {
+    Direct(String),
+    /// `signatureCipher` formats: the URL only works once the scrambled signature
+    /// `s` has been deciphered and appended as the `sp` query parameter.
+    Ciphered {
+        url: String,
+        signature: String,
+        sp: String,
+    },
+}
+
+/// An audio-only entry of `streamingData.adaptiveFormats`.
+#[derive(Debug, Clone)]
+pub(crate) struct AudioFormat {
+    pub itag: u64,
+    pub mime_type: String,
+    pub bitrate: u64,
+    pub content_length: Option<u64>,
+    pub url: StreamUrl,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadResult {
+    pub path: String,
+    pub itag: u64,
+    pub mime_type: String,
+    pub bytes: u64,
+}
+
+fn stream_url(format: &serde_json::Value) -> Option<StreamUrl> {
+    if let Some(url) = format.get("url").and_then(|u| u.as_str()) {
+        return Some(StreamUrl::Direct(url.to_string()));
+    }
+
+    // signatureCipher is itself a query string: s=…&sp=sig&url=…
+    let cipher = format.get("signatureCipher")?.as_str()?;
+    let params = Url::parse(&format!("https://localhost/?{}", cipher)).ok()?;
+    let param = |name: &str| {
+        params
+            .query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    };
+    Some(StreamUrl::Ciphered {
+        url: param("url")?,
+        signature: param("s")?,
+        sp: param("sp").unwrap_or_else(|| "signature".into()),
+    })
+}
+
+/// Lists the audio-only formats of a player response, best bitrate first. With
+/// `mime_prefix` (e.g. `audio/mp4`) only matching containers are returned.
+pub(crate) fn audio_formats(
+    player_json: &serde_json::Value,
+    mime_prefix: Option<&str>,
+) -> Vec<AudioFormat> {
+    let mut formats: Vec<AudioFormat> = player_json
+        .get("streamingData")
+        .and_then(|s| s.get("adaptiveFormats"))
+        .and_then(|f| f.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|f| {
+            let mime_type = f.get("mimeType")?.as_str()?;
+            if !mime_type.starts_with("audio/")
+                || mime_prefix.is_some_and(|p| !mime_type.starts_with(p))
+            {
+                return None;
+            }
+            Some(AudioFormat {
+                itag: f.get("itag").and_then(|i| i.as_u64()).unwrap_or(0),
+                mime_type: mime_type.to_string(),
+                bitrate: f.get("bitrate").and_then(|b| b.as_u64()).unwrap_or(0),
+                content_length: f
+                    .get("contentLength")
+                    .and_then(|l| l.as_str())
+                    .and_then(|l| l.parse().ok()),
+                url: stream_url(f)?,
+            })
+        })
+        .collect();
+    formats.sort_by_key(|f| std::cmp::Reverse(f.bitrate));
+    formats
+}
+
+/// The best audio format of a player response, preferring ones with a direct URL.
+pub(crate) fn select_audio(
+    player_json: &serde_json::Value,
+    mime_prefix: Option<&str>,
+) -> Result<AudioFormat, TranscriptError> {
+    let formats = audio_formats(player_json, mime_prefix);
+    formats
+        .iter()
+        .find(|f| matches!(f.url, StreamUrl::Direct(_)))
+        .or_else(|| formats.first())
+        .cloned()
+        .ok_or_else(|| {
+            innertube::playability_error(player_json).unwrap_or_else(|| {
+                TranscriptError::VideoUnavailable(
+                    "No audio streams are available for this video.".into(),
+                )
+            })
+        })
+}
+
+/// The URL to request for `format`, deciphered with the current player script.
+async fn resolve_url(
+    client: &reqwest::Client,
+    video_id: &str,
+    format: &AudioFormat,
+) -> Result<String, TranscriptError> {
+    let session = innertube::session(client, &innertube::watch_url(video_id), false).await?;
+    let player = match &session.player_js_url {
+        Some(js_url) => Some(cipher::player(client, js_url).await?),
+        None => None,
+    };
+
+    match (&format.url, player) {
+        (StreamUrl::Direct(url), Some(player)) => player.decipher(url, None),
+        // Without the player script the n parameter stays as is, which only throttles
+        (StreamUrl::Direct(url), None) => Ok(url.clone()),
+        (StreamUrl::Ciphered { url, signature, sp }, Some(player)) => {
+            player.decipher(url, Some((signature, sp)))
+        }
+        (StreamUrl::Ciphered { .. }, None) => Err(TranscriptError::VideoUnavailable(
+            "The audio stream of this video is protected and the player script could not be found."
+                .into(),
+        )),
+    }
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+/// Downloads `format` into `out` in ranged chunks, reporting progress as it goes
+/// and stopping once `cancel` fires. Returns the number of bytes written.
+pub(crate) async fn download_to(
+    progress: &Progress,
+    cancel: &CancellationToken,
+    client: &reqwest::Client,
+    video_id: &str,
+    format: &AudioFormat,
+    out: &mut impl Write,
+) -> Result<u64, TranscriptError> {
+    let url = operations::cancellable(cancel, resolve_url(client, video_id, format)).await?;
+    let total = format.content_length;
+    let mut downloaded = 0u64;
+
+    loop {
+        let end = downloaded + CHUNK_SIZE - 1;
+        let end = total.map_or(end, |t| end.min(t.saturating_sub(1)));
+        let request = client
+            .get(&url)
+            .header(RANGE, format!("bytes={}-{}", downloaded, end));
+        let chunk = operations::cancellable(cancel, async {
+            let failed = |e: reqwest::Error| {
+                TranscriptError::NetworkError(format!("Failed to download audio: {}", e))
+            };
+            let res = http::send(request)
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(failed)?;
+            res.bytes().await.map_err(failed)
+        })
+        .await?;
+        out.write_all(&chunk)
+            .map_err(|e| TranscriptError::FileError(format!("Could not save audio: {}", e)))?;
+        downloaded += chunk.len() as u64;
+
+        // The stream size is unknown when the player response does not state it
+        let message = match total {
+            Some(total) => format!("{} of {}", megabytes(downloaded), megabytes(total)),
+            None => megabytes(downloaded),
+        };
+        progress.report(
+            Stage::Downloading,
+            total.map(|t| progress::percent(downloaded, t)),
+            Some(&message),
+        );
+
+        // A short chunk means the server had nothing more to send
+        let done = total.map_or((chunk.len() as u64) < CHUNK_SIZE, |t| downloaded >= t);
+        if done || chunk.is_empty() {
+            return Ok(downloaded);
+        }
+    }
+}
+
+/// Downloads the best audio-only stream of `video_id` to `path`, reporting to
+/// `progress`. Cancelling `operation_id` stops the download.
+pub async fn download_audio(
+    operations: &Operations,
+    video_id: &str,
+    path: String,
+    operation_id: &str,
+    progress: &Progress,
+) -> Result<DownloadResult, TranscriptError> {
+    let operation = operations.start(Some(operation_id));
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, video_id).await?;
+    let format = select_audio(&player_json, None)?;
+
+    let mut file = std::fs::File::create(&path)
+        .map_err(|e| TranscriptError::FileError(format!("Could not create \"{}\": {}", path, e)))?;
+    let bytes = download_to(
+        progress,
+        operation.token(),
+        &client,
+        video_id,
+        &format,
+        &mut file,
+    )
+    .await;
+    if bytes.is_err() {
+        // Don't leave a truncated file behind
+        let _ = std::fs::remove_file(&path);
+    }
+
+    Ok(DownloadResult {
+        path,
+        itag: format.itag,
+        mime_type: format.mime_type,
+        bytes: bytes?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_audio_formats_and_parses_signature_cipher() {
+        let player = serde_json::json!({
+            "streamingData": { "adaptiveFormats": [
+                { "itag": 137, "mimeType": "video/mp4; codecs=\"avc1\"", "bitrate": 4000000, "url": "https://v" },
+                { "itag": 140, "mimeType": "audio/mp4; codecs=\"mp4a.40.2\"", "bitrate": 130000,
+                  "contentLength": "1234", "url": "https://a140" },
+                { "itag": 251, "mimeType": "audio/webm; codecs=\"opus\"", "bitrate": 150000,
+                  "signatureCipher": "s=AB%3DC&sp=sig&url=https%3A%2F%2Fa251%3Fx%3D1" }
+            ]}
+        });
+
+        let formats = audio_formats(&player, None);
+        assert_eq!(
+            formats.iter().map(|f| f.itag).collect::<Vec<_>>(),
+            [251, 140]
+        );
+        assert_eq!(
+            formats[0].url,
+            StreamUrl::Ciphered {
+                url: "https://a251?x=1".into(),
+                signature: "AB=C".into(),
+                sp: "sig".into(),
+            }
+        );
+        assert_eq!(formats[1].content_length, Some(1234));
+
+        // Direct URLs win over higher-bitrate ciphered ones
+        assert_eq!(select_audio(&player, None).unwrap().itag, 140);
+        assert_eq!(audio_formats(&player, Some("audio/webm")).len(), 1);
+    }
+}
@@ -0,0 +1,345 @@
+//! Semantic search over cached transcripts with embedding vectors.
+//!
+//! Transcripts are embedded per paragraph, with the provider's embeddings API
+//! (OpenAI, or a local model through Ollama), and the vectors are stored next to
+//! the cache. A query is embedded with the same model and compared against every
+//! stored passage by cosine similarity. Vectors of different models are kept
+//! apart, as they are not comparable.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::llm::{self, Provider, ProviderKind};
+use crate::transcript::{load_transcript, segmenter, TranscriptParagraph, TranscriptSegment};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// Passages sent per embeddings request.
+const BATCH_SIZE: usize = 64;
+const DEFAULT_LIMIT: usize = 10;
+/// Passages returned per video.
+const MATCHES_PER_VIDEO: usize = 3;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transcript_embeddings (
+    video_id   TEXT    NOT NULL,
+    lang       TEXT    NOT NULL,
+    model      TEXT    NOT NULL,
+    passage    INTEGER NOT NULL,
+    start      REAL    NOT NULL,
+    end        REAL    NOT NULL,
+    text       TEXT    NOT NULL,
+    vector     BLOB    NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (video_id, lang, model, passage)
+);
+
+CREATE TRIGGER IF NOT EXISTS transcript_embeddings_delete AFTER DELETE ON transcript_cache BEGIN
+    DELETE FROM transcript_embeddings WHERE video_id = old.video_id AND lang = old.lang;
+END;
+
+-- Refetching an unchanged transcript keeps its vectors
+CREATE TRIGGER IF NOT EXISTS transcript_embeddings_update AFTER UPDATE OF segments ON transcript_cache
+WHEN old.segments IS NOT new.segments BEGIN
+    DELETE FROM transcript_embeddings WHERE video_id = old.video_id AND lang = old.lang;
+END;
+";
+
+/// A transcript passage with its embedding.
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddedPassage {
+    pub passage: TranscriptParagraph,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingStatus {
+    pub video_id: String,
+    pub lang: String,
+    pub model: String,
+    pub passages: usize,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SemanticSearchOptions {
+    /// Videos returned.
+    pub limit: Option<usize>,
+    /// Matches scoring below this cosine similarity are dropped.
+    pub min_score: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticMatch {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticResult {
+    pub video_id: String,
+    pub lang: String,
+    /// Score of the best match.
+    pub score: f32,
+    pub matches: Vec<SemanticMatch>,
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+fn no_embeddings() -> TranscriptError {
+    TranscriptError::InvalidInput(
+        "This provider has no embeddings. Use OpenAI or Ollama for semantic search.".into(),
+    )
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn stored(
+    db: &Database,
+    video_id: &str,
+    lang: &str,
+    model: &str,
+) -> Result<Vec<EmbeddedPassage>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT text, start, end, vector FROM transcript_embeddings
+             WHERE video_id = ?1 AND lang = ?2 AND model = ?3
+             ORDER BY passage",
+        )?;
+        let rows = stmt.query_map(params![video_id, lang, model], |row| {
+            Ok(EmbeddedPassage {
+                passage: TranscriptParagraph {
+                    text: row.get(0)?,
+                    start: row.get(1)?,
+                    end: row.get(2)?,
+                },
+                vector: decode_vector(&row.get::<_, Vec<u8>>(3)?),
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Returns the embedded passages of a transcript, embedding and storing them first
+/// when this model has not seen the transcript yet.
+pub(crate) async fn ensure(
+    db: &Database,
+    provider: &dyn Provider,
+    video_id: &str,
+    segments: &[TranscriptSegment],
+) -> Result<Vec<EmbeddedPassage>, TranscriptError> {
+    let model = provider.embedding_model().ok_or_else(no_embeddings)?;
+    let lang = segments.first().map_or("", |s| s.lang.as_str());
+
+    let existing = stored(db, video_id, lang, model)?;
+    if !existing.is_empty() {
+        return Ok(existing);
+    }
+
+    let passages = segmenter::paragraphs(segments);
+    let mut vectors = Vec::with_capacity(passages.len());
+    for batch in passages.chunks(BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|p| p.text.clone()).collect();
+        let embedded = provider.embed(&texts).await?;
+        if embedded.len() != texts.len() {
+            return Err(TranscriptError::LlmError(
+                "The embeddings response did not match the request.".into(),
+            ));
+        }
+        vectors.extend(embedded);
+    }
+
+    let embedded: Vec<EmbeddedPassage> = passages
+        .into_iter()
+        .zip(vectors)
+        .map(|(passage, vector)| EmbeddedPassage { passage, vector })
+        .collect();
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO transcript_embeddings
+                 (video_id, lang, model, passage, start, end, text, vector, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            let now = unix_now();
+            for (i, e) in embedded.iter().enumerate() {
+                stmt.execute(params![
+                    video_id,
+                    lang,
+                    model,
+                    i as i64,
+                    e.passage.start,
+                    e.passage.end,
+                    e.passage.text,
+                    encode_vector(&e.vector),
+                    now
+                ])?;
+            }
+        }
+        tx.commit()
+    })?;
+    Ok(embedded)
+}
+
+/// Drops matches below `min_score` and groups the rest per video, best video first.
+fn rank(
+    rows: Vec<(String, String, SemanticMatch)>,
+    limit: usize,
+    min_score: f32,
+) -> Vec<SemanticResult> {
+    let mut rows: Vec<_> = rows
+        .into_iter()
+        .filter(|r| r.2.score >= min_score)
+        .collect();
+    rows.sort_by(|a, b| b.2.score.total_cmp(&a.2.score));
+
+    let mut results: Vec<SemanticResult> = Vec::new();
+    for (video_id, lang, hit) in rows {
+        match results
+            .iter()
+            .position(|r| r.video_id == video_id && r.lang == lang)
+        {
+            Some(i) if results[i].matches.len() < MATCHES_PER_VIDEO => results[i].matches.push(hit),
+            Some(_) => {}
+            None if results.len() < limit => results.push(SemanticResult {
+                video_id,
+                lang,
+                score: hit.score,
+                matches: vec![hit],
+            }),
+            None => {}
+        }
+    }
+    results
+}
+
+/// Embeds the transcript of `video_id` so it shows up in semantic search.
+pub async fn embed_transcript(
+    db: &Database,
+    video_id: String,
+    provider: ProviderKind,
+) -> Result<EmbeddingStatus, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let llm = llm::provider(db, provider, None, "embeddings")?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    let passages = ensure(db, llm.as_ref(), &video_id, &segments).await?;
+
+    Ok(EmbeddingStatus {
+        lang: segments.first().map(|s| s.lang.clone()).unwrap_or_default(),
+        video_id,
+        model: llm.embedding_model().unwrap_or_default().to_string(),
+        passages: passages.len(),
+    })
+}
+
+/// Finds the embedded videos that discuss `query`, by meaning rather than wording.
+pub async fn semantic_search(
+    db: &Database,
+    query: String,
+    provider: ProviderKind,
+    options: Option<SemanticSearchOptions>,
+) -> Result<Vec<SemanticResult>, TranscriptError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "Enter something to search for.".into(),
+        ));
+    }
+    let options = options.unwrap_or_default();
+    let llm = llm::provider(db, provider, None, "search")?;
+    let model = llm.embedding_model().ok_or_else(no_embeddings)?.to_string();
+    let query_vector = llm
+        .embed(std::slice::from_ref(&query))
+        .await?
+        .pop()
+        .unwrap_or_default();
+
+    let rows = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT video_id, lang, text, start, end, vector FROM transcript_embeddings
+             WHERE model = ?1",
+        )?;
+        let rows = stmt.query_map(params![model], |row| {
+            let vector = decode_vector(&row.get::<_, Vec<u8>>(5)?);
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                SemanticMatch {
+                    text: row.get(2)?,
+                    start: row.get(3)?,
+                    end: row.get(4)?,
+                    score: cosine_similarity(&vector, &query_vector),
+                },
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    Ok(rank(
+        rows,
+        options.limit.unwrap_or(DEFAULT_LIMIT).max(1),
+        options.min_score.unwrap_or(0.0),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(video_id: &str, score: f32) -> (String, String, SemanticMatch) {
+        (
+            video_id.into(),
+            "en".into(),
+            SemanticMatch {
+                text: String::new(),
+                start: 0.0,
+                end: 1.0,
+                score,
+            },
+        )
+    }
+
+    #[test]
+    fn round_trips_vectors_and_measures_similarity() {
+        let vector = [0.5, -1.25, 3.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn groups_matches_per_video_best_first() {
+        let rows = vec![hit("a", 0.2), hit("b", 0.9), hit("a", 0.7), hit("c", 0.05)];
+
+        let results = rank(rows, 10, 0.1);
+        let order: Vec<(&str, usize)> = results
+            .iter()
+            .map(|r| (r.video_id.as_str(), r.matches.len()))
+            .collect();
+        assert_eq!(order, [("b", 1), ("a", 2)]);
+        assert_eq!(results[1].score, 0.7);
+    }
+}
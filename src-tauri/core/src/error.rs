@@ -0,0 +1,114 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// Error returned by backend commands.
+///
+/// Serializes to `{ "kind": "rateLimited", "message": "Too many requests…" }` so the
+/// frontend can branch on `kind` and still show `message` to the user.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TranscriptError {
+    #[error("{0}")]
+    InvalidVideoId(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    VideoUnavailable(String),
+    #[error("YouTube is requesting a CAPTCHA. Please try again later.")]
+    CaptchaRequired,
+    #[error("Transcripts are disabled for this video.")]
+    TranscriptsDisabled,
+    #[error("No transcript available for this video.")]
+    NoTranscript,
+    #[error("Transcript was empty. The video may not have captions available.")]
+    EmptyTranscript,
+    #[error("YouTube cannot translate this transcript into \"{0}\".")]
+    TranslationUnavailable(String),
+    #[error("Too many requests. Please try again later.")]
+    RateLimited,
+    #[error("This video is not available in your country.")]
+    RegionBlocked,
+    #[error("This video is private.")]
+    PrivateVideo,
+    #[error("This video is age-restricted. Import the cookies of a signed-in browser to confirm your age.")]
+    AgeRestricted,
+    #[error("This video is only available to members of the channel.")]
+    MembersOnly,
+    #[error("{0}")]
+    LoginRequired(String),
+    #[error(
+        "This video is live right now. Its transcript is available once the stream has ended."
+    )]
+    LiveNow,
+    #[error("{0}")]
+    Upcoming(String),
+    #[error("This live stream has ended, but YouTube is still processing its captions. Please try again later.")]
+    LiveCaptionsPending,
+    #[error("Comments are turned off for this video.")]
+    CommentsDisabled,
+    #[error("{0}")]
+    NetworkError(String),
+    #[error("{0}")]
+    ParseError(String),
+    #[error("Local database error: {0}")]
+    DatabaseError(String),
+    #[error("{0}")]
+    FileError(String),
+    #[cfg_attr(not(feature = "whisper"), allow(dead_code))]
+    #[error("{0}")]
+    TranscriptionError(String),
+    #[error("{0}")]
+    LlmError(String),
+    #[error("{0}")]
+    BudgetExceeded(String),
+    #[error("The operation was cancelled.")]
+    Cancelled,
+}
+
+impl TranscriptError {
+    /// Stable identifier of the variant, used as the `kind` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidVideoId(_) => "invalidVideoId",
+            Self::InvalidInput(_) => "invalidInput",
+            Self::VideoUnavailable(_) => "videoUnavailable",
+            Self::CaptchaRequired => "captchaRequired",
+            Self::TranscriptsDisabled => "transcriptsDisabled",
+            Self::NoTranscript => "noTranscript",
+            Self::EmptyTranscript => "emptyTranscript",
+            Self::TranslationUnavailable(_) => "translationUnavailable",
+            Self::RateLimited => "rateLimited",
+            Self::RegionBlocked => "regionBlocked",
+            Self::PrivateVideo => "privateVideo",
+            Self::AgeRestricted => "ageRestricted",
+            Self::MembersOnly => "membersOnly",
+            Self::LoginRequired(_) => "loginRequired",
+            Self::LiveNow => "liveNow",
+            Self::Upcoming(_) => "upcoming",
+            Self::LiveCaptionsPending => "liveCaptionsPending",
+            Self::CommentsDisabled => "commentsDisabled",
+            Self::NetworkError(_) => "networkError",
+            Self::ParseError(_) => "parseError",
+            Self::DatabaseError(_) => "databaseError",
+            Self::FileError(_) => "fileError",
+            Self::TranscriptionError(_) => "transcriptionError",
+            Self::LlmError(_) => "llmError",
+            Self::BudgetExceeded(_) => "budgetExceeded",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl From<rusqlite::Error> for TranscriptError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::DatabaseError(e.to_string())
+    }
+}
+
+impl Serialize for TranscriptError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TranscriptError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,236 @@
+//! Writing transcripts to files in subtitle and document formats.
+
+mod csv;
+mod json;
+mod markdown;
+mod srt;
+mod vtt;
+
+use crate::activity::{self, ActivityKind};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::notes::Note;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// SubRip subtitles (`.srt`).
+    Srt,
+    /// WebVTT subtitles (`.vtt`).
+    Vtt,
+    /// Markdown paragraphs with timestamp links (`.md`).
+    #[default]
+    Markdown,
+    /// Pretty-printed JSON array (`.json`).
+    Json,
+    /// One JSON object per line (`.ndjson`).
+    Ndjson,
+    /// Comma-separated values with a header row (`.csv`).
+    Csv,
+}
+
+/// A segment field written by the JSON and CSV exporters.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Offset,
+    Duration,
+    Text,
+    Lang,
+    /// The probable speaker; empty unless speakers are detected.
+    Speaker,
+}
+
+impl Column {
+    const ALL: [Column; 5] = [
+        Self::Offset,
+        Self::Duration,
+        Self::Text,
+        Self::Lang,
+        Self::Speaker,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Offset => "offset",
+            Self::Duration => "duration",
+            Self::Text => "text",
+            Self::Lang => "lang",
+            Self::Speaker => "speaker",
+        }
+    }
+
+    fn value(self, segment: &TranscriptSegment) -> Value {
+        match self {
+            Self::Offset => segment.offset.into(),
+            Self::Duration => segment.duration.into(),
+            Self::Text => segment.text.clone().into(),
+            Self::Lang => segment.lang.clone().into(),
+            Self::Speaker => segment.speaker.into(),
+        }
+    }
+}
+
+/// Format-specific tweaks; formats ignore the options that do not apply to them.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    /// Number WebVTT cues, which lets players and scripts refer to individual cues.
+    pub cue_ids: bool,
+    /// CSS written to a WebVTT `STYLE` block as is.
+    pub style: Option<String>,
+    /// Fields and their order for JSON and CSV; when omitted or empty, all of them,
+    /// `speaker` only if the segments have speakers.
+    pub columns: Option<Vec<Column>>,
+    /// Interleave the video's notes with the Markdown paragraphs.
+    pub notes: bool,
+}
+
+impl ExportOptions {
+    fn columns(&self, segments: &[TranscriptSegment]) -> Vec<Column> {
+        match self.columns.as_deref() {
+            Some(columns) if !columns.is_empty() => columns.to_vec(),
+            _ => {
+                let has_speakers = segments.iter().any(|s| s.speaker.is_some());
+                Column::ALL
+                    .into_iter()
+                    .filter(|c| *c != Column::Speaker || has_speakers)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Splits `seconds` into hours, minutes, seconds and milliseconds, rounding to the
+/// nearest millisecond.
+fn split_timestamp(seconds: f64) -> (u64, u64, u64, u64) {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    (
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        total_ms % 1000,
+    )
+}
+
+/// `m:ss`, or `h:mm:ss` from one hour on, for timestamps shown to readers.
+pub fn short_timestamp(seconds: f64) -> String {
+    let (h, m, s, _) = split_timestamp(seconds.floor());
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// Renders the transcript of `video_id` in `format`. Only Markdown shows `notes`.
+pub fn render(
+    format: ExportFormat,
+    video_id: &str,
+    segments: &[TranscriptSegment],
+    notes: &[Note],
+    options: &ExportOptions,
+) -> String {
+    match format {
+        ExportFormat::Srt => srt::render(segments),
+        ExportFormat::Vtt => vtt::render(segments, options),
+        ExportFormat::Markdown => markdown::render(video_id, segments, notes),
+        ExportFormat::Json => json::render(segments, &options.columns(segments)),
+        ExportFormat::Ndjson => json::render_lines(segments, &options.columns(segments)),
+        ExportFormat::Csv => csv::render(segments, &options.columns(segments)),
+    }
+}
+
+/// How a transcript is laid out on the clipboard.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipboardStyle {
+    /// The text only, joined into a single paragraph.
+    #[default]
+    Plain,
+    /// One `[m:ss] text` line per segment.
+    Timestamped,
+    /// Same as the Markdown export.
+    Markdown,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardOptions {
+    /// The style from the export settings when omitted.
+    pub style: Option<ClipboardStyle>,
+}
+
+fn clipboard_text(video_id: &str, segments: &[TranscriptSegment], style: ClipboardStyle) -> String {
+    let texts = segments
+        .iter()
+        .map(|s| (s, s.text.trim()))
+        .filter(|(_, text)| !text.is_empty());
+    match style {
+        ClipboardStyle::Plain => texts.map(|(_, text)| text).collect::<Vec<_>>().join(" "),
+        ClipboardStyle::Timestamped => texts
+            .map(|(s, text)| format!("[{}] {}", short_timestamp(s.offset), text))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ClipboardStyle::Markdown => markdown::render(video_id, segments, &[]),
+    }
+}
+
+/// Exports the transcript of `video_id` to `path`, using the cached copy when there is one.
+pub async fn export_transcript(
+    db: &Database,
+    video_id: String,
+    format: ExportFormat,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<(), TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let result = async {
+        let segments = load_transcript(db, &video_id, None).await?;
+        let options = options.unwrap_or_default();
+        let notes = if options.notes {
+            crate::notes::for_video(db, &video_id)?
+        } else {
+            Vec::new()
+        };
+
+        std::fs::write(
+            &path,
+            render(format, &video_id, &segments, &notes, &options),
+        )
+        .map_err(|e| TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e)))
+    }
+    .await;
+    activity::finished(
+        db,
+        ActivityKind::ExportCompleted,
+        Some(&video_id),
+        "Exporting a transcript",
+        &result,
+        |_| {
+            (
+                format!("Exported the transcript to {}", path),
+                json!({ "destination": "file", "format": format, "path": path }),
+            )
+        },
+    );
+    result
+}
+
+/// The transcript of `video_id` as text for the clipboard.
+pub async fn clipboard_transcript(
+    db: &Database,
+    video_id: String,
+    options: Option<ClipboardOptions>,
+) -> Result<String, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    let style = options
+        .unwrap_or_default()
+        .style
+        .unwrap_or_else(|| crate::settings::current().export.clipboard_style);
+    Ok(clipboard_text(&video_id, &segments, style))
+}
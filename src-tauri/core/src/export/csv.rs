@@ -0,0 +1,65 @@
+//! CSV writer (RFC 4180).
+
+use super::Column;
+use crate::transcript::TranscriptSegment;
+use serde_json::Value;
+
+/// Quotes a field when it contains a delimiter, quote or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn cell(value: Value) -> String {
+    match value {
+        Value::String(s) => field(&s),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// A header row with the column names, then one row per segment.
+pub(super) fn render(segments: &[TranscriptSegment], columns: &[Column]) -> String {
+    let mut lines = vec![columns
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>()
+        .join(",")];
+    lines.extend(segments.iter().map(|segment| {
+        columns
+            .iter()
+            .map(|c| cell(c.value(segment)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }));
+
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_fields_and_respects_column_order() {
+        let segments = [TranscriptSegment {
+            text: "He said \"hi\", then left".into(),
+            duration: 2.5,
+            offset: 1.0,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }];
+
+        assert_eq!(
+            render(&segments, &[Column::Text, Column::Offset]),
+            "text,offset\r\n\"He said \"\"hi\"\", then left\",1.0\r\n"
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! JSON array and newline-delimited JSON writers.
+
+use super::Column;
+use crate::transcript::TranscriptSegment;
+use serde_json::{Map, Value};
+
+fn row(segment: &TranscriptSegment, columns: &[Column]) -> Value {
+    let fields: Map<String, Value> = columns
+        .iter()
+        .map(|c| (c.name().to_string(), c.value(segment)))
+        .collect();
+    Value::Object(fields)
+}
+
+/// A pretty-printed array with one object per segment.
+pub(super) fn render(segments: &[TranscriptSegment], columns: &[Column]) -> String {
+    let rows: Vec<Value> = segments.iter().map(|s| row(s, columns)).collect();
+    let mut out = serde_json::to_string_pretty(&rows).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// One compact object per line, as read by `pandas.read_json(lines=True)`.
+pub(super) fn render_lines(segments: &[TranscriptSegment], columns: &[Column]) -> String {
+    segments
+        .iter()
+        .map(|s| row(s, columns).to_string() + "\n")
+        .collect()
+}
@@ -0,0 +1,126 @@
+//! Markdown writer with a timestamp link per paragraph.
+
+use super::short_timestamp;
+use crate::notes::Note;
+use crate::transcript::segmenter::paragraphs;
+use crate::transcript::TranscriptSegment;
+use std::fmt::Write;
+
+fn link(video_id: &str, seconds: f64) -> String {
+    format!(
+        "[{}](https://youtu.be/{}?t={})",
+        short_timestamp(seconds),
+        video_id,
+        seconds.floor() as u64
+    )
+}
+
+/// Writes `notes` as quotes, each with a link to its own moment.
+fn write_notes<'a>(out: &mut String, video_id: &str, notes: impl Iterator<Item = &'a Note>) {
+    for note in notes {
+        let text = note.text.trim().replace('\n', "\n> ");
+        let _ = write!(
+            out,
+            "> **Note** {} {}\n\n",
+            link(video_id, note.offset),
+            text
+        );
+    }
+}
+
+/// Renders the transcript as paragraphs, each starting with a `[m:ss](https://youtu.be/ID?t=N)`
+/// link to where it begins in the video. `notes`, sorted by offset, follow the
+/// paragraph they fall in.
+pub(super) fn render(video_id: &str, segments: &[TranscriptSegment], notes: &[Note]) -> String {
+    let mut out = format!(
+        "# Transcript\n\nSource: <https://youtu.be/{}>\n\n",
+        video_id
+    );
+    let paragraphs = paragraphs(segments);
+    let mut notes = notes.iter().peekable();
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{} {}\n\n",
+            link(video_id, paragraph.start),
+            paragraph.text
+        );
+        let next_start = paragraphs.get(i + 1).map_or(f64::INFINITY, |p| p.start);
+        write_notes(
+            &mut out,
+            video_id,
+            std::iter::from_fn(|| notes.next_if(|n| n.offset < next_start)),
+        );
+    }
+    // Only notes on a video without paragraphs are left
+    write_notes(&mut out, video_id, notes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(short_timestamp(65.9), "1:05");
+        assert_eq!(short_timestamp(3725.0), "1:02:05");
+    }
+
+    #[test]
+    fn splits_paragraphs_at_pauses_and_links_each_one() {
+        let segments = [
+            segment("Welcome back.", 0.0, 2.0),
+            segment("Today we cook.", 2.0, 2.0),
+            segment("First, the onions.", 75.5, 3.0),
+        ];
+
+        assert_eq!(
+            render("dQw4w9WgXcQ", &segments, &[]),
+            "# Transcript\n\nSource: <https://youtu.be/dQw4w9WgXcQ>\n\n\
+             [0:00](https://youtu.be/dQw4w9WgXcQ?t=0) Welcome back. Today we cook.\n\n\
+             [1:15](https://youtu.be/dQw4w9WgXcQ?t=75) First, the onions.\n\n"
+        );
+    }
+
+    #[test]
+    fn places_notes_after_the_paragraph_they_fall_in() {
+        let segments = [
+            segment("Welcome back.", 0.0, 2.0),
+            segment("First, the onions.", 75.5, 3.0),
+        ];
+        let note = |offset: f64, text: &str| Note {
+            id: 0,
+            video_id: "dQw4w9WgXcQ".into(),
+            offset,
+            text: text.into(),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let notes = [
+            note(1.0, "Nice intro."),
+            note(90.0, "Use red ones.\nOr shallots."),
+        ];
+
+        assert_eq!(
+            render("dQw4w9WgXcQ", &segments, &notes),
+            "# Transcript\n\nSource: <https://youtu.be/dQw4w9WgXcQ>\n\n\
+             [0:00](https://youtu.be/dQw4w9WgXcQ?t=0) Welcome back.\n\n\
+             > **Note** [0:01](https://youtu.be/dQw4w9WgXcQ?t=1) Nice intro.\n\n\
+             [1:15](https://youtu.be/dQw4w9WgXcQ?t=75) First, the onions.\n\n\
+             > **Note** [1:30](https://youtu.be/dQw4w9WgXcQ?t=90) Use red ones.\n> Or shallots.\n\n"
+        );
+    }
+}
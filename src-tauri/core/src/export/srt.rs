@@ -0,0 +1,70 @@
+//! SubRip (`.srt`) writer.
+
+use super::split_timestamp;
+use crate::transcript::TranscriptSegment;
+use std::fmt::Write;
+
+/// `HH:MM:SS,mmm`
+fn timestamp(seconds: f64) -> String {
+    let (h, m, s, ms) = split_timestamp(seconds);
+    format!("{:02}:{:02}:{:02},{:03}", h, m, s, ms)
+}
+
+/// Renders one numbered cue per non-empty segment. Cues are numbered from 1 and
+/// end at `offset + duration`.
+pub(super) fn render(segments: &[TranscriptSegment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .enumerate()
+    {
+        let _ = write!(
+            out,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(segment.offset),
+            timestamp(segment.offset + segment.duration),
+            segment.text.trim()
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(0.0), "00:00:00,000");
+        assert_eq!(timestamp(3723.4567), "01:02:03,457");
+    }
+
+    #[test]
+    fn renders_numbered_cues_and_skips_empty_segments() {
+        let segments = [
+            segment("Hello there", 1.5, 2.0),
+            segment("  ", 3.5, 1.0),
+            segment("General Kenobi", 61.0, 2.25),
+        ];
+
+        assert_eq!(
+            render(&segments),
+            "1\n00:00:01,500 --> 00:00:03,500\nHello there\n\n\
+             2\n00:01:01,000 --> 00:01:03,250\nGeneral Kenobi\n\n"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! WebVTT (`.vtt`) writer.
+
+use super::{split_timestamp, ExportOptions};
+use crate::transcript::TranscriptSegment;
+use std::fmt::Write;
+
+/// `HH:MM:SS.mmm`
+fn timestamp(seconds: f64) -> String {
+    let (h, m, s, ms) = split_timestamp(seconds);
+    format!("{:02}:{:02}:{:02}.{:03}", h, m, s, ms)
+}
+
+/// Escapes cue text; `&`, `<` and `>` would otherwise be read as markup, and `-->`
+/// would end the cue early.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders a `WEBVTT` document. `options.style` is copied into a `STYLE` block
+/// verbatim, so `::cue` rules reach the player unchanged. Segments with a speaker
+/// are wrapped in a voice span, `<v Speaker 1>`, counting speakers from 1.
+pub(super) fn render(segments: &[TranscriptSegment], options: &ExportOptions) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+
+    if let Some(style) = options
+        .style
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        // A blank line would end the block, so it is dropped from the CSS
+        let css: Vec<&str> = style.lines().filter(|l| !l.trim().is_empty()).collect();
+        let _ = write!(out, "STYLE\n{}\n\n", css.join("\n"));
+    }
+
+    for (i, segment) in segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .enumerate()
+    {
+        if options.cue_ids {
+            let _ = writeln!(out, "{}", i + 1);
+        }
+        let mut text = escape(segment.text.trim());
+        if let Some(speaker) = segment.speaker {
+            text = format!("<v Speaker {}>{}", speaker + 1, text);
+        }
+        let _ = write!(
+            out,
+            "{} --> {}\n{}\n\n",
+            timestamp(segment.offset),
+            timestamp(segment.offset + segment.duration),
+            text
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_header_and_escaped_cues() {
+        let segments = [segment("Tom & Jerry <3", 0.0, 1.5)];
+
+        assert_eq!(
+            render(&segments, &ExportOptions::default()),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nTom &amp; Jerry &lt;3\n\n"
+        );
+    }
+
+    #[test]
+    fn tags_cues_with_their_speaker() {
+        let mut segments = [segment("Hello", 0.0, 1.0)];
+        segments[0].speaker = Some(1);
+
+        assert_eq!(
+            render(&segments, &ExportOptions::default()),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.000\n<v Speaker 2>Hello\n\n"
+        );
+    }
+
+    #[test]
+    fn adds_cue_ids_and_style_block() {
+        let options = ExportOptions {
+            cue_ids: true,
+            style: Some("::cue {\n\n  color: yellow;\n}".into()),
+            ..Default::default()
+        };
+        let segments = [segment("Hi", 2.0, 1.0)];
+
+        assert_eq!(
+            render(&segments, &options),
+            "WEBVTT\n\nSTYLE\n::cue {\n  color: yellow;\n}\n\n1\n00:00:02.000 --> 00:00:03.000\nHi\n\n"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Study flashcards from a transcript, and their export for Anki.
+//!
+//! The model reads the transcript in the same timestamped chunks as the summarizer
+//! and writes question and answer pairs, each citing the moment its answer is
+//! given. Long transcripts have the cards shared out between their chunks by
+//! length. The export is a tab-separated file with Anki's header lines, which
+//! File > Import reads without any settings to pick.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{self, CompletionRequest, Provider, ProviderKind};
+use crate::operations::Operations;
+use crate::summarize::{chunks, parse_timestamp};
+use crate::transcript::load_transcript;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const DEFAULT_COUNT: usize = 20;
+const MAX_COUNT: usize = 100;
+/// Chunks worked on at the same time.
+const CONCURRENCY: usize = 3;
+const MAX_TOKENS: u32 = 4_000;
+
+const SYSTEM_PROMPT: &str = "You write study flashcards from the transcripts of YouTube \
+lectures. Every transcript line starts with its [m:ss] timestamp. Reply with a JSON object \
+only, shaped as {\"cards\": [{\"question\": string, \"answer\": string, \"timestamp\": \
+\"m:ss\"}]}. Each card tests one fact, definition or idea the video teaches. The question \
+can be answered without having the video at hand, and the answer is one or two short \
+sentences. The timestamp is that of the line where the answer is given. Skip small talk, \
+sponsors and housekeeping.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FlashcardOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Language to write the cards in; the transcript's language by default.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Flashcard {
+    pub question: String,
+    pub answer: String,
+    /// Seconds into the video.
+    pub timestamp: f64,
+}
+
+/// The JSON requested from the model.
+#[derive(Debug, Deserialize)]
+struct RawCards {
+    #[serde(default)]
+    cards: Vec<RawCard>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawCard {
+    question: String,
+    answer: String,
+    #[serde(default)]
+    timestamp: serde_json::Value,
+}
+
+/// `count` shared out in proportion to `lengths`, by largest remainder.
+fn shares(lengths: &[usize], count: usize) -> Vec<usize> {
+    let total: usize = lengths.iter().sum();
+    if total == 0 {
+        return vec![0; lengths.len()];
+    }
+    let exact: Vec<f64> = lengths
+        .iter()
+        .map(|&len| len as f64 * count as f64 / total as f64)
+        .collect();
+    let mut out: Vec<usize> = exact.iter().map(|x| x.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..lengths.len()).collect();
+    by_remainder.sort_by(|&a, &b| {
+        (exact[b] - exact[b].floor())
+            .total_cmp(&(exact[a] - exact[a].floor()))
+            .then(a.cmp(&b))
+    });
+    let missing = count - out.iter().sum::<usize>();
+    for &i in by_remainder.iter().take(missing) {
+        out[i] += 1;
+    }
+    out
+}
+
+async fn complete(
+    provider: &dyn Provider,
+    prompt: String,
+) -> Result<Vec<Flashcard>, TranscriptError> {
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt,
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    let raw: RawCards = llm::parse_json(&provider.complete(&request, None).await?.text)?;
+    Ok(raw
+        .cards
+        .into_iter()
+        .filter(|c| !c.question.trim().is_empty() && !c.answer.trim().is_empty())
+        .map(|c| Flashcard {
+            question: c.question.trim().to_string(),
+            answer: c.answer.trim().to_string(),
+            timestamp: parse_timestamp(&c.timestamp).unwrap_or(0.0).max(0.0),
+        })
+        .collect())
+}
+
+/// Escapes a field of an HTML-enabled import, where tabs and line breaks would
+/// split it.
+fn field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\t', " ")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Renders `cards` as an Anki import file: front, back with a link to the moment
+/// in the video, and a tag per video.
+fn render_anki(video_id: &str, cards: &[Flashcard]) -> String {
+    let mut out = String::from("#separator:tab\n#html:true\n#tags column:3\n");
+    for card in cards {
+        let _ = writeln!(
+            out,
+            "{}\t{}<br><a href=\"https://youtu.be/{}?t={}\">{}</a>\tyoutube::{}",
+            field(&card.question),
+            field(&card.answer),
+            video_id,
+            card.timestamp.floor() as u64,
+            short_timestamp(card.timestamp),
+            video_id
+        );
+    }
+    out
+}
+
+/// Writes about `count` flashcards on the transcript of `video_id` with
+/// `provider` (20 unless given, at most 100), in the order of the video. Can be
+/// stopped with `cancel_operation(operation_id)`.
+pub async fn generate_flashcards(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    provider: ProviderKind,
+    count: Option<usize>,
+    options: Option<FlashcardOptions>,
+    operation_id: Option<String>,
+) -> Result<Vec<Flashcard>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    let count = count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+    let llm = llm::provider(db, provider, options.model.clone(), "flashcards")?;
+    let language = match options.language.as_deref().map(str::trim) {
+        Some(lang) if !lang.is_empty() => format!(" Write them in {}.", lang),
+        _ => " Write them in the language of the transcript.".into(),
+    };
+
+    let segments = load_transcript(db, &video_id, None).await?;
+    let chunks = chunks(&segments);
+    let lengths: Vec<usize> = chunks.iter().map(String::len).collect();
+    // Prompts are built up front: a lazily mapped stream of borrowing futures
+    // trips up the `Send` check of the command's future
+    let requests: Vec<_> = chunks
+        .iter()
+        .zip(shares(&lengths, count))
+        .filter(|(_, share)| *share > 0)
+        .map(|(chunk, share)| {
+            let prompt = format!(
+                "Write {} flashcards.{}\n\nTranscript:\n{}",
+                share, language, chunk
+            );
+            complete(llm.as_ref(), prompt)
+        })
+        .collect();
+    let operation = operations.start(operation_id.as_deref());
+    let batches: Vec<Vec<Flashcard>> = operation
+        .run(stream::iter(requests).buffered(CONCURRENCY).try_collect())
+        .await?;
+
+    let mut cards: Vec<Flashcard> = Vec::with_capacity(count);
+    for card in batches.into_iter().flatten() {
+        let question = card.question.to_lowercase();
+        if !cards.iter().any(|c| c.question.to_lowercase() == question) {
+            cards.push(card);
+        }
+    }
+    cards.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    cards.truncate(count);
+    Ok(cards)
+}
+
+/// Writes `cards` of `video_id` to `path` as a tab-separated file for Anki's
+/// import.
+pub fn export_flashcards(
+    video_id: String,
+    cards: Vec<Flashcard>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    std::fs::write(&path, render_anki(&video_id, &cards))
+        .map_err(|e| TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_cards_out_by_chunk_length() {
+        assert_eq!(shares(&[100, 100, 50], 5), [2, 2, 1]);
+        assert_eq!(shares(&[300, 100], 3), [2, 1]);
+        assert_eq!(shares(&[10, 10, 10], 1), [1, 0, 0]);
+        assert_eq!(shares(&[], 4), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn renders_anki_import_with_escaped_fields() {
+        let cards = [Flashcard {
+            question: "What does <T> mean?".into(),
+            answer: "A generic\ttype\nparameter.".into(),
+            timestamp: 75.4,
+        }];
+        assert_eq!(
+            render_anki("dQw4w9WgXcQ", &cards),
+            "#separator:tab\n#html:true\n#tags column:3\n\
+             What does &lt;T&gt; mean?\tA generic type<br>parameter.\
+             <br><a href=\"https://youtu.be/dQw4w9WgXcQ?t=75\">1:15</a>\tyoutube::dQw4w9WgXcQ\n"
+        );
+    }
+}
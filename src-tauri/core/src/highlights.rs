@@ -0,0 +1,254 @@
+//! Transcript passages marked as highlights, and "highlights only" documents.
+//!
+//! A highlight keeps the text of the segments it covers as they read when it was
+//! made, so exporting highlights needs neither the cache nor the network.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+use std::fmt::Write;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS highlights (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    video_id   TEXT    NOT NULL,
+    start      REAL    NOT NULL,
+    end        REAL    NOT NULL,
+    text       TEXT    NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS highlights_video ON highlights (video_id, start);
+";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub id: i64,
+    pub video_id: String,
+    /// Seconds into the video.
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub created_at: i64,
+}
+
+const HIGHLIGHT_COLUMNS: &str = "id, video_id, start, end, text, created_at";
+
+fn highlight_from_row(row: &Row) -> rusqlite::Result<Highlight> {
+    Ok(Highlight {
+        id: row.get(0)?,
+        video_id: row.get(1)?,
+        start: row.get(2)?,
+        end: row.get(3)?,
+        text: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// The text of the segments starting within `start..end`, joined with spaces.
+fn covered_text(segments: &[TranscriptSegment], start: f64, end: f64) -> String {
+    segments
+        .iter()
+        .filter(|s| s.offset >= start && s.offset < end)
+        .map(|s| s.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Highlights of `video_id`, in the order they appear in the video.
+pub(crate) fn for_video(db: &Database, video_id: &str) -> Result<Vec<Highlight>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM highlights WHERE video_id = ?1 ORDER BY start, id",
+            HIGHLIGHT_COLUMNS
+        ))?;
+        let rows = stmt.query_map([video_id], highlight_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Title of `video_id` from the history, if it is there.
+pub(crate) fn title_of(db: &Database, video_id: &str) -> Result<Option<String>, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT title FROM history WHERE video_id = ?1",
+            [video_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Renders the highlights of each video as a Markdown list under the video's title,
+/// each linking to its moment. Videos without highlights are left out.
+fn render(sections: &[(String, Option<String>, Vec<Highlight>)]) -> String {
+    let mut out = String::from("# Highlights\n\n");
+    for (video_id, title, highlights) in sections {
+        if highlights.is_empty() {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "## {}\n\nSource: <https://youtu.be/{}>\n\n",
+            title.as_deref().unwrap_or(video_id),
+            video_id
+        );
+        for highlight in highlights {
+            let _ = writeln!(
+                out,
+                "- [{}](https://youtu.be/{}?t={}) {}",
+                short_timestamp(highlight.start),
+                video_id,
+                highlight.start.floor() as u64,
+                highlight.text
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Marks the segments starting within `start..end` seconds as a highlight. Marking
+/// one segment means passing its offset and its end.
+pub async fn add_highlight(
+    db: &Database,
+    video_id: String,
+    start: f64,
+    end: f64,
+) -> Result<Highlight, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    if !(start.is_finite() && end.is_finite() && 0.0 <= start && start < end) {
+        return Err(TranscriptError::InvalidInput(
+            "A highlight must end after it starts.".into(),
+        ));
+    }
+    let segments = load_transcript(db, &video_id, None).await?;
+    let text = covered_text(&segments, start, end);
+    if text.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "No transcript segment starts in this range.".into(),
+        ));
+    }
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "INSERT INTO highlights (video_id, start, end, text, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 RETURNING {}",
+                HIGHLIGHT_COLUMNS
+            ),
+            params![video_id, start, end, text, unix_now()],
+            highlight_from_row,
+        )
+    })
+}
+
+/// Removes a highlight. Returns whether there was one to remove.
+pub fn remove_highlight(db: &Database, id: i64) -> Result<bool, TranscriptError> {
+    db.with_conn(|conn| conn.execute("DELETE FROM highlights WHERE id = ?1", [id]))
+        .map(|n| n > 0)
+}
+
+/// Lists the highlights of a video in the order they appear in it.
+pub fn list_highlights(db: &Database, video_id: String) -> Result<Vec<Highlight>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    for_video(db, &video_id)
+}
+
+/// Writes a Markdown document of only the highlights to `path`, either of one
+/// video or of every video of a collection, in collection order.
+pub fn export_highlights(
+    db: &Database,
+    video_id: Option<String>,
+    collection_id: Option<i64>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    let video_ids = match (video_id, collection_id) {
+        (Some(video_id), None) => vec![crate::video_id::parse(&video_id)?],
+        (None, Some(collection_id)) => crate::collections::video_ids(db, collection_id)?,
+        _ => {
+            return Err(TranscriptError::InvalidInput(
+                "Export the highlights of either a video or a collection.".into(),
+            ))
+        }
+    };
+    let sections = video_ids
+        .into_iter()
+        .map(|video_id| {
+            let title = title_of(db, &video_id)?;
+            let highlights = for_video(db, &video_id)?;
+            Ok((video_id, title, highlights))
+        })
+        .collect::<Result<Vec<_>, TranscriptError>>()?;
+
+    std::fs::write(&path, render(&sections))
+        .map_err(|e| TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 2.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_text_of_segments_starting_in_range() {
+        let segments = [
+            segment("Before.", 0.0),
+            segment("First,", 2.0),
+            segment(" the onions.", 4.0),
+            segment("After.", 6.0),
+        ];
+        assert_eq!(covered_text(&segments, 2.0, 6.0), "First, the onions.");
+        assert_eq!(covered_text(&segments, 10.0, 12.0), "");
+    }
+
+    #[test]
+    fn renders_a_section_per_video_with_highlights() {
+        let highlight = |video_id: &str, start: f64, text: &str| Highlight {
+            id: 0,
+            video_id: video_id.into(),
+            start,
+            end: start + 2.0,
+            text: text.into(),
+            created_at: 0,
+        };
+        let sections = [
+            (
+                "dQw4w9WgXcQ".to_string(),
+                Some("Cooking 101".to_string()),
+                vec![highlight("dQw4w9WgXcQ", 75.5, "First, the onions.")],
+            ),
+            ("jNQXAC9IVRw".to_string(), None, Vec::new()),
+            (
+                "9bZkp7q19f0".to_string(),
+                None,
+                vec![highlight("9bZkp7q19f0", 3.0, "Hello.")],
+            ),
+        ];
+
+        assert_eq!(
+            render(&sections),
+            "# Highlights\n\n\
+             ## Cooking 101\n\nSource: <https://youtu.be/dQw4w9WgXcQ>\n\n\
+             - [1:15](https://youtu.be/dQw4w9WgXcQ?t=75) First, the onions.\n\n\
+             ## 9bZkp7q19f0\n\nSource: <https://youtu.be/9bZkp7q19f0>\n\n\
+             - [0:03](https://youtu.be/9bZkp7q19f0?t=3) Hello.\n\n"
+        );
+    }
+}
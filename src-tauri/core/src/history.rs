@@ -0,0 +1,218 @@
+//! The history of fetched videos, behind the "recently analyzed" view.
+//!
+//! A video is recorded whenever its transcript is fetched from YouTube, with the
+//! title and channel from the same player response, and moved to the top again
+//! whenever it is opened from the cache. Pinned entries stay at the top and
+//! survive [`clear_history`].
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::metadata::VideoMetadata;
+use crate::transcript::CaptionTrack;
+use rusqlite::{params, Row};
+use serde::Serialize;
+
+const DEFAULT_LIMIT: usize = 50;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS history (
+    video_id         TEXT    PRIMARY KEY,
+    title            TEXT    NOT NULL,
+    channel_name     TEXT    NOT NULL,
+    channel_id       TEXT    NOT NULL,
+    languages        TEXT    NOT NULL DEFAULT '[]',
+    pinned           INTEGER NOT NULL DEFAULT 0,
+    fetch_count      INTEGER NOT NULL DEFAULT 1,
+    first_fetched_at INTEGER NOT NULL,
+    last_fetched_at  INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS history_recent ON history (pinned DESC, last_fetched_at DESC);
+";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub channel_id: String,
+    /// Caption languages the video offered when it was last fetched.
+    pub languages: Vec<String>,
+    pub pinned: bool,
+    pub fetch_count: u32,
+    pub first_fetched_at: i64,
+    pub last_fetched_at: i64,
+}
+
+const ENTRY_COLUMNS: &str = "video_id, title, channel_name, channel_id, languages, pinned,
+     fetch_count, first_fetched_at, last_fetched_at";
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let languages: String = row.get(4)?;
+    Ok(HistoryEntry {
+        video_id: row.get(0)?,
+        title: row.get(1)?,
+        channel_name: row.get(2)?,
+        channel_id: row.get(3)?,
+        languages: serde_json::from_str(&languages).unwrap_or_default(),
+        pinned: row.get(5)?,
+        fetch_count: row.get(6)?,
+        first_fetched_at: row.get(7)?,
+        last_fetched_at: row.get(8)?,
+    })
+}
+
+/// Records a fetch of the video described by `metadata`.
+pub(crate) fn record(
+    db: &Database,
+    metadata: &VideoMetadata,
+    tracks: &[CaptionTrack],
+) -> Result<(), TranscriptError> {
+    let languages: Vec<&str> = tracks.iter().map(|t| t.language_code.as_str()).collect();
+    let languages = serde_json::to_string(&languages).unwrap_or_else(|_| "[]".into());
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO history (video_id, title, channel_name, channel_id, languages,
+                                  first_fetched_at, last_fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT (video_id) DO UPDATE SET
+                 title           = excluded.title,
+                 channel_name    = excluded.channel_name,
+                 channel_id      = excluded.channel_id,
+                 languages       = excluded.languages,
+                 fetch_count     = fetch_count + 1,
+                 last_fetched_at = excluded.last_fetched_at",
+            params![
+                metadata.video_id,
+                metadata.title,
+                metadata.channel_name,
+                metadata.channel_id,
+                languages,
+                unix_now()
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Moves an already recorded video to the top, for transcripts served from the cache.
+pub(crate) fn touch(db: &Database, video_id: &str) -> Result<(), TranscriptError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE history SET fetch_count = fetch_count + 1, last_fetched_at = ?2
+             WHERE video_id = ?1",
+            params![video_id, unix_now()],
+        )
+        .map(|_| ())
+    })
+}
+
+/// A `LIKE` pattern matching `query` anywhere, with its wildcards taken literally.
+fn like_pattern(query: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in query.trim().chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+fn query_entries(
+    db: &Database,
+    query: Option<&str>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, TranscriptError> {
+    let pattern = query.map(like_pattern);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1) as i64;
+    let offset = offset.unwrap_or(0) as i64;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM history
+             WHERE ?1 IS NULL
+                OR title LIKE ?1 ESCAPE '\\'
+                OR channel_name LIKE ?1 ESCAPE '\\'
+                OR video_id LIKE ?1 ESCAPE '\\'
+             ORDER BY pinned DESC, last_fetched_at DESC
+             LIMIT ?2 OFFSET ?3",
+            ENTRY_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![pattern, limit, offset], entry_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Lists the history, pinned entries first and then the most recently fetched.
+pub fn list_history(
+    db: &Database,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, TranscriptError> {
+    query_entries(db, None, limit, offset)
+}
+
+/// Finds history entries whose title, channel or video ID contains `query`.
+pub fn search_history(
+    db: &Database,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, TranscriptError> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    query_entries(db, Some(&query), limit, None)
+}
+
+/// Pins or unpins a history entry. Returns whether the video is in the history.
+pub fn set_history_pinned(
+    db: &Database,
+    video_id: String,
+    pinned: bool,
+) -> Result<bool, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE history SET pinned = ?2 WHERE video_id = ?1",
+            params![video_id, pinned],
+        )
+    })
+    .map(|n| n > 0)
+}
+
+/// Removes the given videos from the history and returns how many were removed.
+pub fn delete_history_entries(
+    db: &Database,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    let video_ids = video_ids
+        .iter()
+        .map(|id| crate::video_id::parse(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare("DELETE FROM history WHERE video_id = ?1")?;
+        video_ids
+            .iter()
+            .try_fold(0, |removed, id| Ok(removed + stmt.execute([id])? as u64))
+    })
+}
+
+/// Removes every unpinned entry and returns how many were removed.
+pub fn clear_history(db: &Database) -> Result<u64, TranscriptError> {
+    db.with_conn(|conn| conn.execute("DELETE FROM history WHERE pinned = 0", []))
+        .map(|n| n as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(like_pattern(" rust "), "%rust%");
+        assert_eq!(like_pattern("100%_done"), "%100\\%\\_done%");
+    }
+}
@@ -0,0 +1,499 @@
+//! HTTP client construction and the retry policy shared by all outgoing requests.
+//!
+//! Clients give up on connections and stalled reads after the configured
+//! [`Timeouts`], and on any request after `totalSecs`. The watch page and caption
+//! fetches have their own totals, as one is a megabyte of HTML and the other a
+//! few kilobytes of XML.
+//!
+//! Clients negotiate gzip, deflate and brotli compression and decode responses
+//! transparently. Large bodies are read through [`read_text`], which stops at the
+//! `maxResponseMb` setting so a malformed or hostile response cannot exhaust memory.
+//!
+//! Pages and captions are asked for in the `preferredLanguages`, so YouTube names
+//! tracks in the user's language and offers matching default tracks.
+//!
+//! Each client sends a single User-Agent, drawn at random from the `userAgents`
+//! setting or the bundled [`DEFAULT_USER_AGENTS`]. Fetching a video takes a client
+//! for one User-Agent, so its requests look like one browser while successive
+//! fetches do not all look alike. Clients are kept per User-Agent and shared by
+//! every fetch, the app's commands and the command line alike, so connections
+//! stay open between requests instead of being set up for each video.
+
+use crate::error::TranscriptError;
+use crate::proxy::{self, ProxyConfig};
+use crate::rate_limit;
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, RETRY_AFTER, USER_AGENT};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// How long an unused connection is kept open for the next request.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Interval of TCP keep-alive probes on open connections.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Desktop browsers to pass for when the `userAgents` setting lists none.
+const DEFAULT_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:133.0) Gecko/20100101 Firefox/133.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:133.0) Gecko/20100101 Firefox/133.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.1 Safari/605.1.15",
+];
+
+/// How long requests may take, in seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Timeouts {
+    /// Establishing a connection, including the TLS handshake.
+    pub connect_secs: u32,
+    /// Waiting for the next bytes of a response.
+    pub read_secs: u32,
+    /// A whole request and response, unless its stage has a total of its own.
+    pub total_secs: u32,
+    /// A whole watch page request and response.
+    pub watch_page_secs: u32,
+    /// A whole caption file request and response.
+    pub captions_secs: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: 10,
+            read_secs: 30,
+            total_secs: 120,
+            watch_page_secs: 45,
+            captions_secs: 20,
+        }
+    }
+}
+
+impl Timeouts {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        let all = [
+            self.connect_secs,
+            self.read_secs,
+            self.total_secs,
+            self.watch_page_secs,
+            self.captions_secs,
+        ];
+        if all.contains(&0) {
+            return Err(TranscriptError::InvalidInput(
+                "Timeouts must be at least one second.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn watch_page(&self) -> Duration {
+        Duration::from_secs(self.watch_page_secs as u64)
+    }
+
+    pub(crate) fn captions(&self) -> Duration {
+        Duration::from_secs(self.captions_secs as u64)
+    }
+}
+
+/// What shared clients are built from; a change to any of it retires them.
+#[derive(Debug, Clone, PartialEq)]
+struct ClientConfig {
+    proxy: Option<ProxyConfig>,
+    accept_language: String,
+    timeouts: Timeouts,
+}
+
+/// The clients handed out by [`build_client`], one per User-Agent, and the
+/// configuration they were built with.
+struct SharedClients {
+    config: Option<ClientConfig>,
+    clients: HashMap<HeaderValue, reqwest::Client>,
+}
+
+static SHARED_CLIENTS: Lazy<Mutex<SharedClients>> = Lazy::new(|| {
+    Mutex::new(SharedClients {
+        config: None,
+        clients: HashMap::new(),
+    })
+});
+
+/// A client that goes through the configured [`proxy`](crate::proxy), if any.
+///
+/// Clients are shared: a client (and its pool of kept-alive connections) is built
+/// once per User-Agent and handed out again until the proxy, timeouts or languages
+/// change, so successive fetches reuse connections and TLS sessions.
+pub fn build_client() -> Result<reqwest::Client, TranscriptError> {
+    let settings = crate::settings::current();
+    let config = ClientConfig {
+        proxy: proxy::current(),
+        accept_language: accept_language(&settings.preferred_languages),
+        timeouts: settings.timeouts,
+    };
+    let user_agent = pick_user_agent(&settings.user_agents);
+
+    let mut shared = SHARED_CLIENTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if shared.config.as_ref() != Some(&config) {
+        shared.clients.clear();
+        shared.config = Some(config.clone());
+    }
+    if let Some(client) = shared.clients.get(&user_agent) {
+        return Ok(client.clone());
+    }
+    let client = client_for(&config, user_agent.clone())?;
+    shared.clients.insert(user_agent, client.clone());
+    Ok(client)
+}
+
+/// Checks the `userAgents` setting: every entry must be usable as a header.
+pub(crate) fn validate_user_agents(user_agents: &[String]) -> Result<(), TranscriptError> {
+    for user_agent in user_agents {
+        if user_agent.trim().is_empty() || HeaderValue::from_str(user_agent).is_err() {
+            return Err(TranscriptError::InvalidInput(format!(
+                "\"{}\" is not a valid User-Agent.",
+                user_agent.trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The `preferredLanguages` that can go in a header, without duplicates.
+fn header_languages(preferred: &[String]) -> Vec<&str> {
+    let mut languages: Vec<&str> = Vec::new();
+    for language in preferred.iter().map(|l| l.trim()) {
+        let valid = !language.is_empty()
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if valid && !languages.iter().any(|l| l.eq_ignore_ascii_case(language)) {
+            languages.push(language);
+        }
+    }
+    languages
+}
+
+/// The `Accept-Language` header for the `preferredLanguages`, weighted in order
+/// and ending with English like the track selection does: `de,fr;q=0.9,en;q=0.8`.
+pub(crate) fn accept_language(preferred: &[String]) -> String {
+    let mut languages = header_languages(preferred);
+    if !languages.iter().any(|l| l.eq_ignore_ascii_case("en")) {
+        languages.push("en");
+    }
+    languages
+        .iter()
+        .enumerate()
+        .map(|(i, language)| match i {
+            0 => language.to_string(),
+            _ => format!("{};q={:.1}", language, (10 - i.min(9)) as f32 / 10.0),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The interface language to ask YouTube for (Innertube's `hl`): the first of the
+/// `preferredLanguages`, or English.
+pub(crate) fn interface_language(preferred: &[String]) -> String {
+    header_languages(preferred)
+        .first()
+        .map_or_else(|| "en".to_string(), |l| l.to_string())
+}
+
+/// A User-Agent drawn from `pool`, or from [`DEFAULT_USER_AGENTS`] when it is empty.
+fn pick_user_agent(pool: &[String]) -> HeaderValue {
+    let mut rng = rand::thread_rng();
+    pool.choose(&mut rng)
+        .and_then(|user_agent| HeaderValue::from_str(user_agent.trim()).ok())
+        .unwrap_or_else(|| {
+            HeaderValue::from_static(
+                DEFAULT_USER_AGENTS
+                    .choose(&mut rng)
+                    .copied()
+                    .unwrap_or_default(),
+            )
+        })
+}
+
+/// Builds a client of its own that goes through `proxy`, such as one to try out.
+pub fn build_client_with(proxy: Option<&ProxyConfig>) -> Result<reqwest::Client, TranscriptError> {
+    let settings = crate::settings::current();
+    let config = ClientConfig {
+        proxy: proxy.cloned(),
+        accept_language: accept_language(&settings.preferred_languages),
+        timeouts: settings.timeouts,
+    };
+    client_for(&config, pick_user_agent(&settings.user_agents))
+}
+
+fn client_for(
+    config: &ClientConfig,
+    user_agent: HeaderValue,
+) -> Result<reqwest::Client, TranscriptError> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, user_agent);
+    if let Ok(languages) = HeaderValue::from_str(&config.accept_language) {
+        headers.insert(ACCEPT_LANGUAGE, languages);
+    }
+
+    let timeouts = config.timeouts;
+    let mut builder = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(timeouts.connect_secs as u64))
+        .read_timeout(Duration::from_secs(timeouts.read_secs as u64))
+        .timeout(Duration::from_secs(timeouts.total_secs as u64))
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(TCP_KEEPALIVE);
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(proxy.to_proxy()?);
+    }
+    builder
+        .build()
+        .map_err(|e| TranscriptError::NetworkError(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// When and how often a failed request is retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay cap before the first retry; doubled after every attempt.
+    pub base_delay: Duration,
+    /// Upper bound for any single delay.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Full-jitter exponential backoff: a random delay between zero and
+    /// `base_delay * 2^attempt`, capped at `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = cap.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || matches!(
+            status,
+            StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+}
+
+/// Connection failures (refused, reset, timed out) are transient; anything else,
+/// such as an invalid URL or a redirect loop, is not.
+fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout() || err.is_request()
+}
+
+/// Seconds from a `Retry-After` header, if the server sent one.
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Sends a YouTube request with the default [`RetryPolicy`], within the YouTube rate limit.
+pub(crate) async fn send(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    send_with(&RetryPolicy::default(), request).await
+}
+
+/// Sends `request`, retrying on 429/5xx responses and connection errors. Every
+/// attempt waits for a token from the YouTube [`rate_limit`](crate::rate_limit).
+///
+/// When every attempt is answered with a retryable status, the last response is
+/// returned so callers can still map its status to a specific error. Requests whose
+/// body cannot be cloned are sent once. Requests that end in an error status or
+/// fail outright are recorded for [`crate::diagnostics`].
+pub(crate) async fn send_with(
+    policy: &RetryPolicy,
+    request: RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let result = send_attempts(policy, request).await;
+    crate::diagnostics::record_outcome(&result);
+    result
+}
+
+async fn send_attempts(
+    policy: &RetryPolicy,
+    request: RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        let last = attempt + 1 >= policy.max_attempts;
+        rate_limit::YOUTUBE.acquire().await;
+        let Some(this_try) = (if last { None } else { request.try_clone() }) else {
+            return request.send().await;
+        };
+
+        let delay = match this_try.send().await {
+            Ok(res) if is_retryable_status(res.status()) => retry_after(&res)
+                .map_or_else(|| policy.backoff(attempt), |d| d.min(policy.max_delay)),
+            Ok(res) => return Ok(res),
+            Err(err) if is_retryable_error(&err) => policy.backoff(attempt),
+            Err(err) => return Err(err),
+        };
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// A response body that could not be read, or that was larger than allowed.
+#[derive(Debug)]
+pub(crate) enum BodyError {
+    Network(reqwest::Error),
+    TooLarge { limit_mb: u32 },
+}
+
+impl From<reqwest::Error> for BodyError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(e) => e.fmt(f),
+            Self::TooLarge { limit_mb } => {
+                write!(f, "the response is larger than {} MB", limit_mb)
+            }
+        }
+    }
+}
+
+/// Reads the body of `res` as text, decompressed, giving up as soon as it grows
+/// past the `maxResponseMb` setting.
+pub(crate) async fn read_text(mut res: Response) -> Result<String, BodyError> {
+    let limit_mb = crate::settings::current().max_response_mb.max(1);
+    let limit = limit_mb as usize * 1024 * 1024;
+    let too_large = BodyError::TooLarge { limit_mb };
+
+    if res.content_length().is_some_and(|len| len > limit as u64) {
+        return Err(too_large);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Reads the body of `res` as text like [`read_text`], but stops as soon as
+/// `enough` says the part read so far holds all that is needed, and returns that
+/// part. `enough` is given each newly read stretch with the `overlap` bytes before
+/// it, so that what spans two chunks is seen whole.
+pub(crate) async fn read_text_until(
+    mut res: Response,
+    overlap: usize,
+    mut enough: impl FnMut(&str) -> bool,
+) -> Result<String, BodyError> {
+    let limit_mb = crate::settings::current().max_response_mb.max(1);
+    let limit = limit_mb as usize * 1024 * 1024;
+
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(BodyError::TooLarge { limit_mb });
+        }
+        let from = body.len().saturating_sub(overlap);
+        body.extend_from_slice(&chunk);
+        if enough(&String::from_utf8_lossy(&body[from..])) {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_stays_within_exponential_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+
+        for _ in 0..100 {
+            assert!(policy.backoff(0) <= Duration::from_millis(100));
+            assert!(policy.backoff(2) <= Duration::from_millis(400));
+            assert!(policy.backoff(10) <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn retries_only_transient_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn draws_user_agents_from_the_pool() {
+        let pool = vec!["Agent/1".to_string(), "Agent/2".to_string()];
+        for _ in 0..20 {
+            assert!(["Agent/1", "Agent/2"].contains(&pick_user_agent(&pool).to_str().unwrap()));
+            assert!(DEFAULT_USER_AGENTS.contains(&pick_user_agent(&[]).to_str().unwrap()));
+        }
+
+        assert!(validate_user_agents(&pool).is_ok());
+        assert!(validate_user_agents(&[" ".into()]).is_err());
+        assert!(validate_user_agents(&["Agent/1\nX-Injected: 1".into()]).is_err());
+    }
+
+    #[test]
+    fn weights_preferred_languages_before_english() {
+        let preferred = |codes: &[&str]| codes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(accept_language(&[]), "en");
+        assert_eq!(
+            accept_language(&preferred(&["de", "pt-BR", "DE", "bad code"])),
+            "de,pt-BR;q=0.9,en;q=0.8"
+        );
+        assert_eq!(
+            accept_language(&preferred(&["fr", "en", "es"])),
+            "fr,en;q=0.9,es;q=0.8"
+        );
+
+        assert_eq!(interface_language(&preferred(&[" ", "ja"])), "ja");
+        assert_eq!(interface_language(&[]), "en");
+    }
+}
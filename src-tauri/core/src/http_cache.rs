@@ -0,0 +1,171 @@
+//! An in-memory cache of GET responses, revalidated with `ETag`/`Last-Modified`.
+//!
+//! A caption file can run to hundreds of kilobytes, so fetching the same video again
+//! shortly after (another caption format, a translation, a retry) would download it
+//! all again. Responses that come with a validator are kept, and the next request
+//! for the same URL is sent conditionally; a `304 Not Modified` answer is then
+//! served from memory. The least recently used responses are dropped once they add
+//! up to [`MAX_BYTES`].
+
+use crate::http::{self, BodyError};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderValue, COOKIE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, StatusCode};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Cached bodies kept at most, in bytes.
+const MAX_BYTES: usize = 32 * 1024 * 1024;
+
+struct Entry {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: String,
+    used_at: Instant,
+}
+
+#[derive(Default)]
+struct Store {
+    entries: HashMap<String, Entry>,
+    bytes: usize,
+}
+
+impl Store {
+    fn validators(&self, key: &str) -> Option<(Option<HeaderValue>, Option<HeaderValue>)> {
+        self.entries
+            .get(key)
+            .map(|e| (e.etag.clone(), e.last_modified.clone()))
+    }
+
+    /// The body stored for `key`, marked as just used.
+    fn hit(&mut self, key: &str) -> Option<String> {
+        let entry = self.entries.get_mut(key)?;
+        entry.used_at = Instant::now();
+        Some(entry.body.clone())
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.body.len();
+        }
+    }
+
+    /// Stores `entry`, then drops the least recently used entries until the bodies
+    /// fit in `max_bytes`. A body larger than that is not kept at all.
+    fn insert(&mut self, key: String, entry: Entry, max_bytes: usize) {
+        self.remove(&key);
+        if entry.body.len() > max_bytes {
+            return;
+        }
+        self.bytes += entry.body.len();
+        self.entries.insert(key, entry);
+        while self.bytes > max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.used_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+}
+
+static STORE: Lazy<Mutex<Store>> = Lazy::new(Default::default);
+
+/// Responses differ by the signed-in user, so the cookies sent are part of the key.
+fn cache_key(request: &reqwest::Request) -> String {
+    let cookies = request
+        .headers()
+        .get(COOKIE)
+        .and_then(|c| c.to_str().ok())
+        .unwrap_or_default();
+    format!("{}\n{}", request.url(), cookies)
+}
+
+/// Sends the GET `request` through [`http::send`], conditionally when its response
+/// is cached, and returns the status and body. A body served from the cache comes
+/// with `200 OK`. Bodies are read with [`http::read_text`] and so limited in size.
+pub(crate) async fn get_text(request: RequestBuilder) -> Result<(StatusCode, String), BodyError> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    let key = cache_key(&request);
+    // Kept to resend unconditionally should the entry be evicted meanwhile
+    let unconditional = request.try_clone();
+
+    let validators = STORE.lock().ok().and_then(|s| s.validators(&key));
+    if let Some((etag, last_modified)) = validators {
+        let headers = request.headers_mut();
+        if let Some(etag) = etag {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            headers.insert(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let mut res = http::send(RequestBuilder::from_parts(client.clone(), request)).await?;
+    if res.status() == StatusCode::NOT_MODIFIED {
+        if let Some(body) = STORE.lock().ok().and_then(|mut s| s.hit(&key)) {
+            return Ok((StatusCode::OK, body));
+        }
+        if let Some(request) = unconditional {
+            res = http::send(RequestBuilder::from_parts(client, request)).await?;
+        }
+    }
+
+    let status = res.status();
+    let etag = res.headers().get(ETAG).cloned();
+    let last_modified = res.headers().get(LAST_MODIFIED).cloned();
+    let body = http::read_text(res).await?;
+    if let Ok(mut store) = STORE.lock() {
+        if status.is_success() && (etag.is_some() || last_modified.is_some()) {
+            let entry = Entry {
+                etag,
+                last_modified,
+                body: body.clone(),
+                used_at: Instant::now(),
+            };
+            store.insert(key, entry, MAX_BYTES);
+        } else {
+            store.remove(&key);
+        }
+    }
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(body: &str, used_at: Instant) -> Entry {
+        Entry {
+            etag: Some(HeaderValue::from_static("\"v1\"")),
+            last_modified: None,
+            body: body.into(),
+            used_at,
+        }
+    }
+
+    #[test]
+    fn drops_least_recently_used_bodies_past_the_limit() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut store = Store::default();
+        store.insert("a".into(), entry("aaaa", at(2)), 10);
+        store.insert("b".into(), entry("bbbb", at(1)), 10);
+        store.insert("c".into(), entry("cccc", at(3)), 10);
+
+        assert!(store.validators("b").is_none());
+        assert!(store.validators("a").is_some());
+        assert_eq!(store.bytes, 8);
+
+        store.insert("d".into(), entry(&"d".repeat(11), at(4)), 10);
+        assert!(store.validators("d").is_none());
+        assert_eq!(store.bytes, 8);
+    }
+}
@@ -0,0 +1,349 @@
+//! The pipeline background jobs ingest a video with.
+//!
+//! Ingesting a video runs its [`Stage`]s in order: fetch, parse, normalize, store,
+//! index and summarize. Each stage takes what the ones before it left in the
+//! [`Ingestion`] and adds to it. Fetching and parsing make the transcript every
+//! other stage works on, so they always run; the others are switched on and off in
+//! the `ingest` settings, so summarizing every ingested video, say, takes no more
+//! than a setting. A transcript still fresh in the cache is taken from there, and
+//! is neither parsed nor stored again; otherwise it is downloaded as
+//! [`load_transcript`](crate::transcript::load_transcript) does, so a job and a
+//! window wanting the same video download it once.
+
+use crate::auto_ingest;
+use crate::cache::{self, CacheKey};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::history;
+use crate::summarize::{summarize, SummarizeOptions, Summary};
+use crate::transcript::normalize::{self, NormalizeStage};
+use crate::transcript::{
+    self, CaptionFormat, CaptionKind, Download, Transcript, TranscriptSegment,
+};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IngestSettings {
+    /// Label speakers and filter words as the settings say, then run
+    /// `normalizeStages`.
+    pub normalize: bool,
+    /// Clean-up stages run over the text, in order.
+    pub normalize_stages: Vec<NormalizeStage>,
+    /// Keep the transcript in the cache, which also indexes it for full-text search.
+    pub store: bool,
+    /// Embed the transcript for questions, with the auto-ingestion provider.
+    pub index: bool,
+    /// Summarize the transcript with the auto-ingestion provider. Summary jobs
+    /// summarize either way.
+    pub summarize: bool,
+}
+
+impl Default for IngestSettings {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            normalize_stages: Vec::new(),
+            store: true,
+            index: false,
+            summarize: false,
+        }
+    }
+}
+
+impl IngestSettings {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        if self.index && !self.store {
+            return Err(TranscriptError::InvalidInput(
+                "Indexing ingested videos needs them stored too.".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A video being ingested: what the stages have made of it so far.
+pub(crate) struct Ingestion {
+    pub video_id: String,
+    kind: CaptionKind,
+    /// The caption document, when the transcript is downloaded rather than cached.
+    download: Option<Download>,
+    /// The transcript as fetched, which is what the cache keeps.
+    fetched: Option<Transcript>,
+    /// The transcript as cleaned up, for indexing, summarizing and the webhook.
+    transcript: Option<Transcript>,
+    pub summary: Option<Summary>,
+}
+
+impl Ingestion {
+    fn new(video_id: &str, kind: CaptionKind) -> Self {
+        Self {
+            video_id: video_id.to_string(),
+            kind,
+            download: None,
+            fetched: None,
+            transcript: None,
+            summary: None,
+        }
+    }
+
+    /// The segments of the cleaned-up transcript.
+    pub(crate) fn segments(&self) -> &[TranscriptSegment] {
+        self.transcript.as_ref().map_or(&[], |t| &t.segments)
+    }
+}
+
+/// One step of ingesting a video.
+trait Stage: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Whether the stage runs with `settings`.
+    fn enabled(&self, _settings: &IngestSettings) -> bool {
+        true
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>>;
+}
+
+/// Takes the transcript from the cache, or downloads its caption document.
+struct Fetch;
+
+impl Stage for Fetch {
+    fn name(&self) -> &'static str {
+        "fetch"
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            let video_id = ingestion.video_id.as_str();
+            if let Some(cached) =
+                cache::get(db, video_id, CacheKey::Default(ingestion.kind), false)?
+            {
+                let _ = history::touch(db, video_id);
+                ingestion.fetched = Some(cached);
+                return Ok(());
+            }
+
+            let languages = crate::settings::current().preferred_languages;
+            let download = transcript::download_default(
+                video_id,
+                ingestion.kind,
+                CaptionFormat::Xml,
+                &languages,
+            )
+            .await?;
+            ingestion.download = Some(download);
+            Ok(())
+        })
+    }
+}
+
+/// Reads the downloaded caption document into a transcript.
+struct Parse;
+
+impl Stage for Parse {
+    fn name(&self) -> &'static str {
+        "parse"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            if let Some(download) = &ingestion.download {
+                ingestion.fetched = Some(download.parse(&ingestion.video_id)?);
+            }
+            ingestion.transcript = ingestion.fetched.clone();
+            Ok(())
+        })
+    }
+}
+
+/// Labels speakers, filters words and runs the clean-up stages of the settings.
+struct Normalize;
+
+impl Stage for Normalize {
+    fn name(&self) -> &'static str {
+        "normalize"
+    }
+
+    fn enabled(&self, settings: &IngestSettings) -> bool {
+        settings.normalize
+    }
+
+    fn run<'a>(
+        &'a self,
+        _db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            let settings = crate::settings::current();
+            if let Some(fetched) = ingestion.transcript.take() {
+                let mut cleaned = transcript::post_process(fetched, &settings);
+                normalize::normalize(&mut cleaned.segments, &settings.ingest.normalize_stages);
+                ingestion.transcript = Some(cleaned);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Caches the transcript as fetched, and records the video in the history.
+struct Store;
+
+impl Stage for Store {
+    fn name(&self) -> &'static str {
+        "store"
+    }
+
+    fn enabled(&self, settings: &IngestSettings) -> bool {
+        settings.store
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            if let (Some(download), Some(fetched)) = (&ingestion.download, &ingestion.fetched) {
+                download.store(db, fetched, ingestion.kind)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Embeds the passages of the transcript, for questions about it.
+struct Index;
+
+impl Stage for Index {
+    fn name(&self) -> &'static str {
+        "index"
+    }
+
+    fn enabled(&self, settings: &IngestSettings) -> bool {
+        settings.index
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            let (_, llm) = auto_ingest::provider(db, "embeddings")?;
+            crate::embeddings::ensure(db, llm.as_ref(), &ingestion.video_id, ingestion.segments())
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Summarizes the transcript and stores the summary for later.
+struct Summarize;
+
+impl Stage for Summarize {
+    fn name(&self) -> &'static str {
+        "summarize"
+    }
+
+    fn enabled(&self, settings: &IngestSettings) -> bool {
+        settings.summarize
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a Database,
+        ingestion: &'a mut Ingestion,
+    ) -> BoxFuture<'a, Result<(), TranscriptError>> {
+        Box::pin(async move {
+            let (provider, llm) = auto_ingest::provider(db, "summary")?;
+            let (tldr, key_points) = summarize(
+                llm.as_ref(),
+                ingestion.segments(),
+                &SummarizeOptions::default(),
+                None,
+            )
+            .await?;
+            let summary = Summary {
+                tldr,
+                key_points,
+                provider,
+                model: llm.model().to_string(),
+            };
+            auto_ingest::store(db, &ingestion.video_id, &summary)?;
+            ingestion.summary = Some(summary);
+            Ok(())
+        })
+    }
+}
+
+const STAGES: &[&dyn Stage] = &[&Fetch, &Parse, &Normalize, &Store, &Index, &Summarize];
+
+/// The stages that run with `settings`, in order.
+fn stages(settings: &IngestSettings) -> impl Iterator<Item = &'static dyn Stage> + '_ {
+    STAGES.iter().copied().filter(|s| s.enabled(settings))
+}
+
+/// Ingests `video_id` through the stages enabled in the settings, and the
+/// summarize stage as well with `summarize`.
+#[tracing::instrument(skip(db), err(level = "warn"))]
+pub(crate) async fn ingest(
+    db: &Database,
+    video_id: &str,
+    summarize: bool,
+) -> Result<Ingestion, TranscriptError> {
+    let settings = crate::settings::current();
+    let mut enabled = settings.ingest;
+    enabled.summarize |= summarize;
+
+    let mut ingestion = Ingestion::new(video_id, settings.preferred_caption_kind);
+    for stage in stages(&enabled) {
+        tracing::debug!(stage = stage.name(), "running stage");
+        stage.run(db, &mut ingestion).await?;
+    }
+    Ok(ingestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_the_enabled_stages_in_order() {
+        let names =
+            |settings: &IngestSettings| stages(settings).map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names(&IngestSettings::default()),
+            vec!["fetch", "parse", "normalize", "store"]
+        );
+        let settings = IngestSettings {
+            normalize: false,
+            index: true,
+            summarize: true,
+            ..IngestSettings::default()
+        };
+        assert_eq!(
+            names(&settings),
+            vec!["fetch", "parse", "store", "index", "summarize"]
+        );
+        assert!(IngestSettings {
+            store: false,
+            ..settings
+        }
+        .validate()
+        .is_err());
+    }
+}
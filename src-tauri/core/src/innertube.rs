@@ -0,0 +1,613 @@
+//! Access to YouTube's internal Innertube API.
+//!
+//! Every Innertube call needs the `INNERTUBE_API_KEY` embedded in watch pages. The
+//! key (and the accompanying visitor data) is scraped once and reused until it
+//! expires or the API rejects it.
+
+mod clients;
+mod po_token;
+pub mod versions;
+
+use clients::player_clients;
+pub use clients::InnertubeClient;
+pub use po_token::PoTokenSettings;
+
+use crate::cipher;
+use crate::cookies;
+use crate::error::TranscriptError;
+use crate::http;
+use crate::metrics::{self, Outcome};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::StatusCode;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a scraped API key is reused before the watch page is fetched again.
+const SESSION_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Values scraped from a watch page that authorize Innertube calls.
+#[derive(Debug, Clone)]
+pub(crate) struct InnertubeSession {
+    pub api_key: String,
+    pub visitor_data: Option<String>,
+    /// Path of the player script (`/s/player/…/base.js`) used to decipher streams.
+    pub player_js_url: Option<String>,
+    fetched_at: Instant,
+}
+
+static SESSION: Lazy<Mutex<Option<InnertubeSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads a YouTube "text" object, which is either `{ simpleText }` or `{ runs: [{ text }] }`.
+pub fn text_of(value: &serde_json::Value) -> Option<String> {
+    if let Some(s) = value.get("simpleText").and_then(|s| s.as_str()) {
+        return Some(s.to_string());
+    }
+    let runs = value.get("runs")?.as_array()?;
+    Some(
+        runs.iter()
+            .filter_map(|r| r.get("text").and_then(|t| t.as_str()))
+            .collect(),
+    )
+}
+
+/// Maps an unplayable `playabilityStatus` to the error that explains it, or `None`
+/// when the video plays (or is a stream that has not started).
+///
+/// The status alone is too coarse (`LOGIN_REQUIRED` covers private videos, age
+/// checks and bot checks alike), so the reason and the error screen's subreason
+/// are matched as well. YouTube writes them in English for the clients used here.
+pub fn playability_error(player_json: &serde_json::Value) -> Option<TranscriptError> {
+    let playability = player_json.get("playabilityStatus")?;
+    let status = playability.get("status").and_then(|s| s.as_str())?;
+    if matches!(status, "OK" | "LIVE_STREAM_OFFLINE") {
+        return None;
+    }
+
+    let error_screen = playability
+        .get("errorScreen")
+        .and_then(|e| e.get("playerErrorMessageRenderer"));
+    let reason = playability
+        .get("reason")
+        .and_then(|r| r.as_str())
+        .map(str::to_string)
+        .or_else(|| error_screen.and_then(|e| e.get("reason")).and_then(text_of))
+        .unwrap_or_default();
+    let subreason = error_screen
+        .and_then(|e| e.get("subreason"))
+        .and_then(text_of)
+        .unwrap_or_default();
+    let text = format!("{} {}", reason, subreason).to_lowercase();
+
+    Some(if text.contains("country") {
+        TranscriptError::RegionBlocked
+    } else if text.contains("private") {
+        TranscriptError::PrivateVideo
+    } else if text.contains("confirm your age")
+        || text.contains("age-restricted")
+        || matches!(status, "AGE_CHECK_REQUIRED" | "AGE_VERIFICATION_REQUIRED")
+    {
+        TranscriptError::AgeRestricted
+    } else if text.contains("members") {
+        TranscriptError::MembersOnly
+    } else if status == "LOGIN_REQUIRED" {
+        TranscriptError::LoginRequired(if text.contains("bot") {
+            "YouTube wants you to sign in to confirm you are not a bot. Import the cookies of a signed-in browser or set a PO token, and try again.".into()
+        } else if reason.is_empty() {
+            "YouTube requires signing in to watch this video.".into()
+        } else {
+            format!("YouTube requires signing in: {}", reason)
+        })
+    } else if reason.is_empty() {
+        TranscriptError::VideoUnavailable("This video is unavailable.".into())
+    } else {
+        TranscriptError::VideoUnavailable(reason)
+    })
+}
+
+/// Returns the cached session, scraping `page_url` when there is none, it has
+/// expired, or `refresh` is set.
+pub(crate) async fn session(
+    client: &reqwest::Client,
+    page_url: &str,
+    refresh: bool,
+) -> Result<InnertubeSession, TranscriptError> {
+    if !refresh {
+        let cached = SESSION.lock().ok().and_then(|s| s.clone());
+        if let Some(session) = cached.filter(|s| s.fetched_at.elapsed() < SESSION_TTL) {
+            return Ok(session);
+        }
+    }
+
+    let session = scrape_session(client, page_url).await?;
+    if let Ok(mut cached) = SESSION.lock() {
+        *cached = Some(session.clone());
+    }
+    Ok(session)
+}
+
+static API_KEY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""INNERTUBE_API_KEY":"([^"]+)""#).unwrap());
+static ESCAPED_API_KEY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"INNERTUBE_API_KEY\\":\\"([^\\"]+)\\""#).unwrap());
+static VISITOR_DATA_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""(?:VISITOR_DATA|visitorData)":"([^"]+)""#).unwrap());
+static PLAYER_JS_URL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""(?:jsUrl|PLAYER_JS_URL)":"([^"]+)""#).unwrap());
+static WEB_VERSION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""INNERTUBE_CLIENT_VERSION":"([0-9.]+)""#).unwrap());
+
+/// Longest stretch of page a value is matched in; pages are scanned as they arrive,
+/// in windows overlapping by this much.
+const SCAN_OVERLAP: usize = 4096;
+
+/// The values a session is made of, gathered from a watch page as it streams in.
+#[derive(Debug, Default, PartialEq)]
+struct PageValues {
+    api_key: Option<String>,
+    visitor_data: Option<String>,
+    player_js_url: Option<String>,
+    web_version: Option<String>,
+}
+
+impl PageValues {
+    /// Takes the values not found yet from `text`, a stretch of the page.
+    fn scan(&mut self, text: &str) {
+        let find = |value: &mut Option<String>, patterns: &[&Regex]| {
+            if value.is_none() {
+                *value = patterns
+                    .iter()
+                    .find_map(|re| re.captures(text))
+                    .and_then(|c| c.get(1))
+                    .map(|m| m.as_str().to_string());
+            }
+        };
+        find(&mut self.api_key, &[&API_KEY_RE, &ESCAPED_API_KEY_RE]);
+        find(&mut self.visitor_data, &[&VISITOR_DATA_RE]);
+        find(&mut self.player_js_url, &[&PLAYER_JS_URL_RE]);
+        find(&mut self.web_version, &[&WEB_VERSION_RE]);
+    }
+
+    /// Whether the rest of the page can go unread. All three sit together in the
+    /// `ytcfg` block near the top, so this is usually within the first few hundred
+    /// kilobytes of a megabyte or more.
+    fn complete(&self) -> bool {
+        self.api_key.is_some() && self.visitor_data.is_some() && self.player_js_url.is_some()
+    }
+}
+
+/// Fetches a YouTube page and extracts the Innertube API key, visitor data and
+/// player script URL. The page is read only as far as they are found.
+#[tracing::instrument(name = "watch_page", skip(client), err(level = "warn"))]
+async fn scrape_session(
+    client: &reqwest::Client,
+    page_url: &str,
+) -> Result<InnertubeSession, TranscriptError> {
+    let network_error = |e: &dyn std::fmt::Display| {
+        TranscriptError::NetworkError(format!("Failed to load video page: {}", e))
+    };
+    let timeout = crate::settings::current().timeouts.watch_page();
+    let res = http::send(cookies::authorize(client.get(page_url).timeout(timeout)))
+        .await
+        .map_err(|e| network_error(&e))?;
+
+    let status = res.status();
+    if !status.is_success() {
+        return Err(TranscriptError::VideoUnavailable(format!(
+            "Failed to load video page (HTTP {}). The video may be unavailable.",
+            status.as_u16()
+        )));
+    }
+
+    let mut values = PageValues::default();
+    let page = http::read_text_until(res, SCAN_OVERLAP, |text| {
+        values.scan(text);
+        values.complete()
+    })
+    .await
+    .map_err(|e| network_error(&e))?;
+
+    let Some(api_key) = values.api_key else {
+        if page.contains("class=\"g-recaptcha\"") {
+            return Err(TranscriptError::CaptchaRequired);
+        }
+        return Err(TranscriptError::ParseError(
+            "Could not extract YouTube API key. The video may not have transcripts available."
+                .into(),
+        ));
+    };
+    if let Some(version) = &values.web_version {
+        versions::record(InnertubeClient::Web, version);
+    }
+
+    Ok(InnertubeSession {
+        api_key,
+        visitor_data: values.visitor_data,
+        player_js_url: values.player_js_url,
+        fetched_at: Instant::now(),
+    })
+}
+
+/// Identifies the request as coming from the visitor the session was scraped for
+/// and, with imported cookies, as the signed-in user.
+fn with_identity(
+    request: reqwest::RequestBuilder,
+    session: &InnertubeSession,
+) -> reqwest::RequestBuilder {
+    let request = cookies::authorize(request);
+    match &session.visitor_data {
+        Some(visitor_data) => request.header("X-Goog-Visitor-Id", visitor_data),
+        None => request,
+    }
+}
+
+/// The request context of `innertube_client`, carrying the session's visitor data
+/// so YouTube sees the same visitor as the page the session was scraped from, and
+/// the user's first preferred language.
+fn context(innertube_client: InnertubeClient, session: &InnertubeSession) -> serde_json::Value {
+    let mut context = innertube_client.context();
+    let preferred = crate::settings::current().preferred_languages;
+    context["client"]["hl"] = serde_json::json!(http::interface_language(&preferred));
+    if let Some(visitor_data) = &session.visitor_data {
+        context["client"]["visitorData"] = serde_json::json!(visitor_data);
+    }
+    context
+}
+
+/// The PO token to add to caption and stream URLs as `pot`, if one is configured.
+/// It is bound to the visitor of the current session.
+pub(crate) async fn url_po_token(client: &reqwest::Client) -> Option<String> {
+    let visitor_data = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().and_then(|s| s.visitor_data.clone()));
+    po_token::po_token(client, visitor_data.as_deref()).await
+}
+
+/// Calls the player endpoint as `innertube_client`.
+async fn post_player(
+    client: &reqwest::Client,
+    video_id: &str,
+    session: &InnertubeSession,
+    innertube_client: InnertubeClient,
+) -> Result<reqwest::Response, TranscriptError> {
+    let player_url = format!(
+        "https://www.youtube.com/youtubei/v1/player?key={}",
+        session.api_key
+    );
+
+    let mut player_body = serde_json::json!({
+        "context": context(innertube_client, session),
+        "videoId": video_id
+    });
+    if innertube_client.is_browser() {
+        // Browser stream signatures only decipher with the player version they were issued for
+        if let Some(sts) = cipher::signature_timestamp() {
+            player_body["playbackContext"] = serde_json::json!({
+                "contentPlaybackContext": { "signatureTimestamp": sts }
+            });
+        }
+        if let Some(token) = po_token::po_token(client, session.visitor_data.as_deref()).await {
+            player_body["serviceIntegrityDimensions"] = serde_json::json!({ "poToken": token });
+        }
+    }
+
+    let mut request = with_identity(client.post(&player_url), session)
+        .header(CONTENT_TYPE, "application/json")
+        .header("X-Youtube-Client-Name", innertube_client.id().to_string())
+        .header("X-Youtube-Client-Version", innertube_client.version());
+    request = match innertube_client.user_agent() {
+        Some(user_agent) => request.header(USER_AGENT, user_agent),
+        None => request
+            .header("Origin", "https://www.youtube.com")
+            .header("Referer", watch_url(video_id)),
+    };
+
+    http::send(request.json(&player_body)).await.map_err(|e| {
+        TranscriptError::NetworkError(format!(
+            "Failed to fetch video metadata ({} client): {}",
+            innertube_client.name(),
+            e
+        ))
+    })
+}
+
+pub(crate) fn watch_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", video_id)
+}
+
+/// Fetches the player response of one client.
+#[tracing::instrument(
+    name = "player",
+    skip(client, innertube_client),
+    fields(client = innertube_client.name()),
+    err(level = "warn")
+)]
+async fn client_player_response(
+    client: &reqwest::Client,
+    video_id: &str,
+    innertube_client: InnertubeClient,
+) -> Result<serde_json::Value, TranscriptError> {
+    let watch_url = watch_url(video_id);
+    let mut player_res = post_player(
+        client,
+        video_id,
+        &session(client, &watch_url, false).await?,
+        innertube_client,
+    )
+    .await?;
+
+    // A rejected key has most likely been rotated, so scrape a fresh one and retry once
+    if player_res.status() == StatusCode::FORBIDDEN {
+        let fresh = session(client, &watch_url, true).await?;
+        player_res = post_player(client, video_id, &fresh, innertube_client).await?;
+    }
+
+    // Innertube turns away retired client versions with a failed precondition
+    if player_res.status() == StatusCode::BAD_REQUEST && versions::refresh(client).await {
+        let session = session(client, &watch_url, false).await?;
+        player_res = post_player(client, video_id, &session, innertube_client).await?;
+    }
+
+    if player_res.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Err(TranscriptError::RateLimited);
+    }
+
+    if !player_res.status().is_success() {
+        return Err(TranscriptError::VideoUnavailable(format!(
+            "Failed to fetch video metadata (HTTP {}). The video may be unavailable.",
+            player_res.status().as_u16()
+        )));
+    }
+
+    player_res
+        .json()
+        .await
+        .map_err(|e| TranscriptError::ParseError(format!("Failed to parse player response: {}", e)))
+}
+
+fn has_caption_tracks(player_json: &serde_json::Value) -> bool {
+    player_json
+        .pointer("/captions/playerCaptionsTracklistRenderer/captionTracks")
+        .and_then(|t| t.as_array())
+        .is_some_and(|tracks| !tracks.is_empty())
+}
+
+/// Fetches the Innertube player response for a video.
+///
+/// The clients of the `innertubeClients` setting are tried in order until one
+/// lists caption tracks, or with `adaptiveClientOrder` on, ranked by how reliably
+/// they have listed them so far. When none does, the first playable response is returned,
+/// then the first response at all, so callers still see why the video failed.
+/// Each attempt is recorded in the [`metrics`](crate::metrics).
+pub async fn fetch_player_response(
+    client: &reqwest::Client,
+    video_id: &str,
+) -> Result<serde_json::Value, TranscriptError> {
+    let settings = crate::settings::current();
+    let mut clients = player_clients(&settings.innertube_clients, cookies::is_signed_in());
+    if settings.adaptive_client_order {
+        metrics::rank(&mut clients);
+    }
+
+    let mut playable = None;
+    let mut unplayable = None;
+    let mut last_error = None;
+    for innertube_client in clients {
+        let started = Instant::now();
+        let response = client_player_response(client, video_id, innertube_client).await;
+        let outcome = match &response {
+            Ok(player_json) if has_caption_tracks(player_json) => Outcome::Captions,
+            Ok(_) => Outcome::Failed,
+            Err(e) => Outcome::of_error(e),
+        };
+        metrics::record(innertube_client, outcome, started.elapsed());
+
+        match response {
+            Ok(player_json) if outcome == Outcome::Captions => return Ok(player_json),
+            Ok(player_json) if playability_error(&player_json).is_none() => {
+                tracing::debug!(client = innertube_client.name(), "no caption tracks");
+                playable.get_or_insert(player_json);
+            }
+            Ok(player_json) => {
+                tracing::debug!(client = innertube_client.name(), "video not playable");
+                unplayable.get_or_insert(player_json);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    playable
+        .or(unplayable)
+        .ok_or_else(|| last_error.expect("at least one client is always tried"))
+}
+
+/// Calls an Innertube endpoint such as `browse` or `next` as the WEB client.
+///
+/// `body` holds the endpoint-specific fields; the client context is added here.
+pub async fn post_endpoint(
+    client: &reqwest::Client,
+    endpoint: &str,
+    mut body: serde_json::Value,
+) -> Result<serde_json::Value, TranscriptError> {
+    let mut refresh = false;
+    let mut refreshed_versions = false;
+    loop {
+        let session = session(client, "https://www.youtube.com/", refresh).await?;
+        body["context"] = context(InnertubeClient::Web, &session);
+        let url = format!(
+            "https://www.youtube.com/youtubei/v1/{}?key={}",
+            endpoint, session.api_key
+        );
+
+        let res = http::send(
+            with_identity(client.post(&url), &session)
+                .header(CONTENT_TYPE, "application/json")
+                .header("X-Youtube-Client-Name", "1")
+                .header("X-Youtube-Client-Version", InnertubeClient::Web.version())
+                .header("Origin", "https://www.youtube.com")
+                .json(&body),
+        )
+        .await
+        .map_err(|e| {
+            TranscriptError::NetworkError(format!("Failed to call YouTube {}: {}", endpoint, e))
+        })?;
+
+        // Same key-rotation handling as the player endpoint
+        if res.status() == StatusCode::FORBIDDEN && !refresh {
+            refresh = true;
+            continue;
+        }
+        if res.status() == StatusCode::BAD_REQUEST && !refreshed_versions {
+            refreshed_versions = true;
+            if versions::refresh(client).await {
+                continue;
+            }
+        }
+        if res.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(TranscriptError::RateLimited);
+        }
+        if !res.status().is_success() {
+            return Err(TranscriptError::NetworkError(format!(
+                "YouTube {} request failed (HTTP {}).",
+                endpoint,
+                res.status().as_u16()
+            )));
+        }
+
+        return res.json().await.map_err(|e| {
+            TranscriptError::ParseError(format!("Failed to parse {} response: {}", endpoint, e))
+        });
+    }
+}
+
+/// Collects every value stored under `key` anywhere in `value`, depth first.
+///
+/// Innertube nests renderers differently per client and experiment, so list items
+/// are located by renderer name rather than by a fixed path.
+pub fn find_all<'a>(value: &'a serde_json::Value, key: &str) -> Vec<&'a serde_json::Value> {
+    fn walk<'a>(value: &'a serde_json::Value, key: &str, out: &mut Vec<&'a serde_json::Value>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    if k == key {
+                        out.push(v);
+                    } else {
+                        walk(v, key, out);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    walk(item, key, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(value, key, &mut out);
+    out
+}
+
+/// The token of the first `continuationCommand` in `value`, used to request the next page.
+pub fn continuation_token(value: &serde_json::Value) -> Option<String> {
+    find_all(value, "continuationCommand")
+        .into_iter()
+        .find_map(|c| c.get("token").and_then(|t| t.as_str()))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn gathers_session_values_as_the_page_arrives() {
+        let mut values = PageValues::default();
+        values.scan(r#"<script>ytcfg.set({"INNERTUBE_API_KEY":"AIzaKey","VISITOR_DATA":"Cgt2"#);
+        assert_eq!(values.api_key.as_deref(), Some("AIzaKey"));
+        assert!(!values.complete());
+
+        // The next stretch starts over the value cut off at the end of the last one
+        values.scan(
+            r#""VISITOR_DATA":"Cgt2aXM","INNERTUBE_CLIENT_VERSION":"2.20250101.00.00","PLAYER_JS_URL":"/s/player/abc/base.js"})"#,
+        );
+        values.scan(r#""INNERTUBE_API_KEY":"Other","visitorData":"Other""#);
+        assert_eq!(
+            values,
+            PageValues {
+                api_key: Some("AIzaKey".into()),
+                visitor_data: Some("Cgt2aXM".into()),
+                player_js_url: Some("/s/player/abc/base.js".into()),
+                web_version: Some("2.20250101.00.00".into()),
+            }
+        );
+        assert!(values.complete());
+    }
+
+    fn unplayable(status: &str, reason: &str, subreason: Option<&str>) -> serde_json::Value {
+        json!({
+            "playabilityStatus": {
+                "status": status,
+                "reason": reason,
+                "errorScreen": { "playerErrorMessageRenderer": {
+                    "reason": { "simpleText": reason },
+                    "subreason": { "runs": [{ "text": subreason.unwrap_or("") }] }
+                } }
+            }
+        })
+    }
+
+    #[test]
+    fn tells_unplayable_videos_apart() {
+        let error = |status, reason, subreason| {
+            playability_error(&unplayable(status, reason, subreason)).map(|e| e.kind())
+        };
+        assert_eq!(
+            error(
+                "UNPLAYABLE",
+                "Video unavailable",
+                Some("The uploader has not made this video available in your country")
+            ),
+            Some("regionBlocked")
+        );
+        assert_eq!(
+            error("LOGIN_REQUIRED", "This video is private", None),
+            Some("privateVideo")
+        );
+        assert_eq!(
+            error(
+                "LOGIN_REQUIRED",
+                "Sign in to confirm your age",
+                Some("This video may be inappropriate for some users.")
+            ),
+            Some("ageRestricted")
+        );
+        assert_eq!(
+            error(
+                "UNPLAYABLE",
+                "Join this channel to get access to members-only content like this video, and other exclusive perks.",
+                None
+            ),
+            Some("membersOnly")
+        );
+        assert_eq!(
+            error(
+                "LOGIN_REQUIRED",
+                "Sign in to confirm you’re not a bot",
+                None
+            ),
+            Some("loginRequired")
+        );
+        assert_eq!(
+            error("ERROR", "Video unavailable", None),
+            Some("videoUnavailable")
+        );
+        assert_eq!(error("OK", "", None), None);
+        assert_eq!(playability_error(&json!({})).map(|e| e.kind()), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! The Innertube clients the player endpoint can be called as.
+//!
+//! YouTube decides per client which videos it plays and which caption tracks it
+//! lists, so a video whose captions one client hides can often be read through
+//! another. The player request tries the clients of the settings in order, or
+//! the most reliable first (see [`crate::metrics`]).
+
+use super::versions;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum InnertubeClient {
+    Android,
+    Ios,
+    Web,
+    /// The mobile website.
+    Mweb,
+    /// The player embedded in third-party pages.
+    WebEmbedded,
+    /// The TV player embedded in third-party pages, which often plays videos
+    /// the other clients refuse.
+    TvEmbedded,
+}
+
+impl InnertubeClient {
+    pub(crate) const ALL: [Self; 6] = [
+        Self::Android,
+        Self::Ios,
+        Self::Web,
+        Self::Mweb,
+        Self::WebEmbedded,
+        Self::TvEmbedded,
+    ];
+
+    /// The clients tried when the settings do not list any.
+    pub(crate) const DEFAULT_ORDER: [Self; 3] = [Self::Android, Self::Web, Self::TvEmbedded];
+
+    /// `clientName` in the request context.
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Android => "ANDROID",
+            Self::Ios => "IOS",
+            Self::Web => "WEB",
+            Self::Mweb => "MWEB",
+            Self::WebEmbedded => "WEB_EMBEDDED_PLAYER",
+            Self::TvEmbedded => "TVHTML5_SIMPLY_EMBEDDED_PLAYER",
+        }
+    }
+
+    /// The numeric ID sent as `X-Youtube-Client-Name`.
+    pub(super) fn id(self) -> u32 {
+        match self {
+            Self::Web => 1,
+            Self::Mweb => 2,
+            Self::Android => 3,
+            Self::Ios => 5,
+            Self::WebEmbedded => 56,
+            Self::TvEmbedded => 85,
+        }
+    }
+
+    /// The version to send: the scraped one when there is one, the pinned one
+    /// otherwise.
+    pub(super) fn version(self) -> String {
+        versions::current(self).unwrap_or_else(|| self.pinned_version().to_string())
+    }
+
+    fn pinned_version(self) -> &'static str {
+        match self {
+            Self::Android => "20.10.38",
+            Self::Ios => "20.10.4",
+            Self::Web => "2.20250122.01.00",
+            Self::Mweb => "2.20250122.01.00",
+            Self::WebEmbedded => "1.20250121.00.00",
+            Self::TvEmbedded => "2.0",
+        }
+    }
+
+    /// Whether the client runs in a browser: it sends cookies, and its stream
+    /// signatures only decipher with the player script they were issued for.
+    pub(super) fn is_browser(self) -> bool {
+        !matches!(self, Self::Android | Self::Ios)
+    }
+
+    /// The app's own user agent, for the mobile app clients.
+    pub(super) fn user_agent(self) -> Option<&'static str> {
+        match self {
+            Self::Android => {
+                Some("com.google.android.youtube/20.10.38 (Linux; U; Android 14) gzip")
+            }
+            Self::Ios => Some(
+                "com.google.ios.youtube/20.10.4 (iPhone16,2; U; CPU iOS 18_3_2 like Mac OS X;)",
+            ),
+            _ => None,
+        }
+    }
+
+    /// The `context` object of a request made as this client.
+    pub(super) fn context(self) -> Value {
+        let mut client = json!({
+            "clientName": self.name(),
+            "clientVersion": self.version(),
+            "hl": "en",
+            "gl": "US"
+        });
+        match self {
+            Self::Android => {
+                client["androidSdkVersion"] = json!(34);
+                client["osName"] = json!("Android");
+                client["osVersion"] = json!("14");
+            }
+            Self::Ios => {
+                client["deviceMake"] = json!("Apple");
+                client["deviceModel"] = json!("iPhone16,2");
+                client["osName"] = json!("iPhone");
+                client["osVersion"] = json!("18.3.2.22D82");
+            }
+            Self::TvEmbedded => client["clientScreen"] = json!("EMBED"),
+            Self::Web | Self::Mweb | Self::WebEmbedded => {}
+        }
+
+        let mut context = json!({ "client": client });
+        if matches!(self, Self::WebEmbedded | Self::TvEmbedded) {
+            context["thirdParty"] = json!({ "embedUrl": "https://www.youtube.com/" });
+        }
+        context
+    }
+}
+
+/// The clients to try for a player request, in order. Signed-in requests skip the
+/// app clients, which ignore browser cookies, and fall back to WEB if that leaves
+/// none.
+pub(crate) fn player_clients(
+    configured: &[InnertubeClient],
+    signed_in: bool,
+) -> Vec<InnertubeClient> {
+    let configured = if configured.is_empty() {
+        &InnertubeClient::DEFAULT_ORDER[..]
+    } else {
+        configured
+    };
+    let mut seen = HashSet::new();
+    let mut clients: Vec<_> = configured
+        .iter()
+        .copied()
+        .filter(|c| !signed_in || c.is_browser())
+        .filter(|c| seen.insert(*c))
+        .collect();
+    if clients.is_empty() {
+        clients.push(InnertubeClient::Web);
+    }
+    clients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_app_clients_when_signed_in() {
+        use InnertubeClient::*;
+
+        assert_eq!(player_clients(&[], false), [Android, Web, TvEmbedded]);
+        assert_eq!(player_clients(&[], true), [Web, TvEmbedded]);
+        assert_eq!(player_clients(&[Ios, Mweb], true), [Mweb]);
+        assert_eq!(player_clients(&[Android, Ios], true), [Web]);
+        assert_eq!(player_clients(&[Web, Ios, Web, Ios], false), [Web, Ios]);
+    }
+
+    #[test]
+    fn embedded_clients_name_an_embedding_page() {
+        let context = InnertubeClient::TvEmbedded.context();
+        assert_eq!(context["client"]["clientScreen"], "EMBED");
+        assert_eq!(
+            context["thirdParty"]["embedUrl"],
+            "https://www.youtube.com/"
+        );
+        assert!(InnertubeClient::Android
+            .context()
+            .get("thirdParty")
+            .is_none());
+    }
+}
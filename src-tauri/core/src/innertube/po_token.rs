@@ -0,0 +1,157 @@
+//! Proof-of-origin (PO) tokens, which YouTube asks browser clients for before it
+//! serves them streams and captions without a bot check.
+//!
+//! Minting a token means running BotGuard in a browser, which the app does not
+//! do. A token can be pasted into the settings instead, or minted by an external
+//! provider such as the HTTP server of bgutil-ytdlp-pot-provider, which binds it
+//! to the visitor data of the session.
+
+use crate::error::TranscriptError;
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a minted token is reused for the same visitor data.
+const MINTED_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PoTokenSettings {
+    /// A token to send as is. Takes precedence over the provider.
+    pub token: Option<String>,
+    /// Base URL of a provider that answers `POST /get_pot` with a token bound to
+    /// the given visitor data.
+    pub provider_url: Option<String>,
+}
+
+impl PoTokenSettings {
+    fn token(&self) -> Option<&str> {
+        self.token
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+    }
+
+    fn provider_url(&self) -> Option<&str> {
+        self.provider_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        if let Some(url) = self.provider_url() {
+            Url::parse(url).map_err(|e| {
+                TranscriptError::InvalidInput(format!("Invalid PO token provider URL: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+struct Minted {
+    content_binding: String,
+    token: String,
+    minted_at: Instant,
+}
+
+static MINTED: Lazy<Mutex<Option<Minted>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Deserialize)]
+struct ProviderResponse {
+    #[serde(alias = "poToken")]
+    po_token: String,
+}
+
+async fn mint(
+    client: &reqwest::Client,
+    provider_url: &str,
+    content_binding: &str,
+) -> Result<String, TranscriptError> {
+    let network_error = |e: reqwest::Error| {
+        TranscriptError::NetworkError(format!("PO token provider failed: {}", e))
+    };
+    let url = format!("{}/get_pot", provider_url.trim_end_matches('/'));
+    let res = client
+        .post(&url)
+        .json(&json!({ "content_binding": content_binding }))
+        .send()
+        .await
+        .map_err(network_error)?
+        .error_for_status()
+        .map_err(network_error)?;
+    let minted: ProviderResponse = res.json().await.map_err(|e| {
+        TranscriptError::ParseError(format!("Unexpected PO token provider response: {}", e))
+    })?;
+    Ok(minted.po_token)
+}
+
+/// The PO token to send with requests of the visitor `visitor_data`: the one from
+/// the settings, or one minted by the configured provider. Provider failures are
+/// treated as having no token, so requests are still made without one.
+pub(super) async fn po_token(
+    client: &reqwest::Client,
+    visitor_data: Option<&str>,
+) -> Option<String> {
+    let settings = crate::settings::current().po_token;
+    if let Some(token) = settings.token() {
+        return Some(token.to_string());
+    }
+    let provider_url = settings.provider_url()?;
+    let content_binding = visitor_data?;
+
+    let cached = MINTED.lock().ok().and_then(|minted| {
+        minted
+            .as_ref()
+            .filter(|m| m.content_binding == content_binding)
+            .filter(|m| m.minted_at.elapsed() < MINTED_TTL)
+            .map(|m| m.token.clone())
+    });
+    if cached.is_some() {
+        return cached;
+    }
+
+    let token = mint(client, provider_url, content_binding).await.ok()?;
+    if let Ok(mut minted) = MINTED.lock() {
+        *minted = Some(Minted {
+            content_binding: content_binding.to_string(),
+            token: token.clone(),
+            minted_at: Instant::now(),
+        });
+    }
+    Some(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_both_provider_spellings() {
+        let snake: ProviderResponse =
+            serde_json::from_str(r#"{ "po_token": "abc", "expires_at": "2025-01-01" }"#).unwrap();
+        let camel: ProviderResponse = serde_json::from_str(r#"{ "poToken": "abc" }"#).unwrap();
+        assert_eq!(snake.po_token, "abc");
+        assert_eq!(camel.po_token, "abc");
+    }
+
+    #[test]
+    fn blank_settings_count_as_unset() {
+        let settings = PoTokenSettings {
+            token: Some("  ".into()),
+            provider_url: Some("".into()),
+        };
+        assert_eq!(settings.token(), None);
+        assert_eq!(settings.provider_url(), None);
+        assert!(settings.validate().is_ok());
+
+        let settings = PoTokenSettings {
+            token: None,
+            provider_url: Some("localhost 4416".into()),
+        };
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Client versions kept current, as YouTube stops serving retired ones.
+//!
+//! The versions pinned in [`InnertubeClient`] are only a fallback. The WEB version
+//! is read from `INNERTUBE_CLIENT_VERSION` whenever a page is scraped for a
+//! session. The app and embedded clients have no page of their own, so their
+//! versions are read from yt-dlp's client table, which follows their releases
+//! closely. Both are refreshed at startup and when Innertube rejects a request the
+//! way it rejects outdated clients.
+
+use super::InnertubeClient;
+use crate::error::TranscriptError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// yt-dlp's table of Innertube clients and the versions it sends.
+const APP_VERSIONS_URL: &str =
+    "https://raw.githubusercontent.com/yt-dlp/yt-dlp/master/yt_dlp/extractor/youtube/_base.py";
+
+/// How long after a refresh another rejected request may trigger a new one.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Default)]
+struct Versions {
+    scraped: HashMap<InnertubeClient, String>,
+    refreshed_at: Option<Instant>,
+}
+
+static VERSIONS: Lazy<Mutex<Versions>> = Lazy::new(Default::default);
+
+/// The scraped version of `client`, if one has been found.
+pub(super) fn current(client: InnertubeClient) -> Option<String> {
+    VERSIONS.lock().ok()?.scraped.get(&client).cloned()
+}
+
+pub(super) fn record(client: InnertubeClient, version: &str) {
+    if let Ok(mut versions) = VERSIONS.lock() {
+        versions.scraped.insert(client, version.to_string());
+    }
+}
+
+/// Versions of the clients other than WEB in yt-dlp's client table. The table
+/// lists variants of some clients; the first entry of each name wins.
+fn parse_app_versions(source: &str) -> HashMap<InnertubeClient, String> {
+    static CLIENT_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"'clientName':\s*'([A-Z0-9_]+)',\s*'clientVersion':\s*'([0-9.]+)'"#).unwrap()
+    });
+    let mut versions = HashMap::new();
+    for caps in CLIENT_RE.captures_iter(source) {
+        let client = InnertubeClient::ALL
+            .into_iter()
+            .filter(|c| *c != InnertubeClient::Web)
+            .find(|c| c.name() == &caps[1]);
+        if let Some(client) = client {
+            versions
+                .entry(client)
+                .or_insert_with(|| caps[2].to_string());
+        }
+    }
+    versions
+}
+
+async fn fetch_app_versions(
+    client: &reqwest::Client,
+) -> Result<HashMap<InnertubeClient, String>, TranscriptError> {
+    let network_error = |e: reqwest::Error| {
+        TranscriptError::NetworkError(format!("Failed to fetch client versions: {}", e))
+    };
+    let res = client
+        .get(APP_VERSIONS_URL)
+        .send()
+        .await
+        .map_err(network_error)?
+        .error_for_status()
+        .map_err(network_error)?;
+    Ok(parse_app_versions(
+        &res.text().await.map_err(network_error)?,
+    ))
+}
+
+/// Scrapes the current client versions, unless that was done within
+/// [`MIN_REFRESH_INTERVAL`]. Returns whether any version was refreshed.
+pub async fn refresh(client: &reqwest::Client) -> bool {
+    {
+        let Ok(mut versions) = VERSIONS.lock() else {
+            return false;
+        };
+        if versions
+            .refreshed_at
+            .is_some_and(|at| at.elapsed() < MIN_REFRESH_INTERVAL)
+        {
+            return false;
+        }
+        versions.refreshed_at = Some(Instant::now());
+    }
+
+    // Scraping a fresh session records the WEB version
+    let web = super::session(client, "https://www.youtube.com/", true)
+        .await
+        .is_ok();
+    let apps = match fetch_app_versions(client).await {
+        Ok(found) => {
+            for (app, version) in &found {
+                record(*app, version);
+            }
+            !found.is_empty()
+        }
+        Err(_) => false,
+    };
+    web || apps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_first_version_of_each_client() {
+        let source = r#"
+INNERTUBE_CLIENTS = {
+    'web': {
+        'INNERTUBE_CONTEXT': {
+            'client': {
+                'clientName': 'WEB',
+                'clientVersion': '2.20250312.04.00',
+            },
+        },
+    },
+    'android': {
+        'INNERTUBE_CONTEXT': {
+            'client': {
+                'clientName': 'ANDROID',
+                'clientVersion': '20.10.38',
+                'androidSdkVersion': 30,
+            },
+        },
+    },
+    'android_vr': {
+        'INNERTUBE_CONTEXT': {
+            'client': {
+                'clientName': 'ANDROID_VR',
+                'clientVersion': '1.62.27',
+            },
+        },
+    },
+    'ios': {
+        'INNERTUBE_CONTEXT': {
+            'client': {
+                'clientName': 'IOS',
+                'clientVersion': '20.10.4',
+            },
+        },
+    },
+    'ios_music': {
+        'INNERTUBE_CONTEXT': {
+            'client': {
+                'clientName': 'IOS',
+                'clientVersion': '7.27.0',
+            },
+        },
+    },
+}
+"#;
+        let versions = parse_app_versions(source);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[&InnertubeClient::Android], "20.10.38");
+        assert_eq!(versions[&InnertubeClient::Ios], "20.10.4");
+    }
+}
@@ -0,0 +1,420 @@
+//! A persistent queue of background ingestion jobs.
+//!
+//! Jobs are rows of the `jobs` table, so a queue of thousands of videos survives
+//! the app closing: on the next start, jobs cut off mid-run go back to pending and
+//! the workers pick up where they left off. A playlist job lists the playlist and
+//! enqueues one transcript job per video; a transcript job runs one video through
+//! the [`ingest`](crate::ingest) pipeline; a summary job does the same with its
+//! summarize stage on, for [`crate::auto_ingest`]. At most `parallelism` jobs run
+//! at a time.
+
+use crate::activity::{self, ActivityKind};
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::ingest;
+use crate::operations;
+use crate::playlist::{fetch_all_videos, parse_playlist_id};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+pub(crate) const DEFAULT_PARALLELISM: usize = 2;
+pub const MAX_PARALLELISM: usize = 8;
+const DEFAULT_LIST_LIMIT: usize = 200;
+/// Workers also look for pending jobs this often, in case a wake-up was missed.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind       TEXT    NOT NULL,
+    input      TEXT    NOT NULL,
+    status     TEXT    NOT NULL DEFAULT 'pending',
+    attempts   INTEGER NOT NULL DEFAULT 0,
+    error      TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS jobs_status ON jobs (status, id);
+";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    /// Fetch the transcript of one video.
+    Transcript,
+    /// Enqueue a transcript job for every video of a playlist.
+    Playlist,
+    /// Fetch the transcript of one video and store a summary of it.
+    Summary,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobKind {
+    /// The video or playlist ID of `input`, a URL or ID of what jobs of this kind take.
+    pub(crate) fn parse_input(self, input: &str) -> Result<String, TranscriptError> {
+        match self {
+            Self::Transcript | Self::Summary => crate::video_id::parse(input),
+            Self::Playlist => parse_playlist_id(input),
+        }
+    }
+
+    /// What a job of this kind does, for messages.
+    pub fn task(self) -> &'static str {
+        match self {
+            Self::Transcript => "Ingesting a video",
+            Self::Playlist => "Listing a playlist",
+            Self::Summary => "Summarizing a video",
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Transcript => "transcript",
+            Self::Playlist => "playlist",
+            Self::Summary => "summary",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [Self::Transcript, Self::Playlist, Self::Summary]
+            .into_iter()
+            .find(|k| k.as_str() == value)
+    }
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            Self::Pending,
+            Self::Running,
+            Self::Done,
+            Self::Failed,
+            Self::Cancelled,
+        ]
+        .into_iter()
+        .find(|s| s.as_str() == value)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: i64,
+    pub kind: JobKind,
+    /// A video ID for transcript and summary jobs, a playlist ID for playlist jobs.
+    pub input: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const JOB_COLUMNS: &str = "id, kind, input, status, attempts, error, created_at, updated_at";
+
+fn job_from_row(row: &Row) -> rusqlite::Result<Job> {
+    let text = |i: usize| -> rusqlite::Result<String> { row.get(i) };
+    let invalid = |i: usize, value: String| {
+        rusqlite::Error::FromSqlConversionFailure(
+            i,
+            rusqlite::types::Type::Text,
+            format!("unknown value \"{}\"", value).into(),
+        )
+    };
+    let kind = text(1)?;
+    let status = text(3)?;
+    Ok(Job {
+        id: row.get(0)?,
+        kind: JobKind::parse(&kind).ok_or_else(|| invalid(1, kind))?,
+        input: row.get(2)?,
+        status: JobStatus::parse(&status).ok_or_else(|| invalid(3, status))?,
+        attempts: row.get(4)?,
+        error: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// Worker bookkeeping, kept in Tauri's managed state next to the database.
+pub struct JobQueue {
+    pub parallelism: AtomicUsize,
+    /// Cancellation tokens of the running jobs, by job ID.
+    pub running: Mutex<HashMap<i64, CancellationToken>>,
+    /// Wakes the dispatcher when a job is enqueued or finishes.
+    pub wake: Notify,
+    /// Jobs done and failed since the queue last ran empty.
+    pub done: AtomicUsize,
+    pub failed: AtomicUsize,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            parallelism: AtomicUsize::new(DEFAULT_PARALLELISM),
+            running: Mutex::new(HashMap::new()),
+            wake: Notify::new(),
+            done: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl JobQueue {
+    pub fn active(&self) -> usize {
+        self.running.lock().map(|r| r.len()).unwrap_or(0)
+    }
+
+    pub fn set_parallelism(&self, parallelism: usize) {
+        self.parallelism
+            .store(parallelism.clamp(1, MAX_PARALLELISM), Ordering::Relaxed);
+        self.wake.notify_one();
+    }
+
+    /// Has the dispatcher look for pending jobs now.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// Adds a pending job unless the same one is already waiting or running, in which
+/// case nothing is added and `None` returned.
+pub fn enqueue(db: &Database, kind: JobKind, input: &str) -> Result<Option<Job>, TranscriptError> {
+    let now = unix_now();
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "INSERT INTO jobs (kind, input, created_at, updated_at)
+                 SELECT ?1, ?2, ?3, ?3
+                 WHERE NOT EXISTS (
+                     SELECT 1 FROM jobs
+                     WHERE kind = ?1 AND input = ?2 AND status IN ('pending', 'running')
+                 )
+                 RETURNING {}",
+                JOB_COLUMNS
+            ),
+            params![kind.as_str(), input, now],
+            job_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Marks the oldest pending job as running and returns it.
+pub fn claim(db: &Database) -> Result<Option<Job>, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ?1
+                 WHERE id = (SELECT id FROM jobs WHERE status = 'pending' ORDER BY id LIMIT 1)
+                 RETURNING {}",
+                JOB_COLUMNS
+            ),
+            params![unix_now()],
+            job_from_row,
+        )
+        .optional()
+    })
+}
+
+pub fn finish(
+    db: &Database,
+    id: i64,
+    result: &Result<(), TranscriptError>,
+) -> Result<Option<Job>, TranscriptError> {
+    let (status, error) = match result {
+        Ok(()) => (JobStatus::Done, None),
+        Err(TranscriptError::Cancelled) => (JobStatus::Cancelled, None),
+        Err(e) => (JobStatus::Failed, Some(e.to_string())),
+    };
+    set_status(db, id, status, error.as_deref(), &[JobStatus::Running])
+}
+
+/// Moves job `id` to `status` if it currently is in one of `from`.
+pub fn set_status(
+    db: &Database,
+    id: i64,
+    status: JobStatus,
+    error: Option<&str>,
+    from: &[JobStatus],
+) -> Result<Option<Job>, TranscriptError> {
+    let from: Vec<String> = from.iter().map(|s| format!("'{}'", s.as_str())).collect();
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "UPDATE jobs SET status = ?1, error = ?2, updated_at = ?3
+                 WHERE id = ?4 AND status IN ({})
+                 RETURNING {}",
+                from.join(", "),
+                JOB_COLUMNS
+            ),
+            params![status.as_str(), error, unix_now(), id],
+            job_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Puts jobs that were running when the app last closed back in the queue.
+pub fn requeue_interrupted(db: &Database) -> Result<usize, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE jobs SET status = 'pending', updated_at = ?1 WHERE status = 'running'",
+            params![unix_now()],
+        )
+    })
+}
+
+pub async fn run(
+    db: &Database,
+    job: &Job,
+    cancel: &CancellationToken,
+) -> Result<(), TranscriptError> {
+    match job.kind {
+        JobKind::Transcript | JobKind::Summary => {
+            let summarize = job.kind == JobKind::Summary;
+            let ingestion =
+                operations::cancellable(cancel, ingest::ingest(db, &job.input, summarize)).await?;
+            let segments = ingestion.segments();
+            let language = segments.first().map(|s| s.lang.as_str());
+            activity::record(
+                db,
+                ActivityKind::VideoIngested,
+                Some(&job.input),
+                &format!("Ingested a transcript of {} segments", segments.len()),
+                json!({ "jobId": job.id, "language": language, "segments": segments.len() }),
+            );
+            if job.kind == JobKind::Transcript {
+                crate::webhook::transcript_ingested(db, &job.input, segments);
+            }
+            if let Some(summary) = &ingestion.summary {
+                activity::record(
+                    db,
+                    ActivityKind::SummaryGenerated,
+                    Some(&job.input),
+                    &format!("Summarized with {}", summary.model),
+                    json!({ "jobId": job.id, "provider": summary.provider, "model": summary.model }),
+                );
+                crate::webhook::summary_ingested(db, &job.input, summary);
+            }
+        }
+        JobKind::Playlist => {
+            let client = build_client()?;
+            let videos =
+                operations::cancellable(cancel, fetch_all_videos(&client, &job.input, |_| {}))
+                    .await?;
+            for video in videos {
+                enqueue(db, JobKind::Transcript, &video.video_id)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn has_pending(db: &Database) -> bool {
+    db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM jobs WHERE status = 'pending')",
+            [],
+            |row| row.get(0),
+        )
+    })
+    .unwrap_or(false)
+}
+
+/// Queues a job per input: video IDs or URLs for transcript jobs, playlist IDs or
+/// URLs for playlist jobs. Inputs already waiting or running are skipped.
+pub fn enqueue_jobs(
+    db: &Database,
+    queue: &JobQueue,
+    kind: JobKind,
+    inputs: Vec<String>,
+) -> Result<Vec<Job>, TranscriptError> {
+    let inputs = inputs
+        .iter()
+        .map(|input| kind.parse_input(input))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut jobs = Vec::new();
+    for input in inputs {
+        jobs.extend(enqueue(db, kind, &input)?);
+    }
+    queue.wake.notify_one();
+    Ok(jobs)
+}
+
+/// Lists jobs, newest first, optionally only those with `status`.
+pub fn list_jobs(
+    db: &Database,
+    status: Option<JobStatus>,
+    limit: Option<usize>,
+) -> Result<Vec<Job>, TranscriptError> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).max(1) as i64;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+            JOB_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![status.map(JobStatus::as_str), limit], job_from_row)?;
+        rows.collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_kinds_and_statuses() {
+        for kind in [JobKind::Transcript, JobKind::Playlist, JobKind::Summary] {
+            assert_eq!(JobKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+        for status in [
+            JobStatus::Pending,
+            JobStatus::Running,
+            JobStatus::Done,
+            JobStatus::Failed,
+            JobStatus::Cancelled,
+        ] {
+            assert_eq!(JobStatus::parse(status.as_str()), Some(status));
+            // The column holds the same names the frontend sees
+            assert_eq!(
+                serde_json::to_value(status).unwrap(),
+                serde_json::json!(status.as_str())
+            );
+        }
+        assert_eq!(JobStatus::parse("paused"), None);
+    }
+}
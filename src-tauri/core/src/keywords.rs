@@ -0,0 +1,528 @@
+//! Keywords and topics of a transcript, without a language model.
+//!
+//! Two rankings are offered. RAKE scores multi-word phrases (runs of words between
+//! stopwords) by how strongly their words co-occur; TF-IDF scores single words and
+//! word pairs by frequency, weighted towards terms particular to some parts of the
+//! video, treating each minute as a document. Either way every keyword comes with
+//! the moment it is discussed the most, which is where a topics sidebar jumps to.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_LIMIT: usize = 15;
+/// Longest phrase kept as a RAKE candidate.
+const MAX_PHRASE_WORDS: usize = 3;
+/// Span of the window used to find where a keyword is most concentrated, and of the
+/// documents TF-IDF compares.
+const WINDOW_SECS: f64 = 60.0;
+/// Timestamps listed per keyword.
+const TIMESTAMPS_PER_KEYWORD: usize = 5;
+
+/// English stopwords plus the filler words of spoken language, which would
+/// otherwise top the rankings of any casual video.
+const STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "actually",
+    "after",
+    "again",
+    "against",
+    "all",
+    "also",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "aren't",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "can't",
+    "could",
+    "couldn't",
+    "did",
+    "didn't",
+    "do",
+    "does",
+    "doesn't",
+    "doing",
+    "don't",
+    "down",
+    "during",
+    "each",
+    "even",
+    "every",
+    "few",
+    "for",
+    "from",
+    "further",
+    "get",
+    "gets",
+    "getting",
+    "go",
+    "goes",
+    "going",
+    "gonna",
+    "got",
+    "gotta",
+    "had",
+    "hadn't",
+    "has",
+    "hasn't",
+    "have",
+    "haven't",
+    "having",
+    "he",
+    "he's",
+    "her",
+    "here",
+    "here's",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "i'd",
+    "i'll",
+    "i'm",
+    "i've",
+    "if",
+    "in",
+    "into",
+    "is",
+    "isn't",
+    "it",
+    "it's",
+    "its",
+    "itself",
+    "just",
+    "kind",
+    "know",
+    "let's",
+    "like",
+    "lot",
+    "make",
+    "many",
+    "maybe",
+    "me",
+    "mean",
+    "more",
+    "most",
+    "much",
+    "must",
+    "my",
+    "myself",
+    "need",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "oh",
+    "okay",
+    "on",
+    "once",
+    "one",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "pretty",
+    "really",
+    "right",
+    "said",
+    "same",
+    "say",
+    "see",
+    "she",
+    "she's",
+    "should",
+    "shouldn't",
+    "so",
+    "some",
+    "something",
+    "sort",
+    "stuff",
+    "such",
+    "sure",
+    "than",
+    "that",
+    "that's",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "there's",
+    "these",
+    "they",
+    "they're",
+    "thing",
+    "things",
+    "think",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "uh",
+    "um",
+    "under",
+    "until",
+    "up",
+    "us",
+    "very",
+    "wanna",
+    "want",
+    "was",
+    "wasn't",
+    "way",
+    "we",
+    "we're",
+    "we've",
+    "well",
+    "were",
+    "weren't",
+    "what",
+    "what's",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "won't",
+    "would",
+    "wouldn't",
+    "yeah",
+    "yes",
+    "you",
+    "you'd",
+    "you'll",
+    "you're",
+    "you've",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeywordMethod {
+    /// Multi-word key phrases.
+    #[default]
+    Rake,
+    /// Distinctive words and word pairs.
+    TfIdf,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeywordOptions {
+    pub method: KeywordMethod,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Keyword {
+    pub keyword: String,
+    /// Relative to the other keywords of the same video; 1 for the best.
+    pub score: f64,
+    pub occurrences: usize,
+    /// Start of the minute that mentions the keyword most often.
+    pub peak: f64,
+    /// Where the keyword is mentioned, densest stretches first.
+    pub timestamps: Vec<f64>,
+}
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+/// Lowercased words of `text`, with `None` marking phrase boundaries (punctuation
+/// other than apostrophes and hyphens inside words).
+pub(crate) fn tokens(text: &str) -> Vec<Option<String>> {
+    let mut out = Vec::new();
+    for piece in text.split_whitespace() {
+        let word = piece
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .replace('’', "'")
+            .to_lowercase();
+        if !word.is_empty() {
+            out.push(Some(word));
+        }
+        if piece.ends_with([',', '.', ';', ':', '!', '?', ')', '"', '…']) {
+            out.push(None);
+        }
+    }
+    out
+}
+
+/// A word that can be part of a keyword: no stopword, number or bracketed cue.
+pub(crate) fn is_content_word(word: &str) -> bool {
+    word.chars().count() > 2 && !is_stopword(word) && word.chars().any(char::is_alphabetic)
+}
+
+/// Candidate phrases with the offset of the segment they appear in.
+fn phrases(segments: &[TranscriptSegment]) -> Vec<(Vec<String>, f64)> {
+    let mut out = Vec::new();
+    for segment in segments {
+        let mut current: Vec<String> = Vec::new();
+        let mut flush = |current: &mut Vec<String>| {
+            if !current.is_empty() && current.len() <= MAX_PHRASE_WORDS {
+                out.push((current.clone(), segment.offset));
+            }
+            current.clear();
+        };
+        for token in tokens(&segment.text) {
+            match token {
+                Some(word) if is_content_word(&word) => current.push(word),
+                _ => flush(&mut current),
+            }
+        }
+        flush(&mut current);
+    }
+    out
+}
+
+/// RAKE: a word scores its degree (co-occurring words, itself included) over its
+/// frequency, and a phrase the sum of its words' scores.
+fn rake(segments: &[TranscriptSegment]) -> Vec<(String, f64, Vec<f64>)> {
+    let phrases = phrases(segments);
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for (words, _) in &phrases {
+        for word in words {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += words.len() as f64;
+        }
+    }
+
+    let mut by_phrase: HashMap<String, (f64, Vec<f64>)> = HashMap::new();
+    for (words, at) in &phrases {
+        let entry = by_phrase.entry(words.join(" ")).or_insert_with(|| {
+            let score = words
+                .iter()
+                .map(|w| degree[w.as_str()] / frequency[w.as_str()])
+                .sum();
+            (score, Vec::new())
+        });
+        entry.1.push(*at);
+    }
+
+    // A phrase said once is rarely a topic, unless the transcript is tiny
+    let min_occurrences = if phrases.len() > 50 { 2 } else { 1 };
+    by_phrase
+        .into_iter()
+        .filter(|(_, (_, times))| times.len() >= min_occurrences)
+        .map(|(phrase, (score, times))| {
+            // Repetition counts, with diminishing returns
+            let score = score * (1.0 + (times.len() as f64).ln());
+            (phrase, score, times)
+        })
+        .collect()
+}
+
+/// TF-IDF of words and word pairs, with each minute of the video as a document. A
+/// term scores its summed weight over the minutes it appears in.
+fn tf_idf(segments: &[TranscriptSegment]) -> Vec<(String, f64, Vec<f64>)> {
+    let mut documents: HashMap<i64, HashMap<String, usize>> = HashMap::new();
+    let mut times: HashMap<String, Vec<f64>> = HashMap::new();
+    for (words, at) in phrases(segments) {
+        let document = documents
+            .entry((at / WINDOW_SECS).floor() as i64)
+            .or_default();
+        let pairs = words.windows(2).map(|w| w.join(" "));
+        for term in words.iter().cloned().chain(pairs) {
+            *document.entry(term.clone()).or_default() += 1;
+            times.entry(term).or_default().push(at);
+        }
+    }
+
+    let n = documents.len() as f64;
+    let mut document_frequency: HashMap<&str, f64> = HashMap::new();
+    for document in documents.values() {
+        for term in document.keys() {
+            *document_frequency.entry(term).or_default() += 1.0;
+        }
+    }
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for document in documents.values() {
+        let total: usize = document.values().sum();
+        for (term, &count) in document {
+            let tf = count as f64 / total as f64;
+            let idf = (1.0 + n / document_frequency[term.as_str()]).ln();
+            *scores.entry(term.clone()).or_default() += tf * idf;
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(term, score)| {
+            let times = times.remove(&term).filter(|t| t.len() >= 2)?;
+            Some((term, score, times))
+        })
+        .collect()
+}
+
+/// Mention times ordered by how many other mentions fall in the window they start,
+/// so the first one is the start of the densest stretch.
+fn by_density(mut times: Vec<f64>) -> Vec<f64> {
+    times.sort_by(f64::total_cmp);
+    times.dedup();
+    let density = |start: f64| {
+        times
+            .iter()
+            .filter(|&&t| t >= start && t < start + WINDOW_SECS)
+            .count()
+    };
+    let mut ranked = times.clone();
+    ranked.sort_by(|a, b| density(*b).cmp(&density(*a)).then(a.total_cmp(b)));
+    ranked
+}
+
+/// Ranks the keywords of `segments`.
+pub(crate) fn extract(segments: &[TranscriptSegment], options: &KeywordOptions) -> Vec<Keyword> {
+    let mut scored = match options.method {
+        KeywordMethod::Rake => rake(segments),
+        KeywordMethod::TfIdf => tf_idf(segments),
+    };
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    // Drop keywords contained in a better one, e.g. "borrow" under "borrow checker"
+    let mut kept: Vec<(String, f64, Vec<f64>)> = Vec::new();
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    for candidate in scored {
+        let words: HashSet<&str> = candidate.0.split(' ').collect();
+        let covered = kept.iter().any(|(k, _, _)| {
+            let kept_words: HashSet<&str> = k.split(' ').collect();
+            words.is_subset(&kept_words)
+        });
+        if !covered {
+            kept.push(candidate);
+        }
+        if kept.len() == limit {
+            break;
+        }
+    }
+
+    let best = kept.first().map_or(1.0, |k| k.1).max(f64::EPSILON);
+    kept.into_iter()
+        .map(|(keyword, score, times)| {
+            let occurrences = times.len();
+            let mut timestamps = by_density(times);
+            timestamps.truncate(TIMESTAMPS_PER_KEYWORD);
+            Keyword {
+                keyword,
+                score: score / best,
+                occurrences,
+                peak: timestamps.first().copied().unwrap_or(0.0),
+                timestamps,
+            }
+        })
+        .collect()
+}
+
+/// Extracts the keywords of the transcript of `video_id`, best first.
+pub async fn extract_keywords(
+    db: &Database,
+    video_id: String,
+    options: Option<KeywordOptions>,
+) -> Result<Vec<Keyword>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    Ok(extract(&segments, &options.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 2.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stopwords_are_sorted_for_binary_search() {
+        assert!(STOPWORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn ranks_key_phrases_with_their_densest_minute() {
+        let segments = [
+            segment("So today, um, we look at the borrow checker.", 0.0),
+            segment("The weather is nice.", 30.0),
+            segment("Now the borrow checker is angry.", 120.0),
+            segment("Why is the borrow checker so strict?", 130.0),
+            segment("Lifetimes explain it.", 140.0),
+        ];
+
+        let keywords = extract(&segments, &KeywordOptions::default());
+        assert_eq!(keywords[0].keyword, "borrow checker");
+        assert_eq!(keywords[0].occurrences, 3);
+        assert_eq!(keywords[0].peak, 120.0);
+        assert_eq!(keywords[0].score, 1.0);
+        // Covered by "borrow checker"
+        assert!(keywords.iter().all(|k| k.keyword != "borrow"));
+    }
+
+    #[test]
+    fn tf_idf_keeps_repeated_terms_only() {
+        let segments = [
+            segment("ownership rules matter.", 0.0),
+            segment("ownership again and ownership.", 10.0),
+            segment("closures capture variables.", 70.0),
+            segment("closures are useful.", 80.0),
+            segment("ownership returns.", 130.0),
+        ];
+        let options = KeywordOptions {
+            method: KeywordMethod::TfIdf,
+            limit: Some(2),
+        };
+
+        let keywords: Vec<String> = extract(&segments, &options)
+            .into_iter()
+            .map(|k| k.keyword)
+            .collect();
+        assert_eq!(keywords, ["ownership", "closures"]);
+    }
+}
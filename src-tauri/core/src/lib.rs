@@ -0,0 +1,70 @@
+//! Everything InsightTube does without its window: fetching and caching transcripts,
+//! exporting, searching and summarizing them. The app and the `insighttube-cli`
+//! command-line tool are both built on it.
+
+pub mod action_items;
+pub mod activity;
+pub mod ask;
+pub mod auto_ingest;
+pub mod batch;
+pub mod cache;
+pub mod channel;
+pub mod chapter_summaries;
+pub mod chapters;
+pub mod cipher;
+pub mod collections;
+pub mod comment_analysis;
+pub mod comments;
+pub mod compare;
+pub mod cookies;
+pub mod db;
+pub mod diagnostics;
+pub mod diff;
+pub mod download;
+pub mod embeddings;
+pub mod error;
+pub mod export;
+pub mod flashcards;
+pub mod highlights;
+pub mod history;
+pub mod http;
+pub mod http_cache;
+pub mod ingest;
+pub mod innertube;
+pub mod jobs;
+pub mod keywords;
+pub mod llm;
+pub mod logging;
+pub mod metadata;
+pub mod metrics;
+pub mod notes;
+pub mod notion;
+pub mod obsidian;
+pub mod operations;
+pub mod outline;
+pub mod playlist;
+pub mod progress;
+pub mod prompt;
+pub mod proxy;
+pub mod punctuate;
+pub mod quotes;
+pub mod rate_limit;
+pub mod readwise;
+pub mod related;
+pub mod report;
+pub mod search;
+pub mod secrets;
+pub mod settings;
+pub mod single_flight;
+pub mod stats;
+pub mod storage;
+pub mod subscriptions;
+pub mod summarize;
+pub mod transcript;
+pub mod translate;
+pub mod video_id;
+pub mod video_list;
+pub mod webhook;
+#[cfg(feature = "whisper")]
+pub mod whisper;
+pub mod youtube_search;
@@ -0,0 +1,323 @@
+//! Chat completions from language models behind one [`Provider`] trait: hosted
+//! APIs with a stored key, or a local Ollama server.
+//!
+//! Features built on top (summaries, questions over a transcript) pick a
+//! [`ProviderKind`] and stay unaware of each vendor's wire format. Every provider
+//! they get is metered: the tokens each call uses are recorded in [`usage`], and
+//! calls are refused once a monthly budget is spent.
+
+mod anthropic;
+mod gemini;
+pub(crate) mod lines;
+pub mod ollama;
+mod openai;
+pub(crate) mod stream;
+pub mod usage;
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::secrets::{self, KeyedService};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Completions can take a while for long prompts, but a stalled connection
+/// should not hang a command forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+/// Local models on modest hardware are much slower than hosted ones.
+const LOCAL_REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+/// Listing models is quick; a slow answer means the provider is unreachable.
+const LIST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[serde(rename = "openai")]
+    OpenAi,
+    Anthropic,
+    Gemini,
+    Ollama,
+}
+
+impl ProviderKind {
+    const ALL: [Self; 4] = [Self::OpenAi, Self::Anthropic, Self::Gemini, Self::Ollama];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Gemini => "gemini",
+            Self::Ollama => "ollama",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    /// The service whose stored API key the provider uses; local ones need none.
+    fn keyed_service(self) -> Option<KeyedService> {
+        match self {
+            Self::OpenAi => Some(KeyedService::OpenAi),
+            Self::Anthropic => Some(KeyedService::Anthropic),
+            Self::Gemini => Some(KeyedService::Gemini),
+            Self::Ollama => None,
+        }
+    }
+
+    fn display_name(self) -> &'static str {
+        match self {
+            Self::OpenAi => "OpenAI",
+            Self::Anthropic => "Anthropic",
+            Self::Gemini => "Gemini",
+            Self::Ollama => "Ollama",
+        }
+    }
+}
+
+/// A single-turn completion: instructions plus the user prompt.
+#[derive(Debug, Clone)]
+pub struct CompletionRequest {
+    pub system: String,
+    pub prompt: String,
+    pub max_tokens: u32,
+    /// Ask for a JSON object, where the provider supports enforcing that.
+    pub json: bool,
+}
+
+/// Tokens a call used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub input: u64,
+    pub output: u64,
+}
+
+impl TokenUsage {
+    /// Reads the counts at `input` and `output` of `json`, as providers report
+    /// them; `None` when neither is there.
+    pub(crate) fn at(json: &serde_json::Value, input: &str, output: &str) -> Option<Self> {
+        let count = |pointer: &str| json.pointer(pointer).and_then(|n| n.as_u64());
+        match (count(input), count(output)) {
+            (None, None) => None,
+            (input, output) => Some(Self {
+                input: input.unwrap_or(0),
+                output: output.unwrap_or(0),
+            }),
+        }
+    }
+}
+
+/// The text of a completion, with the tokens it used when the provider said.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+/// A model a provider offers for completions.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub id: String,
+    /// Name to show, the ID itself where the provider has no other.
+    pub name: String,
+}
+
+/// Receives generated text piece by piece while a completion is streamed.
+pub type OnChunk<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+pub trait Provider: Send + Sync {
+    /// Model used for completions.
+    fn model(&self) -> &str;
+
+    /// Returns the completion text for `request`. With `on_chunk` the completion
+    /// is streamed, and each piece is passed to it as it arrives.
+    fn complete<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        on_chunk: Option<&'a OnChunk<'a>>,
+    ) -> BoxFuture<'a, Result<Completion, TranscriptError>>;
+
+    /// Model used by [`embed`](Provider::embed), or `None` when the provider has no
+    /// embeddings API.
+    fn embedding_model(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns one embedding vector per text, in order.
+    fn embed<'a>(
+        &'a self,
+        _texts: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Vec<f32>>, TranscriptError>> {
+        Box::pin(async {
+            Err(TranscriptError::LlmError(
+                "This provider does not offer embeddings.".into(),
+            ))
+        })
+    }
+}
+
+pub(crate) fn client(timeout: Duration) -> Result<reqwest::Client, TranscriptError> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| TranscriptError::NetworkError(format!("Failed to build HTTP client: {}", e)))
+}
+
+fn api_key(kind: ProviderKind) -> Result<String, TranscriptError> {
+    kind.keyed_service()
+        .and_then(secrets::api_key)
+        .ok_or_else(|| {
+            TranscriptError::InvalidInput(format!(
+                "Add an API key for {} in Settings first.",
+                kind.display_name()
+            ))
+        })
+}
+
+/// Creates the `kind` provider, using `model` or the provider's default model.
+/// Hosted providers need their API key stored first. The tokens its calls use are
+/// recorded in `db` against `operation`, the feature making them.
+pub fn provider<'a>(
+    db: &'a Database,
+    kind: ProviderKind,
+    model: Option<String>,
+    operation: &'static str,
+) -> Result<Box<dyn Provider + 'a>, TranscriptError> {
+    let model = model.filter(|m| !m.trim().is_empty());
+    let vendor: Box<dyn Provider> = match kind {
+        ProviderKind::OpenAi => Box::new(openai::OpenAi::new(
+            client(REQUEST_TIMEOUT)?,
+            api_key(kind)?,
+            model,
+        )),
+        ProviderKind::Anthropic => Box::new(anthropic::Anthropic::new(
+            client(REQUEST_TIMEOUT)?,
+            api_key(kind)?,
+            model,
+        )),
+        ProviderKind::Gemini => Box::new(gemini::Gemini::new(
+            client(REQUEST_TIMEOUT)?,
+            api_key(kind)?,
+            model,
+        )),
+        ProviderKind::Ollama => {
+            Box::new(ollama::Ollama::new(client(LOCAL_REQUEST_TIMEOUT)?, model))
+        }
+    };
+    Ok(Box::new(usage::Metered::new(db, kind, operation, vendor)))
+}
+
+/// The models `kind` offers for completions, by name. Hosted providers are asked
+/// with their stored API key.
+pub async fn list_models(kind: ProviderKind) -> Result<Vec<ModelInfo>, TranscriptError> {
+    let mut models = match kind {
+        ProviderKind::OpenAi => openai::list_models(client(LIST_TIMEOUT)?, api_key(kind)?).await?,
+        ProviderKind::Anthropic => {
+            anthropic::list_models(client(LIST_TIMEOUT)?, api_key(kind)?).await?
+        }
+        ProviderKind::Gemini => gemini::list_models(client(LIST_TIMEOUT)?, api_key(kind)?).await?,
+        ProviderKind::Ollama => ollama::list_local_models(None)
+            .await?
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.name.clone(),
+                name: m.name,
+            })
+            .collect(),
+    };
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Turns a non-success response into an error carrying the provider's own message,
+/// which usually says exactly what is wrong (bad key, unknown model, quota).
+pub(crate) async fn check_status(
+    provider: &str,
+    res: reqwest::Response,
+) -> Result<reqwest::Response, TranscriptError> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(TranscriptError::RateLimited);
+    }
+
+    let body: serde_json::Value = res.json().await.unwrap_or_default();
+    let message = body
+        .pointer("/error/message")
+        .or_else(|| body.get("error"))
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+    Err(TranscriptError::LlmError(format!(
+        "{} request failed: {}",
+        provider, message
+    )))
+}
+
+/// Reads `response[pointer]` as a list of embedding vectors.
+pub(crate) fn embeddings_at(
+    response: &serde_json::Value,
+    pointer: &str,
+    vector_key: Option<&str>,
+) -> Result<Vec<Vec<f32>>, TranscriptError> {
+    response
+        .pointer(pointer)
+        .and_then(|items| items.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    let vector = match vector_key {
+                        Some(key) => item.get(key),
+                        None => Some(item),
+                    };
+                    vector
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|x| x.as_f64())
+                        .map(|x| x as f32)
+                        .collect()
+                })
+                .collect()
+        })
+        .ok_or_else(|| TranscriptError::LlmError("The embeddings response was malformed.".into()))
+}
+
+pub(crate) fn network_error(provider: &str, e: reqwest::Error) -> TranscriptError {
+    TranscriptError::NetworkError(format!("Could not reach {}: {}", provider, e))
+}
+
+/// Parses the JSON object in a completion, tolerating Markdown code fences and
+/// text around the object that models add despite being told not to.
+pub fn parse_json<T: serde::de::DeserializeOwned>(completion: &str) -> Result<T, TranscriptError> {
+    let start = completion.find('{');
+    let end = completion.rfind('}');
+    let object = match (start, end) {
+        (Some(start), Some(end)) if start < end => &completion[start..=end],
+        _ => completion,
+    };
+    serde_json::from_str(object).map_err(|e| {
+        TranscriptError::LlmError(format!("The model returned an unexpected response: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Answer {
+        ok: bool,
+    }
+
+    #[test]
+    fn parses_json_wrapped_in_code_fences() {
+        let answer: Answer = parse_json("Here you go:\n```json\n{\"ok\": true}\n```").unwrap();
+        assert!(answer.ok);
+        assert!(parse_json::<Answer>("no json here").is_err());
+    }
+}
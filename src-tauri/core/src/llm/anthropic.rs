@@ -0,0 +1,173 @@
+use super::{
+    check_status, network_error, stream, Completion, CompletionRequest, ModelInfo, OnChunk,
+    Provider, TokenUsage,
+};
+use crate::error::TranscriptError;
+use futures::future::BoxFuture;
+
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+const MODELS_URL: &str = "https://api.anthropic.com/v1/models?limit=1000";
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MODEL: &str = "claude-haiku-4-5";
+const NAME: &str = "Anthropic";
+
+pub(crate) struct Anthropic {
+    client: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl Anthropic {
+    pub fn new(client: reqwest::Client, api_key: String, model: Option<String>) -> Self {
+        Self {
+            client,
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.into()),
+        }
+    }
+
+    async fn send(
+        &self,
+        request: &CompletionRequest,
+        on_chunk: Option<&OnChunk<'_>>,
+    ) -> Result<Completion, TranscriptError> {
+        // There is no JSON mode: `request.json` relies on the prompt asking for JSON
+        // and on `parse_json` skipping any text around the object
+        let body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": request.max_tokens,
+            "system": request.system,
+            "messages": [{ "role": "user", "content": request.prompt }],
+            "stream": on_chunk.is_some()
+        });
+
+        let res = self
+            .client
+            .post(API_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| network_error(NAME, e))?;
+        let res = check_status(NAME, res).await?;
+
+        if let Some(on_chunk) = on_chunk {
+            // Input tokens come with the start of the message; the output tokens so
+            // far with each delta of it
+            let mut usage = TokenUsage::default();
+            let text = stream::read_lines(NAME, res, on_chunk, |line| {
+                let Some(event) = stream::sse_data(line)
+                    .and_then(|data| serde_json::from_str::<serde_json::Value>(data).ok())
+                else {
+                    return Ok(None);
+                };
+                match event.get("type").and_then(|t| t.as_str()) {
+                    Some("message_start") => {
+                        usage = TokenUsage::at(
+                            &event,
+                            "/message/usage/input_tokens",
+                            "/message/usage/output_tokens",
+                        )
+                        .unwrap_or_default();
+                        Ok(None)
+                    }
+                    Some("message_delta") => {
+                        if let Some(output) = event
+                            .pointer("/usage/output_tokens")
+                            .and_then(|n| n.as_u64())
+                        {
+                            usage.output = output;
+                        }
+                        Ok(None)
+                    }
+                    Some("content_block_delta") => Ok(event
+                        .pointer("/delta/text")
+                        .and_then(|t| t.as_str())
+                        .map(str::to_string)),
+                    // Overload and other failures can arrive after the 200 response
+                    Some("error") => Err(TranscriptError::LlmError(format!(
+                        "Anthropic request failed: {}",
+                        event
+                            .pointer("/error/message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("unknown error")
+                    ))),
+                    _ => Ok(None),
+                }
+            })
+            .await?;
+            return Ok(Completion {
+                text,
+                usage: Some(usage),
+            });
+        }
+
+        let json: serde_json::Value = res.json().await.map_err(|e| network_error(NAME, e))?;
+
+        let text: String = json
+            .get("content")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect();
+        if text.is_empty() {
+            return Err(TranscriptError::LlmError(
+                "Anthropic returned no completion.".into(),
+            ));
+        }
+        Ok(Completion {
+            text,
+            usage: TokenUsage::at(&json, "/usage/input_tokens", "/usage/output_tokens"),
+        })
+    }
+}
+
+impl Provider for Anthropic {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        on_chunk: Option<&'a OnChunk<'a>>,
+    ) -> BoxFuture<'a, Result<Completion, TranscriptError>> {
+        Box::pin(self.send(request, on_chunk))
+    }
+}
+
+/// The models the account can use.
+pub(crate) async fn list_models(
+    client: reqwest::Client,
+    api_key: String,
+) -> Result<Vec<ModelInfo>, TranscriptError> {
+    let res = client
+        .get(MODELS_URL)
+        .header("x-api-key", &api_key)
+        .header("anthropic-version", API_VERSION)
+        .send()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    let json: serde_json::Value = check_status(NAME, res)
+        .await?
+        .json()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    Ok(json
+        .get("data")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            let id = m.get("id")?.as_str()?;
+            let name = m.get("display_name").and_then(|n| n.as_str()).unwrap_or(id);
+            Some(ModelInfo {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,198 @@
+use super::{
+    check_status, network_error, stream, Completion, CompletionRequest, ModelInfo, OnChunk,
+    Provider, TokenUsage,
+};
+use crate::error::TranscriptError;
+use futures::future::BoxFuture;
+
+const API_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+const DEFAULT_MODEL: &str = "gemini-2.5-flash";
+const NAME: &str = "Gemini";
+
+/// The text of a response, which comes split over the parts of its first
+/// candidate.
+fn text_of(response: &serde_json::Value) -> Option<String> {
+    let parts = response
+        .pointer("/candidates/0/content/parts")?
+        .as_array()?;
+    Some(
+        parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect(),
+    )
+}
+
+fn usage_of(response: &serde_json::Value) -> Option<TokenUsage> {
+    TokenUsage::at(
+        response,
+        "/usageMetadata/promptTokenCount",
+        "/usageMetadata/candidatesTokenCount",
+    )
+}
+
+pub(crate) struct Gemini {
+    client: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl Gemini {
+    pub fn new(client: reqwest::Client, api_key: String, model: Option<String>) -> Self {
+        Self {
+            client,
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.into()),
+        }
+    }
+
+    async fn send(
+        &self,
+        request: &CompletionRequest,
+        on_chunk: Option<&OnChunk<'_>>,
+    ) -> Result<Completion, TranscriptError> {
+        let mut body = serde_json::json!({
+            "systemInstruction": { "parts": [{ "text": request.system }] },
+            "contents": [{ "role": "user", "parts": [{ "text": request.prompt }] }],
+            "generationConfig": { "maxOutputTokens": request.max_tokens }
+        });
+        if request.json {
+            body["generationConfig"]["responseMimeType"] = "application/json".into();
+        }
+        let url = match on_chunk {
+            Some(_) => format!("{}/{}:streamGenerateContent?alt=sse", API_URL, self.model),
+            None => format!("{}/{}:generateContent", API_URL, self.model),
+        };
+
+        let res = self
+            .client
+            .post(url)
+            .header("x-goog-api-key", &self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| network_error(NAME, e))?;
+        let res = check_status(NAME, res).await?;
+
+        if let Some(on_chunk) = on_chunk {
+            // Each event carries the tokens used so far, so the last one has the total
+            let mut usage = None;
+            let text = stream::read_lines(NAME, res, on_chunk, |line| {
+                let Some(event) = stream::sse_data(line)
+                    .and_then(|data| serde_json::from_str::<serde_json::Value>(data).ok())
+                else {
+                    return Ok(None);
+                };
+                usage = usage_of(&event).or(usage);
+                Ok(text_of(&event))
+            })
+            .await?;
+            return Ok(Completion { text, usage });
+        }
+
+        let json: serde_json::Value = res.json().await.map_err(|e| network_error(NAME, e))?;
+
+        let text = text_of(&json)
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| TranscriptError::LlmError("Gemini returned no completion.".into()))?;
+        Ok(Completion {
+            text,
+            usage: usage_of(&json),
+        })
+    }
+}
+
+impl Provider for Gemini {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        on_chunk: Option<&'a OnChunk<'a>>,
+    ) -> BoxFuture<'a, Result<Completion, TranscriptError>> {
+        Box::pin(self.send(request, on_chunk))
+    }
+}
+
+/// The models in a model list that generate text, rather than only embeddings.
+fn models_of(list: &serde_json::Value) -> Vec<ModelInfo> {
+    list.get("models")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|m| {
+            m.get("supportedGenerationMethods")
+                .and_then(|s| s.as_array())
+                .is_some_and(|s| s.iter().any(|s| s == "generateContent"))
+        })
+        .filter_map(|m| {
+            let id = m.get("name")?.as_str()?;
+            let id = id.strip_prefix("models/").unwrap_or(id);
+            let name = m.get("displayName").and_then(|n| n.as_str()).unwrap_or(id);
+            Some(ModelInfo {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The models the key can generate text with.
+pub(crate) async fn list_models(
+    client: reqwest::Client,
+    api_key: String,
+) -> Result<Vec<ModelInfo>, TranscriptError> {
+    let res = client
+        .get(format!("{}?pageSize=1000", API_URL))
+        .header("x-goog-api-key", &api_key)
+        .send()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    let json: serde_json::Value = check_status(NAME, res)
+        .await?
+        .json()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    Ok(models_of(&json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_responses_and_model_lists() {
+        let response = json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello, " }, { "text": "world" }] } }],
+            "usageMetadata": { "promptTokenCount": 12, "candidatesTokenCount": 3 }
+        });
+        assert_eq!(text_of(&response).as_deref(), Some("Hello, world"));
+        assert_eq!(
+            usage_of(&response),
+            Some(TokenUsage {
+                input: 12,
+                output: 3
+            })
+        );
+        assert_eq!(text_of(&json!({ "candidates": [] })), None);
+
+        let list = json!({ "models": [
+            {
+                "name": "models/gemini-2.5-flash",
+                "displayName": "Gemini 2.5 Flash",
+                "supportedGenerationMethods": ["generateContent", "countTokens"]
+            },
+            { "name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"] }
+        ] });
+        assert_eq!(
+            models_of(&list),
+            [ModelInfo {
+                id: "gemini-2.5-flash".into(),
+                name: "Gemini 2.5 Flash".into()
+            }]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Rewriting transcript lines with a model while keeping them apart.
+//!
+//! The lines go out as `{"lines": [{"id", "text"}]}` in batches of consecutive
+//! lines, so the model reads each in the context of its neighbours, and come back
+//! under the same ids. One reply line per line sent is what lets callers keep the
+//! timing of every segment.
+
+use super::{parse_json, CompletionRequest, Provider};
+use crate::error::TranscriptError;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Transcript characters per prompt. Kept small, since the reply has to repeat
+/// every line and long replies are the likeliest to lose some.
+const BATCH_CHARS: usize = 3_000;
+const CONCURRENCY: usize = 3;
+const MAX_TOKENS: u32 = 4_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Line {
+    id: usize,
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Lines {
+    lines: Vec<Line>,
+}
+
+/// Splits `texts` into consecutive runs of at most `max_chars` (a longer text gets
+/// a run of its own), given as index ranges.
+fn batches(texts: &[String], max_chars: usize) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = 0;
+    for (i, text) in texts.iter().enumerate() {
+        let len = text.chars().count();
+        if i > start && chars + len > max_chars {
+            out.push(start..i);
+            start = i;
+            chars = 0;
+        }
+        chars += len;
+    }
+    if start < texts.len() {
+        out.push(start..texts.len());
+    }
+    out
+}
+
+async fn rewrite_lines(
+    provider: &dyn Provider,
+    system: &str,
+    instruction: &str,
+    lines: Vec<Line>,
+) -> Result<HashMap<usize, String>, TranscriptError> {
+    let request = CompletionRequest {
+        system: system.into(),
+        prompt: format!(
+            "{}\n\n{}",
+            instruction,
+            serde_json::to_string(&Lines { lines }).unwrap_or_default()
+        ),
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    let reply: Lines = parse_json(&provider.complete(&request, None).await?.text)?;
+    Ok(reply.lines.into_iter().map(|l| (l.id, l.text)).collect())
+}
+
+/// Rewrites one batch, asking again for the lines a first reply left out.
+async fn rewrite_batch(
+    provider: &dyn Provider,
+    system: &str,
+    instruction: &str,
+    texts: &[String],
+    ids: Range<usize>,
+) -> Result<Vec<String>, TranscriptError> {
+    let line = |id: usize| Line {
+        id,
+        text: texts[id].clone(),
+    };
+    let mut rewritten = rewrite_lines(
+        provider,
+        system,
+        instruction,
+        ids.clone().map(line).collect(),
+    )
+    .await?;
+
+    let retry: Vec<Line> = ids
+        .clone()
+        .filter(|id| !rewritten.contains_key(id))
+        .map(line)
+        .collect();
+    if !retry.is_empty() {
+        rewritten.extend(rewrite_lines(provider, system, instruction, retry).await?);
+    }
+
+    ids.map(|id| {
+        rewritten.remove(&id).ok_or_else(|| {
+            TranscriptError::LlmError("The model skipped lines of the transcript.".into())
+        })
+    })
+    .collect()
+}
+
+/// Has `provider` rewrite every one of `texts` as `system` and `instruction` say,
+/// returning one text per text sent, in order.
+pub(crate) async fn rewrite(
+    provider: &dyn Provider,
+    system: &str,
+    instruction: &str,
+    texts: &[String],
+) -> Result<Vec<String>, TranscriptError> {
+    // The futures are built up front: a closure in `map` would make the command's
+    // future fail the `Send` check
+    let requests: Vec<_> = batches(texts, BATCH_CHARS)
+        .into_iter()
+        .map(|ids| rewrite_batch(provider, system, instruction, texts, ids))
+        .collect();
+    let rewritten: Vec<Vec<String>> = stream::iter(requests)
+        .buffered(CONCURRENCY)
+        .try_collect()
+        .await?;
+    Ok(rewritten.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_consecutive_lines_under_the_limit() {
+        let texts: Vec<String> = ["aaaa", "bbb", "cc", "dddddddd", "e"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(batches(&texts, 7), [0..2, 2..3, 3..4, 4..5]);
+        assert_eq!(batches(&texts, 100), vec![0..5]);
+        assert!(batches(&[], 7).is_empty());
+    }
+}
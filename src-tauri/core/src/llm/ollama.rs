@@ -0,0 +1,254 @@
+//! A local Ollama server, so transcripts never leave the machine.
+
+use super::{
+    check_status, embeddings_at, stream, Completion, CompletionRequest, OnChunk, Provider,
+    TokenUsage,
+};
+use crate::error::TranscriptError;
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::Duration;
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const DEFAULT_MODEL: &str = "llama3.2";
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+const NAME: &str = "Ollama";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OllamaConfig {
+    /// Server address; `http://localhost:11434` when empty.
+    pub base_url: String,
+    /// Model used when a request does not name one.
+    pub model: Option<String>,
+    /// Model used for embeddings; `nomic-embed-text` when unset.
+    pub embedding_model: Option<String>,
+}
+
+impl OllamaConfig {
+    fn base_url(&self) -> String {
+        match self.base_url.trim().trim_end_matches('/') {
+            "" => DEFAULT_BASE_URL.into(),
+            url => url.into(),
+        }
+    }
+}
+
+/// A model pulled into the local server.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default, alias = "modified_at")]
+    pub modified_at: String,
+}
+
+static CONFIG: Lazy<RwLock<OllamaConfig>> = Lazy::new(|| RwLock::new(OllamaConfig::default()));
+
+pub(crate) fn current() -> OllamaConfig {
+    CONFIG.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+pub(crate) fn set_config(config: OllamaConfig) {
+    if let Ok(mut current) = CONFIG.write() {
+        *current = config;
+    }
+}
+
+/// Connection failures nearly always mean the server is not running.
+fn unreachable(base_url: &str, e: reqwest::Error) -> TranscriptError {
+    TranscriptError::NetworkError(format!(
+        "Could not reach Ollama at {}. Make sure it is running. ({})",
+        base_url, e
+    ))
+}
+
+fn usage_of(response: &serde_json::Value) -> Option<TokenUsage> {
+    TokenUsage::at(response, "/prompt_eval_count", "/eval_count")
+}
+
+pub(crate) struct Ollama {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+}
+
+impl Ollama {
+    pub fn new(client: reqwest::Client, model: Option<String>) -> Self {
+        let config = current();
+        Self {
+            client,
+            base_url: config.base_url(),
+            model: model
+                .or(config.model.filter(|m| !m.trim().is_empty()))
+                .unwrap_or_else(|| DEFAULT_MODEL.into()),
+            embedding_model: config
+                .embedding_model
+                .filter(|m| !m.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.into()),
+        }
+    }
+
+    async fn send(
+        &self,
+        request: &CompletionRequest,
+        on_chunk: Option<&OnChunk<'_>>,
+    ) -> Result<Completion, TranscriptError> {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "stream": on_chunk.is_some(),
+            "messages": [
+                { "role": "system", "content": request.system },
+                { "role": "user", "content": request.prompt }
+            ],
+            "options": { "num_predict": request.max_tokens }
+        });
+        if request.json {
+            body["format"] = "json".into();
+        }
+
+        let res = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+        let res = check_status(NAME, res).await?;
+
+        // Streamed responses are one JSON object per line
+        if let Some(on_chunk) = on_chunk {
+            // The last line, marked done, has the counts
+            let mut usage = None;
+            let text = stream::read_lines(NAME, res, on_chunk, |line| {
+                let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+                    return Ok(None);
+                };
+                if let Some(error) = event.get("error").and_then(|e| e.as_str()) {
+                    return Err(TranscriptError::LlmError(format!(
+                        "Ollama request failed: {}",
+                        error
+                    )));
+                }
+                usage = usage.or(usage_of(&event));
+                Ok(event
+                    .pointer("/message/content")
+                    .and_then(|c| c.as_str())
+                    .map(str::to_string))
+            })
+            .await?;
+            return Ok(Completion { text, usage });
+        }
+
+        let json: serde_json::Value = res
+            .json()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+
+        let text = json
+            .pointer("/message/content")
+            .and_then(|c| c.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| TranscriptError::LlmError("Ollama returned no completion.".into()))?;
+        Ok(Completion {
+            text,
+            usage: usage_of(&json),
+        })
+    }
+
+    async fn embed_texts(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, TranscriptError> {
+        let res = self
+            .client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&serde_json::json!({ "model": self.embedding_model, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+        let json: serde_json::Value = check_status(NAME, res)
+            .await?
+            .json()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+        embeddings_at(&json, "/embeddings", None)
+    }
+}
+
+impl Provider for Ollama {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        on_chunk: Option<&'a OnChunk<'a>>,
+    ) -> BoxFuture<'a, Result<Completion, TranscriptError>> {
+        Box::pin(self.send(request, on_chunk))
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        Some(&self.embedding_model)
+    }
+
+    fn embed<'a>(
+        &'a self,
+        texts: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Vec<f32>>, TranscriptError>> {
+        Box::pin(self.embed_texts(texts))
+    }
+}
+
+/// Lists the models available on the Ollama server at `base_url`, or the
+/// configured one when omitted.
+pub async fn list_local_models(
+    base_url: Option<String>,
+) -> Result<Vec<LocalModel>, TranscriptError> {
+    let config = OllamaConfig {
+        base_url: base_url.unwrap_or_else(|| current().base_url),
+        ..Default::default()
+    };
+    let base_url = config.base_url();
+    let res = super::client(Duration::from_secs(10))?
+        .get(format!("{}/api/tags", base_url))
+        .send()
+        .await
+        .map_err(|e| unreachable(&base_url, e))?;
+
+    #[derive(Deserialize)]
+    struct Tags {
+        #[serde(default)]
+        models: Vec<LocalModel>,
+    }
+    let tags: Tags =
+        check_status(NAME, res).await?.json().await.map_err(|e| {
+            TranscriptError::ParseError(format!("Unexpected Ollama response: {}", e))
+        })?;
+    Ok(tags.models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tags_and_normalizes_base_url() {
+        let json = r#"{"models": [{"name": "llama3.2:latest", "size": 2019393189,
+                       "modified_at": "2025-01-01T00:00:00Z", "digest": "abc"}]}"#;
+        let tags: serde_json::Value = serde_json::from_str(json).unwrap();
+        let model: LocalModel = serde_json::from_value(tags["models"][0].clone()).unwrap();
+        assert_eq!(model.name, "llama3.2:latest");
+        assert_eq!(model.modified_at, "2025-01-01T00:00:00Z");
+
+        let config = OllamaConfig {
+            base_url: " http://gpu-box:11434/ ".into(),
+            ..Default::default()
+        };
+        assert_eq!(config.base_url(), "http://gpu-box:11434");
+        assert_eq!(OllamaConfig::default().base_url(), DEFAULT_BASE_URL);
+    }
+}
@@ -0,0 +1,174 @@
+use super::{
+    check_status, embeddings_at, network_error, stream, Completion, CompletionRequest, ModelInfo,
+    OnChunk, Provider, TokenUsage,
+};
+use crate::error::TranscriptError;
+use futures::future::BoxFuture;
+use reqwest::header::AUTHORIZATION;
+
+const API_URL: &str = "https://api.openai.com/v1/chat/completions";
+const EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+const MODELS_URL: &str = "https://api.openai.com/v1/models";
+/// Prefixes of the chat models, among the embedding, audio and image ones the API
+/// also lists.
+const CHAT_PREFIXES: [&str; 7] = ["gpt-5", "gpt-4", "gpt-3.5", "o1", "o3", "o4", "chatgpt"];
+const DEFAULT_MODEL: &str = "gpt-4.1-nano";
+const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+const NAME: &str = "OpenAI";
+
+fn usage_of(response: &serde_json::Value) -> Option<TokenUsage> {
+    TokenUsage::at(response, "/usage/prompt_tokens", "/usage/completion_tokens")
+}
+
+pub(crate) struct OpenAi {
+    client: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAi {
+    pub fn new(client: reqwest::Client, api_key: String, model: Option<String>) -> Self {
+        Self {
+            client,
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.into()),
+        }
+    }
+
+    async fn send(
+        &self,
+        request: &CompletionRequest,
+        on_chunk: Option<&OnChunk<'_>>,
+    ) -> Result<Completion, TranscriptError> {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_completion_tokens": request.max_tokens,
+            "messages": [
+                { "role": "system", "content": request.system },
+                { "role": "user", "content": request.prompt }
+            ],
+            "stream": on_chunk.is_some()
+        });
+        if request.json {
+            body["response_format"] = serde_json::json!({ "type": "json_object" });
+        }
+        if on_chunk.is_some() {
+            // Streams only report the tokens used, in a last chunk, when asked to
+            body["stream_options"] = serde_json::json!({ "include_usage": true });
+        }
+
+        let res = self
+            .client
+            .post(API_URL)
+            .header(AUTHORIZATION, format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| network_error(NAME, e))?;
+        let res = check_status(NAME, res).await?;
+
+        if let Some(on_chunk) = on_chunk {
+            let mut usage = None;
+            let text =
+                stream::read_lines(NAME, res, on_chunk, |line| match stream::sse_data(line) {
+                    Some(data) if data != "[DONE]" => {
+                        let Ok(event) = serde_json::from_str::<serde_json::Value>(data) else {
+                            return Ok(None);
+                        };
+                        usage = usage.or(usage_of(&event));
+                        Ok(event
+                            .pointer("/choices/0/delta/content")
+                            .and_then(|c| c.as_str())
+                            .map(str::to_string))
+                    }
+                    _ => Ok(None),
+                })
+                .await?;
+            return Ok(Completion { text, usage });
+        }
+
+        let json: serde_json::Value = res.json().await.map_err(|e| network_error(NAME, e))?;
+
+        let text = json
+            .pointer("/choices/0/message/content")
+            .and_then(|c| c.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| TranscriptError::LlmError("OpenAI returned no completion.".into()))?;
+        Ok(Completion {
+            text,
+            usage: usage_of(&json),
+        })
+    }
+
+    async fn embed_texts(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, TranscriptError> {
+        let res = self
+            .client
+            .post(EMBEDDINGS_URL)
+            .header(AUTHORIZATION, format!("Bearer {}", self.api_key))
+            .json(&serde_json::json!({ "model": EMBEDDING_MODEL, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| network_error(NAME, e))?;
+        let json: serde_json::Value = check_status(NAME, res)
+            .await?
+            .json()
+            .await
+            .map_err(|e| network_error(NAME, e))?;
+        embeddings_at(&json, "/data", Some("embedding"))
+    }
+}
+
+impl Provider for OpenAi {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        on_chunk: Option<&'a OnChunk<'a>>,
+    ) -> BoxFuture<'a, Result<Completion, TranscriptError>> {
+        Box::pin(self.send(request, on_chunk))
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        Some(EMBEDDING_MODEL)
+    }
+
+    fn embed<'a>(
+        &'a self,
+        texts: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Vec<f32>>, TranscriptError>> {
+        Box::pin(self.embed_texts(texts))
+    }
+}
+
+/// The chat models the account can use.
+pub(crate) async fn list_models(
+    client: reqwest::Client,
+    api_key: String,
+) -> Result<Vec<ModelInfo>, TranscriptError> {
+    let res = client
+        .get(MODELS_URL)
+        .header(AUTHORIZATION, format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    let json: serde_json::Value = check_status(NAME, res)
+        .await?
+        .json()
+        .await
+        .map_err(|e| network_error(NAME, e))?;
+    Ok(json
+        .get("data")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m.get("id").and_then(|id| id.as_str()))
+        .filter(|id| CHAT_PREFIXES.iter().any(|p| id.starts_with(p)))
+        .map(|id| ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+        })
+        .collect())
+}
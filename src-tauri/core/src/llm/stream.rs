@@ -0,0 +1,90 @@
+//! Reading streamed completions, piece by piece.
+
+use super::{network_error, OnChunk};
+use crate::error::TranscriptError;
+
+/// Splits a byte stream into lines, holding back an incomplete last line until
+/// the rest of it arrives.
+#[derive(Debug, Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let Some(last_newline) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn finish(self) -> Option<String> {
+        let line = String::from_utf8_lossy(&self.pending).trim().to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+/// The payload of a server-sent event `data:` line.
+pub(crate) fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim_start)
+}
+
+/// Reads a streamed completion line by line. `delta` turns a line into the text it
+/// adds (or an error the provider reported mid-stream), noting the tokens used
+/// where the line reports them; every piece is passed to `on_chunk`, and the
+/// whole text is returned.
+pub(crate) async fn read_lines(
+    provider: &str,
+    mut res: reqwest::Response,
+    on_chunk: &OnChunk<'_>,
+    mut delta: impl FnMut(&str) -> Result<Option<String>, TranscriptError>,
+) -> Result<String, TranscriptError> {
+    let mut buffer = LineBuffer::default();
+    let mut text = String::new();
+    let mut handle = |line: &str| -> Result<(), TranscriptError> {
+        if let Some(piece) = delta(line.trim_end_matches('\r'))? {
+            if !piece.is_empty() {
+                on_chunk(&piece);
+                text.push_str(&piece);
+            }
+        }
+        Ok(())
+    };
+
+    while let Some(bytes) = res.chunk().await.map_err(|e| network_error(provider, e))? {
+        for line in buffer.push(&bytes) {
+            handle(&line)?;
+        }
+    }
+    if let Some(line) = buffer.finish() {
+        handle(&line)?;
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_lines_split_across_chunks() {
+        let mut buffer = LineBuffer::default();
+        assert_eq!(buffer.push(b"data: {\"a\""), Vec::<String>::new());
+        assert_eq!(
+            buffer.push(b":1}\r\n\r\ndata: [DO"),
+            ["data: {\"a\":1}", ""]
+        );
+        assert_eq!(buffer.push(b"NE]\n"), ["data: [DONE]"]);
+        assert_eq!(buffer.push(b"{\"done\":true}"), Vec::<String>::new());
+        assert_eq!(buffer.finish().as_deref(), Some("{\"done\":true}"));
+
+        assert_eq!(sse_data("data: [DONE]"), Some("[DONE]"));
+        assert_eq!(sse_data("event: ping"), None);
+    }
+}
@@ -0,0 +1,650 @@
+//! What the language models cost: the tokens each call used, their estimated price,
+//! and monthly budgets that stop further calls once spent.
+//!
+//! Every call made through a provider from [`super::provider`] is recorded with the
+//! feature that made it. Token counts are the provider's own where it reports them,
+//! and estimated from the length of the text otherwise (embeddings, mostly). Costs
+//! are estimated from list prices, which the settings can add to or correct; local
+//! models cost nothing, and calls to models of unknown price are counted without a
+//! cost. Budgets run per calendar month, in US dollars, and are checked before each
+//! call to a hosted provider; a call already under way when a budget runs out is
+//! still let finish. Models of unknown price are refused while a budget covers
+//! their provider, as nothing would stop them spending it. A streamed completion
+//! that fails or is cancelled part-way is recorded with the text it had produced.
+
+use super::{Completion, CompletionRequest, OnChunk, Provider, ProviderKind, TokenUsage};
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use futures::future::BoxFuture;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider      TEXT    NOT NULL,
+    model         TEXT    NOT NULL,
+    operation     TEXT    NOT NULL,
+    input_tokens  INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    -- Estimated US dollars; NULL when the price of the model is unknown
+    cost          REAL,
+    created_at    INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS llm_usage_created ON llm_usage (created_at);
+";
+
+/// List prices in US dollars per million input and output tokens, by the start of
+/// the model name, so dated snapshots (`claude-haiku-4-5-20251001`) match too.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("o4-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+    ("text-embedding-3-small", 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.0),
+    ("claude-haiku-4-5", 1.00, 5.00),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-opus-4-5", 5.00, 25.00),
+    ("claude-opus-4", 15.00, 75.00),
+    ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.0-flash-lite", 0.075, 0.30),
+    ("gemini-2.0-flash", 0.10, 0.40),
+];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageSettings {
+    /// Most US dollars to spend on all hosted providers in a calendar month;
+    /// unlimited when unset.
+    pub monthly_budget: Option<f64>,
+    /// Most US dollars to spend on each provider in a calendar month.
+    pub provider_budgets: HashMap<ProviderKind, f64>,
+    /// Prices of models the built-in list lacks or has wrong.
+    pub prices: Vec<ModelPrice>,
+}
+
+impl UsageSettings {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        let valid = |dollars: f64| dollars.is_finite() && dollars >= 0.0;
+        let mut budgets = self
+            .monthly_budget
+            .into_iter()
+            .chain(self.provider_budgets.values().copied());
+        if !budgets.all(valid) {
+            return Err(TranscriptError::InvalidInput(
+                "Budgets must be amounts of at least $0.".into(),
+            ));
+        }
+        if self
+            .prices
+            .iter()
+            .any(|p| p.model.trim().is_empty() || !valid(p.input) || !valid(p.output))
+        {
+            return Err(TranscriptError::InvalidInput(
+                "Each model price needs the name of the model and prices of at least $0.".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelPrice {
+    /// The model, or the start of the names of a family of them.
+    pub model: String,
+    /// US dollars per million input tokens.
+    pub input: f64,
+    /// US dollars per million output tokens.
+    pub output: f64,
+}
+
+/// The price per million input and output tokens of `model`, from the settings or
+/// the built-in list, the longest matching name winning; `None` when unknown.
+fn price(kind: ProviderKind, model: &str, custom: &[ModelPrice]) -> Option<(f64, f64)> {
+    if kind == ProviderKind::Ollama {
+        return Some((0.0, 0.0));
+    }
+    let listed = PRICES.iter().copied();
+    let custom = custom.iter().map(|p| (p.model.trim(), p.input, p.output));
+    // On equal length the last wins, so the settings go after the list
+    listed
+        .chain(custom)
+        .filter(|(name, _, _)| model.starts_with(name))
+        .max_by_key(|(name, _, _)| name.len())
+        .map(|(_, input, output)| (input, output))
+}
+
+fn cost((input, output): (f64, f64), usage: TokenUsage) -> f64 {
+    (usage.input as f64 * input + usage.output as f64 * output) / 1_000_000.0
+}
+
+/// A rough token count for `text`: about four characters each, in English.
+fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UsagePeriod {
+    /// Since midnight.
+    Today,
+    /// The last seven days.
+    Week,
+    /// Since the start of the calendar month, which budgets run over.
+    #[default]
+    Month,
+    All,
+}
+
+impl UsagePeriod {
+    /// When the period started, in seconds since the epoch, by the local clock.
+    fn start(self, conn: &Connection) -> rusqlite::Result<i64> {
+        let modifiers = match self {
+            Self::Today => "'localtime', 'start of day', 'utc'",
+            Self::Week => "'-7 days'",
+            Self::Month => "'localtime', 'start of month', 'utc'",
+            Self::All => return Ok(0),
+        };
+        conn.query_row(
+            &format!(
+                "SELECT CAST(strftime('%s', 'now', {}) AS INTEGER)",
+                modifiers
+            ),
+            [],
+            |row| row.get(0),
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated US dollars.
+    pub cost: f64,
+    /// Calls to models of unknown price, which `cost` leaves out.
+    pub unpriced_calls: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &Self) {
+        self.calls += other.calls;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+        self.unpriced_calls += other.unpriced_calls;
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBreakdown {
+    pub provider: ProviderKind,
+    pub model: String,
+    /// The feature that made the calls, such as `summary` or `question`.
+    pub operation: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Budget {
+    /// The provider the budget is for; all hosted ones when unset.
+    pub provider: Option<ProviderKind>,
+    /// US dollars a month.
+    pub limit: f64,
+    /// Spent this calendar month.
+    pub spent: f64,
+}
+
+impl Budget {
+    fn covers(&self, kind: ProviderKind) -> bool {
+        self.provider.is_none() || self.provider == Some(kind)
+    }
+
+    fn exceeded(&self) -> bool {
+        self.spent >= self.limit
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmUsage {
+    pub period: UsagePeriod,
+    /// Start of the period, in seconds since the epoch.
+    pub since: i64,
+    pub total: UsageTotals,
+    /// By provider, model and operation, most expensive first.
+    pub breakdown: Vec<UsageBreakdown>,
+    /// The budgets in the settings, with what was spent against them this month.
+    pub budgets: Vec<Budget>,
+}
+
+fn insert(
+    conn: &Connection,
+    kind: ProviderKind,
+    model: &str,
+    operation: &str,
+    usage: TokenUsage,
+    cost: Option<f64>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO llm_usage
+             (provider, model, operation, input_tokens, output_tokens, cost, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            kind.as_str(),
+            model,
+            operation,
+            usage.input as i64,
+            usage.output as i64,
+            cost,
+            unix_now()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Records a call to `model` of `kind`. Failing to only costs a warning in the logs.
+fn record(db: &Database, kind: ProviderKind, model: &str, operation: &str, usage: TokenUsage) {
+    let settings = crate::settings::current().llm_usage;
+    let cost = price(kind, model, &settings.prices).map(|p| cost(p, usage));
+    if let Err(e) = db.with_conn(|conn| insert(conn, kind, model, operation, usage, cost)) {
+        tracing::warn!(model, error = %e, "LLM usage not recorded");
+    }
+}
+
+fn spent_since(conn: &Connection, since: i64, kind: Option<ProviderKind>) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost), 0) FROM llm_usage
+         WHERE created_at >= ?1 AND (?2 IS NULL OR provider = ?2)",
+        params![since, kind.map(ProviderKind::as_str)],
+        |row| row.get(0),
+    )
+}
+
+/// The budgets in `settings`, with what was spent against them this month.
+fn budgets(conn: &Connection, settings: &UsageSettings) -> rusqlite::Result<Vec<Budget>> {
+    let month = UsagePeriod::Month.start(conn)?;
+    let limits = std::iter::once((None, settings.monthly_budget)).chain(
+        ProviderKind::ALL
+            .into_iter()
+            .map(|kind| (Some(kind), settings.provider_budgets.get(&kind).copied())),
+    );
+    let mut budgets = Vec::new();
+    for (provider, limit) in limits {
+        if let Some(limit) = limit {
+            budgets.push(Budget {
+                provider,
+                limit,
+                spent: spent_since(conn, month, provider)?,
+            });
+        }
+    }
+    Ok(budgets)
+}
+
+/// Fails once a budget covering `kind` is spent for the month, or when a budget
+/// covers it and the price of `model` is unknown.
+fn check_budget(db: &Database, kind: ProviderKind, model: &str) -> Result<(), TranscriptError> {
+    if kind == ProviderKind::Ollama {
+        return Ok(());
+    }
+    let settings = crate::settings::current().llm_usage;
+    if settings.monthly_budget.is_none() && !settings.provider_budgets.contains_key(&kind) {
+        return Ok(());
+    }
+    if price(kind, model, &settings.prices).is_none() {
+        return Err(TranscriptError::BudgetExceeded(format!(
+            "The price of {} is unknown, so its calls cannot be kept within the budget. \
+             Add it to the model prices in the settings.",
+            model
+        )));
+    }
+    let budgets = db.with_conn(|conn| budgets(conn, &settings))?;
+    let Some(budget) = budgets.iter().find(|b| b.covers(kind) && b.exceeded()) else {
+        return Ok(());
+    };
+    let name = budget
+        .provider
+        .map_or("language model", ProviderKind::display_name);
+    Err(TranscriptError::BudgetExceeded(format!(
+        "This month's {} budget of ${:.2} is spent (${:.2} so far). Raise it in the \
+         settings to make more calls.",
+        name, budget.limit, budget.spent
+    )))
+}
+
+fn usage(
+    conn: &Connection,
+    period: UsagePeriod,
+    settings: &UsageSettings,
+) -> rusqlite::Result<LlmUsage> {
+    let since = period.start(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT provider, model, operation, COUNT(*), SUM(input_tokens), SUM(output_tokens),
+                COALESCE(SUM(cost), 0), COUNT(*) - COUNT(cost)
+         FROM llm_usage
+         WHERE created_at >= ?1
+         GROUP BY provider, model, operation
+         ORDER BY 7 DESC, 4 DESC",
+    )?;
+    let rows = stmt.query_map([since], |row| {
+        let provider: String = row.get(0)?;
+        let Some(provider) = ProviderKind::parse(&provider) else {
+            return Ok(None);
+        };
+        Ok(Some(UsageBreakdown {
+            provider,
+            model: row.get(1)?,
+            operation: row.get(2)?,
+            totals: UsageTotals {
+                calls: row.get(3)?,
+                input_tokens: row.get(4)?,
+                output_tokens: row.get(5)?,
+                cost: row.get(6)?,
+                unpriced_calls: row.get(7)?,
+            },
+        }))
+    })?;
+    let breakdown: Vec<UsageBreakdown> = rows
+        .filter_map(Result::transpose)
+        .collect::<rusqlite::Result<_>>()?;
+    let mut total = UsageTotals::default();
+    for entry in &breakdown {
+        total.add(&entry.totals);
+    }
+    Ok(LlmUsage {
+        period,
+        since,
+        total,
+        breakdown,
+        budgets: budgets(conn, settings)?,
+    })
+}
+
+/// Records a streamed completion that ends without a result, failed or cancelled,
+/// when dropped: providers bill the tokens generated up to then.
+struct PartialStream<'a> {
+    metered: &'a Metered<'a>,
+    request: &'a CompletionRequest,
+    /// Characters streamed so far.
+    received: &'a AtomicU64,
+    finished: bool,
+}
+
+impl Drop for PartialStream<'_> {
+    fn drop(&mut self) {
+        let received = self.received.load(Ordering::Relaxed);
+        if self.finished || received == 0 {
+            return;
+        }
+        let usage = TokenUsage {
+            input: estimate_tokens(&self.request.system) + estimate_tokens(&self.request.prompt),
+            output: received.div_ceil(4),
+        };
+        let metered = self.metered;
+        record(
+            metered.db,
+            metered.kind,
+            metered.model(),
+            metered.operation,
+            usage,
+        );
+    }
+}
+
+/// A provider whose calls are checked against the budgets and recorded.
+pub(crate) struct Metered<'a> {
+    db: &'a Database,
+    kind: ProviderKind,
+    operation: &'static str,
+    inner: Box<dyn Provider>,
+}
+
+impl<'a> Metered<'a> {
+    pub(crate) fn new(
+        db: &'a Database,
+        kind: ProviderKind,
+        operation: &'static str,
+        inner: Box<dyn Provider>,
+    ) -> Self {
+        Self {
+            db,
+            kind,
+            operation,
+            inner,
+        }
+    }
+}
+
+impl Provider for Metered<'_> {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn complete<'b>(
+        &'b self,
+        request: &'b CompletionRequest,
+        on_chunk: Option<&'b OnChunk>,
+    ) -> BoxFuture<'b, Result<Completion, TranscriptError>> {
+        Box::pin(async move {
+            check_budget(self.db, self.kind, self.model())?;
+            let received = AtomicU64::new(0);
+            let counting = on_chunk.map(|on_chunk| {
+                let received = &received;
+                move |chunk: &str| {
+                    received.fetch_add(chunk.chars().count() as u64, Ordering::Relaxed);
+                    on_chunk(chunk);
+                }
+            });
+            let mut partial = PartialStream {
+                metered: self,
+                request,
+                received: &received,
+                finished: false,
+            };
+            let completion = self
+                .inner
+                .complete(request, counting.as_ref().map(|c| c as &OnChunk))
+                .await?;
+            partial.finished = true;
+            let usage = completion.usage.unwrap_or_else(|| TokenUsage {
+                input: estimate_tokens(&request.system) + estimate_tokens(&request.prompt),
+                output: estimate_tokens(&completion.text),
+            });
+            record(self.db, self.kind, self.model(), self.operation, usage);
+            Ok(completion)
+        })
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        self.inner.embedding_model()
+    }
+
+    fn embed<'b>(
+        &'b self,
+        texts: &'b [String],
+    ) -> BoxFuture<'b, Result<Vec<Vec<f32>>, TranscriptError>> {
+        Box::pin(async move {
+            let model = self.embedding_model().unwrap_or(self.model());
+            check_budget(self.db, self.kind, model)?;
+            let vectors = self.inner.embed(texts).await?;
+            let usage = TokenUsage {
+                input: texts.iter().map(|t| estimate_tokens(t)).sum(),
+                output: 0,
+            };
+            record(self.db, self.kind, model, self.operation, usage);
+            Ok(vectors)
+        })
+    }
+}
+
+/// The tokens used and what they are estimated to have cost over `period` (this
+/// month by default), by provider, model and operation, with the budgets in the
+/// settings.
+pub fn get_llm_usage(
+    db: &Database,
+    period: Option<UsagePeriod>,
+) -> Result<LlmUsage, TranscriptError> {
+    let settings = crate::settings::current().llm_usage;
+    db.with_conn(|conn| usage(conn, period.unwrap_or_default(), &settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_calls_by_the_longest_matching_model() {
+        let tokens = TokenUsage {
+            input: 2_000_000,
+            output: 1_000_000,
+        };
+        assert_eq!(
+            price(ProviderKind::OpenAi, "gpt-4.1-nano", &[]),
+            Some((0.10, 0.40))
+        );
+        assert_eq!(
+            price(ProviderKind::Anthropic, "claude-haiku-4-5-20251001", &[])
+                .map(|p| cost(p, tokens)),
+            Some(7.0)
+        );
+        assert_eq!(price(ProviderKind::OpenAi, "ft:custom", &[]), None);
+        assert_eq!(
+            price(ProviderKind::Ollama, "llama3.2", &[]),
+            Some((0.0, 0.0))
+        );
+        let custom = [ModelPrice {
+            model: "gpt-4.1".into(),
+            input: 1.0,
+            output: 2.0,
+        }];
+        assert_eq!(
+            price(ProviderKind::OpenAi, "gpt-4.1-2025-04-14", &custom),
+            Some((1.0, 2.0))
+        );
+        assert_eq!(
+            price(ProviderKind::OpenAi, "gpt-4.1-mini", &custom),
+            Some((0.40, 1.60))
+        );
+    }
+
+    #[test]
+    fn totals_usage_against_the_budgets() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        let tokens = TokenUsage {
+            input: 1000,
+            output: 200,
+        };
+        for (kind, model, operation, cost) in [
+            (ProviderKind::OpenAi, "gpt-4.1-nano", "summary", Some(0.5)),
+            (ProviderKind::OpenAi, "gpt-4.1-nano", "summary", Some(0.5)),
+            (
+                ProviderKind::Anthropic,
+                "claude-haiku-4-5",
+                "question",
+                Some(2.0),
+            ),
+            (ProviderKind::OpenAi, "ft:custom", "summary", None),
+        ] {
+            insert(&conn, kind, model, operation, tokens, cost).unwrap();
+        }
+
+        let settings = UsageSettings {
+            monthly_budget: Some(5.0),
+            provider_budgets: HashMap::from([(ProviderKind::OpenAi, 1.0)]),
+            ..UsageSettings::default()
+        };
+        let report = usage(&conn, UsagePeriod::All, &settings).unwrap();
+        assert_eq!(report.total.calls, 4);
+        assert_eq!(report.total.input_tokens, 4000);
+        assert_eq!(report.total.cost, 3.0);
+        assert_eq!(report.total.unpriced_calls, 1);
+        assert_eq!(report.breakdown[0].operation, "question");
+        assert_eq!(report.breakdown[1].totals.calls, 2);
+
+        let exceeded: Vec<_> = report.budgets.iter().filter(|b| b.exceeded()).collect();
+        assert_eq!(exceeded.len(), 1);
+        assert!(exceeded[0].covers(ProviderKind::OpenAi));
+        assert!(!exceeded[0].covers(ProviderKind::Anthropic));
+        assert_eq!(report.budgets[0].spent, 3.0);
+    }
+
+    /// Streams a few characters, then fails or, with `hang`, never finishes.
+    struct Interrupted {
+        hang: bool,
+    }
+
+    impl Provider for Interrupted {
+        fn model(&self) -> &str {
+            "gpt-4.1-nano"
+        }
+
+        fn complete<'a>(
+            &'a self,
+            _request: &'a CompletionRequest,
+            on_chunk: Option<&'a OnChunk<'a>>,
+        ) -> BoxFuture<'a, Result<Completion, TranscriptError>> {
+            Box::pin(async move {
+                if let Some(on_chunk) = on_chunk {
+                    on_chunk("Twelve chars");
+                }
+                if self.hang {
+                    futures::future::pending::<()>().await;
+                }
+                Err(TranscriptError::NetworkError("Connection reset.".into()))
+            })
+        }
+    }
+
+    #[test]
+    fn records_streams_that_end_early() {
+        use futures::FutureExt;
+
+        let db = Database::in_memory();
+        let request = CompletionRequest {
+            system: String::new(),
+            prompt: "a".repeat(40),
+            max_tokens: 100,
+            json: false,
+        };
+        let on_chunk = |_: &str| {};
+        for hang in [false, true] {
+            let metered = Metered::new(
+                &db,
+                ProviderKind::OpenAi,
+                "summary",
+                Box::new(Interrupted { hang }),
+            );
+            // Dropped unfinished when it hangs, as a cancelled call is
+            let result = metered.complete(&request, Some(&on_chunk)).now_or_never();
+            assert_eq!(result.is_none(), hang);
+        }
+        let report = db
+            .with_conn(|conn| usage(conn, UsagePeriod::All, &UsageSettings::default()))
+            .unwrap();
+        assert_eq!(report.total.calls, 2);
+        assert_eq!(report.total.input_tokens, 20);
+        assert_eq!(report.total.output_tokens, 6);
+    }
+}
@@ -0,0 +1,165 @@
+//! Diagnostic logs, written with `tracing` to daily files under `logs/` in the app
+//! data directory.
+//!
+//! Fetching a transcript runs in nested spans (`fetch_transcript`, `watch_page`,
+//! `player`, `captions`, `parse`), so each line tells which video and which stage
+//! it came from. A week of files is kept. The level is a setting, swapped at
+//! runtime through a reload handle, and `get_recent_logs` reads the newest lines
+//! back for in-app diagnostics.
+
+use crate::error::TranscriptError;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+const LOG_DIR: &str = "logs";
+const FILE_PREFIX: &str = "insighttube";
+const KEPT_FILES: usize = 7;
+const DEFAULT_LINES: usize = 500;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            Self::Error => LevelFilter::ERROR,
+            Self::Warn => LevelFilter::WARN,
+            Self::Info => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+            Self::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+struct Logger {
+    dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+    /// Flushes the background writer when the app exits.
+    _guard: WorkerGuard,
+}
+
+static LOGGER: OnceCell<Logger> = OnceCell::new();
+
+/// Starts writing logs under `data_dir`, at the default level until the settings
+/// are applied. Without a writable log directory the app runs without logs.
+pub fn init(data_dir: &Path) {
+    let dir = data_dir.join(LOG_DIR);
+    let Ok(appender) = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(KEPT_FILES)
+        .build(&dir)
+    else {
+        return;
+    };
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, level) = reload::Layer::new(LogLevel::default().filter());
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(writer).with_ansi(false));
+    if tracing::subscriber::set_global_default(subscriber).is_ok() {
+        let _ = LOGGER.set(Logger {
+            dir,
+            level,
+            _guard: guard,
+        });
+    }
+}
+
+/// Applies the `logLevel` setting.
+pub(crate) fn set_level(level: LogLevel) {
+    if let Some(logger) = LOGGER.get() {
+        let _ = logger.level.reload(level.filter());
+    }
+}
+
+/// The last `count` lines of `files`, given newest first, in the order written.
+fn last_lines(files: impl IntoIterator<Item = String>, count: usize) -> Vec<String> {
+    let mut lines = VecDeque::with_capacity(count);
+    for contents in files {
+        let needed = count - lines.len();
+        for line in contents.lines().rev().take(needed) {
+            lines.push_front(line.to_string());
+        }
+        if lines.len() == count {
+            break;
+        }
+    }
+    lines.into()
+}
+
+/// The last `count` lines logged, oldest first.
+pub fn recent_lines(count: usize) -> Result<Vec<String>, TranscriptError> {
+    let Some(logger) = LOGGER.get() else {
+        return Ok(Vec::new());
+    };
+    let read_error =
+        |e: std::io::Error| TranscriptError::FileError(format!("Could not read logs: {}", e));
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&logger.dir)
+        .map_err(read_error)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX))
+        })
+        .collect();
+    // Rotated files end in their date, so the newest sorts last
+    files.sort();
+    let newest_first = files
+        .iter()
+        .rev()
+        .filter_map(|path| std::fs::read_to_string(path).ok());
+    Ok(last_lines(newest_first, count))
+}
+
+/// Bytes taken by the log files.
+pub(crate) fn disk_usage() -> u64 {
+    let Some(logger) = LOGGER.get() else {
+        return 0;
+    };
+    std::fs::read_dir(&logger.dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// The most recent log lines, oldest first: `lines` of them, 500 by default.
+pub fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, TranscriptError> {
+    recent_lines(lines.unwrap_or(DEFAULT_LINES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_last_lines_across_files() {
+        let files = ["d\ne\n".to_string(), "a\nb\nc\n".to_string()];
+
+        assert_eq!(last_lines(files.clone(), 3), ["c", "d", "e"]);
+        assert_eq!(last_lines(files.clone(), 10), ["a", "b", "c", "d", "e"]);
+        assert!(last_lines(files, 0).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{fetch_player_response, playability_error, text_of};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Thumbnail {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Video details read from the Innertube player response.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoMetadata {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub channel_id: String,
+    pub duration_seconds: u64,
+    pub view_count: u64,
+    /// ISO 8601 date, only present when the responding client includes a microformat.
+    pub upload_date: Option<String>,
+    pub thumbnails: Vec<Thumbnail>,
+    pub description: String,
+    pub keywords: Vec<String>,
+}
+
+/// Numeric fields in the player response are usually encoded as strings.
+fn number_of(value: Option<&Value>) -> u64 {
+    value
+        .and_then(|v| {
+            v.as_u64()
+                .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+        })
+        .unwrap_or(0)
+}
+
+fn str_of(value: Option<&Value>) -> Option<String> {
+    value.and_then(|v| v.as_str()).map(String::from)
+}
+
+/// Builds [`VideoMetadata`] from `videoDetails` and, when available, `microformat`.
+pub fn parse_video_metadata(
+    video_id: &str,
+    player_json: &Value,
+) -> Result<VideoMetadata, TranscriptError> {
+    let details = player_json.get("videoDetails").ok_or_else(|| {
+        playability_error(player_json).unwrap_or_else(|| {
+            TranscriptError::VideoUnavailable(
+                "YouTube did not return any details for this video.".into(),
+            )
+        })
+    })?;
+    let microformat = player_json
+        .get("microformat")
+        .and_then(|m| m.get("playerMicroformatRenderer"));
+
+    let mut thumbnails: Vec<Thumbnail> = details
+        .get("thumbnail")
+        .and_then(|t| t.get("thumbnails"))
+        .and_then(|t| t.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|t| {
+                    Some(Thumbnail {
+                        url: str_of(t.get("url"))?,
+                        width: number_of(t.get("width")) as u32,
+                        height: number_of(t.get("height")) as u32,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    thumbnails.sort_by_key(|t| t.width);
+
+    let description = str_of(details.get("shortDescription"))
+        .or_else(|| {
+            microformat
+                .and_then(|m| m.get("description"))
+                .and_then(text_of)
+        })
+        .unwrap_or_default();
+
+    Ok(VideoMetadata {
+        video_id: str_of(details.get("videoId")).unwrap_or_else(|| video_id.to_string()),
+        title: str_of(details.get("title")).unwrap_or_default(),
+        channel_name: str_of(details.get("author")).unwrap_or_default(),
+        channel_id: str_of(details.get("channelId")).unwrap_or_default(),
+        duration_seconds: number_of(details.get("lengthSeconds")),
+        view_count: number_of(details.get("viewCount")),
+        upload_date: microformat
+            .and_then(|m| str_of(m.get("uploadDate")).or_else(|| str_of(m.get("publishDate")))),
+        thumbnails,
+        description,
+        keywords: details
+            .get("keywords")
+            .and_then(|k| k.as_array())
+            .map(|k| {
+                k.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+pub async fn fetch_video_metadata(video_id: String) -> Result<VideoMetadata, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, &video_id).await?;
+    parse_video_metadata(&video_id, &player_json)
+}
@@ -0,0 +1,200 @@
+//! How well each Innertube client has been fetching transcripts.
+//!
+//! Every player request records its outcome and how long it took, counting CAPTCHA
+//! pages and `429 Too Many Requests` answers apart. `get_metrics` reports the
+//! success rate and average latency per client, so it shows which clients work
+//! from the user's network, and with `adaptiveClientOrder` on, the clients are
+//! tried in the order [`rank`] gives them. The counts start over when the app
+//! restarts.
+
+use crate::error::TranscriptError;
+use crate::innertube::InnertubeClient;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How much ranking favours clients tried less often over those that work best.
+const EXPLORATION: f64 = 0.5;
+
+/// How a player request as one client ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The response listed caption tracks.
+    Captions,
+    /// A response without caption tracks, or an error other than the two below.
+    Failed,
+    Captcha,
+    RateLimited,
+}
+
+impl Outcome {
+    pub(crate) fn of_error(error: &TranscriptError) -> Self {
+        match error {
+            TranscriptError::CaptchaRequired => Self::Captcha,
+            TranscriptError::RateLimited => Self::RateLimited,
+            _ => Self::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counts {
+    attempts: u64,
+    successes: u64,
+    captchas: u64,
+    rate_limited: u64,
+    total_latency: Duration,
+}
+
+impl Counts {
+    fn record(&mut self, outcome: Outcome, latency: Duration) {
+        self.attempts += 1;
+        self.total_latency += latency;
+        match outcome {
+            Outcome::Captions => self.successes += 1,
+            Outcome::Failed => {}
+            Outcome::Captcha => self.captchas += 1,
+            Outcome::RateLimited => self.rate_limited += 1,
+        }
+    }
+
+    /// An upper confidence bound on the success rate (UCB1): the observed rate,
+    /// pulled towards one half while there are few attempts, plus a bonus that
+    /// shrinks as the client is tried and grows slowly as others are, so a client
+    /// that failed a few times still gets another chance now and then. A 429 says
+    /// more about the network than about the client, so those attempts are left out.
+    fn score(&self, total_attempts: u64) -> f64 {
+        let attempts = (self.attempts - self.rate_limited) as f64;
+        let rate = (self.successes as f64 + 1.0) / (attempts + 2.0);
+        let bonus = ((total_attempts as f64 + 1.0).ln() / (attempts + 1.0)).sqrt();
+        rate + EXPLORATION * bonus
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMetrics {
+    pub client: InnertubeClient,
+    pub attempts: u64,
+    pub successes: u64,
+    /// Attempts that failed, CAPTCHAs and rate limiting included.
+    pub failures: u64,
+    pub captchas: u64,
+    pub rate_limited: u64,
+    /// Share of attempts that listed caption tracks, from 0 to 1.
+    pub success_rate: f64,
+    pub average_latency_ms: u64,
+}
+
+impl ClientMetrics {
+    fn new(client: InnertubeClient, counts: &Counts) -> Self {
+        let attempts = counts.attempts.max(1);
+        Self {
+            client,
+            attempts: counts.attempts,
+            successes: counts.successes,
+            failures: counts.attempts - counts.successes,
+            captchas: counts.captchas,
+            rate_limited: counts.rate_limited,
+            success_rate: counts.successes as f64 / attempts as f64,
+            average_latency_ms: (counts.total_latency.as_millis() / attempts as u128) as u64,
+        }
+    }
+}
+
+static COUNTS: Lazy<Mutex<HashMap<InnertubeClient, Counts>>> = Lazy::new(Default::default);
+
+/// Records the outcome of a player request as `client` that took `latency`.
+pub(crate) fn record(client: InnertubeClient, outcome: Outcome, latency: Duration) {
+    if let Ok(mut counts) = COUNTS.lock() {
+        counts.entry(client).or_default().record(outcome, latency);
+    }
+}
+
+/// The metrics of the clients tried so far, in [`InnertubeClient::ALL`] order.
+pub(crate) fn client_metrics() -> Vec<ClientMetrics> {
+    let Ok(counts) = COUNTS.lock() else {
+        return Vec::new();
+    };
+    InnertubeClient::ALL
+        .iter()
+        .filter_map(|client| counts.get(client).map(|c| ClientMetrics::new(*client, c)))
+        .collect()
+}
+
+fn rank_by(clients: &mut [InnertubeClient], counts: &HashMap<InnertubeClient, Counts>) {
+    let total: u64 = counts.values().map(|c| c.attempts - c.rate_limited).sum();
+    let score =
+        |client: &InnertubeClient| counts.get(client).copied().unwrap_or_default().score(total);
+    // Stable, so clients that score the same keep the configured order
+    clients.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
+/// Orders `clients` by how likely they are to list captions, judging by the
+/// attempts so far. Before any, the order is left as configured.
+pub(crate) fn rank(clients: &mut [InnertubeClient]) {
+    if let Ok(counts) = COUNTS.lock() {
+        rank_by(clients, &counts);
+    }
+}
+
+/// Success rates, latencies and CAPTCHA/429 counts of the Innertube clients tried
+/// since the app started.
+pub fn get_metrics() -> Vec<ClientMetrics> {
+    client_metrics()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_and_averages_attempts() {
+        let mut counts = Counts::default();
+        counts.record(Outcome::Captions, Duration::from_millis(300));
+        counts.record(Outcome::Captions, Duration::from_millis(500));
+        counts.record(Outcome::RateLimited, Duration::from_millis(1_000));
+        counts.record(Outcome::Captcha, Duration::from_millis(200));
+
+        let metrics = ClientMetrics::new(InnertubeClient::Web, &counts);
+        assert_eq!(metrics.attempts, 4);
+        assert_eq!(metrics.failures, 2);
+        assert_eq!(metrics.captchas, 1);
+        assert_eq!(metrics.rate_limited, 1);
+        assert_eq!(metrics.success_rate, 0.5);
+        assert_eq!(metrics.average_latency_ms, 500);
+    }
+
+    #[test]
+    fn ranks_reliable_clients_first_but_keeps_exploring() {
+        use InnertubeClient::*;
+        let counts_of = |successes: u64, failures: u64| {
+            let mut counts = Counts::default();
+            for _ in 0..successes {
+                counts.record(Outcome::Captions, Duration::ZERO);
+            }
+            for _ in 0..failures {
+                counts.record(Outcome::Failed, Duration::ZERO);
+            }
+            counts
+        };
+
+        let mut clients = [Android, Web, TvEmbedded];
+        rank_by(&mut clients, &HashMap::new());
+        assert_eq!(clients, [Android, Web, TvEmbedded]);
+
+        let counts = HashMap::from([(Android, counts_of(0, 6)), (Web, counts_of(90, 4))]);
+        rank_by(&mut clients, &counts);
+        assert_eq!(clients, [TvEmbedded, Web, Android]);
+
+        let counts = HashMap::from([
+            (Android, counts_of(0, 6)),
+            (Web, counts_of(90, 4)),
+            (TvEmbedded, counts_of(1, 4)),
+        ]);
+        rank_by(&mut clients, &counts);
+        assert_eq!(clients, [Web, TvEmbedded, Android]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Text notes anchored to a moment of a video's transcript.
+//!
+//! A note belongs to a (video_id, offset) pair rather than to a segment, so it
+//! keeps its place when the transcript is fetched again in another language or
+//! with different fragment boundaries. Markdown exports can interleave them with
+//! the transcript paragraphs.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notes (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    video_id   TEXT    NOT NULL,
+    offset     REAL    NOT NULL,
+    text       TEXT    NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS notes_video ON notes (video_id, offset);
+";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub id: i64,
+    pub video_id: String,
+    /// Seconds into the video.
+    pub offset: f64,
+    pub text: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const NOTE_COLUMNS: &str = "id, video_id, offset, text, created_at, updated_at";
+
+fn note_from_row(row: &Row) -> rusqlite::Result<Note> {
+    Ok(Note {
+        id: row.get(0)?,
+        video_id: row.get(1)?,
+        offset: row.get(2)?,
+        text: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn valid_text(text: &str) -> Result<&str, TranscriptError> {
+    match text.trim() {
+        "" => Err(TranscriptError::InvalidInput(
+            "Notes cannot be empty.".into(),
+        )),
+        text => Ok(text),
+    }
+}
+
+fn valid_offset(offset: f64) -> Result<f64, TranscriptError> {
+    if offset.is_finite() && offset >= 0.0 {
+        Ok(offset)
+    } else {
+        Err(TranscriptError::InvalidInput(
+            "A note's offset must be a number of seconds from the start.".into(),
+        ))
+    }
+}
+
+fn not_found() -> TranscriptError {
+    TranscriptError::InvalidInput("This note no longer exists.".into())
+}
+
+/// Notes on `video_id`, in the order they appear in the video.
+pub(crate) fn for_video(db: &Database, video_id: &str) -> Result<Vec<Note>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM notes WHERE video_id = ?1 ORDER BY offset, id",
+            NOTE_COLUMNS
+        ))?;
+        let rows = stmt.query_map([video_id], note_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Adds a note `offset` seconds into the video.
+pub fn create_note(
+    db: &Database,
+    video_id: String,
+    offset: f64,
+    text: String,
+) -> Result<Note, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let offset = valid_offset(offset)?;
+    let text = valid_text(&text)?;
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "INSERT INTO notes (video_id, offset, text, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 RETURNING {}",
+                NOTE_COLUMNS
+            ),
+            params![video_id, offset, text, unix_now()],
+            note_from_row,
+        )
+    })
+}
+
+/// Changes the text of a note or moves it; omitted fields are kept.
+pub fn update_note(
+    db: &Database,
+    id: i64,
+    text: Option<String>,
+    offset: Option<f64>,
+) -> Result<Note, TranscriptError> {
+    let text = text.as_deref().map(valid_text).transpose()?;
+    let offset = offset.map(valid_offset).transpose()?;
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "UPDATE notes SET text = COALESCE(?2, text),
+                                  offset = COALESCE(?3, offset),
+                                  updated_at = ?4
+                 WHERE id = ?1
+                 RETURNING {}",
+                NOTE_COLUMNS
+            ),
+            params![id, text, offset, unix_now()],
+            note_from_row,
+        )
+        .optional()
+    })?
+    .ok_or_else(not_found)
+}
+
+/// Deletes a note. Returns whether there was one to delete.
+pub fn delete_note(db: &Database, id: i64) -> Result<bool, TranscriptError> {
+    db.with_conn(|conn| conn.execute("DELETE FROM notes WHERE id = ?1", [id]))
+        .map(|n| n > 0)
+}
+
+/// Lists the notes on a video in the order they appear in it.
+pub fn list_notes(db: &Database, video_id: String) -> Result<Vec<Note>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    for_video(db, &video_id)
+}
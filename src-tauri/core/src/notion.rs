@@ -0,0 +1,299 @@
+//! Notes on videos pushed to Notion through its API.
+//!
+//! Each export creates a page under the parent page set in the settings, holding
+//! the same material as an Obsidian note: the video embedded, its summary,
+//! chapters, highlights and notes with links to their moments. The parent page
+//! must be shared with the integration whose token is stored for Notion.
+
+use crate::activity::{self, ActivityKind};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{self, ProviderKind};
+use crate::report::Report;
+use crate::secrets::{self, KeyedService};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const API_URL: &str = "https://api.notion.com/v1";
+const API_VERSION: &str = "2022-06-28";
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Most blocks Notion accepts in one request.
+const MAX_BLOCKS: usize = 100;
+/// Longest text Notion accepts in one rich text object.
+const MAX_TEXT: usize = 2_000;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotionSettings {
+    /// Link to, or ID of, the page new pages are created under.
+    pub parent_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotionOptions {
+    /// Summarizes the video with this provider when no summary is stored for it.
+    pub provider: Option<ProviderKind>,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+}
+
+/// The page ID in `input`, a bare ID or a link to the page, hyphenated as the
+/// API expects.
+fn page_id(input: &str) -> Option<String> {
+    let path = input.trim().split(['?', '#']).next()?;
+    let last = path.trim_end_matches('/').rsplit('/').next()?;
+    let hex: String = last.chars().filter(|c| *c != '-').collect();
+    let id = hex.get(hex.len().checked_sub(32)?..)?;
+    if !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &id[..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    ))
+}
+
+fn text(content: &str) -> Value {
+    let content: String = content.chars().take(MAX_TEXT).collect();
+    json!({ "type": "text", "text": { "content": content } })
+}
+
+fn timestamp_link(video_id: &str, seconds: f64) -> Value {
+    let url = format!("https://youtu.be/{}?t={}", video_id, seconds.floor() as u64);
+    json!({
+        "type": "text",
+        "text": { "content": short_timestamp(seconds), "link": { "url": url } },
+    })
+}
+
+fn block(kind: &str, rich_text: Vec<Value>) -> Value {
+    json!({ "object": "block", "type": kind, kind: { "rich_text": rich_text } })
+}
+
+/// A block of `kind` starting with a link to `seconds` into the video.
+fn timed(kind: &str, video_id: &str, seconds: f64, content: &str) -> Value {
+    block(
+        kind,
+        vec![
+            timestamp_link(video_id, seconds),
+            text(&format!(" {}", content.trim())),
+        ],
+    )
+}
+
+fn blocks(report: &Report) -> Vec<Value> {
+    let meta = &report.metadata;
+    let id = meta.video_id.as_str();
+    let mut details = meta.channel_name.clone();
+    if let Some(date) = &meta.upload_date {
+        details = format!("{} · {}", details, date);
+    }
+    let mut out = vec![
+        json!({
+            "object": "block",
+            "type": "video",
+            "video": {
+                "type": "external",
+                "external": { "url": format!("https://www.youtube.com/watch?v={}", id) },
+            },
+        }),
+        block("paragraph", vec![text(&details)]),
+    ];
+
+    if let Some((tldr, key_points)) = &report.summary {
+        out.push(block("heading_2", vec![text("Summary")]));
+        out.push(block("paragraph", vec![text(tldr)]));
+        for point in key_points {
+            out.push(timed(
+                "bulleted_list_item",
+                id,
+                point.timestamp,
+                &point.text,
+            ));
+        }
+    }
+    if !report.chapters.is_empty() {
+        out.push(block("heading_2", vec![text("Chapters")]));
+        for chapter in &report.chapters {
+            out.push(timed(
+                "numbered_list_item",
+                id,
+                chapter.start_time,
+                &chapter.title,
+            ));
+        }
+    }
+    if !report.highlights.is_empty() {
+        out.push(block("heading_2", vec![text("Highlights")]));
+        for highlight in &report.highlights {
+            out.push(timed("quote", id, highlight.start, &highlight.text));
+        }
+    }
+    if !report.notes.is_empty() {
+        out.push(block("heading_2", vec![text("Notes")]));
+        for note in &report.notes {
+            out.push(timed("paragraph", id, note.offset, &note.text));
+        }
+    }
+    out
+}
+
+/// Sends `body` to the API and returns the JSON it answers with.
+async fn request(
+    builder: reqwest::RequestBuilder,
+    token: &str,
+    body: &Value,
+) -> Result<Value, TranscriptError> {
+    let res = builder
+        .bearer_auth(token)
+        .header("Notion-Version", API_VERSION)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| TranscriptError::NetworkError(format!("Could not reach Notion: {}", e)))?;
+
+    match res.status().as_u16() {
+        200..=299 => res
+            .json()
+            .await
+            .map_err(|e| TranscriptError::ParseError(format!("Unexpected Notion response: {}", e))),
+        401 => Err(TranscriptError::InvalidInput(
+            "Notion rejected the token. Check it in Settings.".into(),
+        )),
+        404 => Err(TranscriptError::InvalidInput(
+            "Notion cannot find the parent page. Share it with your integration.".into(),
+        )),
+        429 => Err(TranscriptError::RateLimited),
+        status => {
+            let body: Value = res.json().await.unwrap_or_default();
+            let message = body
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("HTTP {}", status));
+            Err(TranscriptError::NetworkError(format!(
+                "Notion request failed: {}",
+                message
+            )))
+        }
+    }
+}
+
+async fn create_page(
+    db: &Database,
+    video_id: &str,
+    options: NotionOptions,
+) -> Result<String, TranscriptError> {
+    let token = secrets::api_key(KeyedService::Notion).ok_or_else(|| {
+        TranscriptError::InvalidInput("Add a Notion integration token in Settings first.".into())
+    })?;
+    let parent = crate::settings::current()
+        .notion
+        .parent_page
+        .as_deref()
+        .and_then(page_id)
+        .ok_or_else(|| {
+            TranscriptError::InvalidInput(
+                "Set the Notion page to add videos under in Settings first.".into(),
+            )
+        })?;
+
+    let report = Report::gather(db, video_id, options.provider, options.model).await?;
+    let client = llm::client(TIMEOUT)?;
+    let mut blocks = blocks(&report).into_iter();
+    let first: Vec<Value> = blocks.by_ref().take(MAX_BLOCKS).collect();
+    let page = request(
+        client.post(format!("{}/pages", API_URL)),
+        &token,
+        &json!({
+            "parent": { "page_id": parent },
+            "properties": { "title": { "title": [text(&report.metadata.title)] } },
+            "children": first,
+        }),
+    )
+    .await?;
+    let page_id = page.get("id").and_then(Value::as_str).unwrap_or_default();
+
+    // Longer pages get the rest of their blocks appended in batches
+    let rest: Vec<Value> = blocks.collect();
+    for batch in rest.chunks(MAX_BLOCKS) {
+        request(
+            client.patch(format!("{}/blocks/{}/children", API_URL, page_id)),
+            &token,
+            &json!({ "children": batch }),
+        )
+        .await?;
+    }
+    Ok(page
+        .get("url")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Creates a Notion page on `video_id` under the parent page set in the settings,
+/// and returns its link. The summary is the one stored by auto-ingestion, or one
+/// made with the `provider` of the options; without either it is left out.
+pub async fn export_to_notion(
+    db: &Database,
+    video_id: String,
+    options: Option<NotionOptions>,
+) -> Result<String, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let result = create_page(db, &video_id, options.unwrap_or_default()).await;
+    activity::finished(
+        db,
+        ActivityKind::ExportCompleted,
+        Some(&video_id),
+        "Exporting to Notion",
+        &result,
+        |url| {
+            (
+                "Exported a page to Notion".to_string(),
+                json!({ "destination": "notion", "url": url }),
+            )
+        },
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_page_ids_from_links() {
+        let id = Some("1a2b3c4d-5e6f-7a8b-9c0d-1e2f3a4b5c6d".to_string());
+        assert_eq!(
+            page_id("https://www.notion.so/team/Videos-1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d?pvs=4"),
+            id
+        );
+        assert_eq!(page_id("1a2b3c4d-5e6f-7a8b-9c0d-1e2f3a4b5c6d"), id);
+        assert_eq!(page_id("https://www.notion.so/team/Videos"), None);
+    }
+
+    #[test]
+    fn links_timed_blocks_to_their_moment() {
+        assert_eq!(
+            timed("quote", "dQw4w9WgXcQ", 75.5, "Ownership\n"),
+            json!({
+                "object": "block",
+                "type": "quote",
+                "quote": { "rich_text": [
+                    { "type": "text", "text": {
+                        "content": "1:15",
+                        "link": { "url": "https://youtu.be/dQw4w9WgXcQ?t=75" },
+                    } },
+                    { "type": "text", "text": { "content": " Ownership" } },
+                ] },
+            })
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Notes on videos written straight into an Obsidian vault.
+//!
+//! A note is plain Markdown with YAML frontmatter (title, channel, URL, tags and
+//! date) that Obsidian's properties and Dataview read, followed by the video
+//! embedded, its summary, chapters, highlights and notes, every timestamp linking
+//! back to its moment. Exporting a video again rewrites its note, so the vault
+//! always holds the latest highlights and notes.
+
+use crate::activity::{self, ActivityKind};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::ProviderKind;
+use crate::report::Report;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// YouTube tags of the video carried over into the note's tags.
+const MAX_VIDEO_TAGS: usize = 10;
+/// Characters Obsidian or the file system do not allow in a note's name.
+const FORBIDDEN: &[char] = &[
+    '\\', '/', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']',
+];
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ObsidianSettings {
+    /// Folder of the vault; exports are refused until it is set.
+    pub vault_path: Option<String>,
+    /// Folder inside the vault the notes go to.
+    pub folder: String,
+    /// Tags every note gets, before the video's own.
+    pub tags: Vec<String>,
+}
+
+impl Default for ObsidianSettings {
+    fn default() -> Self {
+        Self {
+            vault_path: None,
+            folder: "YouTube".into(),
+            tags: vec!["youtube".into()],
+        }
+    }
+}
+
+impl ObsidianSettings {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        let inside = Path::new(&self.folder)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if inside {
+            Ok(())
+        } else {
+            Err(TranscriptError::InvalidInput(
+                "The Obsidian folder must be a folder inside the vault.".into(),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ObsidianOptions {
+    /// Summarizes the video with this provider when no summary is stored for it.
+    pub provider: Option<ProviderKind>,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+}
+
+/// A double-quoted YAML string.
+fn yaml(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', " ")
+    )
+}
+
+/// `text` as an Obsidian tag: lowercase, words joined by hyphens, and only the
+/// characters tags may hold. Tags made only of digits are not allowed.
+fn tag(text: &str) -> Option<String> {
+    let tag = text
+        .trim()
+        .trim_start_matches('#')
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+        .collect::<String>();
+    (tag.chars().any(|c| !c.is_ascii_digit() && c != '-')).then_some(tag)
+}
+
+/// The configured tags, then the video's own, without repeats.
+fn tags(configured: &[String], video: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let video = video.iter().take(MAX_VIDEO_TAGS);
+    for tag in configured.iter().chain(video).filter_map(|t| tag(t)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// The note's file name: the video's title without the characters Obsidian does
+/// not allow in links.
+fn file_name(title: &str, video_id: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if FORBIDDEN.contains(&c) { ' ' } else { c })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        format!("{}.md", video_id)
+    } else {
+        format!("{}.md", name)
+    }
+}
+
+fn link(video_id: &str, seconds: f64) -> String {
+    format!(
+        "[{}](https://youtu.be/{}?t={})",
+        short_timestamp(seconds),
+        video_id,
+        seconds.floor() as u64
+    )
+}
+
+fn render_note(report: &Report, tags: &[String]) -> String {
+    let meta = &report.metadata;
+    let id = meta.video_id.as_str();
+    let mut out = String::from("---\n");
+    let _ = writeln!(out, "title: {}", yaml(&meta.title));
+    let _ = writeln!(out, "channel: {}", yaml(&meta.channel_name));
+    let _ = writeln!(out, "url: https://youtu.be/{}", id);
+    if let Some(date) = &meta.upload_date {
+        let _ = writeln!(out, "date: {}", date);
+    }
+    if !tags.is_empty() {
+        out.push_str("tags:\n");
+        for tag in tags {
+            let _ = writeln!(out, "  - {}", tag);
+        }
+    }
+    let _ = write!(
+        out,
+        "---\n\n# {}\n\n![](https://youtu.be/{})\n\n",
+        meta.title, id
+    );
+
+    if let Some((tldr, key_points)) = &report.summary {
+        let _ = write!(out, "## Summary\n\n{}\n\n", tldr);
+        for point in key_points {
+            let _ = writeln!(out, "- {} {}", link(id, point.timestamp), point.text);
+        }
+        if !key_points.is_empty() {
+            out.push('\n');
+        }
+    }
+    if !report.chapters.is_empty() {
+        out.push_str("## Chapters\n\n");
+        for chapter in &report.chapters {
+            let _ = writeln!(out, "- {} {}", link(id, chapter.start_time), chapter.title);
+        }
+        out.push('\n');
+    }
+    if !report.highlights.is_empty() {
+        out.push_str("## Highlights\n\n");
+        for highlight in &report.highlights {
+            let text = highlight.text.trim().replace('\n', " ");
+            let _ = write!(out, "> {} {}\n\n", link(id, highlight.start), text);
+        }
+    }
+    if !report.notes.is_empty() {
+        out.push_str("## Notes\n\n");
+        for note in &report.notes {
+            let text = note.text.trim().replace('\n', "\n  ");
+            let _ = writeln!(out, "- {} {}", link(id, note.offset), text);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+async fn write_note(
+    db: &Database,
+    video_id: &str,
+    options: ObsidianOptions,
+) -> Result<String, TranscriptError> {
+    let settings = crate::settings::current().obsidian;
+    let vault = settings
+        .vault_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            TranscriptError::InvalidInput("Choose your Obsidian vault in Settings first.".into())
+        })?;
+    if !vault.is_dir() {
+        return Err(TranscriptError::FileError(format!(
+            "The Obsidian vault \"{}\" does not exist.",
+            vault.display()
+        )));
+    }
+
+    let report = Report::gather(db, video_id, options.provider, options.model).await?;
+    let folder = vault.join(settings.folder.trim());
+    std::fs::create_dir_all(&folder).map_err(|e| {
+        TranscriptError::FileError(format!("Could not create \"{}\": {}", folder.display(), e))
+    })?;
+    let mut path = folder.join(file_name(&report.metadata.title, video_id));
+    // Another video with the same title keeps its note
+    let url = format!("url: https://youtu.be/{}\n", video_id);
+    if std::fs::read_to_string(&path).is_ok_and(|note| !note.contains(&url)) {
+        path = folder.join(file_name(
+            &format!("{} {}", report.metadata.title, video_id),
+            video_id,
+        ));
+    }
+
+    let tags = tags(&settings.tags, &report.metadata.keywords);
+    std::fs::write(&path, render_note(&report, &tags)).map_err(|e| {
+        TranscriptError::FileError(format!("Could not write \"{}\": {}", path.display(), e))
+    })?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Writes a note on `video_id` into the folder of the Obsidian vault set in the
+/// settings, and returns the path of the note. The summary is the one stored by
+/// auto-ingestion, or one made with the `provider` of the options; without either
+/// it is left out.
+pub async fn export_to_obsidian(
+    db: &Database,
+    video_id: String,
+    options: Option<ObsidianOptions>,
+) -> Result<String, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let result = write_note(db, &video_id, options.unwrap_or_default()).await;
+    activity::finished(
+        db,
+        ActivityKind::ExportCompleted,
+        Some(&video_id),
+        "Exporting to Obsidian",
+        &result,
+        |path| {
+            (
+                format!("Exported a note to {}", path),
+                json!({ "destination": "obsidian", "path": path }),
+            )
+        },
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::VideoMetadata;
+    use crate::summarize::KeyPoint;
+
+    #[test]
+    fn makes_tags_and_file_names_obsidian_accepts() {
+        let video = [
+            "Rust Programming".into(),
+            "2024".into(),
+            "#async/await".into(),
+        ];
+        assert_eq!(
+            tags(&["YouTube".into()], &video),
+            ["youtube", "rust-programming", "async/await"]
+        );
+        assert_eq!(
+            file_name("Rust: Why #1? [Part 2/3]", "dQw4w9WgXcQ"),
+            "Rust Why 1 Part 2 3.md"
+        );
+        assert_eq!(file_name("???", "dQw4w9WgXcQ"), "dQw4w9WgXcQ.md");
+    }
+
+    #[test]
+    fn renders_frontmatter_and_sections() {
+        let report = Report {
+            metadata: VideoMetadata {
+                video_id: "dQw4w9WgXcQ".into(),
+                title: "Say \"hi\"".into(),
+                channel_name: "Ferris".into(),
+                channel_id: "UC1".into(),
+                duration_seconds: 600,
+                view_count: 42,
+                upload_date: Some("2024-05-01".into()),
+                thumbnails: Vec::new(),
+                description: String::new(),
+                keywords: Vec::new(),
+            },
+            summary: Some((
+                "A talk.".into(),
+                vec![KeyPoint {
+                    text: "Ownership".into(),
+                    timestamp: 75.0,
+                }],
+            )),
+            chapters: Vec::new(),
+            keywords: Vec::new(),
+            highlights: Vec::new(),
+            notes: Vec::new(),
+        };
+        assert_eq!(
+            render_note(&report, &["youtube".into()]),
+            "---\ntitle: \"Say \\\"hi\\\"\"\nchannel: \"Ferris\"\n\
+             url: https://youtu.be/dQw4w9WgXcQ\ndate: 2024-05-01\ntags:\n  - youtube\n---\n\n\
+             # Say \"hi\"\n\n![](https://youtu.be/dQw4w9WgXcQ)\n\n\
+             ## Summary\n\nA talk.\n\n\
+             - [1:15](https://youtu.be/dQw4w9WgXcQ?t=75) Ownership\n\n"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Cancelling long-running commands.
+//!
+//! A cancellable command takes an `operation_id` chosen by the frontend (the one
+//! its progress events carry) and registers a cancellation token under it while it
+//! runs; `cancel_operation` fires the token. Network waits race against the token
+//! and loops check it between steps, so cancelling takes effect promptly and work
+//! already done (earlier videos of a batch) is kept.
+
+use crate::error::TranscriptError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// The running cancellable operations, kept in Tauri's managed state.
+#[derive(Default)]
+pub struct Operations {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl Operations {
+    /// Registers `operation_id` until the returned guard is dropped. Without an ID
+    /// the operation simply cannot be cancelled.
+    pub fn start(&self, operation_id: Option<&str>) -> Operation<'_> {
+        let token = CancellationToken::new();
+        if let (Some(id), Ok(mut tokens)) = (operation_id, self.tokens.lock()) {
+            tokens.insert(id.to_string(), token.clone());
+        }
+        Operation {
+            operations: self,
+            id: operation_id.map(str::to_string),
+            token,
+        }
+    }
+
+    fn cancel(&self, operation_id: &str) -> bool {
+        let token = self
+            .tokens
+            .lock()
+            .ok()
+            .and_then(|mut tokens| tokens.remove(operation_id));
+        token.map(|t| t.cancel()).is_some()
+    }
+}
+
+/// A registered operation; unregisters itself when dropped.
+pub struct Operation<'a> {
+    operations: &'a Operations,
+    id: Option<String>,
+    token: CancellationToken,
+}
+
+impl Operation<'_> {
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Runs `task` unless or until the operation is cancelled.
+    pub async fn run<T>(
+        &self,
+        task: impl Future<Output = Result<T, TranscriptError>>,
+    ) -> Result<T, TranscriptError> {
+        cancellable(&self.token, task).await
+    }
+}
+
+impl Drop for Operation<'_> {
+    fn drop(&mut self) {
+        if let (Some(id), Ok(mut tokens)) = (&self.id, self.operations.tokens.lock()) {
+            tokens.remove(id);
+        }
+    }
+}
+
+/// Fails with [`TranscriptError::Cancelled`] once `token` is cancelled.
+#[cfg_attr(not(feature = "whisper"), allow(dead_code))]
+pub(crate) fn check(token: &CancellationToken) -> Result<(), TranscriptError> {
+    if token.is_cancelled() {
+        Err(TranscriptError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs `task`, dropping it as soon as `token` is cancelled.
+pub async fn cancellable<T>(
+    token: &CancellationToken,
+    task: impl Future<Output = Result<T, TranscriptError>>,
+) -> Result<T, TranscriptError> {
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(TranscriptError::Cancelled),
+        result = task => result,
+    }
+}
+
+/// Cancels the command running with `operation_id`. Returns whether it was still
+/// running.
+pub fn cancel_operation(operations: &Operations, operation_id: String) -> bool {
+    operations.cancel(&operation_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancels_registered_operations_until_they_finish() {
+        let operations = Operations::default();
+        let operation = operations.start(Some("batch-1"));
+        assert!(check(operation.token()).is_ok());
+
+        assert!(operations.cancel("batch-1"));
+        assert!(matches!(
+            check(operation.token()),
+            Err(TranscriptError::Cancelled)
+        ));
+
+        let other = operations.start(Some("batch-2"));
+        drop(other);
+        assert!(!operations.cancel("batch-2"));
+        assert!(!operations.cancel("unknown"));
+    }
+
+    #[test]
+    fn drops_the_task_once_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = futures::executor::block_on(cancellable(
+            &token,
+            std::future::pending::<Result<(), _>>(),
+        ));
+        assert!(matches!(result, Err(TranscriptError::Cancelled)));
+    }
+}
@@ -0,0 +1,358 @@
+//! An outline for videos without chapters, from shifts of topic in the transcript.
+//!
+//! Sections are found TextTiling-style: the vocabulary of a few sentences before
+//! every sentence boundary is compared with that of a few sentences after it, and
+//! the boundaries where the similarity dips deepest start new sections. Each
+//! section is titled after its top key phrase, or by a language model when a
+//! provider is given.
+
+use crate::chapters::Chapter;
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::keywords::{self, KeywordMethod, KeywordOptions};
+use crate::llm::{self, CompletionRequest, Provider, ProviderKind};
+use crate::transcript::{load_transcript, segmenter, TranscriptParagraph, TranscriptSegment};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Sentences on either side of a boundary whose vocabulary is compared.
+const BLOCK_SENTENCES: usize = 6;
+const DEFAULT_MIN_SECTION_SECS: f64 = 120.0;
+const DEFAULT_MAX_SECTIONS: usize = 12;
+/// Transcript characters of each section shown to the model for its title.
+const TITLE_EXCERPT_CHARS: usize = 1_500;
+const TITLE_MAX_TOKENS: u32 = 1_000;
+
+const TITLE_SYSTEM_PROMPT: &str = "You title the sections of a YouTube video transcript. \
+Every section starts with a line \"Section n:\" followed by an excerpt of its transcript. \
+Reply with a JSON object only, shaped as {\"titles\": [string]}, holding one title per \
+section, in order. A title names the topic of its section in two to six words, like a \
+chapter title, in the language of the transcript.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutlineOptions {
+    /// Provider to title the sections with; they are titled by key phrase without.
+    pub provider: Option<ProviderKind>,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    pub max_sections: Option<usize>,
+    /// Shortest section, in seconds.
+    pub min_section_secs: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTitles {
+    #[serde(default)]
+    titles: Vec<String>,
+}
+
+/// Content words of `text`, counted.
+fn term_counts(text: &str) -> HashMap<String, f64> {
+    let mut counts = HashMap::new();
+    for word in keywords::tokens(text).into_iter().flatten() {
+        if keywords::is_content_word(&word) {
+            *counts.entry(word).or_default() += 1.0;
+        }
+    }
+    counts
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(term, x)| b.get(term).map(|y| x * y))
+        .sum();
+    let norm = |v: &HashMap<String, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Similarity across every boundary between sentences; the one at `i` lies before
+/// sentence `i + 1`.
+fn gap_similarities(terms: &[HashMap<String, f64>]) -> Vec<f64> {
+    let block = |range: Range<usize>| {
+        let mut sum: HashMap<String, f64> = HashMap::new();
+        for counts in &terms[range] {
+            for (term, count) in counts {
+                *sum.entry(term.clone()).or_default() += count;
+            }
+        }
+        sum
+    };
+    (1..terms.len())
+        .map(|i| {
+            let before = block(i.saturating_sub(BLOCK_SENTENCES)..i);
+            let after = block(i..(i + BLOCK_SENTENCES).min(terms.len()));
+            cosine(&before, &after)
+        })
+        .collect()
+}
+
+/// How far the similarity at each boundary dips below the nearest peaks on either
+/// side.
+fn depth_scores(similarities: &[f64]) -> Vec<f64> {
+    (0..similarities.len())
+        .map(|i| {
+            let at = similarities[i];
+            let mut left = at;
+            for &s in similarities[..i].iter().rev() {
+                if s < left {
+                    break;
+                }
+                left = s;
+            }
+            let mut right = at;
+            for &s in &similarities[i + 1..] {
+                if s < right {
+                    break;
+                }
+                right = s;
+            }
+            (left - at) + (right - at)
+        })
+        .collect()
+}
+
+/// Indices of the sentences starting a new section: the deepest dips over the
+/// usual cutoff of the mean depth less half its standard deviation, skipping those
+/// that would leave a section shorter than `min_secs`.
+fn section_starts(
+    sentences: &[TranscriptParagraph],
+    min_secs: f64,
+    max_sections: usize,
+) -> Vec<usize> {
+    let (Some(first), Some(last)) = (sentences.first(), sentences.last()) else {
+        return Vec::new();
+    };
+    let terms: Vec<_> = sentences.iter().map(|s| term_counts(&s.text)).collect();
+    let depths = depth_scores(&gap_similarities(&terms));
+    if depths.is_empty() {
+        return Vec::new();
+    }
+    let n = depths.len() as f64;
+    let mean = depths.iter().sum::<f64>() / n;
+    let deviation = (depths.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let cutoff = mean - deviation / 2.0;
+
+    let mut candidates: Vec<(usize, f64)> = depths
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, depth)| depth > 0.0 && depth > cutoff)
+        .map(|(gap, depth)| (gap + 1, depth))
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut starts: Vec<usize> = Vec::new();
+    for (sentence, _) in candidates {
+        if starts.len() + 1 >= max_sections {
+            break;
+        }
+        let at = sentences[sentence].start;
+        let fits = at - first.start >= min_secs
+            && last.end - at >= min_secs
+            && starts
+                .iter()
+                .all(|&s| (sentences[s].start - at).abs() >= min_secs);
+        if fits {
+            starts.push(sentence);
+        }
+    }
+    starts.sort_unstable();
+    starts
+}
+
+/// `text` with its first letter in upper case.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The sections of `segments` as chapters, titled by key phrase. Empty for an
+/// empty transcript.
+pub(crate) fn sections(segments: &[TranscriptSegment], options: &OutlineOptions) -> Vec<Chapter> {
+    let sentences = segmenter::sentences(segments);
+    let Some(end) = sentences.last().map(|s| s.end) else {
+        return Vec::new();
+    };
+    let min_secs = options
+        .min_section_secs
+        .unwrap_or(DEFAULT_MIN_SECTION_SECS)
+        .max(0.0);
+    let max_sections = options.max_sections.unwrap_or(DEFAULT_MAX_SECTIONS).max(1);
+    let mut starts = vec![0];
+    starts.extend(section_starts(&sentences, min_secs, max_sections));
+
+    let times: Vec<f64> = starts.iter().map(|&i| sentences[i].start).collect();
+    times
+        .iter()
+        .enumerate()
+        .map(|(i, &start_time)| {
+            let end_time = times.get(i + 1).copied().unwrap_or(end);
+            let part: Vec<TranscriptSegment> = segments
+                .iter()
+                .filter(|s| s.offset >= start_time && (s.offset < end_time || i + 1 == times.len()))
+                .cloned()
+                .collect();
+            let keyword_options = KeywordOptions {
+                method: KeywordMethod::Rake,
+                limit: Some(1),
+            };
+            let title = keywords::extract(&part, &keyword_options)
+                .into_iter()
+                .next()
+                .map(|k| capitalize(&k.keyword))
+                .unwrap_or_else(|| format!("Part {}", i + 1));
+            Chapter {
+                title,
+                start_time,
+                end_time,
+            }
+        })
+        .collect()
+}
+
+/// Asks `provider` for a title per section, keeping the key phrase titles of any
+/// the reply leaves out.
+async fn title_sections(
+    provider: &dyn Provider,
+    sentences: &[TranscriptParagraph],
+    sections: &mut [Chapter],
+) -> Result<(), TranscriptError> {
+    let prompt: String = sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| {
+            let text: String = sentences
+                .iter()
+                .filter(|s| s.start >= section.start_time && s.start < section.end_time)
+                .map(|s| s.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let excerpt: String = text.chars().take(TITLE_EXCERPT_CHARS).collect();
+            format!("Section {}:\n{}\n\n", i + 1, excerpt)
+        })
+        .collect();
+    let request = CompletionRequest {
+        system: TITLE_SYSTEM_PROMPT.into(),
+        prompt,
+        max_tokens: TITLE_MAX_TOKENS,
+        json: true,
+    };
+    let raw: RawTitles = llm::parse_json(&provider.complete(&request, None).await?.text)?;
+    for (section, title) in sections.iter_mut().zip(raw.titles) {
+        let title = title.trim();
+        if !title.is_empty() {
+            section.title = title.to_string();
+        }
+    }
+    Ok(())
+}
+
+/// Splits the transcript of `video_id` into sections at its shifts of topic, for
+/// videos without chapters. Sections are titled by key phrase, or by `provider`
+/// when the options name one.
+pub async fn generate_outline(
+    db: &Database,
+    video_id: String,
+    options: Option<OutlineOptions>,
+) -> Result<Vec<Chapter>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    let llm = match options.provider {
+        Some(provider) => Some(llm::provider(
+            db,
+            provider,
+            options.model.clone(),
+            "outline",
+        )?),
+        None => None,
+    };
+
+    let segments = load_transcript(db, &video_id, None).await?;
+    let mut sections = sections(&segments, &options);
+    if let Some(llm) = llm {
+        title_sections(
+            llm.as_ref(),
+            &segmenter::sentences(&segments),
+            &mut sections,
+        )
+        .await?;
+    }
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 10.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    /// Twenty sentences on compilers, then twenty on cooking, ten seconds each.
+    fn two_topics() -> Vec<TranscriptSegment> {
+        let compilers = [
+            "The borrow checker tracks lifetimes of references.",
+            "Lifetimes tell the compiler how long references live.",
+            "The compiler rejects references that outlive their data.",
+            "Borrow checker errors mention lifetimes and references.",
+        ];
+        let cooking = [
+            "Simmer the tomato sauce with garlic and basil.",
+            "Fresh basil makes the tomato sauce taste brighter.",
+            "Add garlic before the tomatoes so the sauce mellows.",
+            "Pour the sauce over pasta with more basil.",
+        ];
+        compilers
+            .iter()
+            .cycle()
+            .take(20)
+            .chain(cooking.iter().cycle().take(20))
+            .enumerate()
+            .map(|(i, text)| segment(text, i as f64 * 10.0))
+            .collect()
+    }
+
+    #[test]
+    fn dips_are_measured_against_the_peaks_around_them() {
+        assert_eq!(depth_scores(&[0.8, 0.2, 0.6]), [0.0, 1.0, 0.0]);
+        assert_eq!(depth_scores(&[0.5, 0.5]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn splits_where_the_topic_changes() {
+        let sections = sections(&two_topics(), &OutlineOptions::default());
+        let starts: Vec<f64> = sections.iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, [0.0, 200.0]);
+        assert_eq!(sections[1].end_time, 400.0);
+        assert_eq!(sections[0].title, "Long references live");
+        assert_eq!(sections[1].title, "Fresh basil makes");
+    }
+
+    #[test]
+    fn keeps_sections_apart_by_the_minimum_length() {
+        let options = OutlineOptions {
+            min_section_secs: Some(250.0),
+            ..Default::default()
+        };
+        assert_eq!(sections(&two_topics(), &options).len(), 1);
+        assert!(sections(&[], &OutlineOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! Listing the videos of a playlist through the Innertube `browse` endpoint.
+
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{continuation_token, find_all, post_endpoint, text_of};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistVideo {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: Option<String>,
+    /// Missing for unavailable and live entries.
+    pub duration_seconds: Option<u64>,
+}
+
+/// One page of a playlist; pass `continuation` back to fetch the next one.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistPage {
+    pub playlist_id: String,
+    /// Only known on the first page.
+    pub title: Option<String>,
+    pub videos: Vec<PlaylistVideo>,
+    pub continuation: Option<String>,
+}
+
+/// Accepts a playlist ID or any URL carrying a `list=` parameter.
+pub fn parse_playlist_id(input: &str) -> Result<String, TranscriptError> {
+    let input = input.trim();
+    let is_id = |s: &str| {
+        s.len() >= 12
+            && s.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    };
+
+    if is_id(input) {
+        return Ok(input.to_string());
+    }
+
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+
+    Url::parse(&with_scheme)
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(k, _)| k == "list")
+                .map(|(_, v)| v.into_owned())
+        })
+        .filter(|id| is_id(id))
+        .ok_or_else(|| {
+            TranscriptError::InvalidInput(format!("Could not find a playlist ID in \"{}\".", input))
+        })
+}
+
+fn parse_playlist_video(renderer: &Value) -> Option<PlaylistVideo> {
+    Some(PlaylistVideo {
+        video_id: renderer.get("videoId")?.as_str()?.to_string(),
+        title: renderer.get("title").and_then(text_of).unwrap_or_default(),
+        channel_name: renderer.get("shortBylineText").and_then(text_of),
+        duration_seconds: renderer
+            .get("lengthSeconds")
+            .and_then(|l| l.as_str())
+            .and_then(|l| l.parse().ok()),
+    })
+}
+
+/// Fetches one page of a playlist. Without `continuation` the first page is returned.
+pub async fn fetch_playlist_page(
+    client: &reqwest::Client,
+    playlist_id: &str,
+    continuation: Option<String>,
+) -> Result<PlaylistPage, TranscriptError> {
+    let body = match &continuation {
+        Some(token) => serde_json::json!({ "continuation": token }),
+        None => serde_json::json!({ "browseId": format!("VL{}", playlist_id) }),
+    };
+    let response = post_endpoint(client, "browse", body).await?;
+
+    if continuation.is_none()
+        && response.get("alerts").is_some()
+        && find_all(&response, "playlistVideoRenderer").is_empty()
+    {
+        return Err(TranscriptError::VideoUnavailable(
+            "This playlist does not exist or is private.".into(),
+        ));
+    }
+
+    let title = response
+        .get("metadata")
+        .and_then(|m| m.get("playlistMetadataRenderer"))
+        .and_then(|m| m.get("title"))
+        .and_then(|t| t.as_str())
+        .map(String::from);
+
+    Ok(PlaylistPage {
+        playlist_id: playlist_id.to_string(),
+        title,
+        videos: find_all(&response, "playlistVideoRenderer")
+            .into_iter()
+            .filter_map(parse_playlist_video)
+            .collect(),
+        continuation: continuation_token(&response),
+    })
+}
+
+/// Fetches every page of a playlist, passing the number of videos found so far to
+/// `on_page` after each one.
+pub async fn fetch_all_videos(
+    client: &reqwest::Client,
+    playlist_id: &str,
+    mut on_page: impl FnMut(usize),
+) -> Result<Vec<PlaylistVideo>, TranscriptError> {
+    let mut videos = Vec::new();
+    let mut continuation = None;
+    loop {
+        let page = fetch_playlist_page(client, playlist_id, continuation).await?;
+        videos.extend(page.videos);
+        on_page(videos.len());
+        continuation = page.continuation;
+        if continuation.is_none() {
+            return Ok(videos);
+        }
+    }
+}
+
+pub async fn fetch_playlist_videos(
+    playlist_id: String,
+    continuation: Option<String>,
+) -> Result<PlaylistPage, TranscriptError> {
+    let playlist_id = parse_playlist_id(&playlist_id)?;
+    let client = build_client()?;
+    fetch_playlist_page(&client, &playlist_id, continuation).await
+}
@@ -0,0 +1,99 @@
+//! Progress of long-running operations.
+//!
+//! Operations that take a while (batch fetches, playlist ingestion, downloads,
+//! transcription, collection and chapter summaries) report to a [`Progress`],
+//! which passes every update, tagged with the operation's ID, to a sink. The app
+//! emits them as `progress` events, so several progress bars can run side by side.
+
+use serde::Serialize;
+use std::sync::Arc;
+
+/// What an operation is busy with.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    /// Collecting the videos of a playlist.
+    Listing,
+    /// Fetching transcripts.
+    Fetching,
+    Downloading,
+    #[cfg_attr(not(feature = "whisper"), allow(dead_code))]
+    Decoding,
+    #[cfg_attr(not(feature = "whisper"), allow(dead_code))]
+    Transcribing,
+    /// Summarizing transcripts with a language model.
+    Summarizing,
+    /// Analyzing comments with a language model.
+    Analyzing,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent<'a> {
+    operation_id: &'a str,
+    stage: Stage,
+    /// 0–100 within the stage; unknown while the total is.
+    percent: Option<u8>,
+    message: Option<&'a str>,
+}
+
+/// Receives the updates of a [`Progress`].
+pub type Sink = dyn Fn(&ProgressEvent) + Send + Sync;
+
+/// Reports the progress of one operation. Without an operation ID nothing is
+/// reported, so callers that do not track progress need no special casing.
+#[derive(Clone)]
+pub struct Progress {
+    target: Option<(Arc<Sink>, String)>,
+}
+
+impl Progress {
+    pub fn new(sink: Arc<Sink>, operation_id: Option<String>) -> Self {
+        Self {
+            target: operation_id.map(|id| (sink, id)),
+        }
+    }
+
+    pub fn report(&self, stage: Stage, percent: Option<u8>, message: Option<&str>) {
+        if let Some((sink, operation_id)) = &self.target {
+            sink(&ProgressEvent {
+                operation_id,
+                stage,
+                percent,
+                message,
+            });
+        }
+    }
+
+    /// Reports `done` of `total` items, with a message such as "3 of 10 videos".
+    pub fn report_count(&self, stage: Stage, done: usize, total: usize, unit: &str) {
+        let message = format!("{} of {} {}", done, total, unit);
+        self.report(
+            stage,
+            Some(percent(done as u64, total as u64)),
+            Some(&message),
+        );
+    }
+}
+
+/// `done` out of `total` as a whole percentage, capped at 100. An empty total
+/// counts as finished.
+pub(crate) fn percent(done: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    (done.saturating_mul(100) / total).min(100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_capped_percentages() {
+        assert_eq!(percent(0, 40), 0);
+        assert_eq!(percent(13, 40), 32);
+        assert_eq!(percent(41, 40), 100);
+        assert_eq!(percent(0, 0), 100);
+    }
+}
@@ -0,0 +1,68 @@
+//! Completions of prompts the frontend writes itself: quizzes, study materials and
+//! chat about a video.
+//!
+//! The webview never holds an API key, so rather than calling a provider it hands
+//! the prompt over and the call is made here, with the key from the keychain and
+//! metered like any other feature.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, CompletionRequest, OnChunk, ProviderKind};
+use serde::Deserialize;
+
+const MAX_TOKENS: u32 = 8_000;
+
+/// The feature a prompt is for, which the tokens it uses are recorded against.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PromptFeature {
+    Quiz,
+    StudyMaterial,
+    Chat,
+}
+
+impl PromptFeature {
+    fn operation(self) -> &'static str {
+        match self {
+            Self::Quiz => "quiz",
+            Self::StudyMaterial => "studyMaterial",
+            Self::Chat => "chat",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRequest {
+    pub feature: PromptFeature,
+    /// Instructions to the model, if any beyond the prompt.
+    #[serde(default)]
+    pub system: String,
+    pub prompt: String,
+    /// Ask for a JSON object.
+    #[serde(default)]
+    pub json: bool,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+}
+
+/// Completes `request` with `provider`, streaming the text to `on_chunk` as it is
+/// generated.
+pub async fn complete_prompt(
+    db: &Database,
+    provider: ProviderKind,
+    request: PromptRequest,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<String, TranscriptError> {
+    if request.prompt.trim().is_empty() {
+        return Err(TranscriptError::InvalidInput("The prompt is empty.".into()));
+    }
+    let llm = llm::provider(db, provider, request.model, request.feature.operation())?;
+    let completion = CompletionRequest {
+        system: request.system,
+        prompt: request.prompt,
+        max_tokens: MAX_TOKENS,
+        json: request.json,
+    };
+    Ok(llm.complete(&completion, on_chunk).await?.text)
+}
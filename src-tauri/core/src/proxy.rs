@@ -0,0 +1,94 @@
+//! Optional HTTP/SOCKS5 proxy for all outgoing requests.
+//!
+//! The proxy is part of the [`crate::settings`]; [`set_proxy`] changes just that
+//! entry.
+
+use crate::error::TranscriptError;
+use crate::http::build_client_with;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+const TEST_URL: &str = "https://www.youtube.com/generate_204";
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL.
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyTestResult {
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+static PROXY: Lazy<RwLock<Option<ProxyConfig>>> = Lazy::new(|| RwLock::new(None));
+
+impl ProxyConfig {
+    pub(crate) fn to_proxy(&self) -> Result<reqwest::Proxy, TranscriptError> {
+        let url = self.url.trim();
+        let scheme = url.split("://").next().unwrap_or("");
+        if !url.contains("://") || !matches!(scheme, "http" | "https" | "socks5" | "socks5h") {
+            return Err(TranscriptError::InvalidInput(format!(
+                "\"{}\" is not an http(s):// or socks5:// proxy URL.",
+                url
+            )));
+        }
+
+        let proxy = reqwest::Proxy::all(url)
+            .map_err(|e| TranscriptError::InvalidInput(format!("Invalid proxy URL: {}", e)))?;
+        Ok(match self.username.as_deref().filter(|u| !u.is_empty()) {
+            Some(username) => proxy.basic_auth(username, self.password.as_deref().unwrap_or("")),
+            None => proxy,
+        })
+    }
+}
+
+/// The proxy currently applied by [`build_client`], if any.
+pub(crate) fn current() -> Option<ProxyConfig> {
+    PROXY.read().ok().and_then(|p| p.clone())
+}
+
+/// Applies `config`; an empty URL turns the proxy off.
+pub(crate) fn apply(config: Option<ProxyConfig>) -> Result<(), TranscriptError> {
+    let config = config.filter(|c| !c.url.trim().is_empty());
+    if let Some(config) = &config {
+        config.to_proxy()?;
+    }
+    if let Ok(mut proxy) = PROXY.write() {
+        *proxy = config;
+    }
+    Ok(())
+}
+
+/// Sends one request to YouTube through `config` (or the current proxy when omitted).
+pub async fn test_proxy_connection(
+    config: Option<ProxyConfig>,
+) -> Result<ProxyTestResult, TranscriptError> {
+    let config = config
+        .or_else(current)
+        .ok_or_else(|| TranscriptError::InvalidInput("No proxy is configured.".into()))?;
+    let client = build_client_with(Some(&config))?;
+
+    let started = Instant::now();
+    let res = client
+        .get(TEST_URL)
+        .timeout(TEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| TranscriptError::NetworkError(format!("Proxy connection failed: {}", e)))?;
+
+    Ok(ProxyTestResult {
+        status: res.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
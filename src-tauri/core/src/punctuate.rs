@@ -0,0 +1,156 @@
+//! Restoring punctuation and casing in automatic captions with a language model.
+//!
+//! Speech recognition captions come in lower case and without punctuation. The
+//! model rewrites them segment by segment, so every segment keeps its offset and
+//! duration. It may only add punctuation and change case: a line whose letters come
+//! back different is kept as it was, rather than letting a reworded line drift
+//! from the audio.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use serde::Deserialize;
+
+const SYSTEM_PROMPT: &str = "You restore punctuation and capitalization in the automatic \
+captions of a YouTube video. You receive a JSON object {\"lines\": [{\"id\": number, \"text\": \
+string}]} and reply with a JSON object only, of the same shape, holding every line under its \
+id. Add punctuation and fix the case of letters, and change nothing else: do not add, drop, \
+reorder, correct or translate words, and do not write numbers as digits. The lines are \
+fragments of continuous speech, so sentences run across lines, but every word stays in its \
+own line so the timing stays aligned.";
+const INSTRUCTION: &str = "Restore the punctuation and capitalization of these lines.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PunctuateOptions {
+    /// Provider to use instead of the one chosen in the settings.
+    pub provider: Option<ProviderKind>,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+}
+
+/// The letters and digits of `text`, lowercased: what punctuation and casing
+/// cannot change.
+fn letters(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The segments with their text replaced by `texts`, in order, where a text only
+/// differs in punctuation and case. Timed words take the new spelling when the
+/// text still splits into as many words.
+fn punctuated(segments: Vec<TranscriptSegment>, texts: Vec<String>) -> Vec<TranscriptSegment> {
+    segments
+        .into_iter()
+        .zip(texts)
+        .map(|(mut segment, text)| {
+            let text = text.trim();
+            if text.is_empty() || letters(text) != letters(&segment.text) {
+                return segment;
+            }
+            let tokens: Vec<&str> = text.split_whitespace().collect();
+            if tokens.len() == segment.words.len() {
+                for (word, token) in segment.words.iter_mut().zip(&tokens) {
+                    word.text = token.to_string();
+                }
+            }
+            segment.text = tokens.join(" ");
+            segment
+        })
+        .collect()
+}
+
+/// Restores punctuation and casing in the transcript of `video_id` with a language
+/// model, the one chosen in the settings unless `options` name another (Ollama runs
+/// locally). Segments keep their timing. Can be stopped with
+/// `cancel_operation(operation_id)`.
+pub async fn punctuate_transcript(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    options: Option<PunctuateOptions>,
+    operation_id: Option<String>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    let settings = crate::settings::current();
+    let (provider, model) = match options.provider {
+        Some(provider) => (Some(provider), options.model),
+        None => (settings.llm.provider, options.model.or(settings.llm.model)),
+    };
+    let provider = provider.ok_or_else(|| {
+        TranscriptError::InvalidInput("Choose a language model provider in the settings.".into())
+    })?;
+    let llm = llm::provider(db, provider, model, "punctuation")?;
+
+    let segments = load_transcript(db, &video_id, None).await?;
+    let texts: Vec<String> = segments.iter().map(|s| s.text.clone()).collect();
+    let operation = operations.start(operation_id.as_deref());
+    let rewritten = operation
+        .run(llm::lines::rewrite(
+            llm.as_ref(),
+            SYSTEM_PROMPT,
+            INSTRUCTION,
+            &texts,
+        ))
+        .await?;
+    Ok(punctuated(segments, rewritten))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TranscriptWord;
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 2.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_timing_and_rejects_reworded_lines() {
+        let segments = vec![
+            segment("so i dont think", 0.0),
+            segment("it works in paris", 2.0),
+        ];
+        let restored = punctuated(
+            segments,
+            vec![
+                "So I don't think".into(),
+                "it really works in Paris.".into(),
+            ],
+        );
+        assert_eq!(restored[0].text, "So I don't think");
+        assert_eq!((restored[0].offset, restored[0].duration), (0.0, 2.0));
+        assert_eq!(restored[1].text, "it works in paris");
+    }
+
+    #[test]
+    fn respells_timed_words() {
+        let mut timed = segment("hello world", 0.0);
+        timed.words = ["hello", "world"]
+            .iter()
+            .enumerate()
+            .map(|(i, w)| TranscriptWord {
+                text: w.to_string(),
+                offset: i as f64,
+                duration: 1.0,
+            })
+            .collect();
+        let restored = punctuated(vec![timed], vec!["Hello, world!".into()]);
+        let words: Vec<&str> = restored[0].words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, ["Hello,", "world!"]);
+        assert_eq!(restored[0].words[1].offset, 1.0);
+    }
+}
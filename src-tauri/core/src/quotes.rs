@@ -0,0 +1,307 @@
+//! Picking the most quotable moments of a video, for shareable quote cards.
+//!
+//! Candidates are whole sentences, plus a question joined with the answer that
+//! follows it. They are scored on length (a quote card fits one or two lines),
+//! on reading as a complete thought (punctuated, not opening with a conjunction,
+//! free of fillers) and on emphatic wording. Start times are snapped to word timing
+//! when the transcript has it, so a shared link starts on the quote's first word.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::transcript::{load_transcript, segmenter, TranscriptParagraph, TranscriptSegment};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 50;
+const MIN_WORDS: usize = 6;
+const MAX_WORDS: usize = 40;
+/// Length of the most quotable sentences, in words.
+const IDEAL_WORDS: f64 = 18.0;
+/// An answer must start this soon after its question.
+const ANSWER_GAP_SECS: f64 = 3.0;
+const QUESTION_ANSWER_BONUS: f64 = 0.8;
+/// Furthest a start time moves to reach the first word of a quote.
+const SNAP_SECS: f64 = 1.0;
+
+const EMPHASIS_WORDS: &[&str] = &[
+    "always",
+    "best",
+    "biggest",
+    "can't",
+    "change",
+    "crucial",
+    "essential",
+    "every",
+    "everyone",
+    "everything",
+    "exactly",
+    "forget",
+    "important",
+    "key",
+    "love",
+    "most",
+    "must",
+    "never",
+    "nobody",
+    "nothing",
+    "only",
+    "remember",
+    "secret",
+    "simply",
+    "truth",
+    "worst",
+];
+const FILLER_WORDS: &[&str] = &["er", "erm", "hmm", "uh", "uhm", "um"];
+const FILLER_PHRASES: &[&str] = &["you know", "i mean", "kind of", "sort of"];
+/// Openers that make a sentence lean on the one before it.
+const WEAK_OPENERS: &[&str] = &["and", "because", "but", "or", "so", "then", "which"];
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuoteOptions {
+    /// Quotes returned.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuoteKind {
+    Sentence,
+    /// A question together with the answer right after it.
+    QuestionAnswer,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Quote {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    pub score: f64,
+    pub kind: QuoteKind,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .replace('’', "'")
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// How quotable `text` is on its own, or `None` when it cannot be a quote at all.
+fn score(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let words = words(text);
+    if !(MIN_WORDS..=MAX_WORDS).contains(&words.len()) || text.contains('[') {
+        return None;
+    }
+
+    let mut score = (1.0 - (words.len() as f64 - IDEAL_WORDS).abs() / IDEAL_WORDS).max(0.0);
+
+    if text.ends_with(['.', '!', '?', '…', '"', '”']) {
+        score += 0.5;
+    }
+    if text.starts_with(char::is_uppercase) {
+        score += 0.25;
+    }
+    if WEAK_OPENERS.contains(&words[0].as_str()) {
+        score -= 0.5;
+    }
+    let joined = words.join(" ");
+    let fillers = words
+        .iter()
+        .filter(|w| FILLER_WORDS.contains(&w.as_str()))
+        .count()
+        + FILLER_PHRASES
+            .iter()
+            .map(|p| joined.matches(p).count())
+            .sum::<usize>();
+    score -= 0.3 * fillers as f64;
+
+    let emphasis = words
+        .iter()
+        .filter(|w| EMPHASIS_WORDS.binary_search(&w.as_str()).is_ok())
+        .count();
+    score += (0.4 * emphasis as f64).min(1.2);
+    if text.ends_with('!') {
+        score += 0.3;
+    }
+    Some(score)
+}
+
+/// Every scored candidate: the sentences, and questions joined with their answers.
+fn candidates(sentences: &[TranscriptParagraph]) -> Vec<Quote> {
+    let mut out: Vec<Quote> = sentences
+        .iter()
+        .filter_map(|s| {
+            Some(Quote {
+                score: score(&s.text)?,
+                text: s.text.clone(),
+                start: s.start,
+                end: s.end,
+                kind: QuoteKind::Sentence,
+            })
+        })
+        .collect();
+
+    for pair in sentences.windows(2) {
+        let (question, answer) = (&pair[0], &pair[1]);
+        if !question.text.trim_end().ends_with('?')
+            || answer.text.trim_end().ends_with('?')
+            || answer.start - question.end > ANSWER_GAP_SECS
+        {
+            continue;
+        }
+        let text = format!("{} {}", question.text.trim(), answer.text.trim());
+        if let Some(score) = score(&text) {
+            out.push(Quote {
+                text,
+                start: question.start,
+                end: answer.end,
+                score: score + QUESTION_ANSWER_BONUS,
+                kind: QuoteKind::QuestionAnswer,
+            });
+        }
+    }
+    out
+}
+
+/// The start of the word closest to `start`, if one is near enough.
+fn snap_to_word(segments: &[TranscriptSegment], start: f64) -> f64 {
+    segments
+        .iter()
+        .flat_map(|s| &s.words)
+        .map(|w| w.offset)
+        .filter(|offset| (offset - start).abs() <= SNAP_SECS)
+        .min_by(|a, b| (a - start).abs().total_cmp(&(b - start).abs()))
+        .unwrap_or(start)
+}
+
+/// The `limit` most quotable moments of `segments`, best first. Quotes never
+/// overlap, so a question-and-answer pair hides the sentences it is made of.
+pub(crate) fn extract(segments: &[TranscriptSegment], limit: usize) -> Vec<Quote> {
+    let mut candidates = candidates(&segmenter::sentences(segments));
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.start.total_cmp(&b.start))
+    });
+
+    let mut picked: Vec<Quote> = Vec::new();
+    for candidate in candidates {
+        if picked.len() == limit {
+            break;
+        }
+        let overlaps = picked
+            .iter()
+            .any(|q| candidate.start < q.end && q.start < candidate.end);
+        if !overlaps {
+            picked.push(candidate);
+        }
+    }
+    for quote in &mut picked {
+        quote.start = snap_to_word(segments, quote.start);
+    }
+    picked
+}
+
+/// Finds the most quotable moments of `video_id`, with their start times.
+pub async fn extract_quotes(
+    db: &Database,
+    video_id: String,
+    options: Option<QuoteOptions>,
+) -> Result<Vec<Quote>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let limit = options
+        .unwrap_or_default()
+        .limit
+        .unwrap_or(DEFAULT_LIMIT)
+        .clamp(1, MAX_LIMIT);
+    let segments = load_transcript(db, &video_id, None).await?;
+    Ok(extract(&segments, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TranscriptWord;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn emphasis_word_list_is_sorted_for_binary_search() {
+        assert!(EMPHASIS_WORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn prefers_complete_emphatic_sentences() {
+        let emphatic = score("Never ship code you have not read at least once yourself.").unwrap();
+        let plain = score("We moved the office to the building across the street.").unwrap();
+        let rambling = score("and um you know we kind of moved it over there").unwrap();
+        assert!(emphatic > plain);
+        assert!(plain > rambling);
+        assert_eq!(score("Thanks for watching."), None);
+        assert_eq!(
+            score("[Music] playing softly in the background for a while"),
+            None
+        );
+    }
+
+    #[test]
+    fn joins_questions_with_their_answers_without_overlaps() {
+        let segments = [
+            segment("So what is the secret to good bread?", 0.0, 3.0),
+            segment("Time, patience and the best flour you can find.", 3.2, 4.0),
+            segment(
+                "Anyway, let me show you the oven we built last year.",
+                20.0,
+                4.0,
+            ),
+        ];
+
+        let quotes = extract(&segments, 5);
+        assert_eq!(quotes[0].kind, QuoteKind::QuestionAnswer);
+        assert_eq!((quotes[0].start, quotes[0].end), (0.0, 7.2));
+        // The question and the answer are not repeated on their own
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[1].start, 20.0);
+    }
+
+    #[test]
+    fn snaps_start_times_to_word_timing() {
+        let mut segment = segment(
+            "Intro. Remember that every great loaf starts with a cold proof.",
+            10.0,
+            6.0,
+        );
+        segment.words = vec![
+            TranscriptWord {
+                text: "Intro.".into(),
+                offset: 10.0,
+                duration: 0.4,
+            },
+            TranscriptWord {
+                text: "Remember".into(),
+                offset: 10.9,
+                duration: 0.4,
+            },
+        ];
+
+        let quotes = extract(&[segment], 1);
+        assert_eq!(quotes[0].start, 10.9);
+    }
+}
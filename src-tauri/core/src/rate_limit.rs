@@ -0,0 +1,156 @@
+//! Token-bucket limit on the number of requests sent to YouTube.
+//!
+//! Every attempt made by [`crate::http::send`] takes a token, so batch, playlist and
+//! channel operations together stay under the configured requests-per-minute.
+
+use crate::error::TranscriptError;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    pub requests_per_minute: u32,
+    /// Requests that may be sent back to back before the per-minute rate applies.
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            burst: 5,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        if self.requests_per_minute == 0 || self.burst == 0 {
+            return Err(TranscriptError::InvalidInput(
+                "Requests per minute and burst must both be at least 1.".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    config: RateLimitConfig,
+    /// May go negative: each reservation takes a token even when none is left and
+    /// waits until the deficit has been refilled.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(config: RateLimitConfig, now: Instant) -> Self {
+        Self {
+            config,
+            tokens: config.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    fn rate_per_sec(&self) -> f64 {
+        self.config.requests_per_minute as f64 / 60.0
+    }
+
+    /// Takes a token and returns how long the caller has to wait before using it.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec()).min(self.config.burst as f64);
+        self.updated_at = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate_per_sec())
+        }
+    }
+}
+
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    fn new(config: RateLimitConfig) -> Self {
+        Self {
+            bucket: Mutex::new(Bucket::new(config, Instant::now())),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let wait = match self.bucket.lock() {
+            Ok(mut bucket) => bucket.reserve(Instant::now()),
+            Err(_) => Duration::ZERO,
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.bucket.lock().map(|b| b.config).unwrap_or_default()
+    }
+
+    pub fn set_config(&self, config: RateLimitConfig) -> Result<(), TranscriptError> {
+        config.validate()?;
+        if let Ok(mut bucket) = self.bucket.lock() {
+            *bucket = Bucket::new(config, Instant::now());
+        }
+        Ok(())
+    }
+}
+
+/// The limiter shared by all YouTube requests.
+pub static YOUTUBE: Lazy<RateLimiter> = Lazy::new(|| RateLimiter::new(RateLimitConfig::default()));
+
+pub fn get_rate_limit() -> RateLimitConfig {
+    YOUTUBE.config()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_burst_then_spaces_requests() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(
+            RateLimitConfig {
+                requests_per_minute: 60,
+                burst: 2,
+            },
+            start,
+        );
+
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_secs(1));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn refills_over_time_up_to_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(
+            RateLimitConfig {
+                requests_per_minute: 120,
+                burst: 1,
+            },
+            start,
+        );
+
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::from_millis(500));
+    }
+}
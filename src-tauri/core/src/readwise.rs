@@ -0,0 +1,220 @@
+//! Highlights sent to Readwise, where they join the user's other reading.
+//!
+//! Each video becomes a Readwise source titled and authored after the video and
+//! its channel, and every highlight links back to its moment. Highlights that
+//! made it are recorded, so a sync only sends the ones made since; Readwise would
+//! ignore repeats anyway, but a library of highlights would be sent whole each time.
+
+use crate::activity::{self, ActivityKind};
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::secrets::{self, KeyedService};
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS readwise_synced (
+    highlight_id INTEGER PRIMARY KEY,
+    synced_at    INTEGER NOT NULL
+);
+";
+
+const API_URL: &str = "https://readwise.io/api/v2/highlights/";
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Highlights sent per request.
+const BATCH_SIZE: usize = 100;
+/// Longest highlight text Readwise accepts.
+const MAX_TEXT: usize = 8_191;
+
+/// A highlight not sent yet, with the details of its video.
+#[derive(Debug, Clone, PartialEq)]
+struct Pending {
+    id: i64,
+    video_id: String,
+    start: f64,
+    text: String,
+    title: Option<String>,
+    channel_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadwiseSync {
+    /// Highlights sent by this sync.
+    pub synced: usize,
+    /// Highlights sent by earlier syncs and left out.
+    pub already_synced: usize,
+}
+
+/// Highlights not sent yet, of `video_id` or of every video, and how many were.
+fn pending(
+    db: &Database,
+    video_id: Option<&str>,
+) -> Result<(Vec<Pending>, usize), TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT h.id, h.video_id, h.start, h.text, v.title, v.channel_name,
+                    r.highlight_id IS NOT NULL
+             FROM highlights h
+             LEFT JOIN history v ON v.video_id = h.video_id
+             LEFT JOIN readwise_synced r ON r.highlight_id = h.id
+             WHERE ?1 IS NULL OR h.video_id = ?1
+             ORDER BY h.video_id, h.start, h.id",
+        )?;
+        let rows = stmt.query_map([video_id], |row| {
+            let highlight = Pending {
+                id: row.get(0)?,
+                video_id: row.get(1)?,
+                start: row.get(2)?,
+                text: row.get(3)?,
+                title: row.get(4)?,
+                channel_name: row.get(5)?,
+            };
+            Ok((highlight, row.get::<_, bool>(6)?))
+        })?;
+        let mut pending = Vec::new();
+        let mut synced = 0;
+        for row in rows {
+            match row? {
+                (_, true) => synced += 1,
+                (highlight, false) => pending.push(highlight),
+            }
+        }
+        Ok((pending, synced))
+    })
+}
+
+/// A highlight as the Readwise API takes it.
+fn to_readwise(highlight: &Pending) -> Value {
+    let seconds = highlight.start.floor() as u64;
+    let text: String = highlight.text.chars().take(MAX_TEXT).collect();
+    json!({
+        "text": text,
+        "title": highlight.title.as_deref().unwrap_or(&highlight.video_id),
+        "author": highlight.channel_name.as_deref().unwrap_or("YouTube"),
+        "source_url": format!("https://youtu.be/{}", highlight.video_id),
+        "source_type": "insighttube",
+        "category": "podcasts",
+        "location": seconds,
+        "location_type": "time_offset",
+        "highlight_url": format!("https://youtu.be/{}?t={}", highlight.video_id, seconds),
+    })
+}
+
+async fn send(
+    client: &reqwest::Client,
+    token: &str,
+    batch: &[Value],
+) -> Result<(), TranscriptError> {
+    let res = client
+        .post(API_URL)
+        .header("Authorization", format!("Token {}", token))
+        .json(&json!({ "highlights": batch }))
+        .send()
+        .await
+        .map_err(|e| TranscriptError::NetworkError(format!("Could not reach Readwise: {}", e)))?;
+
+    match res.status().as_u16() {
+        200..=299 => Ok(()),
+        401 | 403 => Err(TranscriptError::InvalidInput(
+            "Readwise rejected the access token. Check it in Settings.".into(),
+        )),
+        429 => Err(TranscriptError::RateLimited),
+        status => {
+            let body = res.text().await.unwrap_or_default();
+            let message = match body.trim() {
+                "" => format!("HTTP {}", status),
+                body => body.chars().take(200).collect(),
+            };
+            Err(TranscriptError::NetworkError(format!(
+                "Readwise request failed: {}",
+                message
+            )))
+        }
+    }
+}
+
+async fn sync(db: &Database, video_id: Option<&str>) -> Result<ReadwiseSync, TranscriptError> {
+    let token = secrets::api_key(KeyedService::Readwise).ok_or_else(|| {
+        TranscriptError::InvalidInput("Add a Readwise access token in Settings first.".into())
+    })?;
+    let (pending, already_synced) = pending(db, video_id)?;
+
+    let client = crate::llm::client(TIMEOUT)?;
+    for batch in pending.chunks(BATCH_SIZE) {
+        let highlights: Vec<Value> = batch.iter().map(to_readwise).collect();
+        send(&client, &token, &highlights).await?;
+        let now = unix_now();
+        db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "INSERT OR REPLACE INTO readwise_synced (highlight_id, synced_at) VALUES (?1, ?2)",
+            )?;
+            for highlight in batch {
+                stmt.execute(params![highlight.id, now])?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ReadwiseSync {
+        synced: pending.len(),
+        already_synced,
+    })
+}
+
+/// Sends the highlights of `video_id`, or of every video without one, that are not
+/// in Readwise yet. Each batch is recorded once Readwise took it, so a failed sync
+/// can simply be run again.
+pub async fn sync_readwise(
+    db: &Database,
+    video_id: Option<String>,
+) -> Result<ReadwiseSync, TranscriptError> {
+    let video_id = video_id.map(|id| crate::video_id::parse(&id)).transpose()?;
+    let result = sync(db, video_id.as_deref()).await;
+    activity::finished(
+        db,
+        ActivityKind::ExportCompleted,
+        video_id.as_deref(),
+        "Syncing highlights to Readwise",
+        &result,
+        |sync| {
+            (
+                format!("Sent {} highlights to Readwise", sync.synced),
+                json!({ "destination": "readwise", "synced": sync.synced }),
+            )
+        },
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_highlights_to_their_moment() {
+        let highlight = Pending {
+            id: 1,
+            video_id: "dQw4w9WgXcQ".into(),
+            start: 75.5,
+            text: "First, the onions.".into(),
+            title: Some("Cooking 101".into()),
+            channel_name: None,
+        };
+        assert_eq!(
+            to_readwise(&highlight),
+            json!({
+                "text": "First, the onions.",
+                "title": "Cooking 101",
+                "author": "YouTube",
+                "source_url": "https://youtu.be/dQw4w9WgXcQ",
+                "source_type": "insighttube",
+                "category": "podcasts",
+                "location": 75,
+                "location_type": "time_offset",
+                "highlight_url": "https://youtu.be/dQw4w9WgXcQ?t=75",
+            })
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Videos YouTube suggests next to a video, from the watch-next feed.
+//!
+//! The feed lists them as `compactVideoRenderer`s, or as `lockupViewModel`s where
+//! YouTube has moved to its newer layout; both are read. Mixes, playlists and ads
+//! in the feed are skipped.
+
+use crate::comments::parse_count;
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{find_all, post_endpoint};
+use crate::youtube_search::{parse_length, parse_video, SearchVideo};
+use serde_json::Value;
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer)?.as_str()
+}
+
+/// Reads a video `lockupViewModel`. Its metadata rows are the channel, then the
+/// views and the upload time.
+fn parse_lockup(lockup: &Value) -> Option<SearchVideo> {
+    if str_at(lockup, "/contentType") != Some("LOCKUP_CONTENT_TYPE_VIDEO") {
+        return None;
+    }
+    let metadata = lockup.pointer("/metadata/lockupMetadataViewModel")?;
+    let rows: Vec<Vec<&str>> = metadata
+        .pointer("/metadata/contentMetadataViewModel/metadataRows")
+        .and_then(|rows| rows.as_array())
+        .into_iter()
+        .flatten()
+        .map(|row| {
+            row.get("metadataParts")
+                .and_then(|parts| parts.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|part| str_at(part, "/text/content"))
+                .collect()
+        })
+        .collect();
+    let row_part = |row: usize, part: usize| rows.get(row)?.get(part).copied();
+
+    let image = lockup.pointer("/contentImage/thumbnailViewModel")?;
+    Some(SearchVideo {
+        video_id: str_at(lockup, "/contentId")?.to_string(),
+        title: str_at(metadata, "/title/content")
+            .unwrap_or_default()
+            .to_string(),
+        channel_name: row_part(0, 0).map(String::from),
+        channel_id: None,
+        duration_seconds: find_all(image, "thumbnailBadgeViewModel")
+            .into_iter()
+            .find_map(|badge| parse_length(str_at(badge, "/text")?)),
+        view_count: row_part(1, 0)
+            .filter(|v| v.contains("view"))
+            .map(parse_count),
+        published_time: row_part(1, 1).map(String::from),
+        thumbnail_url: image
+            .pointer("/image/sources")
+            .and_then(|sources| sources.as_array())
+            .and_then(|sources| sources.last())
+            .and_then(|source| str_at(source, "/url"))
+            .map(String::from),
+    })
+}
+
+/// The suggested videos of a watch-next response, in the order shown.
+fn parse_related(next: &Value) -> Vec<SearchVideo> {
+    let Some(results) = next
+        .pointer("/contents/twoColumnWatchNextResults/secondaryResults/secondaryResults/results")
+        .and_then(|r| r.as_array())
+    else {
+        return Vec::new();
+    };
+    results
+        .iter()
+        .filter_map(|item| {
+            if let Some(renderer) = item.get("compactVideoRenderer") {
+                parse_video(renderer)
+            } else {
+                parse_lockup(item.get("lockupViewModel")?)
+            }
+        })
+        .collect()
+}
+
+/// Lists the videos YouTube suggests watching after `video_id`.
+pub async fn fetch_related_videos(video_id: String) -> Result<Vec<SearchVideo>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+    let next = post_endpoint(&client, "next", serde_json::json!({ "videoId": video_id })).await?;
+    Ok(parse_related(&next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_compact_renderers_and_lockups() {
+        let next = json!({ "contents": { "twoColumnWatchNextResults": { "secondaryResults": {
+            "secondaryResults": { "results": [
+                { "compactVideoRenderer": {
+                    "videoId": "dQw4w9WgXcQ",
+                    "title": { "simpleText": "Cooking 101" },
+                    "longBylineText": { "runs": [{ "text": "Chef" }] },
+                    "lengthText": { "simpleText": "12:34" }
+                } },
+                { "lockupViewModel": {
+                    "contentId": "jNQXAC9IVRw",
+                    "contentType": "LOCKUP_CONTENT_TYPE_VIDEO",
+                    "contentImage": { "thumbnailViewModel": {
+                        "image": { "sources": [{ "url": "small" }, { "url": "large" }] },
+                        "overlays": [{ "thumbnailOverlayBadgeViewModel": { "thumbnailBadges": [
+                            { "thumbnailBadgeViewModel": { "text": "3:05" } }
+                        ] } }]
+                    } },
+                    "metadata": { "lockupMetadataViewModel": {
+                        "title": { "content": "Baking 101" },
+                        "metadata": { "contentMetadataViewModel": { "metadataRows": [
+                            { "metadataParts": [{ "text": { "content": "Baker" } }] },
+                            { "metadataParts": [
+                                { "text": { "content": "1.2M views" } },
+                                { "text": { "content": "3 years ago" } }
+                            ] }
+                        ] } }
+                    } }
+                } },
+                { "lockupViewModel": {
+                    "contentId": "RDdQw4w9WgXcQ",
+                    "contentType": "LOCKUP_CONTENT_TYPE_PLAYLIST"
+                } }
+            ] }
+        } } } });
+
+        let related = parse_related(&next);
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0].channel_name.as_deref(), Some("Chef"));
+        assert_eq!(related[0].duration_seconds, Some(754));
+
+        let lockup = &related[1];
+        assert_eq!(lockup.video_id, "jNQXAC9IVRw");
+        assert_eq!(lockup.title, "Baking 101");
+        assert_eq!(lockup.channel_name.as_deref(), Some("Baker"));
+        assert_eq!(lockup.duration_seconds, Some(185));
+        assert_eq!(lockup.view_count, Some(1_200_000));
+        assert_eq!(lockup.published_time.as_deref(), Some("3 years ago"));
+        assert_eq!(lockup.thumbnail_url.as_deref(), Some("large"));
+    }
+}
@@ -0,0 +1,363 @@
+//! A single document gathering everything known about a video.
+//!
+//! The report puts the video's details and thumbnail, its summary, chapters,
+//! keywords, highlights and notes into one self-contained HTML page, every
+//! timestamp linking back to its moment. PDFs are printed from that page by a
+//! Chromium-based browser run headless, since one is installed on most machines
+//! and lays out the page exactly as it is shown.
+
+use crate::chapters::{extract_chapters, Chapter};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::highlights::{self, Highlight};
+use crate::http::build_client;
+use crate::innertube::fetch_player_response;
+use crate::keywords::{self, Keyword, KeywordOptions};
+use crate::llm::{self, ProviderKind};
+use crate::metadata::{parse_video_metadata, VideoMetadata};
+use crate::notes::{self, Note};
+use crate::summarize::{summarize, KeyPoint, SummarizeOptions};
+use crate::transcript::load_transcript;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:52rem;margin:2rem auto;\
+padding:0 1rem;line-height:1.5;color:#1a1a1a}h1{margin-bottom:.25rem}.meta{color:#555}\
+img{max-width:100%;border-radius:8px}a{color:#0b57d0;text-decoration:none}\
+blockquote{margin:.5rem 0;padding-left:1rem;border-left:3px solid #ccc}\
+.keywords li{display:inline-block;margin:0 .5rem .5rem 0;padding:.1rem .6rem;\
+border-radius:1rem;background:#eef}.keywords{padding:0}";
+
+/// Executables of Chromium-based browsers looked up on the `PATH`.
+const BROWSER_NAMES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "microsoft-edge",
+    "msedge",
+];
+/// Install locations that are not on the `PATH`.
+const BROWSER_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+];
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    /// Printed by a headless Chrome, Chromium or Edge.
+    Pdf,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    /// Summarizes the video with this provider when no summary is stored for it.
+    pub provider: Option<ProviderKind>,
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+}
+
+/// What goes into a report.
+pub struct Report {
+    pub metadata: VideoMetadata,
+    /// TL;DR and key points.
+    pub summary: Option<(String, Vec<KeyPoint>)>,
+    pub chapters: Vec<Chapter>,
+    pub keywords: Vec<Keyword>,
+    pub highlights: Vec<Highlight>,
+    pub notes: Vec<Note>,
+}
+
+impl Report {
+    /// Gathers what is known about `video_id`. The summary is the one stored by
+    /// auto-ingestion, or one made with `provider`; without either it is left out.
+    pub(crate) async fn gather(
+        db: &Database,
+        video_id: &str,
+        provider: Option<ProviderKind>,
+        model: Option<String>,
+    ) -> Result<Self, TranscriptError> {
+        let client = build_client()?;
+        let player_json = fetch_player_response(&client, video_id).await?;
+        let metadata = parse_video_metadata(video_id, &player_json)?;
+        let chapters = extract_chapters(video_id, &player_json)?;
+        let segments = load_transcript(db, video_id, None).await?;
+
+        let summary = match (crate::auto_ingest::stored(db, video_id)?, provider) {
+            (Some(stored), _) => Some((stored.summary.tldr, stored.summary.key_points)),
+            (None, Some(provider)) => {
+                let llm = llm::provider(db, provider, model.clone(), "summary")?;
+                let options = SummarizeOptions {
+                    model,
+                    ..Default::default()
+                };
+                Some(summarize(llm.as_ref(), &segments, &options, None).await?)
+            }
+            (None, None) => None,
+        };
+        Ok(Self {
+            metadata,
+            summary,
+            chapters,
+            keywords: keywords::extract(&segments, &KeywordOptions::default()),
+            highlights: highlights::for_video(db, video_id)?,
+            notes: notes::for_video(db, video_id)?,
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn link(video_id: &str, seconds: f64) -> String {
+    format!(
+        "<a href=\"https://youtu.be/{}?t={}\">{}</a>",
+        video_id,
+        seconds.floor() as u64,
+        short_timestamp(seconds)
+    )
+}
+
+fn render_html(report: &Report) -> String {
+    let meta = &report.metadata;
+    let id = meta.video_id.as_str();
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(&meta.title),
+        STYLE,
+        escape(&meta.title)
+    );
+    let mut details = vec![escape(&meta.channel_name)];
+    if let Some(date) = &meta.upload_date {
+        details.push(escape(date));
+    }
+    details.push(short_timestamp(meta.duration_seconds as f64));
+    details.push(format!("{} views", meta.view_count));
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\">{} · <a href=\"https://youtu.be/{}\">youtu.be/{}</a></p>",
+        details.join(" · "),
+        id,
+        id
+    );
+    if let Some(thumbnail) = meta.thumbnails.iter().max_by_key(|t| t.width) {
+        let _ = writeln!(
+            out,
+            "<img src=\"{}\" alt=\"Thumbnail\">",
+            escape(&thumbnail.url)
+        );
+    }
+
+    if let Some((tldr, key_points)) = &report.summary {
+        let _ = writeln!(out, "<h2>Summary</h2>\n<p>{}</p>", escape(tldr));
+        if !key_points.is_empty() {
+            out.push_str("<ul>\n");
+            for point in key_points {
+                let _ = writeln!(
+                    out,
+                    "<li>{} {}</li>",
+                    link(id, point.timestamp),
+                    escape(&point.text)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+    }
+    if !report.chapters.is_empty() {
+        out.push_str("<h2>Chapters</h2>\n<ol>\n");
+        for chapter in &report.chapters {
+            let _ = writeln!(
+                out,
+                "<li>{} {}</li>",
+                link(id, chapter.start_time),
+                escape(&chapter.title)
+            );
+        }
+        out.push_str("</ol>\n");
+    }
+    if !report.keywords.is_empty() {
+        out.push_str("<h2>Keywords</h2>\n<ul class=\"keywords\">\n");
+        for keyword in &report.keywords {
+            let _ = writeln!(out, "<li>{}</li>", escape(&keyword.keyword));
+        }
+        out.push_str("</ul>\n");
+    }
+    if !report.highlights.is_empty() {
+        out.push_str("<h2>Highlights</h2>\n");
+        for highlight in &report.highlights {
+            let _ = writeln!(
+                out,
+                "<blockquote>{} {}</blockquote>",
+                link(id, highlight.start),
+                escape(&highlight.text)
+            );
+        }
+    }
+    if !report.notes.is_empty() {
+        out.push_str("<h2>Notes</h2>\n");
+        for note in &report.notes {
+            let text = escape(note.text.trim()).replace('\n', "<br>");
+            let _ = writeln!(out, "<p>{} {}</p>", link(id, note.offset), text);
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// The first Chromium-based browser found on the `PATH` or in its usual place.
+fn find_browser() -> Option<PathBuf> {
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let exe = if cfg!(windows) { ".exe" } else { "" };
+    on_path
+        .iter()
+        .flat_map(|dir| {
+            BROWSER_NAMES
+                .iter()
+                .map(move |name| dir.join(format!("{}{}", name, exe)))
+        })
+        .chain(BROWSER_PATHS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+/// Prints the HTML page `html` to the PDF file `path` with a headless browser.
+async fn print_pdf(html: String, path: &str) -> Result<(), TranscriptError> {
+    let browser = find_browser().ok_or_else(|| {
+        TranscriptError::InvalidInput(
+            "PDF reports need Chrome, Chromium or Edge installed. Export as HTML instead.".into(),
+        )
+    })?;
+    let page = std::env::temp_dir().join(format!("insighttube-report-{}.html", std::process::id()));
+    std::fs::write(&page, html)
+        .map_err(|e| TranscriptError::FileError(format!("Could not write the report: {}", e)))?;
+    let output = PathBuf::from(path);
+
+    let url = file_url(&page);
+    let run = tokio::task::spawn_blocking(move || {
+        Command::new(browser)
+            .arg("--headless")
+            .arg("--disable-gpu")
+            .arg("--no-pdf-header-footer")
+            .arg(format!("--print-to-pdf={}", output.display()))
+            .arg(url)
+            .output()
+    })
+    .await;
+    let _ = std::fs::remove_file(&page);
+
+    let output = run
+        .map_err(|e| TranscriptError::FileError(format!("The PDF printer stopped: {}", e)))?
+        .map_err(|e| TranscriptError::FileError(format!("Could not start the browser: {}", e)))?;
+    if !output.status.success() {
+        return Err(TranscriptError::FileError(format!(
+            "The browser could not print the report: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a report on `video_id` to `path`: its details and thumbnail, summary,
+/// chapters, keywords, highlights and notes. The summary is the one stored by
+/// auto-ingestion, or one made with the `provider` of the options; without either
+/// it is left out.
+pub async fn export_report(
+    db: &Database,
+    video_id: String,
+    path: String,
+    options: Option<ReportOptions>,
+) -> Result<(), TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let options = options.unwrap_or_default();
+    let report = Report::gather(db, &video_id, options.provider, options.model).await?;
+
+    let html = render_html(&report);
+    match options.format {
+        ReportFormat::Html => std::fs::write(&path, html).map_err(|e| {
+            TranscriptError::FileError(format!("Could not write \"{}\": {}", path, e))
+        }),
+        ReportFormat::Pdf => print_pdf(html, &path).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            metadata: VideoMetadata {
+                video_id: "dQw4w9WgXcQ".into(),
+                title: "Rust & <Friends>".into(),
+                channel_name: "Ferris".into(),
+                channel_id: "UC1".into(),
+                duration_seconds: 600,
+                view_count: 42,
+                upload_date: Some("2024-05-01".into()),
+                thumbnails: Vec::new(),
+                description: String::new(),
+                keywords: Vec::new(),
+            },
+            summary: Some((
+                "A talk.".into(),
+                vec![KeyPoint {
+                    text: "Ownership".into(),
+                    timestamp: 75.0,
+                }],
+            )),
+            chapters: Vec::new(),
+            keywords: Vec::new(),
+            highlights: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_escaped_sections_with_timestamp_links() {
+        let html = render_html(&report());
+        assert!(html.contains("<title>Rust &amp; &lt;Friends&gt;</title>"));
+        assert!(html.contains("Ferris · 2024-05-01 · 10:00 · 42 views"));
+        assert!(html
+            .contains("<li><a href=\"https://youtu.be/dQw4w9WgXcQ?t=75\">1:15</a> Ownership</li>"));
+        assert!(!html.contains("<h2>Chapters</h2>"));
+        assert!(!html.contains("<h2>Notes</h2>"));
+    }
+
+    #[test]
+    fn builds_file_urls_on_every_platform() {
+        assert_eq!(file_url(Path::new("/tmp/r.html")), "file:///tmp/r.html");
+        assert_eq!(
+            file_url(Path::new(r"C:\Temp\r.html")),
+            "file:///C:/Temp/r.html"
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Searching transcripts: full-text search over every cached transcript, and
+//! keyword search within one.
+//!
+//! Each cached segment is a row of an FTS5 table. Triggers on `transcript_cache`
+//! keep the index in step with the cache, so it never needs to be rebuilt by hand.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use regex::{Regex, RegexBuilder};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: usize = 20;
+/// Segments shown before and after an in-transcript match unless requested otherwise.
+const DEFAULT_CONTEXT: usize = 1;
+/// Matches kept per video; the best-ranked ones are returned.
+const MATCHES_PER_VIDEO: usize = 5;
+
+pub(crate) const SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS transcript_fts USING fts5(
+    video_id UNINDEXED,
+    lang     UNINDEXED,
+    offset   UNINDEXED,
+    text,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS transcript_fts_insert AFTER INSERT ON transcript_cache BEGIN
+    INSERT INTO transcript_fts (video_id, lang, offset, text)
+    SELECT new.video_id, new.lang, json_extract(value, '$.offset'), json_extract(value, '$.text')
+    FROM json_each(new.segments);
+END;
+
+CREATE TRIGGER IF NOT EXISTS transcript_fts_delete AFTER DELETE ON transcript_cache BEGIN
+    DELETE FROM transcript_fts WHERE video_id = old.video_id AND lang = old.lang;
+END;
+
+CREATE TRIGGER IF NOT EXISTS transcript_fts_update AFTER UPDATE OF segments ON transcript_cache BEGIN
+    DELETE FROM transcript_fts WHERE video_id = old.video_id AND lang = old.lang;
+    INSERT INTO transcript_fts (video_id, lang, offset, text)
+    SELECT new.video_id, new.lang, json_extract(value, '$.offset'), json_extract(value, '$.text')
+    FROM json_each(new.segments);
+END;
+
+-- Indexes transcripts cached before the search table existed
+INSERT INTO transcript_fts (video_id, lang, offset, text)
+SELECT c.video_id, c.lang, json_extract(s.value, '$.offset'), json_extract(s.value, '$.text')
+FROM transcript_cache c, json_each(c.segments) s
+WHERE NOT EXISTS (SELECT 1 FROM transcript_fts);
+";
+
+/// A matching segment; `snippet` wraps the matched terms in `<mark>…</mark>`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub offset: f64,
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub video_id: String,
+    pub lang: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Turns user input into an FTS5 query: every word must appear, the last one as a
+/// prefix so results show up while typing. Words are quoted, so FTS5 operators and
+/// punctuation in the input are searched for literally.
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    let (last, rest) = terms.split_last()?;
+    let mut query = rest.join(" ");
+    if !query.is_empty() {
+        query.push(' ');
+    }
+    query.push_str(last);
+    query.push('*');
+    Some(query)
+}
+
+/// Searches all cached transcripts. Videos are ordered by their best match.
+pub fn search(
+    db: &Database,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, TranscriptError> {
+    let fts_query = fts_query(query)
+        .ok_or_else(|| TranscriptError::InvalidInput("Enter something to search for.".into()))?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+    let rows = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT video_id, lang, offset,
+                    snippet(transcript_fts, 3, '<mark>', '</mark>', '…', 16)
+             FROM transcript_fts
+             WHERE transcript_fts MATCH ?1
+             ORDER BY rank",
+        )?;
+        let rows = stmt.query_map(params![fts_query], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                SearchMatch {
+                    offset: row.get(2)?,
+                    snippet: row.get(3)?,
+                },
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (video_id, lang, hit) in rows {
+        match results
+            .iter()
+            .position(|r| r.video_id == video_id && r.lang == lang)
+        {
+            Some(i) if results[i].matches.len() < MATCHES_PER_VIDEO => results[i].matches.push(hit),
+            Some(_) => {}
+            None if results.len() < limit => results.push(SearchResult {
+                video_id,
+                lang,
+                matches: vec![hit],
+            }),
+            None => {}
+        }
+    }
+
+    for result in &mut results {
+        result.matches.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    }
+    Ok(results)
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    /// Only match whole words, so "art" does not match "start".
+    pub whole_word: bool,
+    /// Treat the query as a regular expression instead of literal text.
+    pub regex: bool,
+    /// Neighbouring segments returned on each side of a match.
+    pub context: Option<usize>,
+}
+
+/// Position of a match within a segment's text, in UTF-16 code units so it can be
+/// used with JavaScript string methods directly.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMatch {
+    pub segment_index: usize,
+    pub offset: f64,
+    pub duration: f64,
+    pub text: String,
+    pub ranges: Vec<TextRange>,
+    pub context_before: Vec<TranscriptSegment>,
+    pub context_after: Vec<TranscriptSegment>,
+}
+
+fn build_pattern(query: &str, options: &SearchOptions) -> Result<Regex, TranscriptError> {
+    if query.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "Enter something to search for.".into(),
+        ));
+    }
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| TranscriptError::InvalidInput(format!("Invalid search pattern: {}", e)))
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// Finds every segment matching `query`, with the requested surrounding segments.
+pub(crate) fn find_in_segments(
+    segments: &[TranscriptSegment],
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<TranscriptMatch>, TranscriptError> {
+    let pattern = build_pattern(query, options)?;
+    let context = options.context.unwrap_or(DEFAULT_CONTEXT);
+
+    Ok(segments
+        .iter()
+        .enumerate()
+        .filter_map(|(i, segment)| {
+            let ranges: Vec<TextRange> = pattern
+                .find_iter(&segment.text)
+                .filter(|m| !m.is_empty())
+                .map(|m| TextRange {
+                    start: utf16_len(&segment.text[..m.start()]),
+                    end: utf16_len(&segment.text[..m.end()]),
+                })
+                .collect();
+            if ranges.is_empty() {
+                return None;
+            }
+            Some(TranscriptMatch {
+                segment_index: i,
+                offset: segment.offset,
+                duration: segment.duration,
+                text: segment.text.clone(),
+                ranges,
+                context_before: segments[i.saturating_sub(context)..i].to_vec(),
+                context_after: segments[i + 1..(i + 1 + context).min(segments.len())].to_vec(),
+            })
+        })
+        .collect())
+}
+
+/// Searches all cached transcripts; see [`search`].
+pub fn search_transcripts(
+    db: &Database,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, TranscriptError> {
+    search(db, &query, limit)
+}
+
+/// Searches one video's transcript for `query`, returning matches in playback order.
+pub async fn search_in_transcript(
+    db: &Database,
+    video_id: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<TranscriptMatch>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    find_in_segments(&segments, &query, &options.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_terms_and_prefixes_the_last_one() {
+        assert_eq!(fts_query("rust  async"), Some("\"rust\" \"async\"*".into()));
+        assert_eq!(
+            fts_query("say \"hi\" OR"),
+            Some("\"say\" \"\"\"hi\"\"\" \"OR\"*".into())
+        );
+        assert_eq!(fts_query("   "), None);
+    }
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 1.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_whole_words_case_insensitively_with_context() {
+        let segments = [
+            segment("Let's start", 0.0),
+            segment("Art is long, ART is fleeting", 1.0),
+            segment("the end", 2.0),
+        ];
+        let options = SearchOptions {
+            whole_word: true,
+            ..Default::default()
+        };
+
+        let matches = find_in_segments(&segments, "art", &options).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].segment_index, 1);
+        assert_eq!(
+            matches[0].ranges,
+            [
+                TextRange { start: 0, end: 3 },
+                TextRange { start: 13, end: 16 }
+            ]
+        );
+        assert_eq!(matches[0].context_before[0].text, "Let's start");
+        assert_eq!(matches[0].context_after[0].text, "the end");
+    }
+
+    #[test]
+    fn supports_regex_and_reports_utf16_ranges() {
+        let segments = [segment("café 2024 and 1999", 0.0)];
+        let options = SearchOptions {
+            regex: true,
+            case_sensitive: true,
+            context: Some(0),
+            ..Default::default()
+        };
+
+        let matches = find_in_segments(&segments, r"\d{4}", &options).unwrap();
+        assert_eq!(
+            matches[0].ranges,
+            [
+                TextRange { start: 5, end: 9 },
+                TextRange { start: 14, end: 18 }
+            ]
+        );
+        assert!(find_in_segments(&segments, "(", &options).is_err());
+    }
+}
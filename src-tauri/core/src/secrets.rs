@@ -0,0 +1,275 @@
+//! API keys for third-party services, kept in the operating system's keychain:
+//! the Keychain on macOS, the Credential Manager on Windows and the Secret Service
+//! on Linux.
+//!
+//! Keys never travel back to the webview: the frontend can set or delete a key and
+//! ask which services have one, but not read it. Older versions kept the keys in
+//! plaintext: the OpenAI and Gemini keys among the frontend's own settings, and
+//! later every key in an `api_keys.json`. On startup both move into the keychain
+//! and are removed from where they were.
+//!
+//! The key of an encrypted local database is kept there too, under its own entry.
+
+use crate::error::TranscriptError;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Service name the keychain entries are filed under.
+const KEYRING_SERVICE: &str = "com.insighttube.app";
+const LEGACY_KEY_FILE: &str = "api_keys.json";
+/// Fields of the frontend's settings that older versions kept API keys in.
+const LEGACY_SETTINGS_FIELDS: [(&str, KeyedService); 2] = [
+    ("openaiApiKey", KeyedService::OpenAi),
+    ("geminiApiKey", KeyedService::Gemini),
+];
+/// Keychain entry of the key the local database is encrypted with.
+const DATABASE_KEY_ID: &str = "database";
+
+/// Keys read from the keychain at startup, so requests do not query it each time.
+static KEYS: Lazy<RwLock<HashMap<KeyedService, String>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// A service authenticated with a stored API key.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyedService {
+    #[serde(rename = "openai")]
+    OpenAi,
+    Anthropic,
+    Gemini,
+    #[serde(rename = "deepl")]
+    DeepL,
+    /// Integration token for exporting pages to Notion.
+    Notion,
+    /// Access token for syncing highlights to Readwise.
+    Readwise,
+}
+
+impl KeyedService {
+    const ALL: [Self; 6] = [
+        Self::OpenAi,
+        Self::Anthropic,
+        Self::Gemini,
+        Self::DeepL,
+        Self::Notion,
+        Self::Readwise,
+    ];
+
+    /// Identifier the key is stored under.
+    fn id(self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Gemini => "gemini",
+            Self::DeepL => "deepl",
+            Self::Notion => "notion",
+            Self::Readwise => "readwise",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.id() == id)
+    }
+}
+
+/// Writes `contents` readable only by the current user.
+pub fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(path)?, contents.as_bytes())
+}
+
+pub(crate) fn api_key(service: KeyedService) -> Option<String> {
+    KEYS.read().ok()?.get(&service).cloned()
+}
+
+fn keychain_error(e: keyring::Error) -> TranscriptError {
+    TranscriptError::FileError(format!("Could not access the keychain: {}", e))
+}
+
+fn entry(service: KeyedService) -> Result<keyring::Entry, TranscriptError> {
+    keyring::Entry::new(KEYRING_SERVICE, service.id()).map_err(keychain_error)
+}
+
+/// Saves `key` for `service` in the keychain, or removes it when `None`.
+fn store(service: KeyedService, key: Option<&str>) -> Result<(), TranscriptError> {
+    let entry = entry(service)?;
+    match key {
+        Some(key) => entry.set_password(key).map_err(keychain_error)?,
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(keychain_error(e)),
+        },
+    }
+    if let Ok(mut cached) = KEYS.write() {
+        match key {
+            Some(key) => cached.insert(service, key.to_string()),
+            None => cached.remove(&service),
+        };
+    }
+    Ok(())
+}
+
+fn database_entry() -> Result<keyring::Entry, TranscriptError> {
+    keyring::Entry::new(KEYRING_SERVICE, DATABASE_KEY_ID).map_err(keychain_error)
+}
+
+/// The key the local database is encrypted with, if it is.
+pub(crate) fn database_key() -> Result<Option<String>, TranscriptError> {
+    match database_entry()?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+/// Saves the key of the local database, or removes it when `None`.
+pub(crate) fn set_database_key(key: Option<&str>) -> Result<(), TranscriptError> {
+    let entry = database_entry()?;
+    match key {
+        Some(key) => entry.set_password(key).map_err(keychain_error),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keychain_error(e)),
+        },
+    }
+}
+
+/// Moves the keys of a plaintext `api_keys.json` into the keychain. The file is
+/// only removed once every key made it, so a locked keychain loses nothing.
+fn migrate(data_dir: &Path) {
+    let path = data_dir.join(LEGACY_KEY_FILE);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let keys: HashMap<String, String> = serde_json::from_str(&contents).unwrap_or_default();
+    let mut migrated = true;
+    for (id, key) in &keys {
+        if let Some(service) = KeyedService::from_id(id) {
+            migrated &= store(service, Some(key)).is_ok();
+        }
+    }
+    if migrated {
+        let _ = std::fs::remove_file(&path);
+    } else if let Ok(mut cached) = KEYS.write() {
+        // Still usable this session even though the keychain refused them
+        for (id, key) in keys {
+            if let Some(service) = KeyedService::from_id(&id) {
+                cached.insert(service, key);
+            }
+        }
+    }
+}
+
+/// Removes the key fields from the frontend's settings `legacy`, passing each key
+/// to `save`. A field stays when `save` refuses its key. Returns whether any field
+/// was removed.
+fn take_settings_keys(
+    legacy: &mut Value,
+    mut save: impl FnMut(KeyedService, &str) -> bool,
+) -> bool {
+    let Some(fields) = legacy.as_object_mut() else {
+        return false;
+    };
+    let mut changed = false;
+    for (field, service) in LEGACY_SETTINGS_FIELDS {
+        let Some(value) = fields.get(field) else {
+            continue;
+        };
+        let key = value.as_str().unwrap_or_default().trim().to_string();
+        if key.is_empty() || save(service, &key) {
+            fields.remove(field);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Moves the API keys that older versions of the frontend kept among its settings
+/// `legacy` into the keychain, and removes them from `legacy`. Returns whether it
+/// changed, so the caller knows to write it back. A key the keychain refuses stays,
+/// and is still used this session.
+pub fn migrate_settings(legacy: &mut Value) -> bool {
+    take_settings_keys(legacy, |service, key| {
+        if store(service, Some(key)).is_ok() {
+            return true;
+        }
+        if let Ok(mut cached) = KEYS.write() {
+            cached.insert(service, key.to_string());
+        }
+        false
+    })
+}
+
+/// Migrates plaintext keys and reads the stored keys from the keychain.
+pub fn load(data_dir: &Path) {
+    migrate(data_dir);
+    for service in KeyedService::ALL {
+        let Ok(key) = entry(service).and_then(|e| e.get_password().map_err(keychain_error)) else {
+            continue;
+        };
+        if let Ok(mut cached) = KEYS.write() {
+            cached.insert(service, key);
+        }
+    }
+}
+
+/// Stores the API key for `service` in the keychain.
+pub fn set_secret(service: KeyedService, value: String) -> Result<(), TranscriptError> {
+    match value.trim() {
+        "" => Err(TranscriptError::InvalidInput(
+            "The API key is empty.".into(),
+        )),
+        key => store(service, Some(key)),
+    }
+}
+
+/// Removes the API key for `service` from the keychain.
+pub fn delete_secret(service: KeyedService) -> Result<(), TranscriptError> {
+    store(service, None)
+}
+
+/// Services that have an API key stored.
+pub fn get_api_key_status() -> Vec<KeyedService> {
+    KeyedService::ALL
+        .into_iter()
+        .filter(|s| api_key(*s).is_some())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn takes_keys_out_of_the_frontend_settings() {
+        let mut legacy = json!({
+            "openaiApiKey": " sk-test ",
+            "geminiApiKey": "",
+            "selectedProvider": "openai"
+        });
+        let mut saved = Vec::new();
+        assert!(take_settings_keys(&mut legacy, |service, key| {
+            saved.push((service, key.to_string()));
+            true
+        }));
+        assert_eq!(saved, [(KeyedService::OpenAi, "sk-test".to_string())]);
+        assert_eq!(legacy, json!({ "selectedProvider": "openai" }));
+
+        // Nothing left to move, and a refused key stays where it is
+        assert!(!take_settings_keys(&mut legacy, |_, _| true));
+        let mut legacy = json!({ "geminiApiKey": "AIza-test" });
+        assert!(!take_settings_keys(&mut legacy, |_, _| false));
+        assert_eq!(legacy, json!({ "geminiApiKey": "AIza-test" }));
+    }
+}
@@ -0,0 +1,92 @@
+//! Coalescing of identical work running at the same time.
+//!
+//! A double click or two windows asking for the same video would otherwise fetch it
+//! twice. Work started under a key that is already being worked on is not run;
+//! its caller waits for the running one and gets a copy of its result. Should the
+//! running caller go away before finishing, one of the waiting callers runs its own
+//! work instead.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OnceCell;
+
+/// Results of work in flight, by key.
+pub struct SingleFlight<K, V> {
+    in_flight: Lazy<Mutex<HashMap<K, Arc<OnceCell<V>>>>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> SingleFlight<K, V> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            in_flight: Lazy::new(Default::default),
+        }
+    }
+
+    /// Runs `work` under `key`, or waits for the work already running under it.
+    pub(crate) async fn run<F, Fut>(&self, key: K, work: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let value = cell.get_or_init(work).await.clone();
+
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if in_flight.get(&key).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+            in_flight.remove(&key);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Poll;
+
+    #[test]
+    fn runs_concurrent_work_under_one_key_once() {
+        static FLIGHTS: SingleFlight<&str, usize> = SingleFlight::new();
+        let runs = AtomicUsize::new(0);
+        let work = || async {
+            // Let the other callers arrive while this one is in flight
+            let mut yielded = false;
+            futures::future::poll_fn(|cx| {
+                if std::mem::replace(&mut yielded, true) {
+                    return Poll::Ready(());
+                }
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await;
+            runs.fetch_add(1, Ordering::SeqCst) + 1
+        };
+
+        let (a, b, c) = futures::executor::block_on(async {
+            futures::join!(
+                FLIGHTS.run("dQw4w9WgXcQ", work),
+                FLIGHTS.run("dQw4w9WgXcQ", work),
+                FLIGHTS.run("9bZkp7q19f0", work),
+            )
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Once finished, the same key runs afresh
+        let d = futures::executor::block_on(FLIGHTS.run("dQw4w9WgXcQ", work));
+        assert_eq!(d, 3);
+    }
+}
@@ -0,0 +1,254 @@
+//! Reading-time and speech-rate statistics of a transcript, for a stats panel.
+//!
+//! Words are counted without the bracketed cues of auto-captions (`[Music]`,
+//! `[Applause]`). The speaking rate is charted in buckets of a minute by default:
+//! each word counts towards the bucket it is spoken in, by its own timing when the
+//! transcript has word timing and spread evenly over its caption otherwise. Silent
+//! stretches are the pauses between captions, overlapping captions merged.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::transcript::{load_transcript, TranscriptSegment};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const DEFAULT_BUCKET_SECS: f64 = 60.0;
+const MIN_BUCKET_SECS: f64 = 10.0;
+const DEFAULT_GAPS: usize = 5;
+const MAX_GAPS: usize = 50;
+/// Shorter pauses are breaths between sentences, not silences.
+const MIN_GAP_SECS: f64 = 2.0;
+/// Typical silent reading speed of adults, in words per minute.
+const READING_WPM: f64 = 238.0;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsOptions {
+    /// Width of the speech-rate buckets, in seconds; a minute by default.
+    pub bucket_secs: Option<f64>,
+    /// How many of the longest silences to list; five by default.
+    pub gaps: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateBucket {
+    /// Seconds from the start of the video.
+    pub start: f64,
+    pub end: f64,
+    pub words: usize,
+    pub words_per_minute: f64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceGap {
+    /// Seconds from the start of the video.
+    pub start: f64,
+    pub end: f64,
+    pub duration: f64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptStats {
+    pub word_count: usize,
+    /// Distinct words, ignoring case.
+    pub unique_words: usize,
+    /// From the start of the video to the end of the last caption, in seconds.
+    pub duration: f64,
+    /// Average over the whole duration, pauses included.
+    pub words_per_minute: f64,
+    pub speech_rate: Vec<RateBucket>,
+    /// The longest silences, longest first.
+    pub longest_gaps: Vec<SilenceGap>,
+    /// How long reading the transcript takes, in minutes.
+    pub reading_minutes: f64,
+}
+
+/// The spoken words of `text`, without bracketed cues.
+fn spoken_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    for piece in text.split_whitespace() {
+        if piece.starts_with('[') {
+            depth += 1;
+        }
+        if depth == 0 && piece.chars().any(char::is_alphanumeric) {
+            words.push(piece);
+        }
+        if piece.ends_with(']') {
+            depth = depth.saturating_sub(1);
+        }
+    }
+    words
+}
+
+/// When each spoken word of `segment` is said.
+fn word_times(segment: &TranscriptSegment) -> Vec<f64> {
+    if !segment.words.is_empty() {
+        return segment
+            .words
+            .iter()
+            .filter(|w| !spoken_words(&w.text).is_empty())
+            .map(|w| w.offset)
+            .collect();
+    }
+    let count = spoken_words(&segment.text).len();
+    (0..count)
+        .map(|i| segment.offset + segment.duration * (i as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// Pauses of at least [`MIN_GAP_SECS`] between captions, longest first.
+fn silences(segments: &[TranscriptSegment], limit: usize) -> Vec<SilenceGap> {
+    let mut spans: Vec<(f64, f64)> = segments
+        .iter()
+        .filter(|s| !spoken_words(&s.text).is_empty())
+        .map(|s| (s.offset, s.offset + s.duration))
+        .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut gaps = Vec::new();
+    let mut spoken_until: Option<f64> = None;
+    for (start, end) in spans {
+        if let Some(until) = spoken_until.filter(|until| start - until >= MIN_GAP_SECS) {
+            gaps.push(SilenceGap {
+                start: until,
+                end: start,
+                duration: start - until,
+            });
+        }
+        spoken_until = Some(spoken_until.map_or(end, |until| until.max(end)));
+    }
+    gaps.sort_by(|a, b| b.duration.total_cmp(&a.duration));
+    gaps.truncate(limit);
+    gaps
+}
+
+fn per_minute(words: usize, secs: f64) -> f64 {
+    if secs > 0.0 {
+        words as f64 * 60.0 / secs
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn compute(segments: &[TranscriptSegment], options: &StatsOptions) -> TranscriptStats {
+    let bucket_secs = options
+        .bucket_secs
+        .filter(|s| s.is_finite())
+        .unwrap_or(DEFAULT_BUCKET_SECS)
+        .max(MIN_BUCKET_SECS);
+    let duration = segments
+        .iter()
+        .map(|s| s.offset + s.duration)
+        .fold(0.0, f64::max);
+
+    let mut unique = HashSet::new();
+    let mut word_count = 0;
+    for segment in segments {
+        for word in spoken_words(&segment.text) {
+            word_count += 1;
+            unique.insert(
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .replace('’', "'")
+                    .to_lowercase(),
+            );
+        }
+    }
+
+    let bucket_count = (duration / bucket_secs).ceil() as usize;
+    let mut counts = vec![0usize; bucket_count];
+    for time in segments.iter().flat_map(word_times) {
+        let index = ((time / bucket_secs).max(0.0) as usize).min(bucket_count.saturating_sub(1));
+        if let Some(count) = counts.get_mut(index) {
+            *count += 1;
+        }
+    }
+    let speech_rate = counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, words)| {
+            let start = i as f64 * bucket_secs;
+            let end = (start + bucket_secs).min(duration);
+            RateBucket {
+                start,
+                end,
+                words,
+                words_per_minute: per_minute(words, end - start),
+            }
+        })
+        .collect();
+
+    TranscriptStats {
+        word_count,
+        unique_words: unique.len(),
+        duration,
+        words_per_minute: per_minute(word_count, duration),
+        speech_rate,
+        longest_gaps: silences(segments, options.gaps.unwrap_or(DEFAULT_GAPS).min(MAX_GAPS)),
+        reading_minutes: word_count as f64 / READING_WPM,
+    }
+}
+
+/// Word counts, speaking rate over time, longest silences and reading time of
+/// `video_id`.
+pub async fn transcript_stats(
+    db: &Database,
+    video_id: String,
+    options: Option<StatsOptions>,
+) -> Result<TranscriptStats, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    Ok(compute(&segments, &options.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn counts_words_rates_and_silences() {
+        let segments = [
+            segment("Hello there, hello world.", 0.0, 4.0),
+            segment("[Music]", 4.0, 20.0),
+            segment("One two three", 30.0, 3.0),
+            segment("four five six", 32.0, 4.0),
+            segment("[Applause] seven", 50.0, 10.0),
+        ];
+        let options = StatsOptions {
+            bucket_secs: Some(30.0),
+            gaps: None,
+        };
+
+        let stats = compute(&segments, &options);
+        assert_eq!(stats.word_count, 11);
+        assert_eq!(stats.unique_words, 10);
+        assert_eq!(stats.duration, 60.0);
+        assert_eq!(stats.words_per_minute, 11.0);
+
+        let buckets: Vec<_> = stats.speech_rate.iter().map(|b| b.words).collect();
+        assert_eq!(buckets, [4, 7]);
+        assert_eq!(stats.speech_rate[0].words_per_minute, 8.0);
+
+        let gaps: Vec<_> = stats
+            .longest_gaps
+            .iter()
+            .map(|g| (g.start, g.end))
+            .collect();
+        assert_eq!(gaps, [(4.0, 30.0), (36.0, 50.0)]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Limits on the disk space the app takes, and where it goes.
+//!
+//! The transcript cache is the part that grows with use. With a limit on its size
+//! or on the number of videos in it, the transcripts of the least recently used
+//! videos (with their search index rows and embeddings) are dropped whenever one is
+//! stored, or a limit is lowered. Videos pinned in the history are never dropped.
+//! Notes, highlights and history entries stay; a dropped transcript is simply
+//! fetched again when next opened.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StorageSettings {
+    /// Most megabytes of cached transcripts and their embeddings; unlimited when unset.
+    pub max_cache_mb: Option<u32>,
+    /// Most videos with a cached transcript; unlimited when unset.
+    pub max_transcripts: Option<u32>,
+}
+
+impl StorageSettings {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        if self.max_cache_mb == Some(0) || self.max_transcripts == Some(0) {
+            return Err(TranscriptError::InvalidInput(
+                "Storage limits must be at least 1; clear a limit to lift it.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn max_bytes(&self) -> Option<u64> {
+        self.max_cache_mb.map(|mb| mb as u64 * BYTES_PER_MB)
+    }
+}
+
+/// A video in the transcript cache, as eviction sees it.
+#[derive(Debug, Clone, PartialEq)]
+struct CachedVideo {
+    video_id: String,
+    /// Its cached transcripts and their embeddings.
+    bytes: u64,
+    /// Pinned, or just stored.
+    kept: bool,
+}
+
+/// The videos to drop, from `videos` given least recently used first, to bring
+/// them within `max_count` videos and `max_bytes`.
+fn victims(videos: &[CachedVideo], max_count: Option<u64>, max_bytes: Option<u64>) -> Vec<String> {
+    let mut count = videos.len() as u64;
+    let mut bytes: u64 = videos.iter().map(|v| v.bytes).sum();
+    let over = |count: u64, bytes: u64| {
+        max_count.is_some_and(|max| count > max) || max_bytes.is_some_and(|max| bytes > max)
+    };
+    let mut victims = Vec::new();
+    for video in videos.iter().filter(|v| !v.kept) {
+        if !over(count, bytes) {
+            break;
+        }
+        count -= 1;
+        bytes -= video.bytes;
+        victims.push(video.video_id.clone());
+    }
+    victims
+}
+
+fn cached_videos(db: &Database, keep: Option<&str>) -> Result<Vec<CachedVideo>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT c.video_id,
+                    SUM(COALESCE(c.size, LENGTH(c.segments)))
+                      + COALESCE((SELECT SUM(LENGTH(e.vector) + LENGTH(e.text))
+                                  FROM transcript_embeddings e
+                                  WHERE e.video_id = c.video_id), 0),
+                    EXISTS (SELECT 1 FROM history h
+                            WHERE h.video_id = c.video_id AND h.pinned = 1)
+                      OR c.video_id IS ?1
+             FROM transcript_cache c
+             GROUP BY c.video_id
+             ORDER BY MAX(COALESCE(c.used_at, c.fetched_at)), c.video_id",
+        )?;
+        let rows = stmt.query_map([keep], |row| {
+            Ok(CachedVideo {
+                video_id: row.get(0)?,
+                bytes: row.get::<_, i64>(1)?.max(0) as u64,
+                kept: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Drops the least recently used transcripts until the cache is within the limits
+/// in the settings, never `keep`. Returns how many videos were dropped.
+pub fn enforce(db: &Database, keep: Option<&str>) -> Result<usize, TranscriptError> {
+    let settings = crate::settings::current().storage;
+    let max_count = settings.max_transcripts.map(u64::from);
+    let max_bytes = settings.max_bytes();
+    if max_count.is_none() && max_bytes.is_none() {
+        return Ok(0);
+    }
+    let victims = victims(&cached_videos(db, keep)?, max_count, max_bytes);
+    if victims.is_empty() {
+        return Ok(0);
+    }
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for video_id in &victims {
+            tx.execute(
+                "DELETE FROM transcript_cache WHERE video_id = ?1",
+                params![video_id],
+            )?;
+        }
+        tx.commit()
+    })?;
+    tracing::info!(
+        videos = victims.len(),
+        "cache trimmed to the storage limits"
+    );
+    Ok(victims.len())
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    /// Videos with a cached transcript.
+    pub transcripts: u64,
+    pub transcript_bytes: u64,
+    /// The full-text search index of the cached transcripts.
+    pub search_index_bytes: u64,
+    pub embedding_bytes: u64,
+    /// History, notes, highlights, jobs and everything else in the database.
+    pub other_bytes: u64,
+    /// The database file with its write-ahead log; the sum of the above and free pages.
+    pub database_bytes: u64,
+    pub log_bytes: u64,
+}
+
+/// Bytes on disk of the tables in the database, by the part of the app they belong to.
+fn table_usage(db: &Database, usage: &mut StorageUsage) -> Result<(), TranscriptError> {
+    let tables = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(m.tbl_name, s.name), SUM(s.pgsize)
+             FROM dbstat s
+             LEFT JOIN sqlite_master m ON m.name = s.name
+             GROUP BY 1",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    for (table, bytes) in tables {
+        let bytes = bytes.max(0) as u64;
+        match table.as_str() {
+            "transcript_cache" => usage.transcript_bytes += bytes,
+            t if t.starts_with("transcript_fts") => usage.search_index_bytes += bytes,
+            t if t.starts_with("transcript_embeddings") => usage.embedding_bytes += bytes,
+            _ => usage.other_bytes += bytes,
+        }
+    }
+    Ok(())
+}
+
+/// How much disk space the app takes, broken down. Audio is not counted: downloads
+/// go where they are saved, and audio for local transcription is never written.
+pub fn get_storage_usage(db: &Database) -> Result<StorageUsage, TranscriptError> {
+    let mut usage = StorageUsage {
+        transcripts: db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT COUNT(DISTINCT video_id) FROM transcript_cache",
+                [],
+                |row| row.get(0),
+            )
+        })?,
+        log_bytes: crate::logging::disk_usage(),
+        ..StorageUsage::default()
+    };
+    table_usage(db, &mut usage)?;
+    let file_size = |suffix: &str| {
+        let mut path = db.path().as_os_str().to_os_string();
+        path.push(suffix);
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    };
+    usage.database_bytes = file_size("") + file_size("-wal");
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(video_id: &str, bytes: u64, kept: bool) -> CachedVideo {
+        CachedVideo {
+            video_id: video_id.into(),
+            bytes,
+            kept,
+        }
+    }
+
+    #[test]
+    fn drops_least_recently_used_videos_past_the_limits() {
+        let videos = [
+            video("a", 40, false),
+            video("b", 30, true),
+            video("c", 20, false),
+            video("d", 10, false),
+        ];
+        assert_eq!(victims(&videos, Some(2), None), vec!["a", "c"]);
+        assert_eq!(victims(&videos, None, Some(60)), vec!["a"]);
+        assert_eq!(victims(&videos, Some(4), Some(100)), Vec::<String>::new());
+        // Pinned videos are kept even when the limit cannot be met without them
+        assert_eq!(victims(&videos, Some(0), None), vec!["a", "c", "d"]);
+    }
+}
@@ -0,0 +1,486 @@
+//! Channel and playlist subscriptions, kept up to date from their RSS feeds.
+//!
+//! Every channel publishes its latest uploads as an Atom feed at
+//! `feeds/videos.xml?channel_id=…`, and every playlist its latest additions at
+//! `feeds/videos.xml?playlist_id=…`, which is far cheaper to poll than the
+//! Innertube browse pages. The feeds of all subscriptions are read on the
+//! [`crate::auto_ingest`] schedule; videos not seen before are stored, announced
+//! with a `subscription-uploads` event and handed to auto-ingestion. The feed's
+//! current entries are stored when subscribing, so only videos added afterwards
+//! are announced.
+
+use crate::channel::resolve_channel_id;
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::http::{self, build_client};
+use crate::jobs::JobQueue;
+use crate::playlist::parse_playlist_id;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: usize = 50;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS subscriptions (
+    channel_id      TEXT    PRIMARY KEY,
+    title           TEXT    NOT NULL,
+    added_at        INTEGER NOT NULL,
+    last_checked_at INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS subscription_videos (
+    video_id      TEXT    PRIMARY KEY,
+    channel_id    TEXT    NOT NULL,
+    title         TEXT    NOT NULL,
+    published_at  TEXT    NOT NULL,
+    discovered_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS subscription_videos_recent
+    ON subscription_videos (channel_id, published_at DESC);
+";
+
+/// Lets playlists be subscribed to as well: subscriptions are keyed by the channel
+/// or playlist ID and record which of the two it is.
+pub(crate) const ADD_PLAYLISTS: &str = "
+ALTER TABLE subscriptions RENAME COLUMN channel_id TO id;
+ALTER TABLE subscriptions ADD COLUMN kind TEXT NOT NULL DEFAULT 'channel';
+ALTER TABLE subscription_videos RENAME COLUMN channel_id TO subscription_id;
+";
+
+/// Records whether the new videos of each subscription are auto-ingested.
+pub(crate) const ADD_AUTO_INGEST: &str = "
+ALTER TABLE subscriptions ADD COLUMN auto_ingest INTEGER NOT NULL DEFAULT 1;
+";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    Channel,
+    Playlist,
+}
+
+impl SubscriptionKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Channel => "channel",
+            Self::Playlist => "playlist",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [Self::Channel, Self::Playlist]
+            .into_iter()
+            .find(|k| k.as_str() == value)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    /// The channel ID or playlist ID.
+    pub id: String,
+    pub kind: SubscriptionKind,
+    pub title: String,
+    /// Whether new videos are auto-ingested, when auto-ingestion is on.
+    pub auto_ingest: bool,
+    pub added_at: i64,
+    pub last_checked_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionVideo {
+    pub video_id: String,
+    pub subscription_id: String,
+    pub title: String,
+    /// RFC 3339 time of the upload, as the feed gives it.
+    pub published_at: String,
+    pub discovered_at: i64,
+}
+
+/// Payload of `subscription-uploads` events: the new videos of one subscription.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NewUploads {
+    pub subscription_id: String,
+    pub title: String,
+    pub videos: Vec<SubscriptionVideo>,
+}
+
+/// A channel or playlist feed: its name and its latest videos, newest first.
+#[derive(Debug, Default, PartialEq)]
+struct Feed {
+    title: String,
+    entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct FeedEntry {
+    video_id: String,
+    title: String,
+    published_at: String,
+}
+
+fn parse_error(e: impl std::fmt::Display) -> TranscriptError {
+    TranscriptError::ParseError(format!("Failed to parse subscription feed: {}", e))
+}
+
+/// Reads the title and the entries of an Atom channel or playlist feed.
+fn parse_feed(xml: &str) -> Result<Feed, TranscriptError> {
+    let mut reader = Reader::from_str(xml);
+    let mut feed = Feed::default();
+    let mut entry: Option<FeedEntry> = None;
+    // Qualified name of the element whose text is being read
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event().map_err(parse_error)? {
+            Event::Start(e) => {
+                element = e.name().as_ref().to_vec();
+                if e.local_name().as_ref() == b"entry" {
+                    entry = Some(FeedEntry::default());
+                }
+            }
+            Event::Text(e) => {
+                let text = e.unescape().map_err(parse_error)?;
+                let field = match (&mut entry, element.as_slice()) {
+                    (Some(entry), b"yt:videoId") => &mut entry.video_id,
+                    (Some(entry), b"title") => &mut entry.title,
+                    (Some(entry), b"published") => &mut entry.published_at,
+                    (None, b"title") => &mut feed.title,
+                    _ => continue,
+                };
+                field.push_str(&text);
+            }
+            Event::End(e) => {
+                element.clear();
+                if e.local_name().as_ref() == b"entry" {
+                    if let Some(entry) = entry.take().filter(|e| !e.video_id.is_empty()) {
+                        feed.entries.push(entry);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(feed)
+}
+
+fn feed_url(kind: SubscriptionKind, id: &str) -> String {
+    let param = match kind {
+        SubscriptionKind::Channel => "channel_id",
+        SubscriptionKind::Playlist => "playlist_id",
+    };
+    format!("https://www.youtube.com/feeds/videos.xml?{}={}", param, id)
+}
+
+async fn fetch_feed(
+    client: &reqwest::Client,
+    kind: SubscriptionKind,
+    id: &str,
+) -> Result<Feed, TranscriptError> {
+    let res = http::send(client.get(feed_url(kind, id)))
+        .await
+        .map_err(|e| {
+            TranscriptError::NetworkError(format!("Failed to fetch subscription feed: {}", e))
+        })?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        let message = match kind {
+            SubscriptionKind::Channel => "This channel does not exist or has no public uploads.",
+            SubscriptionKind::Playlist => "This playlist does not exist or is private.",
+        };
+        return Err(TranscriptError::VideoUnavailable(message.into()));
+    }
+    if !res.status().is_success() {
+        return Err(TranscriptError::NetworkError(format!(
+            "Failed to fetch subscription feed (HTTP {}).",
+            res.status().as_u16()
+        )));
+    }
+    let body = res.text().await.map_err(|e| {
+        TranscriptError::NetworkError(format!("Failed to read subscription feed: {}", e))
+    })?;
+    parse_feed(&body)
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "id, kind, title, auto_ingest, added_at, last_checked_at";
+
+fn subscription_from_row(row: &Row) -> rusqlite::Result<Subscription> {
+    let kind: String = row.get(1)?;
+    Ok(Subscription {
+        id: row.get(0)?,
+        kind: SubscriptionKind::parse(&kind).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Text,
+                format!("unknown value \"{}\"", kind).into(),
+            )
+        })?,
+        title: row.get(2)?,
+        auto_ingest: row.get(3)?,
+        added_at: row.get(4)?,
+        last_checked_at: row.get(5)?,
+    })
+}
+
+const VIDEO_COLUMNS: &str = "video_id, subscription_id, title, published_at, discovered_at";
+
+fn video_from_row(row: &Row) -> rusqlite::Result<SubscriptionVideo> {
+    Ok(SubscriptionVideo {
+        video_id: row.get(0)?,
+        subscription_id: row.get(1)?,
+        title: row.get(2)?,
+        published_at: row.get(3)?,
+        discovered_at: row.get(4)?,
+    })
+}
+
+pub fn subscriptions(db: &Database) -> Result<Vec<Subscription>, TranscriptError> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM subscriptions ORDER BY title COLLATE NOCASE",
+            SUBSCRIPTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], subscription_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Stores the entries of `feed` not seen before and returns them, oldest first.
+fn store_entries(
+    db: &Database,
+    subscription_id: &str,
+    feed: &Feed,
+) -> Result<Vec<SubscriptionVideo>, TranscriptError> {
+    let now = unix_now();
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let mut new = Vec::new();
+        for entry in feed.entries.iter().rev() {
+            let inserted = tx
+                .query_row(
+                    &format!(
+                        "INSERT INTO subscription_videos
+                             (video_id, subscription_id, title, published_at, discovered_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5)
+                         ON CONFLICT (video_id) DO NOTHING
+                         RETURNING {}",
+                        VIDEO_COLUMNS
+                    ),
+                    params![
+                        entry.video_id,
+                        subscription_id,
+                        entry.title,
+                        entry.published_at,
+                        now
+                    ],
+                    video_from_row,
+                )
+                .optional()?;
+            new.extend(inserted);
+        }
+        tx.execute(
+            "UPDATE subscriptions SET last_checked_at = ?2 WHERE id = ?1",
+            params![subscription_id, now],
+        )?;
+        tx.commit()?;
+        Ok(new)
+    })
+}
+
+/// Subscribes to the channel or playlist `id`, storing its current videos.
+async fn subscribe(
+    db: &Database,
+    client: &reqwest::Client,
+    kind: SubscriptionKind,
+    id: &str,
+) -> Result<Subscription, TranscriptError> {
+    let feed = fetch_feed(client, kind, id).await?;
+    let subscription = db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "INSERT INTO subscriptions (id, kind, title, added_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO UPDATE SET title = excluded.title
+                 RETURNING {}",
+                SUBSCRIPTION_COLUMNS
+            ),
+            params![id, kind.as_str(), feed.title.trim(), unix_now()],
+            subscription_from_row,
+        )
+    })?;
+    store_entries(db, id, &feed)?;
+    Ok(subscription)
+}
+
+/// Subscribes to a channel, given as an ID, `@handle` or URL. Its current uploads
+/// are stored without being announced. Subscribing again refreshes the title.
+pub async fn subscribe_channel(
+    db: &Database,
+    channel: String,
+) -> Result<Subscription, TranscriptError> {
+    let client = build_client()?;
+    let channel_id = resolve_channel_id(&client, &channel).await?;
+    subscribe(db, &client, SubscriptionKind::Channel, &channel_id).await
+}
+
+/// Subscribes to a playlist, given as an ID or URL. Its current videos are stored
+/// without being announced. Subscribing again refreshes the title.
+pub async fn subscribe_playlist(
+    db: &Database,
+    playlist: String,
+) -> Result<Subscription, TranscriptError> {
+    let playlist_id = parse_playlist_id(&playlist)?;
+    let client = build_client()?;
+    subscribe(db, &client, SubscriptionKind::Playlist, &playlist_id).await
+}
+
+/// Unsubscribes from a channel or playlist and forgets its videos. Returns whether
+/// it was subscribed.
+pub fn unsubscribe(db: &Database, id: String) -> Result<bool, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM subscription_videos WHERE subscription_id = ?1",
+            [&id],
+        )?;
+        conn.execute("DELETE FROM subscriptions WHERE id = ?1", [&id])
+    })
+    .map(|n| n > 0)
+}
+
+/// Turns auto-ingestion of the new videos of one subscription on or off.
+pub fn set_subscription_auto_ingest(
+    db: &Database,
+    id: String,
+    enabled: bool,
+) -> Result<Subscription, TranscriptError> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            &format!(
+                "UPDATE subscriptions SET auto_ingest = ?2 WHERE id = ?1 RETURNING {}",
+                SUBSCRIPTION_COLUMNS
+            ),
+            params![id, enabled],
+            subscription_from_row,
+        )
+        .optional()
+    })?
+    .ok_or_else(|| TranscriptError::InvalidInput("No such subscription.".into()))
+}
+
+/// Lists the subscriptions by name.
+pub fn list_subscriptions(db: &Database) -> Result<Vec<Subscription>, TranscriptError> {
+    subscriptions(db)
+}
+
+/// Lists the videos of the subscriptions, or of one subscription, newest first.
+pub fn list_subscription_videos(
+    db: &Database,
+    subscription_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SubscriptionVideo>, TranscriptError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1) as i64;
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM subscription_videos
+             WHERE ?1 IS NULL OR subscription_id = ?1
+             ORDER BY published_at DESC
+             LIMIT ?2",
+            VIDEO_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![subscription_id, limit], video_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Reads the feed of every subscription, stores the videos not seen before and
+/// hands them to auto-ingestion. A feed that fails to load is skipped until the
+/// next check.
+pub async fn check_all(
+    db: &Database,
+    queue: &JobQueue,
+) -> Result<Vec<NewUploads>, TranscriptError> {
+    let client = build_client()?;
+    let mut found = Vec::new();
+    for subscription in subscriptions(db)? {
+        let Ok(feed) = fetch_feed(&client, subscription.kind, &subscription.id).await else {
+            continue;
+        };
+        let videos = store_entries(db, &subscription.id, &feed)?;
+        if videos.is_empty() {
+            continue;
+        }
+        if subscription.auto_ingest {
+            crate::auto_ingest::enqueue(db, queue, &videos)?;
+        }
+        found.push(NewUploads {
+            subscription_id: subscription.id,
+            title: subscription.title,
+            videos,
+        });
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_channel_feeds() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns:yt="http://www.youtube.com/xml/schemas/2015" xmlns="http://www.w3.org/2005/Atom">
+ <link rel="self" href="http://www.youtube.com/feeds/videos.xml?channel_id=UC1"/>
+ <yt:channelId>UC1</yt:channelId>
+ <title>Chef &amp; Co</title>
+ <entry>
+  <id>yt:video:dQw4w9WgXcQ</id>
+  <yt:videoId>dQw4w9WgXcQ</yt:videoId>
+  <yt:channelId>UC1</yt:channelId>
+  <title>Cooking 101</title>
+  <published>2025-01-02T10:00:00+00:00</published>
+  <media:group>
+   <media:title>Cooking 101</media:title>
+  </media:group>
+ </entry>
+ <entry>
+  <yt:videoId>jNQXAC9IVRw</yt:videoId>
+  <title>Baking 101</title>
+  <published>2025-01-01T10:00:00+00:00</published>
+ </entry>
+</feed>"#;
+
+        let feed = parse_feed(xml).unwrap();
+        assert_eq!(feed.title, "Chef & Co");
+        assert_eq!(
+            feed.entries,
+            [
+                FeedEntry {
+                    video_id: "dQw4w9WgXcQ".into(),
+                    title: "Cooking 101".into(),
+                    published_at: "2025-01-02T10:00:00+00:00".into(),
+                },
+                FeedEntry {
+                    video_id: "jNQXAC9IVRw".into(),
+                    title: "Baking 101".into(),
+                    published_at: "2025-01-01T10:00:00+00:00".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_channel_and_playlist_feed_urls() {
+        assert_eq!(
+            feed_url(SubscriptionKind::Channel, "UC1"),
+            "https://www.youtube.com/feeds/videos.xml?channel_id=UC1"
+        );
+        assert_eq!(
+            feed_url(SubscriptionKind::Playlist, "PL1"),
+            "https://www.youtube.com/feeds/videos.xml?playlist_id=PL1"
+        );
+    }
+}
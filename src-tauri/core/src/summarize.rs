@@ -0,0 +1,272 @@
+//! Summaries of a transcript from a language model.
+//!
+//! Transcripts longer than one prompt are summarized in chunks (map), and the
+//! partial summaries are merged into one (reduce). The model sees paragraphs
+//! prefixed with their `[m:ss]` start, and cites those for each key point.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::export::short_timestamp;
+use crate::llm::{self, CompletionRequest, OnChunk, Provider, ProviderKind};
+use crate::transcript::{load_transcript, segmenter, TranscriptSegment};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+/// Transcript characters per prompt, about 3,000 tokens of English.
+const CHUNK_CHARS: usize = 12_000;
+/// Chunk summaries requested at the same time.
+const CONCURRENCY: usize = 3;
+const DEFAULT_KEY_POINTS: usize = 8;
+const MAX_TOKENS: u32 = 2_000;
+
+const SYSTEM_PROMPT: &str =
+    "You summarize YouTube video transcripts. Every transcript line starts \
+with its [m:ss] timestamp. Reply with a JSON object only, shaped as \
+{\"tldr\": string, \"keyPoints\": [{\"text\": string, \"timestamp\": \"m:ss\"}]}. \
+The tldr is two or three sentences. Each key point is one sentence, and its timestamp is \
+that of the line where the point is made. Key points are in the order they come up.";
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SummarizeOptions {
+    /// Model to use instead of the provider's default.
+    pub model: Option<String>,
+    /// Language to write the summary in; the transcript's language by default.
+    pub language: Option<String>,
+    pub max_key_points: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPoint {
+    pub text: String,
+    /// Seconds into the video.
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub tldr: String,
+    pub key_points: Vec<KeyPoint>,
+    pub provider: ProviderKind,
+    pub model: String,
+}
+
+/// The JSON requested from the model.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSummary {
+    tldr: String,
+    #[serde(default)]
+    key_points: Vec<RawKeyPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawKeyPoint {
+    text: String,
+    /// `"m:ss"` as asked for, though some models answer in seconds.
+    #[serde(default)]
+    timestamp: serde_json::Value,
+}
+
+/// Seconds from `"h:mm:ss"`, `"m:ss"`, `"[m:ss]"` or a plain number.
+pub fn parse_timestamp(value: &serde_json::Value) -> Option<f64> {
+    if let Some(seconds) = value.as_f64() {
+        return Some(seconds);
+    }
+    let text = value.as_str()?.trim().trim_matches(['[', ']']);
+    text.split(':').try_fold(0.0, |total, part| {
+        part.trim().parse::<f64>().ok().map(|n| total * 60.0 + n)
+    })
+}
+
+impl RawSummary {
+    fn key_points(self, limit: usize) -> (String, Vec<KeyPoint>) {
+        let points = self
+            .key_points
+            .into_iter()
+            .filter(|p| !p.text.trim().is_empty())
+            .map(|p| KeyPoint {
+                timestamp: parse_timestamp(&p.timestamp).unwrap_or(0.0).max(0.0),
+                text: p.text.trim().to_string(),
+            })
+            .take(limit)
+            .collect();
+        (self.tldr.trim().to_string(), points)
+    }
+}
+
+/// Splits the transcript into prompt-sized chunks of `[m:ss] paragraph` lines.
+pub fn chunks(segments: &[TranscriptSegment]) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in segmenter::paragraphs(segments) {
+        let line = format!(
+            "[{}] {}\n",
+            short_timestamp(paragraph.start),
+            paragraph.text
+        );
+        if !current.is_empty() && current.len() + line.len() > CHUNK_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn language_instruction(options: &SummarizeOptions) -> String {
+    match options.language.as_deref().map(str::trim) {
+        Some(lang) if !lang.is_empty() => format!(" Write the summary in {}.", lang),
+        _ => " Write the summary in the language of the transcript.".into(),
+    }
+}
+
+async fn complete(
+    provider: &dyn Provider,
+    prompt: String,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<RawSummary, TranscriptError> {
+    let request = CompletionRequest {
+        system: SYSTEM_PROMPT.into(),
+        prompt,
+        max_tokens: MAX_TOKENS,
+        json: true,
+    };
+    let completion = provider.complete(&request, on_chunk).await?;
+    llm::parse_json(&completion.text)
+}
+
+/// Summarizes `segments`, merging chunk summaries when the transcript is long.
+/// Only the final completion is streamed to `on_chunk`.
+pub async fn summarize(
+    provider: &dyn Provider,
+    segments: &[TranscriptSegment],
+    options: &SummarizeOptions,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<(String, Vec<KeyPoint>), TranscriptError> {
+    let max_points = options.max_key_points.unwrap_or(DEFAULT_KEY_POINTS).max(1);
+    let language = language_instruction(options);
+    let chunks = chunks(segments);
+
+    if let [chunk] = chunks.as_slice() {
+        let prompt = format!(
+            "List at most {} key points.{}\n\nTranscript:\n{}",
+            max_points, language, chunk
+        );
+        return Ok(complete(provider, prompt, on_chunk)
+            .await?
+            .key_points(max_points));
+    }
+
+    // Prompts are built up front: a lazily mapped stream of borrowing futures
+    // trips up the `Send` check of the command's future
+    let total = chunks.len();
+    let requests: Vec<_> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let prompt = format!(
+                "This is part {} of {} of a transcript. List at most {} key points of this part.{}\n\nTranscript:\n{}",
+                i + 1,
+                total,
+                max_points,
+                language,
+                chunk
+            );
+            complete(provider, prompt, None)
+        })
+        .collect();
+    let partials: Vec<(String, Vec<KeyPoint>)> = stream::iter(requests)
+        .buffered(CONCURRENCY)
+        .map_ok(|raw| raw.key_points(max_points))
+        .try_collect()
+        .await?;
+
+    // The merged key points keep the timestamps the chunk summaries cited
+    let notes: String = partials
+        .iter()
+        .enumerate()
+        .map(|(i, (tldr, points))| {
+            let points: String = points
+                .iter()
+                .map(|p| format!("[{}] {}\n", short_timestamp(p.timestamp), p.text))
+                .collect();
+            format!("Part {} summary: {}\n{}\n", i + 1, tldr, points)
+        })
+        .collect();
+    let prompt = format!(
+        "These are summaries of consecutive parts of one video, with timestamped key points. \
+         Merge them into one summary of the whole video with at most {} of the most important \
+         key points, keeping their timestamps.{}\n\n{}",
+        max_points, language, notes
+    );
+    Ok(complete(provider, prompt, on_chunk)
+        .await?
+        .key_points(max_points))
+}
+
+/// Summarizes the transcript of `video_id` with `provider`, passing the summary to
+/// `on_chunk` as it is generated.
+pub async fn summarize_transcript(
+    db: &Database,
+    video_id: &str,
+    provider: ProviderKind,
+    options: Option<SummarizeOptions>,
+    on_chunk: Option<&OnChunk<'_>>,
+) -> Result<Summary, TranscriptError> {
+    let options = options.unwrap_or_default();
+    let llm = llm::provider(db, provider, options.model.clone(), "summary")?;
+    let segments = load_transcript(db, video_id, None).await?;
+    let (tldr, key_points) = summarize(llm.as_ref(), &segments, &options, on_chunk).await?;
+    Ok(Summary {
+        tldr,
+        key_points,
+        provider,
+        model: llm.model().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 1.0,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_timestamps_in_any_shape() {
+        assert_eq!(parse_timestamp(&"1:05".into()), Some(65.0));
+        assert_eq!(parse_timestamp(&"[1:02:05]".into()), Some(3725.0));
+        assert_eq!(parse_timestamp(&serde_json::json!(42)), Some(42.0));
+        assert_eq!(parse_timestamp(&"soon".into()), None);
+    }
+
+    #[test]
+    fn chunks_paragraphs_within_the_budget() {
+        let long = "word ".repeat(2_000);
+        let segments = [
+            segment(&format!("{}.", long.trim()), 0.0),
+            segment(&format!("{}.", long.trim()), 100.0),
+            segment("The end.", 200.0),
+        ];
+
+        let chunks = chunks(&segments);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("[0:00] word"));
+        assert!(chunks[1].starts_with("[1:40] word"));
+        assert!(chunks[1].ends_with("[3:20] The end.\n"));
+    }
+}
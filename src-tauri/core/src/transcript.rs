@@ -0,0 +1,933 @@
+pub mod filter;
+pub mod normalize;
+mod parser;
+pub mod segmenter;
+mod speakers;
+
+use crate::cache::{self, CacheKey};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::history;
+use crate::http::{self, build_client};
+use crate::http_cache;
+use crate::innertube::{fetch_player_response, playability_error, text_of};
+use crate::metadata::{parse_video_metadata, VideoMetadata};
+use crate::settings::Settings;
+use crate::single_flight::SingleFlight;
+use normalize::NormalizeStage;
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub duration: f64,
+    pub offset: f64,
+    pub lang: String,
+    /// Word-level timing, only populated when the transcript was fetched as json3.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<TranscriptWord>,
+    /// Probable speaker, only populated when `detectSpeakers` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<u32>,
+    /// Filtered words the segment contains, when the content filter flags them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flagged: Vec<String>,
+}
+
+/// A single word (or ASR token) of a segment, with absolute timing in seconds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptWord {
+    pub text: String,
+    pub offset: f64,
+    pub duration: f64,
+}
+
+/// Consecutive segments merged into a paragraph of whole sentences.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptParagraph {
+    pub text: String,
+    /// Seconds from the start of the video.
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Caption document format requested from the timedtext endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CaptionFormat {
+    /// Default XML, cue-level timing only.
+    Xml,
+    /// JSON with per-word offsets for auto-generated tracks.
+    Json3,
+}
+
+impl CaptionFormat {
+    fn from_word_timing(word_timing: Option<bool>) -> Self {
+        if word_timing.unwrap_or(false) {
+            Self::Json3
+        } else {
+            Self::Xml
+        }
+    }
+}
+
+/// Where a transcript's segments come from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackInfo {
+    pub language_code: String,
+    pub language_name: String,
+    pub kind: CaptionKind,
+    pub is_auto_generated: bool,
+    /// Machine-translated by YouTube from a track in another language.
+    pub is_translated: bool,
+}
+
+impl From<&CaptionTrack> for TrackInfo {
+    fn from(track: &CaptionTrack) -> Self {
+        Self {
+            language_code: track.language_code.clone(),
+            language_name: track.language_name.clone(),
+            kind: track.kind,
+            is_auto_generated: track.is_auto_generated,
+            is_translated: false,
+        }
+    }
+}
+
+/// A transcript with the details of its caption track, as returned by
+/// [`fetch_transcript_v2`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub video_id: String,
+    #[serde(flatten)]
+    pub track: TrackInfo,
+    /// The watch page of the video the captions belong to.
+    pub source_url: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl Transcript {
+    pub(crate) fn new(video_id: &str, track: TrackInfo, segments: Vec<TranscriptSegment>) -> Self {
+        Self {
+            video_id: video_id.to_string(),
+            track,
+            source_url: format!("https://www.youtube.com/watch?v={}", video_id),
+            segments,
+        }
+    }
+}
+
+/// Transcript and metadata obtained from a single player API call.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptWithMetadata {
+    pub metadata: VideoMetadata,
+    /// The caption track the segments come from.
+    pub track: CaptionTrack,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoInfo {
+    pub title: String,
+    pub author: String,
+}
+
+/// Who made a caption track: a person, or YouTube's speech recognition.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptionKind {
+    #[default]
+    Manual,
+    /// `kind == "asr"` in the player response.
+    Auto,
+}
+
+impl CaptionKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+/// A caption track advertised in the player response.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionTrack {
+    pub language_code: String,
+    pub language_name: String,
+    pub kind: CaptionKind,
+    pub is_auto_generated: bool,
+    pub is_translatable: bool,
+    #[serde(skip)]
+    pub base_url: String,
+}
+
+/// Why a live stream or premiere has no transcript to fetch, or `None` for
+/// videos that are not live content.
+fn live_status(player_json: &serde_json::Value) -> Option<TranscriptError> {
+    let details = player_json.get("videoDetails");
+    let flag = |key: &str| {
+        details
+            .and_then(|d| d.get(key))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    let broadcast = player_json
+        .get("microformat")
+        .and_then(|m| m.get("playerMicroformatRenderer"))
+        .and_then(|m| m.get("liveBroadcastDetails"));
+    let status = player_json
+        .get("playabilityStatus")
+        .and_then(|p| p.get("status"))
+        .and_then(|s| s.as_str());
+
+    if flag("isUpcoming") || status == Some("LIVE_STREAM_OFFLINE") {
+        let start = broadcast
+            .and_then(|b| b.get("startTimestamp"))
+            .and_then(|t| t.as_str());
+        return Some(TranscriptError::Upcoming(match start {
+            Some(start) => format!(
+                "This premiere or live stream has not started yet. It is scheduled for {}.",
+                start
+            ),
+            None => "This premiere or live stream has not started yet.".into(),
+        }));
+    }
+    let live_now = broadcast
+        .and_then(|b| b.get("isLiveNow"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if flag("isLive") || live_now {
+        return Some(TranscriptError::LiveNow);
+    }
+    if flag("isLiveContent") {
+        return Some(TranscriptError::LiveCaptionsPending);
+    }
+    None
+}
+
+/// Extracts the caption tracks from a player response.
+///
+/// Streams that are live right now are refused up front: their live-caption track
+/// cannot be downloaded until the stream has ended. Once it has, its auto-generated
+/// track is picked like any other.
+pub fn caption_tracks(
+    player_json: &serde_json::Value,
+) -> Result<Vec<CaptionTrack>, TranscriptError> {
+    if let Some(e @ (TranscriptError::LiveNow | TranscriptError::Upcoming(_))) =
+        live_status(player_json)
+    {
+        return Err(e);
+    }
+
+    let tracklist = player_json
+        .get("captions")
+        .and_then(|c| c.get("playerCaptionsTracklistRenderer"))
+        .or_else(|| player_json.get("playerCaptionsTracklistRenderer"));
+
+    let tracks = tracklist
+        .and_then(|t| t.get("captionTracks"))
+        .and_then(|t| t.as_array());
+
+    // A finished stream without tracks yet is still being processed
+    if tracks.map_or(0, Vec::len) == 0 {
+        if let Some(e) = live_status(player_json) {
+            return Err(e);
+        }
+    }
+
+    if tracklist.is_none() {
+        let is_playable = player_json
+            .get("playabilityStatus")
+            .and_then(|p| p.get("status"))
+            .and_then(|s| s.as_str())
+            == Some("OK");
+
+        return Err(playability_error(player_json).unwrap_or(if is_playable {
+            TranscriptError::TranscriptsDisabled
+        } else {
+            TranscriptError::NoTranscript
+        }));
+    }
+
+    let tracks = tracks.ok_or(TranscriptError::TranscriptsDisabled)?;
+    if tracks.is_empty() {
+        return Err(TranscriptError::TranscriptsDisabled);
+    }
+
+    Ok(tracks
+        .iter()
+        .filter_map(|t| {
+            let language_code = t.get("languageCode").and_then(|l| l.as_str())?.to_string();
+            let base_url = t
+                .get("baseUrl")
+                .or_else(|| t.get("url"))
+                .and_then(|u| u.as_str())
+                .unwrap_or("")
+                .to_string();
+            let kind = if t.get("kind").and_then(|k| k.as_str()) == Some("asr") {
+                CaptionKind::Auto
+            } else {
+                CaptionKind::Manual
+            };
+            Some(CaptionTrack {
+                language_name: t
+                    .get("name")
+                    .and_then(text_of)
+                    .unwrap_or_else(|| language_code.clone()),
+                kind,
+                is_auto_generated: kind == CaptionKind::Auto,
+                is_translatable: t
+                    .get("isTranslatable")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                language_code,
+                base_url,
+            })
+        })
+        .collect())
+}
+
+/// Picks the caption track to download: the first of the preferred languages that
+/// has one, then English, then any track. Within a language, a track of `kind` wins
+/// over one of the other kind.
+pub(crate) fn select_track<'a>(
+    tracks: &'a [CaptionTrack],
+    preferred: &[String],
+    kind: CaptionKind,
+) -> Result<&'a CaptionTrack, TranscriptError> {
+    let best = |lang: Option<&str>| {
+        tracks
+            .iter()
+            .filter(|t| lang.is_none() || lang == Some(t.language_code.as_str()))
+            .min_by_key(|t| t.kind != kind)
+    };
+    preferred
+        .iter()
+        .map(String::as_str)
+        .chain(["en"])
+        .find_map(|lang| best(Some(lang)))
+        .or_else(|| best(None))
+        .ok_or(TranscriptError::NoTranscript)
+}
+
+/// The URL of the caption document of `track` in `format`, translated into `tlang`
+/// and carrying the PO token `po_token` when given.
+fn caption_url(
+    track: &CaptionTrack,
+    format: CaptionFormat,
+    tlang: Option<&str>,
+    po_token: Option<&str>,
+) -> Result<reqwest::Url, TranscriptError> {
+    let mut transcript_url = reqwest::Url::parse(&track.base_url)
+        .map_err(|e| TranscriptError::ParseError(format!("Invalid caption URL: {}", e)))?;
+    // Drop the track's own format, so the document is XML unless json3 is asked for
+    let kept: Vec<(String, String)> = transcript_url
+        .query_pairs()
+        .into_owned()
+        .filter(|(key, _)| key != "fmt")
+        .collect();
+    {
+        let mut query = transcript_url.query_pairs_mut();
+        query.clear().extend_pairs(kept);
+        if format == CaptionFormat::Json3 {
+            query.append_pair("fmt", "json3");
+        }
+        if let Some(target) = tlang {
+            query.append_pair("tlang", target);
+        }
+        if let Some(token) = po_token {
+            query.append_pair("pot", token);
+        }
+    }
+    Ok(transcript_url)
+}
+
+/// Downloads and parses a caption track, optionally machine-translated into `tlang`.
+#[tracing::instrument(
+    name = "captions",
+    skip(client, track),
+    fields(lang = %track.language_code),
+    err(level = "warn")
+)]
+async fn fetch_track_segments(
+    client: &reqwest::Client,
+    track: &CaptionTrack,
+    tlang: Option<&str>,
+    format: CaptionFormat,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let (body, lang_code) = download_track(client, track, tlang, format).await?;
+    parse_track(&body, &lang_code, format)
+}
+
+/// Downloads the caption document of a track, optionally machine-translated into
+/// `tlang`. Returns it with the language of its captions.
+pub(crate) async fn download_track(
+    client: &reqwest::Client,
+    track: &CaptionTrack,
+    tlang: Option<&str>,
+    format: CaptionFormat,
+) -> Result<(String, String), TranscriptError> {
+    // Step 3: Fetch transcript XML (or json3)
+    if track.base_url.is_empty() {
+        return Err(TranscriptError::NoTranscript);
+    }
+
+    // Captions served to browser clients may be withheld without a PO token
+    let po_token = crate::innertube::url_po_token(client).await;
+    let transcript_url = caption_url(track, format, tlang, po_token.as_deref())?;
+    let lang_code = tlang.map_or_else(|| track.language_code.clone(), str::to_string);
+
+    let timeout = crate::settings::current().timeouts.captions();
+    let (status, transcript_body) =
+        http_cache::get_text(client.get(transcript_url).timeout(timeout))
+            .await
+            .map_err(|e| {
+                TranscriptError::NetworkError(format!("Failed to fetch transcript: {}", e))
+            })?;
+
+    if status.as_u16() == 429 {
+        return Err(TranscriptError::RateLimited);
+    }
+
+    if !status.is_success() {
+        return Err(TranscriptError::NetworkError(format!(
+            "Failed to fetch transcript (HTTP {}).",
+            status.as_u16()
+        )));
+    }
+
+    Ok((transcript_body, lang_code))
+}
+
+/// Parses a caption document downloaded in `format` into segments.
+pub(crate) fn parse_track(
+    body: &str,
+    lang_code: &str,
+    format: CaptionFormat,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let segments = tracing::debug_span!("parse", ?format).in_scope(|| match format {
+        CaptionFormat::Xml => parser::parse_transcript_xml(body, lang_code),
+        CaptionFormat::Json3 => parser::parse_transcript_json3(body, lang_code),
+    })?;
+
+    if segments.is_empty() {
+        return Err(TranscriptError::EmptyTranscript);
+    }
+
+    Ok(segments)
+}
+
+/// Codes and names of the languages YouTube can machine-translate captions into,
+/// if advertised.
+fn translation_languages(player_json: &serde_json::Value) -> Vec<(String, String)> {
+    player_json
+        .get("captions")
+        .and_then(|c| c.get("playerCaptionsTracklistRenderer"))
+        .and_then(|t| t.get("translationLanguages"))
+        .and_then(|t| t.as_array())
+        .map(|langs| {
+            langs
+                .iter()
+                .filter_map(|l| {
+                    let code = l.get("languageCode").and_then(|c| c.as_str())?;
+                    let name = l
+                        .get("languageName")
+                        .and_then(text_of)
+                        .unwrap_or_else(|| code.to_string());
+                    Some((code.to_string(), name))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the default transcript of `video_id` (already normalized), from the cache
+/// when fresh and from YouTube otherwise.
+pub async fn load_transcript(
+    db: &Database,
+    video_id: &str,
+    word_timing: Option<bool>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    load_transcript_with_track(db, video_id, word_timing, None)
+        .await
+        .map(|t| t.segments)
+}
+
+/// Like [`load_transcript`], with the details of the track and preferring tracks
+/// of `kind` over the kind in the settings.
+#[tracing::instrument(name = "fetch_transcript", skip(db), err(level = "warn"))]
+pub async fn load_transcript_with_track(
+    db: &Database,
+    video_id: &str,
+    word_timing: Option<bool>,
+    kind: Option<CaptionKind>,
+) -> Result<Transcript, TranscriptError> {
+    let format = CaptionFormat::from_word_timing(word_timing);
+    let settings = crate::settings::current();
+    let kind = kind.unwrap_or(settings.preferred_caption_kind);
+
+    if let Some(transcript) = cache::get(
+        db,
+        video_id,
+        CacheKey::Default(kind),
+        format == CaptionFormat::Json3,
+    )? {
+        tracing::debug!("served from cache");
+        let _ = history::touch(db, video_id);
+        return Ok(post_process(transcript, &settings));
+    }
+
+    let download = download_default(video_id, kind, format, &settings.preferred_languages).await?;
+    let transcript = download.parse(video_id)?;
+    download.store(db, &transcript, kind)?;
+    Ok(post_process(transcript, &settings))
+}
+
+/// The caption document of the default transcript of a video, and what the same
+/// player response said about the video.
+#[derive(Debug, Clone)]
+pub(crate) struct Download {
+    track: CaptionTrack,
+    tracks: Vec<CaptionTrack>,
+    metadata: Option<VideoMetadata>,
+    format: CaptionFormat,
+    body: String,
+    /// The language of the captions.
+    lang: String,
+}
+
+impl Download {
+    /// Parses the caption document into a transcript of `video_id`.
+    pub(crate) fn parse(&self, video_id: &str) -> Result<Transcript, TranscriptError> {
+        let segments = parse_track(&self.body, &self.lang, self.format)?;
+        tracing::info!(lang = %self.lang, segments = segments.len(), "fetched transcript");
+        Ok(Transcript::new(video_id, (&self.track).into(), segments))
+    }
+
+    /// Caches `transcript`, parsed from this download, and records the video in the
+    /// history.
+    pub(crate) fn store(
+        &self,
+        db: &Database,
+        transcript: &Transcript,
+        kind: CaptionKind,
+    ) -> Result<(), TranscriptError> {
+        cache::put(db, transcript, Some(kind))?;
+        if let Some(metadata) = &self.metadata {
+            let _ = history::record(db, metadata, &self.tracks);
+        }
+        Ok(())
+    }
+}
+
+/// Downloads of default transcripts in flight, by video, kind and format.
+static DEFAULT_FETCHES: SingleFlight<
+    (String, CaptionKind, CaptionFormat),
+    Result<Download, TranscriptError>,
+> = SingleFlight::new();
+
+/// Downloads the caption document of the default transcript of `video_id` from
+/// YouTube. A second download of the same document while one is running (a double
+/// click, or a background job and a window wanting the same video) waits for that
+/// one rather than starting another.
+pub(crate) async fn download_default(
+    video_id: &str,
+    kind: CaptionKind,
+    format: CaptionFormat,
+    preferred_languages: &[String],
+) -> Result<Download, TranscriptError> {
+    DEFAULT_FETCHES
+        .run((video_id.to_string(), kind, format), || async {
+            let client = build_client()?;
+            let player_json = fetch_player_response(&client, video_id).await?;
+            let tracks = caption_tracks(&player_json)?;
+            let track = select_track(&tracks, preferred_languages, kind)?.clone();
+            let (body, lang) = download_track(&client, &track, None, format)
+                .instrument(tracing::info_span!("captions", lang = %track.language_code))
+                .await?;
+            Ok(Download {
+                metadata: parse_video_metadata(video_id, &player_json).ok(),
+                track,
+                tracks,
+                format,
+                body,
+                lang,
+            })
+        })
+        .await
+}
+
+/// Labels the probable speakers of `transcript` and filters its words, as the
+/// settings say. The cache keeps transcripts as fetched, so changed settings take
+/// effect right away.
+pub(crate) fn post_process(mut transcript: Transcript, settings: &Settings) -> Transcript {
+    if settings.detect_speakers {
+        speakers::label(&mut transcript.segments);
+    }
+    filter::apply(&mut transcript.segments, &settings.content_filter);
+    transcript
+}
+
+/// Returns the transcript of `video_id` in `target_lang`, as YouTube translates it,
+/// from the cache when fresh.
+pub async fn load_translated_transcript(
+    db: &Database,
+    video_id: &str,
+    target_lang: String,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let settings = crate::settings::current();
+    if let Some(transcript) = cache::get(db, video_id, CacheKey::Lang(&target_lang), false)? {
+        return Ok(post_process(transcript, &settings).segments);
+    }
+
+    let transcript = fetch_translated(video_id, target_lang).await?;
+    cache::put(db, &transcript, None)?;
+    Ok(post_process(transcript, &settings).segments)
+}
+
+async fn fetch_translated(
+    video_id: &str,
+    target_lang: String,
+) -> Result<Transcript, TranscriptError> {
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, video_id).await?;
+    let tracks = caption_tracks(&player_json)?;
+
+    // A track already in the target language needs no translation
+    if let Some(track) = tracks.iter().find(|t| t.language_code == target_lang) {
+        let segments = fetch_track_segments(&client, track, None, CaptionFormat::Xml).await?;
+        return Ok(Transcript::new(video_id, track.into(), segments));
+    }
+
+    let available = translation_languages(&player_json);
+    if !available.is_empty() && !available.iter().any(|(code, _)| *code == target_lang) {
+        return Err(TranscriptError::TranslationUnavailable(target_lang));
+    }
+
+    // Prefer a translatable English track, fallback to any translatable track
+    let source_track = tracks
+        .iter()
+        .find(|t| t.is_translatable && t.language_code == "en")
+        .or_else(|| tracks.iter().find(|t| t.is_translatable))
+        .ok_or(TranscriptError::TranslationUnavailable(target_lang.clone()))?;
+
+    let segments = fetch_track_segments(
+        &client,
+        source_track,
+        Some(&target_lang),
+        CaptionFormat::Xml,
+    )
+    .await?;
+    let language_name = available
+        .into_iter()
+        .find(|(code, _)| *code == target_lang)
+        .map_or_else(|| target_lang.clone(), |(_, name)| name);
+    let track = TrackInfo {
+        language_code: target_lang,
+        language_name,
+        is_translated: true,
+        ..source_track.into()
+    };
+    Ok(Transcript::new(video_id, track, segments))
+}
+
+pub async fn list_available_transcripts(
+    video_id: String,
+) -> Result<Vec<CaptionTrack>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, &video_id).await?;
+    caption_tracks(&player_json)
+}
+
+/// Fetches the transcript of a video, given its ID or any YouTube URL. With `word_timing`,
+/// the track is requested as json3 and each segment carries its individual words.
+/// `caption_kind` overrides whether manual or auto-generated captions are preferred,
+/// and `normalize` lists clean-up stages to run over the text, in order.
+///
+/// Transcripts are served from the local cache while it is fresh.
+pub async fn fetch_transcript(
+    db: &Database,
+    video_id: String,
+    word_timing: Option<bool>,
+    caption_kind: Option<CaptionKind>,
+    normalize: Option<Vec<NormalizeStage>>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let mut transcript =
+        load_transcript_with_track(db, &video_id, word_timing, caption_kind).await?;
+    normalize::normalize(&mut transcript.segments, &normalize.unwrap_or_default());
+    Ok(transcript.segments)
+}
+
+/// Version 2 of [`fetch_transcript`], taking the same arguments. Returns a
+/// [`Transcript`]: the segments together with the language, kind and origin of
+/// their caption track. `fetch_transcript` keeps returning bare segments until the
+/// frontend has moved over.
+pub async fn fetch_transcript_v2(
+    db: &Database,
+    video_id: String,
+    word_timing: Option<bool>,
+    caption_kind: Option<CaptionKind>,
+    normalize: Option<Vec<NormalizeStage>>,
+) -> Result<Transcript, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let mut transcript =
+        load_transcript_with_track(db, &video_id, word_timing, caption_kind).await?;
+    normalize::normalize(&mut transcript.segments, &normalize.unwrap_or_default());
+    Ok(transcript)
+}
+
+/// Whether this build can transcribe videos locally with Whisper.
+pub fn is_local_transcription_available() -> bool {
+    cfg!(feature = "whisper")
+}
+
+/// Fetches the transcript of a video and merges its fragments into paragraphs.
+pub async fn fetch_transcript_paragraphs(
+    db: &Database,
+    video_id: String,
+) -> Result<Vec<TranscriptParagraph>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let segments = load_transcript(db, &video_id, None).await?;
+    Ok(segmenter::paragraphs(&segments))
+}
+
+/// Fetches the transcript and the [`VideoMetadata`] of a video with one watch-page
+/// fetch and one player API call.
+pub async fn fetch_transcript_with_metadata(
+    db: &Database,
+    video_id: String,
+    word_timing: Option<bool>,
+    caption_kind: Option<CaptionKind>,
+) -> Result<TranscriptWithMetadata, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let settings = crate::settings::current();
+    let kind = caption_kind.unwrap_or(settings.preferred_caption_kind);
+    let client = build_client()?;
+    let player_json = fetch_player_response(&client, &video_id).await?;
+    let metadata = parse_video_metadata(&video_id, &player_json)?;
+    let tracks = caption_tracks(&player_json)?;
+    let selected_track = select_track(&tracks, &settings.preferred_languages, kind)?;
+
+    let segments = fetch_track_segments(
+        &client,
+        selected_track,
+        None,
+        CaptionFormat::from_word_timing(word_timing),
+    )
+    .await?;
+    let transcript = Transcript::new(&video_id, selected_track.into(), segments);
+    cache::put(db, &transcript, Some(kind))?;
+    let _ = history::record(db, &metadata, &tracks);
+
+    Ok(TranscriptWithMetadata {
+        metadata,
+        track: selected_track.clone(),
+        segments: post_process(transcript, &settings).segments,
+    })
+}
+
+pub async fn fetch_translated_transcript(
+    db: &Database,
+    video_id: String,
+    target_lang: String,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    load_translated_transcript(db, &video_id, target_lang).await
+}
+
+pub async fn fetch_video_info(video_id: String) -> Result<VideoInfo, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let client = build_client()?;
+
+    let oembed_url = format!(
+        "https://www.youtube.com/oembed?url=https://www.youtube.com/watch?v={}&format=json",
+        video_id
+    );
+
+    let res = http::send(client.get(&oembed_url))
+        .await
+        .map_err(|e| TranscriptError::NetworkError(format!("Failed to fetch video info: {}", e)))?;
+
+    if !res.status().is_success() {
+        return Ok(VideoInfo {
+            title: format!("YouTube Video ({})", video_id),
+            author: String::new(),
+        });
+    }
+
+    let body: serde_json::Value = res
+        .json()
+        .await
+        .map_err(|e| TranscriptError::ParseError(format!("Failed to parse video info: {}", e)))?;
+
+    let title = body
+        .get("title")
+        .and_then(|t| t.as_str())
+        .unwrap_or(&video_id)
+        .to_string();
+
+    let author = body
+        .get("author_name")
+        .and_then(|a| a.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    Ok(VideoInfo { title, author })
+}
+
+/// Transcribes `video_id` locally with the whisper model at `model_path`, and caches
+/// the transcript.
+#[cfg(feature = "whisper")]
+pub async fn transcribe_locally(
+    db: &Database,
+    video_id: &str,
+    model_path: &str,
+    progress: &crate::progress::Progress,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let segments = crate::whisper::transcribe(progress, cancel, video_id, model_path).await?;
+    let lang = segments.first().map(|s| s.lang.clone()).unwrap_or_default();
+    let track = TrackInfo {
+        language_name: lang.clone(),
+        language_code: lang,
+        kind: CaptionKind::Auto,
+        is_auto_generated: true,
+        is_translated: false,
+    };
+    let transcript = Transcript::new(video_id, track, segments);
+    let settings = crate::settings::current();
+    cache::put(db, &transcript, Some(settings.preferred_caption_kind))?;
+    Ok(post_process(transcript, &settings).segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(language_code: &str, kind: CaptionKind) -> CaptionTrack {
+        CaptionTrack {
+            language_code: language_code.into(),
+            language_name: language_code.into(),
+            kind,
+            is_auto_generated: kind == CaptionKind::Auto,
+            is_translatable: true,
+            base_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn selects_the_preferred_kind_within_the_preferred_language() {
+        let tracks = [
+            track("de", CaptionKind::Auto),
+            track("en", CaptionKind::Auto),
+            track("en", CaptionKind::Manual),
+        ];
+        let german = ["de".to_string()];
+
+        let pick = |preferred: &[String], kind| {
+            let track = select_track(&tracks, preferred, kind).unwrap();
+            (track.language_code.as_str(), track.kind)
+        };
+        assert_eq!(pick(&[], CaptionKind::Manual), ("en", CaptionKind::Manual));
+        assert_eq!(pick(&[], CaptionKind::Auto), ("en", CaptionKind::Auto));
+        // The language still comes first when it only has the other kind
+        assert_eq!(
+            pick(&german, CaptionKind::Manual),
+            ("de", CaptionKind::Auto)
+        );
+
+        let french = [
+            track("fr", CaptionKind::Auto),
+            track("it", CaptionKind::Manual),
+        ];
+        let track = select_track(&french, &[], CaptionKind::Manual).unwrap();
+        assert_eq!(track.language_code, "it");
+    }
+
+    #[test]
+    fn encodes_caption_url_parameters() {
+        let track = CaptionTrack {
+            base_url: "https://www.youtube.com/api/timedtext?v=abc&lang=en&fmt=srv3".into(),
+            ..track("en", CaptionKind::Manual)
+        };
+        let url = caption_url(
+            &track,
+            CaptionFormat::Json3,
+            Some("zh-Hans"),
+            Some("a+b/c=&d"),
+        )
+        .unwrap();
+        let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+        assert_eq!(
+            query,
+            [
+                pair("v", "abc"),
+                pair("lang", "en"),
+                pair("fmt", "json3"),
+                pair("tlang", "zh-Hans"),
+                pair("pot", "a+b/c=&d"),
+            ]
+        );
+
+        let first = CaptionTrack {
+            base_url: "https://www.youtube.com/api/timedtext?fmt=srv3&v=abc".into(),
+            ..track
+        };
+        let url = caption_url(&first, CaptionFormat::Xml, None, None).unwrap();
+        assert_eq!(url.query(), Some("v=abc"));
+    }
+
+    #[test]
+    fn explains_why_live_content_has_no_transcript() {
+        let upcoming = serde_json::json!({
+            "playabilityStatus": { "status": "LIVE_STREAM_OFFLINE" },
+            "videoDetails": { "isUpcoming": true, "isLiveContent": true },
+            "microformat": { "playerMicroformatRenderer": {
+                "liveBroadcastDetails": { "startTimestamp": "2026-10-20T18:00:00+00:00" }
+            } }
+        });
+        assert!(matches!(
+            caption_tracks(&upcoming),
+            Err(TranscriptError::Upcoming(message)) if message.contains("2026-10-20T18:00")
+        ));
+
+        let live = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "isLive": true, "isLiveContent": true },
+            "captions": { "playerCaptionsTracklistRenderer": {
+                "captionTracks": [{ "languageCode": "en", "kind": "asr", "baseUrl": "x" }]
+            } }
+        });
+        assert!(matches!(
+            caption_tracks(&live),
+            Err(TranscriptError::LiveNow)
+        ));
+
+        let ended = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "isLiveContent": true }
+        });
+        assert!(matches!(
+            caption_tracks(&ended),
+            Err(TranscriptError::LiveCaptionsPending)
+        ));
+
+        let processed = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "isLiveContent": true },
+            "captions": { "playerCaptionsTracklistRenderer": {
+                "captionTracks": [{ "languageCode": "en", "kind": "asr", "baseUrl": "x" }]
+            } }
+        });
+        let tracks = caption_tracks(&processed).unwrap();
+        assert_eq!(tracks[0].kind, CaptionKind::Auto);
+    }
+}
@@ -0,0 +1,203 @@
+//! Masking or flagging profanity and other unwanted words in transcripts.
+//!
+//! The filter matches a bundled list of English profanity and the words of the
+//! `contentFilter` setting, whole and ignoring case. Masking keeps the first
+//! letter of a match and stars the rest (`s***`), in the words of word-timed
+//! segments too; flagging leaves the text alone and lists the matches on the
+//! segment instead, for the interface to highlight or skip.
+
+use super::TranscriptSegment;
+use crate::error::TranscriptError;
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+const PROFANITY: &[&str] = &[
+    "arse",
+    "ass",
+    "asshole",
+    "assholes",
+    "bastard",
+    "bastards",
+    "bitch",
+    "bitches",
+    "bullshit",
+    "cock",
+    "cocks",
+    "crap",
+    "cunt",
+    "cunts",
+    "damn",
+    "dick",
+    "dicks",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fuckers",
+    "fucking",
+    "fucks",
+    "motherfucker",
+    "motherfuckers",
+    "motherfucking",
+    "piss",
+    "pissed",
+    "shit",
+    "shits",
+    "shitty",
+    "slut",
+    "sluts",
+    "whore",
+    "whores",
+];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterMode {
+    #[default]
+    Off,
+    /// Star out the matches.
+    Mask,
+    /// List the matches in the segment's `flagged` field.
+    Flag,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContentFilterSettings {
+    pub mode: FilterMode,
+    /// Filter the bundled list of English profanity.
+    pub profanity: bool,
+    /// Further words or phrases to filter.
+    pub words: Vec<String>,
+}
+
+impl Default for ContentFilterSettings {
+    fn default() -> Self {
+        Self {
+            mode: FilterMode::Off,
+            profanity: true,
+            words: Vec::new(),
+        }
+    }
+}
+
+impl ContentFilterSettings {
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        if self.words.iter().any(|w| w.trim().is_empty()) {
+            return Err(TranscriptError::InvalidInput(
+                "Filtered words must not be blank.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// A pattern matching any filtered word, or `None` when there is none.
+    fn pattern(&self) -> Option<Regex> {
+        let mut words: Vec<&str> = self.words.iter().map(|w| w.trim()).collect();
+        if self.profanity {
+            words.extend(PROFANITY);
+        }
+        if words.is_empty() {
+            return None;
+        }
+        // Longest first, so a phrase wins over a word it starts with
+        words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+        let alternatives: Vec<String> = words.iter().map(|w| regex::escape(w)).collect();
+        RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+            .case_insensitive(true)
+            .build()
+            .ok()
+    }
+}
+
+/// `word` with every letter or digit but the first starred.
+fn mask(word: &str) -> String {
+    word.chars()
+        .enumerate()
+        .map(|(i, c)| if i > 0 && c.is_alphanumeric() { '*' } else { c })
+        .collect()
+}
+
+/// Masks or flags the filtered words of `segments`, as `settings` say.
+pub(crate) fn apply(segments: &mut [TranscriptSegment], settings: &ContentFilterSettings) {
+    if settings.mode == FilterMode::Off {
+        return;
+    }
+    let Some(pattern) = settings.pattern() else {
+        return;
+    };
+    let masked = |text: &str| {
+        pattern
+            .replace_all(text, |c: &Captures| mask(&c[0]))
+            .into_owned()
+    };
+    for segment in segments {
+        match settings.mode {
+            FilterMode::Mask => {
+                segment.text = masked(&segment.text);
+                for word in &mut segment.words {
+                    word.text = masked(&word.text);
+                }
+            }
+            FilterMode::Flag => {
+                let mut flagged: Vec<String> = Vec::new();
+                for found in pattern.find_iter(&segment.text) {
+                    let word = found.as_str().to_lowercase();
+                    if !flagged.contains(&word) {
+                        flagged.push(word);
+                    }
+                }
+                segment.flagged = flagged;
+            }
+            FilterMode::Off => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration: 1.0,
+            offset: 0.0,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    fn settings(mode: FilterMode, words: &[&str]) -> ContentFilterSettings {
+        ContentFilterSettings {
+            mode,
+            profanity: true,
+            words: words.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn masks_whole_words_only() {
+        let mut segments = [segment(
+            "Oh SHIT, the Shitake pass is closed, Acme Corp said.",
+        )];
+        apply(&mut segments, &settings(FilterMode::Mask, &["acme corp"]));
+        assert_eq!(
+            segments[0].text,
+            "Oh S***, the Shitake pass is closed, A*** **** said."
+        );
+    }
+
+    #[test]
+    fn flags_without_changing_the_text() {
+        let mut segments = [segment("damn, that damn assumption")];
+        apply(&mut segments, &settings(FilterMode::Flag, &[]));
+        assert_eq!(segments[0].text, "damn, that damn assumption");
+        assert_eq!(segments[0].flagged, ["damn"]);
+
+        let mut segments = [segment("damn")];
+        apply(&mut segments, &settings(FilterMode::Off, &[]));
+        assert!(segments[0].flagged.is_empty());
+    }
+}
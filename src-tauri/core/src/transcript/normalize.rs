@@ -0,0 +1,302 @@
+//! Optional clean-up of transcript text, chosen per request.
+//!
+//! Each [`NormalizeStage`] is a [`Stage`] rewriting the text of the segments; the
+//! stages run in the order requested, so they compose freely. Word timing is left
+//! as fetched, as it belongs to what was said rather than to the cleaned-up text.
+
+use super::TranscriptSegment;
+use serde::{Deserialize, Serialize};
+
+const FILLER_WORDS: &[&str] = &["er", "erm", "hmm", "mm", "uh", "uhh", "uhm", "um", "umm"];
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [(u64, &str); 3] = [
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+/// Larger numbers are left as digits.
+const MAX_SPELLED: u64 = 999_999_999_999;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NormalizeStage {
+    /// Drop hesitations such as "um" and "uh".
+    StripFillers,
+    /// Capitalize sentence starts and the pronoun "I".
+    FixCapitalization,
+    /// Spell out whole numbers: "42" becomes "forty-two".
+    ExpandNumerals,
+    /// Collapse words the speech recognition repeated: "the the" becomes "the".
+    CollapseRepeats,
+}
+
+/// One step of the pipeline.
+trait Stage {
+    fn apply(&self, segments: &mut [TranscriptSegment]);
+}
+
+/// A stage that rewrites each segment's words on their own.
+trait WordStage {
+    /// The words to keep in place of `words`.
+    fn rewrite(&self, words: Vec<&str>) -> Vec<String>;
+}
+
+impl<T: WordStage> Stage for T {
+    fn apply(&self, segments: &mut [TranscriptSegment]) {
+        for segment in segments {
+            let words = self.rewrite(segment.text.split_whitespace().collect());
+            segment.text = words.join(" ");
+        }
+    }
+}
+
+/// `word` without the punctuation around it, lowercased.
+fn bare(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+struct StripFillers;
+
+impl WordStage for StripFillers {
+    fn rewrite(&self, words: Vec<&str>) -> Vec<String> {
+        words
+            .into_iter()
+            .filter(|w| FILLER_WORDS.binary_search(&bare(w).as_str()).is_err())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+struct CollapseRepeats;
+
+impl WordStage for CollapseRepeats {
+    fn rewrite(&self, words: Vec<&str>) -> Vec<String> {
+        let mut kept: Vec<String> = Vec::with_capacity(words.len());
+        for word in words {
+            match kept.last_mut() {
+                Some(last) if !bare(word).is_empty() && bare(last) == bare(word) => {
+                    // The last repetition carries the punctuation that follows
+                    *last = word.to_string();
+                }
+                _ => kept.push(word.to_string()),
+            }
+        }
+        kept
+    }
+}
+
+/// `n` in words, with hyphenated tens: 1_234 is "one thousand two hundred
+/// thirty-four".
+fn spell(n: u64) -> String {
+    fn below_thousand(n: u64, out: &mut Vec<String>) {
+        if n >= 100 {
+            out.push(format!("{} hundred", ONES[(n / 100) as usize]));
+        }
+        match n % 100 {
+            0 => {}
+            r if r < 20 => out.push(ONES[r as usize].to_string()),
+            r if r % 10 == 0 => out.push(TENS[(r / 10) as usize].to_string()),
+            r => out.push(format!(
+                "{}-{}",
+                TENS[(r / 10) as usize],
+                ONES[(r % 10) as usize]
+            )),
+        }
+    }
+
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut out = Vec::new();
+    let mut rest = n;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            below_thousand(rest / scale, &mut out);
+            out.push(name.to_string());
+            rest %= scale;
+        }
+    }
+    below_thousand(rest, &mut out);
+    out.join(" ")
+}
+
+struct ExpandNumerals;
+
+impl WordStage for ExpandNumerals {
+    fn rewrite(&self, words: Vec<&str>) -> Vec<String> {
+        words
+            .into_iter()
+            .map(|word| {
+                let start = word.find(|c: char| c.is_ascii_digit());
+                let end = word.rfind(|c: char| c.is_ascii_digit()).map(|i| i + 1);
+                let (Some(start), Some(end)) = (start, end) else {
+                    return word.to_string();
+                };
+                let (prefix, digits, suffix) = (&word[..start], &word[start..end], &word[end..]);
+                // Digits with thousands separators only; decimals, times, versions
+                // and words such as "mp3" keep their digits
+                let plain = digits.chars().all(|c| c.is_ascii_digit() || c == ',')
+                    && !prefix.chars().any(char::is_alphanumeric)
+                    && !suffix.chars().any(char::is_alphanumeric);
+                match digits.replace(',', "").parse::<u64>() {
+                    Ok(n) if plain && n <= MAX_SPELLED => {
+                        format!("{}{}{}", prefix, spell(n), suffix)
+                    }
+                    _ => word.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+struct FixCapitalization;
+
+impl Stage for FixCapitalization {
+    fn apply(&self, segments: &mut [TranscriptSegment]) {
+        // Sentences run on across segments, so whether one ended is carried over
+        let mut sentence_start = true;
+        for segment in segments {
+            let mut words = Vec::new();
+            for word in segment.text.split_whitespace() {
+                let bare = bare(word);
+                let is_pronoun = bare == "i" || bare.starts_with("i'") || bare.starts_with("i’");
+                words.push(if sentence_start || is_pronoun {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                });
+                if word.chars().any(char::is_alphanumeric) {
+                    sentence_start = false;
+                }
+                if word.ends_with(['.', '!', '?', '…']) {
+                    sentence_start = true;
+                }
+            }
+            segment.text = words.join(" ");
+        }
+    }
+}
+
+/// `word` with its first letter in upper case.
+fn capitalize(word: &str) -> String {
+    match word.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, c)) => format!(
+            "{}{}{}",
+            &word[..i],
+            c.to_uppercase(),
+            &word[i + c.len_utf8()..]
+        ),
+        None => word.to_string(),
+    }
+}
+
+impl NormalizeStage {
+    fn stage(self) -> &'static dyn Stage {
+        match self {
+            Self::StripFillers => &StripFillers,
+            Self::FixCapitalization => &FixCapitalization,
+            Self::ExpandNumerals => &ExpandNumerals,
+            Self::CollapseRepeats => &CollapseRepeats,
+        }
+    }
+}
+
+/// Runs `stages` over `segments`, in order.
+pub(crate) fn normalize(segments: &mut [TranscriptSegment], stages: &[NormalizeStage]) {
+    for stage in stages {
+        stage.stage().apply(segments);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(texts: &[&str]) -> Vec<TranscriptSegment> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TranscriptSegment {
+                text: text.to_string(),
+                duration: 1.0,
+                offset: i as f64,
+                lang: "en".into(),
+                words: Vec::new(),
+                speaker: None,
+                flagged: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn texts(segments: &[TranscriptSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn filler_word_list_is_sorted_for_binary_search() {
+        assert!(FILLER_WORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn strips_fillers_and_collapses_repeats() {
+        let mut segs = segments(&["so um, the the model, uh works", "I I I think"]);
+        normalize(
+            &mut segs,
+            &[
+                NormalizeStage::StripFillers,
+                NormalizeStage::CollapseRepeats,
+            ],
+        );
+        assert_eq!(texts(&segs), ["so the model, works", "I think"]);
+    }
+
+    #[test]
+    fn spells_out_whole_numbers_only() {
+        assert_eq!(spell(0), "zero");
+        assert_eq!(spell(42), "forty-two");
+        assert_eq!(spell(1_234), "one thousand two hundred thirty-four");
+        assert_eq!(spell(3_000_015), "three million fifteen");
+
+        let mut segs = segments(&["it took 3 tries, (12) days and 1,500 lines of v2.0 at 9.5"]);
+        normalize(&mut segs, &[NormalizeStage::ExpandNumerals]);
+        assert_eq!(
+            texts(&segs),
+            ["it took three tries, (twelve) days and one thousand five hundred lines of v2.0 at 9.5"]
+        );
+    }
+
+    #[test]
+    fn capitalizes_sentences_across_segments() {
+        let mut segs = segments(&["so i think. this", "works? i'm sure", "it does"]);
+        normalize(&mut segs, &[NormalizeStage::FixCapitalization]);
+        assert_eq!(
+            texts(&segs),
+            ["So I think. This", "works? I'm sure", "it does"]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Parsing of YouTube timedtext caption documents.
+//!
+//! Two XML flavours are served depending on the `fmt` parameter:
+//!
+//! * the legacy format (`<transcript><text start="1.2" dur="3.4">…</text>`), with
+//!   times in seconds, and
+//! * format 3 / `srv3` (`<timedtext format="3"><body><p t="1200" d="3400">…</p>`), with
+//!   times in milliseconds and optional `<s>` word spans.
+//!
+//! Cue text may contain formatting tags such as `<b>`, `<i>` or `<font>`, which are
+//! flattened into plain text.
+//!
+//! With `fmt=json3` the same track is served as JSON events whose `segs` carry
+//! per-word offsets, which [`parse_transcript_json3`] turns into [`TranscriptWord`]s.
+
+use super::{TranscriptSegment, TranscriptWord};
+use crate::error::TranscriptError;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+
+/// Cue element currently being read.
+struct OpenCue {
+    offset: f64,
+    duration: f64,
+    text: String,
+}
+
+/// Parses a timedtext XML document into transcript segments tagged with `lang`.
+pub fn parse_transcript_xml(
+    xml: &str,
+    lang: &str,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let mut reader = Reader::from_str(xml);
+    let mut segments = Vec::new();
+    let mut cue: Option<OpenCue> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                if cue.is_none() {
+                    cue = open_cue(&e)?;
+                } else if e.local_name().as_ref() == b"br" {
+                    push_text(&mut cue, " ");
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                if e.local_name().as_ref() == b"br" {
+                    push_text(&mut cue, " ");
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if cue.is_some() {
+                    let text = e.unescape().map_err(|err| {
+                        TranscriptError::ParseError(format!(
+                            "Failed to parse transcript XML: {}",
+                            err
+                        ))
+                    })?;
+                    push_text(&mut cue, &text);
+                }
+            }
+            Ok(Event::CData(e)) => {
+                if cue.is_some() {
+                    push_text(&mut cue, &String::from_utf8_lossy(&e));
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if matches!(name.as_ref(), b"text" | b"p") {
+                    if let Some(open) = cue.take() {
+                        let text = normalize_text(&open.text);
+                        if !text.is_empty() {
+                            segments.push(TranscriptSegment {
+                                text,
+                                duration: open.duration,
+                                offset: open.offset,
+                                lang: lang.to_string(),
+                                words: Vec::new(),
+                                speaker: None,
+                                flagged: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(err) => {
+                return Err(TranscriptError::ParseError(format!(
+                    "Failed to parse transcript XML at position {}: {}",
+                    reader.error_position(),
+                    err
+                )))
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Document {
+    #[serde(default)]
+    events: Vec<Json3Event>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Event {
+    #[serde(default)]
+    t_start_ms: f64,
+    #[serde(default)]
+    d_duration_ms: f64,
+    #[serde(default)]
+    segs: Vec<Json3Seg>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Seg {
+    #[serde(default)]
+    utf8: String,
+    #[serde(default)]
+    t_offset_ms: f64,
+}
+
+/// Parses a json3 caption document into segments with word-level timing.
+///
+/// A word lasts until the next word of its event starts; the last word runs to the
+/// end of the event.
+pub fn parse_transcript_json3(
+    json: &str,
+    lang: &str,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let doc: Json3Document = serde_json::from_str(json).map_err(|e| {
+        TranscriptError::ParseError(format!("Failed to parse json3 transcript: {}", e))
+    })?;
+
+    let segments = doc
+        .events
+        .iter()
+        .filter_map(|event| {
+            let start = event.t_start_ms / 1000.0;
+            let end = (event.t_start_ms + event.d_duration_ms) / 1000.0;
+
+            let timed: Vec<(f64, &str)> = event
+                .segs
+                .iter()
+                .map(|seg| {
+                    (
+                        (event.t_start_ms + seg.t_offset_ms) / 1000.0,
+                        seg.utf8.as_str(),
+                    )
+                })
+                .filter(|(_, text)| !text.trim().is_empty())
+                .collect();
+
+            let words: Vec<TranscriptWord> = timed
+                .iter()
+                .enumerate()
+                .map(|(i, (offset, text))| {
+                    let next = timed.get(i + 1).map(|(o, _)| *o).unwrap_or(end);
+                    TranscriptWord {
+                        text: normalize_text(text),
+                        offset: *offset,
+                        duration: (next - offset).max(0.0),
+                    }
+                })
+                .collect();
+
+            let text = normalize_text(&timed.iter().map(|(_, t)| *t).collect::<String>());
+            if text.is_empty() {
+                return None;
+            }
+
+            Some(TranscriptSegment {
+                text,
+                duration: event.d_duration_ms / 1000.0,
+                offset: start,
+                lang: lang.to_string(),
+                words,
+                speaker: None,
+                flagged: Vec::new(),
+            })
+        })
+        .collect();
+
+    Ok(segments)
+}
+
+/// Starts a cue if `e` is a `<text>` (seconds) or `<p>` (milliseconds) element.
+fn open_cue(e: &BytesStart) -> Result<Option<OpenCue>, TranscriptError> {
+    let (start_attr, dur_attr, scale): (&[u8], &[u8], f64) = match e.local_name().as_ref() {
+        b"text" => (b"start", b"dur", 1.0),
+        b"p" => (b"t", b"d", 1000.0),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(OpenCue {
+        offset: numeric_attr(e, start_attr)? / scale,
+        duration: numeric_attr(e, dur_attr)? / scale,
+        text: String::new(),
+    }))
+}
+
+/// Reads a numeric attribute regardless of attribute order, defaulting to zero.
+fn numeric_attr(e: &BytesStart, name: &[u8]) -> Result<f64, TranscriptError> {
+    let attr = e.try_get_attribute(name).map_err(|err| {
+        TranscriptError::ParseError(format!("Failed to parse transcript XML: {}", err))
+    })?;
+
+    Ok(attr
+        .and_then(|a| a.unescape_value().ok().and_then(|v| v.trim().parse().ok()))
+        .unwrap_or(0.0))
+}
+
+fn push_text(cue: &mut Option<OpenCue>, text: &str) {
+    if let Some(open) = cue {
+        open.text.push_str(text);
+    }
+}
+
+/// Decodes the second layer of entities YouTube leaves in cue text and collapses
+/// line breaks and repeated whitespace into single spaces.
+fn normalize_text(text: &str) -> String {
+    decode_entities(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Caption text is frequently double-escaped (`&amp;#39;`), so after XML unescaping
+/// entities such as `&#39;` can still remain.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|c| (c, semi))
+        });
+
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_legacy_format() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8" ?><transcript><text start="0.5" dur="1.25">Hello there</text><text start="1.75" dur="2">General Kenobi</text></transcript>"#;
+        let segments = parse_transcript_xml(xml, "en").unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Hello there");
+        assert_eq!(segments[0].offset, 0.5);
+        assert_eq!(segments[0].duration, 1.25);
+        assert_eq!(segments[1].offset, 1.75);
+        assert_eq!(segments[1].lang, "en");
+    }
+
+    #[test]
+    fn accepts_attributes_in_any_order() {
+        let xml = r#"<transcript><text dur="3.0" start="10.0">Swapped</text></transcript>"#;
+        let segments = parse_transcript_xml(xml, "en").unwrap();
+
+        assert_eq!(segments[0].offset, 10.0);
+        assert_eq!(segments[0].duration, 3.0);
+    }
+
+    #[test]
+    fn decodes_double_escaped_entities() {
+        let xml = r#"<transcript><text start="0" dur="1">it&amp;#39;s &amp;quot;fine&amp;quot; &amp;amp; good</text></transcript>"#;
+        let segments = parse_transcript_xml(xml, "en").unwrap();
+
+        assert_eq!(segments[0].text, "it's \"fine\" & good");
+    }
+
+    #[test]
+    fn joins_multi_line_cues() {
+        let xml =
+            "<transcript><text start=\"0\" dur=\"2\">first line\nsecond line</text></transcript>";
+        let segments = parse_transcript_xml(xml, "en").unwrap();
+
+        assert_eq!(segments[0].text, "first line second line");
+    }
+
+    #[test]
+    fn flattens_formatting_tags_in_manual_captions() {
+        let xml = r#"<timedtext format="3"><body><p t="1000" d="2500">This is <b>bold</b> and <i>italic</i><br/>text</p></body></timedtext>"#;
+        let segments = parse_transcript_xml(xml, "de").unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "This is bold and italic text");
+        assert_eq!(segments[0].offset, 1.0);
+        assert_eq!(segments[0].duration, 2.5);
+        assert_eq!(segments[0].lang, "de");
+    }
+
+    #[test]
+    fn parses_auto_generated_word_spans() {
+        let xml = r#"<timedtext format="3"><head><ws id="0"/></head><body><w t="0" id="1"/><p t="160" d="4080" w="1"><s ac="0">so</s><s t="320" ac="0"> today</s><s t="640" ac="0"> we</s></p><p t="2000" d="2240" w="1" a="1">
+</p></body></timedtext>"#;
+        let segments = parse_transcript_xml(xml, "en").unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "so today we");
+        assert_eq!(segments[0].offset, 0.16);
+    }
+
+    #[test]
+    fn parses_json3_word_timing() {
+        let json = r#"{"wireMagic":"pb3","events":[
+            {"tStartMs":0,"dDurationMs":5000,"id":1,"wpWinPosId":1},
+            {"tStartMs":1000,"dDurationMs":2000,"wWinId":1,"segs":[{"utf8":"hello"},{"utf8":" big","tOffsetMs":400},{"utf8":" world","tOffsetMs":900}]},
+            {"tStartMs":2900,"dDurationMs":100,"wWinId":1,"aAppend":1,"segs":[{"utf8":"\n"}]}
+        ]}"#;
+        let segments = parse_transcript_json3(json, "en").unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "hello big world");
+        assert_eq!(segments[0].offset, 1.0);
+        assert_eq!(segments[0].duration, 2.0);
+
+        let words = &segments[0].words;
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[1].text, "big");
+        assert_eq!(words[1].offset, 1.4);
+        assert!((words[1].duration - 0.5).abs() < 1e-9);
+        assert!((words[2].duration - 1.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        let xml = r#"<transcript><text start="0" dur="1">oops</txet></transcript>"#;
+        assert!(parse_transcript_xml(xml, "en").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Rebuilding sentences and paragraphs from caption fragments.
+//!
+//! Auto-generated captions arrive as short, overlapping fragments that split
+//! sentences at arbitrary points. Fragments are cut at sentence punctuation (with
+//! timing interpolated by character count), rejoined into sentences, and the
+//! sentences grouped into paragraphs at longer pauses. Unpunctuated ASR text falls
+//! back to pauses and a maximum sentence length.
+
+use super::{TranscriptParagraph, TranscriptSegment};
+
+/// A pause this long ends a sentence even without punctuation.
+const SENTENCE_GAP_SECS: f64 = 1.0;
+/// Unpunctuated text is cut into sentences of at most this length.
+const MAX_SENTENCE_SECS: f64 = 20.0;
+/// A pause this long always starts a new paragraph.
+const PARAGRAPH_GAP_SECS: f64 = 2.0;
+/// Paragraphs end at the first sentence boundary past this length.
+const TARGET_PARAGRAPH_SECS: f64 = 45.0;
+
+/// A piece of caption text with its (possibly interpolated) timing.
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    start: f64,
+    end: f64,
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end()
+        .trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(['.', '!', '?', '…'])
+}
+
+/// Cuts a segment after every sentence-ending punctuation mark that is followed by
+/// whitespace, sharing its duration between the pieces by length.
+fn split_segment(segment: &TranscriptSegment) -> Vec<Span> {
+    let text = segment.text.trim();
+    let mut cuts = vec![0];
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?' | '…') {
+            if let Some(&(next, n)) = chars.peek() {
+                if n.is_whitespace() {
+                    cuts.push(next);
+                }
+            }
+        }
+    }
+    cuts.push(text.len());
+
+    let total = text.chars().count().max(1) as f64;
+    let mut consumed = 0usize;
+    cuts.windows(2)
+        .filter_map(|w| {
+            let piece = &text[w[0]..w[1]];
+            let start = segment.offset + segment.duration * consumed as f64 / total;
+            consumed += piece.chars().count();
+            let end = segment.offset + segment.duration * consumed as f64 / total;
+            let piece = piece.trim();
+            (!piece.is_empty()).then(|| Span {
+                text: piece.to_string(),
+                start,
+                end,
+            })
+        })
+        .collect()
+}
+
+/// Joins spans into one, keeping the first start and the last end.
+fn merge(spans: &[Span]) -> Span {
+    Span {
+        text: spans
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        start: spans.first().map_or(0.0, |s| s.start),
+        end: spans.last().map_or(0.0, |s| s.end),
+    }
+}
+
+/// Groups consecutive spans, closing a group whenever `should_close(group, next)`
+/// holds. `next` is `None` after the last span.
+fn group(spans: Vec<Span>, should_close: impl Fn(&[Span], Option<&Span>) -> bool) -> Vec<Span> {
+    let mut out = Vec::new();
+    let mut current: Vec<Span> = Vec::new();
+    let mut spans = spans.into_iter().peekable();
+    while let Some(span) = spans.next() {
+        current.push(span);
+        if should_close(&current, spans.peek()) {
+            out.push(merge(&current));
+            current.clear();
+        }
+    }
+    out
+}
+
+impl From<Span> for TranscriptParagraph {
+    fn from(span: Span) -> Self {
+        TranscriptParagraph {
+            text: span.text,
+            start: span.start,
+            end: span.end,
+        }
+    }
+}
+
+fn sentence_spans(segments: &[TranscriptSegment]) -> Vec<Span> {
+    let pieces = segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .flat_map(split_segment)
+        .collect();
+
+    group(pieces, |current, next| {
+        let Some(next) = next else { return true };
+        let last = &current[current.len() - 1];
+        ends_sentence(&last.text)
+            || next.start - last.end >= SENTENCE_GAP_SECS
+            || next.start - current[0].start >= MAX_SENTENCE_SECS
+    })
+}
+
+/// Rebuilds the sentences of a transcript.
+pub(crate) fn sentences(segments: &[TranscriptSegment]) -> Vec<TranscriptParagraph> {
+    sentence_spans(segments)
+        .into_iter()
+        .map(Into::into)
+        .collect()
+}
+
+/// Rebuilds the paragraphs of a transcript.
+pub(crate) fn paragraphs(segments: &[TranscriptSegment]) -> Vec<TranscriptParagraph> {
+    group(sentence_spans(segments), |current, next| {
+        let Some(next) = next else { return true };
+        let last = &current[current.len() - 1];
+        next.start - last.end >= PARAGRAPH_GAP_SECS
+            || last.end - current[0].start >= TARGET_PARAGRAPH_SECS
+    })
+    .into_iter()
+    .map(Into::into)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn splits_sentences_inside_a_fragment_with_interpolated_timing() {
+        let segments = [
+            segment("so that's it. Now the", 0.0, 2.1),
+            segment("second part", 2.1, 1.0),
+        ];
+
+        let sentences = sentences(&segments);
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(sentences[0].text, "so that's it.");
+        assert_eq!(sentences[1].text, "Now the second part");
+        assert!((sentences[1].start - 1.3).abs() < 1e-9);
+        assert_eq!(sentences[1].end, 3.1);
+    }
+
+    #[test]
+    fn falls_back_to_pauses_without_punctuation() {
+        let segments = [
+            segment("hey everyone", 0.0, 1.5),
+            segment("welcome back", 1.5, 1.5),
+            segment("let's get started", 4.0, 1.5),
+        ];
+
+        let sentences = sentences(&segments);
+        let texts: Vec<&str> = sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["hey everyone welcome back", "let's get started"]);
+    }
+
+    #[test]
+    fn starts_paragraphs_at_long_pauses() {
+        let segments = [
+            segment("One.", 0.0, 1.0),
+            segment("Two.", 1.2, 1.0),
+            segment("Three.", 10.0, 1.0),
+        ];
+
+        let paragraphs = paragraphs(&segments);
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(paragraphs[0].text, "One. Two.");
+        assert_eq!((paragraphs[0].start, paragraphs[0].end), (0.0, 2.2));
+        assert_eq!(paragraphs[1].text, "Three.");
+    }
+}
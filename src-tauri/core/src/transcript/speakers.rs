@@ -0,0 +1,106 @@
+//! Guessing where the speaker changes in captions.
+//!
+//! Captions do not say who is speaking, but many tracks mark a change of speaker
+//! with `>>`. Tracks that have these markers change speaker at them; in tracks
+//! without any, a sentence that starts after a pause of [`TURN_GAP_SECS`] is taken
+//! as someone else speaking. Voices cannot be told apart from the text, so the
+//! indices tell turns apart rather than identify people: they alternate between 0
+//! and 1, as in the interviews and dialogues where the markers mostly appear.
+
+use super::TranscriptSegment;
+
+const MARKER: &str = ">>";
+/// A pause this long between two sentences suggests another speaker.
+const TURN_GAP_SECS: f64 = 2.0;
+const SPEAKERS: u32 = 2;
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end().ends_with(['.', '!', '?', '…', '"', '”'])
+}
+
+/// Sets the `speaker` of every segment.
+pub(crate) fn label(segments: &mut [TranscriptSegment]) {
+    let has_markers = segments.iter().any(|s| s.text.contains(MARKER));
+    let mut speaker = 0;
+    // End of the previous segment, and whether it finished a sentence
+    let mut previous: Option<(f64, bool)> = None;
+    for segment in segments.iter_mut() {
+        let text = segment.text.trim_start();
+        let (turn, rest) = if has_markers {
+            match text.strip_prefix(MARKER) {
+                Some(rest) => (true, rest),
+                None => (false, text),
+            }
+        } else {
+            let after_pause =
+                previous.is_some_and(|(end, ended)| ended && segment.offset - end >= TURN_GAP_SECS);
+            (after_pause, text)
+        };
+        if turn && previous.is_some() {
+            speaker = (speaker + 1) % SPEAKERS;
+        }
+        segment.speaker = Some(speaker);
+
+        // Markers further into the caption hand over to the next speakers, from
+        // the following caption on
+        let handovers = rest.matches(MARKER).count() as u32;
+        speaker = (speaker + handovers) % SPEAKERS;
+        previous = Some((
+            segment.offset + segment.duration,
+            ends_sentence(&segment.text),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, offset: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.into(),
+            duration,
+            offset,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        }
+    }
+
+    fn speakers(segments: &[TranscriptSegment]) -> Vec<Option<u32>> {
+        segments.iter().map(|s| s.speaker).collect()
+    }
+
+    #[test]
+    fn changes_speaker_at_markers() {
+        let mut segments = [
+            segment(">> so what brought you here", 0.0, 2.0),
+            segment(">> well it started in", 2.0, 2.0),
+            segment("college actually", 4.0, 2.0),
+            segment("a long pause. >> interesting", 9.0, 2.0),
+            segment("tell me more", 11.0, 2.0),
+        ];
+        label(&mut segments);
+        assert_eq!(
+            speakers(&segments),
+            [Some(0), Some(1), Some(1), Some(1), Some(0)]
+        );
+    }
+
+    #[test]
+    fn changes_speaker_after_pauses_between_sentences() {
+        let mut segments = [
+            segment("Welcome to the show.", 0.0, 2.0),
+            segment("Thanks for having me.", 5.0, 2.0),
+            segment("It's a pleasure,", 7.5, 1.0),
+            segment("really.", 12.0, 1.0),
+            segment("So, tell us.", 16.0, 1.0),
+        ];
+        label(&mut segments);
+        assert_eq!(
+            speakers(&segments),
+            [Some(0), Some(1), Some(1), Some(1), Some(0)]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Translating a transcript while keeping its timing.
+//!
+//! YouTube's own machine translation (`tlang`) is free and cached like any other
+//! track. DeepL and language models translate the default transcript segment by
+//! segment: every segment keeps its offset and duration and only its text is
+//! replaced, so timestamps, exports and the player sync work as for the original.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+use crate::secrets::{self, KeyedService};
+use crate::transcript::{filter, load_transcript, load_translated_transcript, TranscriptSegment};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Texts per DeepL request, the most the API accepts.
+const DEEPL_BATCH_SIZE: usize = 50;
+const DEEPL_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SYSTEM_PROMPT: &str = "You translate the caption lines of a YouTube video. \
+You receive a JSON object {\"lines\": [{\"id\": number, \"text\": string}]} and reply with a \
+JSON object only, of the same shape, holding the translation of every line under its id. \
+The lines are fragments of continuous speech: translate each in the context of its \
+neighbours, but keep every translation in its own line so the timing stays aligned.";
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TranslationEngine {
+    /// YouTube's machine translation of the caption track.
+    #[default]
+    Youtube,
+    /// The DeepL API, with the key stored for `deepl`.
+    Deepl,
+    /// A language model.
+    Llm {
+        provider: ProviderKind,
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+/// The segments with their text replaced by `texts`, in order.
+fn aligned(
+    segments: Vec<TranscriptSegment>,
+    texts: Vec<String>,
+    target_lang: &str,
+) -> Vec<TranscriptSegment> {
+    segments
+        .into_iter()
+        .zip(texts)
+        .map(|(segment, text)| TranscriptSegment {
+            text,
+            lang: target_lang.to_string(),
+            // Word timing and filter matches belong to the original wording
+            words: Vec::new(),
+            flagged: Vec::new(),
+            ..segment
+        })
+        .collect()
+}
+
+/// Free keys are marked with a `:fx` suffix and use their own host.
+fn deepl_url(key: &str) -> &'static str {
+    if key.ends_with(":fx") {
+        "https://api-free.deepl.com/v2/translate"
+    } else {
+        "https://api.deepl.com/v2/translate"
+    }
+}
+
+async fn translate_deepl(
+    texts: &[String],
+    target_lang: &str,
+) -> Result<Vec<String>, TranscriptError> {
+    let key = secrets::api_key(KeyedService::DeepL).ok_or_else(|| {
+        TranscriptError::InvalidInput("Add an API key for DeepL in Settings first.".into())
+    })?;
+    let client = llm::client(DEEPL_TIMEOUT)?;
+
+    let mut translated = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(DEEPL_BATCH_SIZE) {
+        let res = client
+            .post(deepl_url(&key))
+            .header("Authorization", format!("DeepL-Auth-Key {}", key))
+            .json(&serde_json::json!({
+                "text": batch,
+                "target_lang": target_lang.to_uppercase(),
+            }))
+            .send()
+            .await
+            .map_err(|e| TranscriptError::NetworkError(format!("Could not reach DeepL: {}", e)))?;
+
+        match res.status().as_u16() {
+            200..=299 => {}
+            403 => {
+                return Err(TranscriptError::InvalidInput(
+                    "DeepL rejected the API key. Check it in Settings.".into(),
+                ))
+            }
+            429 => return Err(TranscriptError::RateLimited),
+            456 => {
+                return Err(TranscriptError::InvalidInput(
+                    "The DeepL character quota for this billing period is used up.".into(),
+                ))
+            }
+            status => {
+                let body: serde_json::Value = res.json().await.unwrap_or_default();
+                let message = body
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("HTTP {}", status));
+                return Err(TranscriptError::NetworkError(format!(
+                    "DeepL request failed: {}",
+                    message
+                )));
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Translation {
+            text: String,
+        }
+        #[derive(Deserialize)]
+        struct Response {
+            translations: Vec<Translation>,
+        }
+        let body: Response = res.json().await.map_err(|e| {
+            TranscriptError::ParseError(format!("Unexpected DeepL response: {}", e))
+        })?;
+        if body.translations.len() != batch.len() {
+            return Err(TranscriptError::ParseError(
+                "DeepL returned a different number of lines than it was sent.".into(),
+            ));
+        }
+        translated.extend(body.translations.into_iter().map(|t| t.text));
+    }
+    Ok(translated)
+}
+
+/// Translates the transcript of `video_id` into `target_lang` with `engine`
+/// (YouTube's translation by default). Segments keep their timing. DeepL and LLM
+/// translations can be stopped with `cancel_operation(operation_id)`.
+pub async fn translate_transcript(
+    db: &Database,
+    operations: &Operations,
+    video_id: String,
+    target_lang: String,
+    engine: Option<TranslationEngine>,
+    operation_id: Option<String>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let target_lang = target_lang.trim().to_string();
+    if target_lang.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "Choose a language to translate into.".into(),
+        ));
+    }
+
+    let llm = match engine.unwrap_or_default() {
+        TranslationEngine::Youtube => {
+            return load_translated_transcript(db, &video_id, target_lang).await
+        }
+        TranslationEngine::Deepl => None,
+        TranslationEngine::Llm { provider, model } => {
+            Some(llm::provider(db, provider, model, "translation")?)
+        }
+    };
+
+    let segments = load_transcript(db, &video_id, None).await?;
+    if segments
+        .first()
+        .is_some_and(|s| s.lang.eq_ignore_ascii_case(&target_lang))
+    {
+        return Ok(segments);
+    }
+    let texts: Vec<String> = segments.iter().map(|s| s.text.clone()).collect();
+    let operation = operations.start(operation_id.as_deref());
+    let translated = match llm {
+        Some(llm) => {
+            let instruction = format!("Translate into the language with code \"{}\".", target_lang);
+            operation
+                .run(llm::lines::rewrite(
+                    llm.as_ref(),
+                    SYSTEM_PROMPT,
+                    &instruction,
+                    &texts,
+                ))
+                .await?
+        }
+        None => operation.run(translate_deepl(&texts, &target_lang)).await?,
+    };
+    let mut segments = aligned(segments, translated, &target_lang);
+    filter::apply(&mut segments, &crate::settings::current().content_filter);
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_timing_of_translated_segments() {
+        let segments = vec![TranscriptSegment {
+            text: "Hello there".into(),
+            duration: 1.5,
+            offset: 12.0,
+            lang: "en".into(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: vec!["damn".into()],
+        }];
+
+        let translated = aligned(segments, vec!["Hallo zusammen".into()], "de");
+        assert_eq!(translated[0].text, "Hallo zusammen");
+        assert_eq!((translated[0].offset, translated[0].duration), (12.0, 1.5));
+        assert_eq!(translated[0].lang, "de");
+        // The flagged word is not in the translation
+        assert!(translated[0].flagged.is_empty());
+
+        let engine: TranslationEngine =
+            serde_json::from_str(r#"{"kind": "llm", "provider": "ollama"}"#).unwrap();
+        assert_eq!(
+            engine,
+            TranslationEngine::Llm {
+                provider: ProviderKind::Ollama,
+                model: None
+            }
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Normalization of user input into canonical 11-character YouTube video IDs.
+
+use crate::error::TranscriptError;
+use reqwest::Url;
+
+const VIDEO_ID_LEN: usize = 11;
+
+/// Path prefixes that are directly followed by the video ID.
+const ID_PATH_PREFIXES: &[&str] = &["shorts", "live", "embed", "v", "e"];
+
+/// Parses a bare video ID or any common YouTube URL into a video ID.
+///
+/// Accepted forms include `watch?v=`, `youtu.be/`, `/shorts/`, `/live/`, `/embed/`
+/// and `/v/` links on any YouTube host, with or without a scheme, timestamps or
+/// playlist parameters.
+pub fn parse(input: &str) -> Result<String, TranscriptError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(TranscriptError::InvalidVideoId(
+            "No video URL or ID was provided.".into(),
+        ));
+    }
+
+    if is_video_id(input) {
+        return Ok(input.to_string());
+    }
+
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+
+    let url = Url::parse(&with_scheme).map_err(|_| invalid(input))?;
+    let host = url.host_str().unwrap_or("").trim_start_matches("www.");
+
+    let candidate = if host == "youtu.be" {
+        url.path_segments()
+            .and_then(|mut s| s.next())
+            .map(String::from)
+    } else if is_youtube_host(host) {
+        let mut segments = url
+            .path_segments()
+            .map(|s| s.collect::<Vec<_>>())
+            .unwrap_or_default();
+        segments.retain(|s| !s.is_empty());
+
+        match segments.as_slice() {
+            ["watch", ..] | [] => query_param(&url, "v"),
+            ["attribution_link", ..] => query_param(&url, "u")
+                .and_then(|u| Url::parse(&format!("https://www.youtube.com{}", u)).ok())
+                .and_then(|u| query_param(&u, "v")),
+            [prefix, id, ..] if ID_PATH_PREFIXES.contains(prefix) => Some(id.to_string()),
+            _ => None,
+        }
+    } else {
+        return Err(TranscriptError::InvalidVideoId(format!(
+            "\"{}\" is not a YouTube URL.",
+            input
+        )));
+    };
+
+    match candidate {
+        Some(id) if is_video_id(&id) => Ok(id),
+        _ => Err(invalid(input)),
+    }
+}
+
+fn is_video_id(candidate: &str) -> bool {
+    candidate.len() == VIDEO_ID_LEN
+        && candidate
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn is_youtube_host(host: &str) -> bool {
+    matches!(
+        host,
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" | "youtube-nocookie.com"
+    )
+}
+
+fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn invalid(input: &str) -> TranscriptError {
+    TranscriptError::InvalidVideoId(format!(
+        "Could not find a video ID in \"{}\". Paste a YouTube video link or an 11-character video ID.",
+        input
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "dQw4w9WgXcQ";
+
+    #[test]
+    fn accepts_bare_id() {
+        assert_eq!(parse(ID).unwrap(), ID);
+        assert_eq!(parse("  dQw4w9WgXcQ\n").unwrap(), ID);
+    }
+
+    #[test]
+    fn parses_common_url_forms() {
+        let inputs = [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=42s",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG&index=2",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?t=10",
+            "youtu.be/dQw4w9WgXcQ",
+            "www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ?si=abc",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ?start=5",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/v/dQw4w9WgXcQ",
+        ];
+
+        for input in inputs {
+            assert_eq!(parse(input).unwrap(), ID, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for input in [
+            "",
+            "not a video",
+            "dQw4w9WgXc",
+            "https://vimeo.com/123456",
+            "https://www.youtube.com/watch?v=short",
+            "https://www.youtube.com/@channel",
+        ] {
+            assert!(
+                matches!(parse(input), Err(TranscriptError::InvalidVideoId(_))),
+                "input: {}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Lists of videos kept in text or CSV files, queued for ingestion in one go.
+//!
+//! A text list has one URL or ID per line; blank lines and lines starting with `#`
+//! are skipped. In a CSV (or tab-separated) file, the first cell of a row holding a
+//! URL or ID is taken, so exports of spreadsheets and watch lists work as they are,
+//! and a first row without one is taken for a header.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::jobs::{self, JobKind, JobQueue};
+use serde::Serialize;
+use std::path::Path;
+
+/// Largest list read.
+const MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// A line of a list that is not blank or a comment, and what it names.
+#[derive(Debug)]
+pub struct Entry {
+    /// 1-based line number in the file.
+    pub line: usize,
+    pub input: String,
+    pub id: Result<String, TranscriptError>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ListLineStatus {
+    Queued,
+    /// The same job was already waiting or running.
+    AlreadyQueued,
+    /// An earlier line names the same video.
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListLine {
+    pub line: usize,
+    pub input: String,
+    pub status: ListLineStatus,
+    pub id: Option<String>,
+    pub job_id: Option<i64>,
+    /// Why an invalid line was not taken.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoListImport {
+    /// Jobs queued by this import.
+    pub queued: usize,
+    pub lines: Vec<ListLine>,
+}
+
+/// The cells of a CSV row split on `delimiter`, unquoted. Quoted cells may hold
+/// the delimiter and `""` for a quote.
+fn cells(row: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+}
+
+/// The entries of a list of `kind` inputs. `delimiter` is the cell separator of a
+/// CSV list, `None` for a text list.
+pub fn entries(text: &str, kind: JobKind, delimiter: Option<char>) -> Vec<Entry> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut entries = Vec::new();
+    let mut first_row = true;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let header = std::mem::replace(&mut first_row, false);
+        let id = match delimiter {
+            None => kind.parse_input(line),
+            Some(delimiter) => {
+                let cells = cells(line, delimiter);
+                let mut ids = cells
+                    .iter()
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .map(|c| kind.parse_input(c));
+                match ids.find(Result::is_ok) {
+                    Some(id) => id,
+                    None if header => continue,
+                    None => Err(TranscriptError::InvalidInput(
+                        "No video URL or ID in this row.".into(),
+                    )),
+                }
+            }
+        };
+        entries.push(Entry {
+            line: i + 1,
+            input: line.to_string(),
+            id,
+        });
+    }
+    entries
+}
+
+fn delimiter(path: &Path) -> Option<char> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "csv" => Some(','),
+        "tsv" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Reads the list of videos in the text or CSV file at `path`, and queues a job per
+/// video (or playlist, for playlist jobs) not queued yet. Every line that is not
+/// blank or a comment is reported, with the job it queued or why it did not.
+pub fn import_video_list(
+    db: &Database,
+    queue: &JobQueue,
+    path: String,
+    kind: Option<JobKind>,
+) -> Result<VideoListImport, TranscriptError> {
+    let kind = kind.unwrap_or(JobKind::Transcript);
+    let path = Path::new(&path);
+    let error = |e: std::io::Error| {
+        TranscriptError::FileError(format!("Could not read \"{}\": {}", path.display(), e))
+    };
+    if std::fs::metadata(path).map_err(error)?.len() > MAX_BYTES {
+        return Err(TranscriptError::InvalidInput(format!(
+            "\"{}\" is too large for a list of videos.",
+            path.display()
+        )));
+    }
+    let text = std::fs::read_to_string(path).map_err(error)?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut lines = Vec::new();
+    let mut queued = 0;
+    for entry in entries(&text, kind, delimiter(path)) {
+        let (status, id, job_id, error) = match entry.id {
+            Err(e) => (ListLineStatus::Invalid, None, None, Some(e.to_string())),
+            Ok(id) if !seen.insert(id.clone()) => (ListLineStatus::Duplicate, Some(id), None, None),
+            Ok(id) => match jobs::enqueue(db, kind, &id)? {
+                Some(job) => {
+                    queued += 1;
+                    (ListLineStatus::Queued, Some(id), Some(job.id), None)
+                }
+                None => (ListLineStatus::AlreadyQueued, Some(id), None, None),
+            },
+        };
+        lines.push(ListLine {
+            line: entry.line,
+            input: entry.input,
+            status,
+            id,
+            job_id,
+            error,
+        });
+    }
+    queue.wake();
+    Ok(VideoListImport { queued, lines })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(entries: &[Entry]) -> Vec<(usize, Option<&str>)> {
+        entries
+            .iter()
+            .map(|e| (e.line, e.id.as_deref().ok()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_text_lists() {
+        let list =
+            "\u{feff}# cooking\nhttps://youtu.be/dQw4w9WgXcQ\n\n  9bZkp7q19f0  \nnot a video\n";
+        assert_eq!(
+            ids(&entries(list, JobKind::Transcript, None)),
+            vec![
+                (2, Some("dQw4w9WgXcQ")),
+                (4, Some("9bZkp7q19f0")),
+                (5, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_first_video_of_csv_rows() {
+        let list = "title,url\n\
+                    \"Onions, slowly\",https://www.youtube.com/watch?v=dQw4w9WgXcQ\n\
+                    Gangnam Style,9bZkp7q19f0,extra\n\
+                    \"Say \"\"hi\"\"\",\n";
+        assert_eq!(
+            ids(&entries(list, JobKind::Transcript, Some(','))),
+            vec![
+                (2, Some("dQw4w9WgXcQ")),
+                (3, Some("9bZkp7q19f0")),
+                (4, None)
+            ]
+        );
+        assert_eq!(cells("\"a,b\",\"c\"\"d\",", ','), vec!["a,b", "c\"d", ""]);
+    }
+}
@@ -0,0 +1,216 @@
+//! An outgoing webhook called when background ingestion finishes a video.
+//!
+//! Transcript and summary jobs post what they produced as JSON to the URL in the
+//! settings, so automation tools (n8n, Zapier, Make) can pick videos up as they
+//! are ingested. With a secret set, every request carries an HMAC-SHA256 of its
+//! body in `X-InsightTube-Signature`, for receivers to check it came from here.
+//! Calls run apart from the job queue and are retried a few times; a receiver that
+//! stays down only costs a warning in the logs.
+
+use crate::db::{unix_now, Database};
+use crate::error::TranscriptError;
+use crate::summarize::Summary;
+use crate::transcript::TranscriptSegment;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled before each next one.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookSettings {
+    /// Where ingestion results are posted; none are when unset.
+    pub url: Option<String>,
+    /// Key of the signature sent with each request.
+    pub secret: Option<String>,
+}
+
+impl WebhookSettings {
+    fn url(&self) -> Option<&str> {
+        self.url.as_deref().map(str::trim).filter(|u| !u.is_empty())
+    }
+
+    fn secret(&self) -> Option<&str> {
+        self.secret.as_deref().filter(|s| !s.is_empty())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), TranscriptError> {
+        let Some(url) = self.url() else {
+            return Ok(());
+        };
+        match reqwest::Url::parse(url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+            _ => Err(TranscriptError::InvalidInput(
+                "The webhook URL must be an http:// or https:// address.".into(),
+            )),
+        }
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` keyed with `secret`.
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+/// The payload of `event` for `video_id`, with the fields of `results` added.
+fn payload(event: &str, video_id: &str, title: Option<String>, results: Value) -> Value {
+    let mut payload = json!({
+        "event": event,
+        "videoId": video_id,
+        "url": format!("https://youtu.be/{}", video_id),
+        "title": title,
+        "sentAt": unix_now(),
+    });
+    if let (Some(payload), Value::Object(results)) = (payload.as_object_mut(), results) {
+        payload.extend(results);
+    }
+    payload
+}
+
+async fn post(
+    settings: &WebhookSettings,
+    event: &str,
+    body: &[u8],
+) -> Result<u16, TranscriptError> {
+    let url = settings
+        .url()
+        .ok_or_else(|| TranscriptError::InvalidInput("No webhook URL is configured.".into()))?;
+    let mut request = crate::llm::client(TIMEOUT)?
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("X-InsightTube-Event", event)
+        .body(body.to_vec());
+    if let Some(secret) = settings.secret() {
+        request = request.header("X-InsightTube-Signature", signature(secret, body));
+    }
+    let res = request
+        .send()
+        .await
+        .map_err(|e| TranscriptError::NetworkError(format!("Webhook request failed: {}", e)))?;
+    let status = res.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err(TranscriptError::NetworkError(format!(
+            "The webhook answered HTTP {}.",
+            status.as_u16()
+        )))
+    }
+}
+
+/// The webhook settings, when a URL is set.
+fn configured() -> Option<WebhookSettings> {
+    Some(crate::settings::current().webhook).filter(|w| w.url().is_some())
+}
+
+/// Posts `event` for `video_id` with `results` in the background.
+fn send(
+    settings: WebhookSettings,
+    db: &Database,
+    event: &'static str,
+    video_id: &str,
+    results: Value,
+) {
+    let title = crate::highlights::title_of(db, video_id).ok().flatten();
+    let body = serde_json::to_vec(&payload(event, video_id, title, results)).unwrap_or_default();
+    tokio::spawn(async move {
+        let mut delay = RETRY_DELAY;
+        for attempt in 1..=ATTEMPTS {
+            match post(&settings, event, &body).await {
+                Ok(_) => return,
+                Err(e) if attempt == ATTEMPTS => {
+                    tracing::warn!(event, error = %e, "webhook not delivered");
+                }
+                Err(_) => {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+    });
+}
+
+/// Announces that a transcript job fetched the transcript of `video_id`.
+pub(crate) fn transcript_ingested(db: &Database, video_id: &str, segments: &[TranscriptSegment]) {
+    let Some(settings) = configured() else {
+        return;
+    };
+    let text = segments
+        .iter()
+        .map(|s| s.text.trim())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let results = json!({
+        "transcript": {
+            "language": segments.first().map(|s| s.lang.as_str()),
+            "segments": segments,
+            "text": text,
+        },
+    });
+    send(settings, db, "transcript.ingested", video_id, results);
+}
+
+/// Announces that a summary job summarized `video_id`.
+pub(crate) fn summary_ingested(db: &Database, video_id: &str, summary: &Summary) {
+    if let Some(settings) = configured() {
+        let results = json!({ "summary": summary });
+        send(settings, db, "summary.ingested", video_id, results);
+    }
+}
+
+/// Posts a `ping` event to the configured webhook and returns the HTTP status it
+/// answered with, to check the URL and secret before ingesting anything.
+pub async fn test_webhook() -> Result<u16, TranscriptError> {
+    let settings = crate::settings::current().webhook;
+    let body = json!({ "event": "ping", "sentAt": unix_now() });
+    post(
+        &settings,
+        "ping",
+        &serde_json::to_vec(&body).unwrap_or_default(),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_bodies_with_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn accepts_only_http_urls() {
+        let settings = |url: &str| WebhookSettings {
+            url: Some(url.into()),
+            secret: None,
+        };
+        assert!(settings("https://n8n.example/webhook/abc")
+            .validate()
+            .is_ok());
+        assert!(settings("  ").validate().is_ok());
+        assert!(settings("ftp://example.com").validate().is_err());
+        assert!(settings("not a url").validate().is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Local transcription with whisper.cpp for videos without captions.
+//!
+//! Only built with the `whisper` feature. The audio-only AAC stream is downloaded,
+//! decoded to 16 kHz mono as whisper expects, and transcribed on a blocking thread
+//! while progress events report how far along it is.
+
+use crate::download::{download_to, select_audio};
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::fetch_player_response;
+use crate::operations;
+use crate::progress::{Progress, Stage};
+use crate::transcript::TranscriptSegment;
+use std::io::Cursor;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tokio_util::sync::CancellationToken;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+const SAMPLE_RATE: u32 = 16_000;
+fn transcription_error(context: &str, e: impl std::fmt::Display) -> TranscriptError {
+    TranscriptError::TranscriptionError(format!("{}: {}", context, e))
+}
+
+/// Decodes an AAC/MP4 file into mono samples at [`SAMPLE_RATE`].
+fn decode_to_mono(bytes: Vec<u8>) -> Result<Vec<f32>, TranscriptError> {
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension("m4a");
+
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| transcription_error("Unsupported audio format", e))?
+        .format;
+    let track = format.default_track().ok_or_else(|| {
+        TranscriptError::TranscriptionError("The audio file has no tracks.".into())
+    })?;
+    let track_id = track.id;
+    let source_rate = track.codec_params.sample_rate.unwrap_or(44_100);
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| transcription_error("Unsupported audio codec", e))?;
+
+    let mut mono = Vec::new();
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        // A corrupt packet is skipped rather than failing the whole file
+        let Ok(decoded) = decoder.decode(&packet) else {
+            continue;
+        };
+        let channels = decoded.spec().channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+        buffer.copy_interleaved_ref(decoded);
+        mono.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    Ok(resample(&mono, source_rate, SAMPLE_RATE))
+}
+
+/// Linear-interpolation resampling, which is plenty for speech recognition.
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let len = (samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let idx = pos as usize;
+            let frac = (pos - idx as f64) as f32;
+            let a = samples[idx];
+            let b = samples.get(idx + 1).copied().unwrap_or(a);
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+/// Runs whisper over `samples`; `on_progress` receives percentages as they advance,
+/// and whisper stops early once `cancel` fires.
+fn run_whisper(
+    model_path: &str,
+    samples: &[f32],
+    on_progress: impl FnMut(i32) + 'static,
+    cancel: CancellationToken,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+        .map_err(|e| transcription_error("Could not load the Whisper model", e))?;
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| transcription_error("Could not start Whisper", e))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some("auto"));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    params.set_progress_callback_safe(on_progress);
+    let abort = cancel.clone();
+    params.set_abort_callback_safe(move || abort.is_cancelled());
+
+    let result = state.full(params, samples);
+    operations::check(&cancel)?;
+    result.map_err(|e| transcription_error("Transcription failed", e))?;
+
+    let lang = state
+        .full_lang_id_from_state()
+        .ok()
+        .and_then(whisper_rs::get_lang_str)
+        .unwrap_or("und")
+        .to_string();
+    let count = state
+        .full_n_segments()
+        .map_err(|e| transcription_error("Transcription failed", e))?;
+
+    let mut segments = Vec::new();
+    for i in 0..count {
+        let text = state
+            .full_get_segment_text_lossy(i)
+            .map_err(|e| transcription_error("Transcription failed", e))?;
+        // Whisper timestamps are in centiseconds
+        let t0 = state.full_get_segment_t0(i).unwrap_or(0) as f64 / 100.0;
+        let t1 = state.full_get_segment_t1(i).unwrap_or(0) as f64 / 100.0;
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        segments.push(TranscriptSegment {
+            text: text.to_string(),
+            duration: (t1 - t0).max(0.0),
+            offset: t0,
+            lang: lang.clone(),
+            words: Vec::new(),
+            speaker: None,
+            flagged: Vec::new(),
+        });
+    }
+    Ok(segments)
+}
+
+/// Downloads the audio of `video_id` and transcribes it with the ggml model at `model_path`.
+pub(crate) async fn transcribe(
+    progress: &Progress,
+    cancel: &CancellationToken,
+    video_id: &str,
+    model_path: &str,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    if !std::path::Path::new(model_path).is_file() {
+        return Err(TranscriptError::InvalidInput(format!(
+            "Whisper model not found at \"{}\".",
+            model_path
+        )));
+    }
+
+    progress.report(Stage::Downloading, Some(0), None);
+    let client = build_client()?;
+    let player_json =
+        operations::cancellable(cancel, fetch_player_response(&client, video_id)).await?;
+    // Symphonia decodes AAC but not Opus, so only MP4 audio is usable
+    let format = select_audio(&player_json, Some("audio/mp4"))?;
+    let mut audio = Vec::new();
+    download_to(progress, cancel, &client, video_id, &format, &mut audio).await?;
+
+    let model_path = model_path.to_string();
+    let progress = progress.clone();
+    let cancel = cancel.clone();
+
+    tokio::task::spawn_blocking(move || {
+        progress.report(Stage::Decoding, None, None);
+        let samples = decode_to_mono(audio)?;
+        operations::check(&cancel)?;
+        progress.report(Stage::Transcribing, Some(0), None);
+
+        let whisper_progress = progress.clone();
+        let segments = run_whisper(
+            &model_path,
+            &samples,
+            move |percent| {
+                whisper_progress.report(
+                    Stage::Transcribing,
+                    Some(percent.clamp(0, 100) as u8),
+                    None,
+                );
+            },
+            cancel,
+        )?;
+        if segments.is_empty() {
+            return Err(TranscriptError::EmptyTranscript);
+        }
+        Ok(segments)
+    })
+    .await
+    .map_err(|e| transcription_error("Transcription was interrupted", e))?
+}
@@ -0,0 +1,365 @@
+//! Searching YouTube through the Innertube `search` endpoint.
+//!
+//! Filters travel as the `params` of the request, the same base64 protobuf the
+//! website puts in its `sp=` URL parameter: a message whose field 2 holds the
+//! upload date (1), result type (2) and duration (3) as enum values.
+
+use crate::error::TranscriptError;
+use crate::http::build_client;
+use crate::innertube::{continuation_token, find_all, post_endpoint, text_of};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResultType {
+    Video,
+    Channel,
+    Playlist,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DurationFilter {
+    /// Under 4 minutes.
+    Short,
+    /// 4 to 20 minutes.
+    Medium,
+    /// Over 20 minutes.
+    Long,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UploadDate {
+    LastHour,
+    Today,
+    ThisWeek,
+    ThisMonth,
+    ThisYear,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    #[serde(rename = "type")]
+    pub result_type: Option<ResultType>,
+    pub duration: Option<DurationFilter>,
+    pub upload_date: Option<UploadDate>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchVideo {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: Option<String>,
+    pub channel_id: Option<String>,
+    /// Missing for live streams.
+    pub duration_seconds: Option<u64>,
+    pub view_count: Option<u64>,
+    /// As YouTube shows it, such as "3 weeks ago".
+    pub published_time: Option<String>,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchChannel {
+    pub channel_id: String,
+    pub title: String,
+    /// As YouTube shows it, such as "1.2M subscribers".
+    pub subscribers: Option<String>,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPlaylist {
+    pub playlist_id: String,
+    pub title: String,
+    pub channel_name: Option<String>,
+    pub video_count: Option<u64>,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SearchResult {
+    Video(SearchVideo),
+    Channel(SearchChannel),
+    Playlist(SearchPlaylist),
+}
+
+/// One page of results; pass `continuation` back to fetch the next one.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPage {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub continuation: Option<String>,
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The `params` of a search with `filters`, or `None` when nothing is filtered.
+fn filter_params(filters: &SearchFilters) -> Option<String> {
+    let mut inner = Vec::new();
+    if let Some(date) = filters.upload_date {
+        let value = match date {
+            UploadDate::LastHour => 1,
+            UploadDate::Today => 2,
+            UploadDate::ThisWeek => 3,
+            UploadDate::ThisMonth => 4,
+            UploadDate::ThisYear => 5,
+        };
+        inner.extend([0x08, value]);
+    }
+    if let Some(result_type) = filters.result_type {
+        let value = match result_type {
+            ResultType::Video => 1,
+            ResultType::Channel => 2,
+            ResultType::Playlist => 3,
+        };
+        inner.extend([0x10, value]);
+    }
+    if let Some(duration) = filters.duration {
+        let value = match duration {
+            DurationFilter::Short => 1,
+            DurationFilter::Long => 2,
+            DurationFilter::Medium => 3,
+        };
+        inner.extend([0x18, value]);
+    }
+    if inner.is_empty() {
+        return None;
+    }
+
+    let mut message = vec![0x12, inner.len() as u8];
+    message.extend(inner);
+    Some(base64(&message))
+}
+
+/// The last (largest) of the `thumbnails` of a thumbnail object.
+fn thumbnail_url(thumbnail: &Value) -> Option<String> {
+    let thumbnails = thumbnail.get("thumbnails")?.as_array()?;
+    let url = thumbnails.last()?.get("url")?.as_str()?;
+    Some(match url.strip_prefix("//") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    })
+}
+
+/// Seconds from a length such as "1:02:03".
+pub fn parse_length(text: &str) -> Option<u64> {
+    text.trim().split(':').try_fold(0, |total, part| {
+        part.parse::<u64>().ok().map(|n| total * 60 + n)
+    })
+}
+
+/// The digits of a text such as "1,234,567 views", which "No views" has none of.
+fn parse_digits(text: &str) -> Option<u64> {
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+pub fn parse_video(renderer: &Value) -> Option<SearchVideo> {
+    let owner = renderer
+        .get("ownerText")
+        .or_else(|| renderer.get("longBylineText"));
+    Some(SearchVideo {
+        video_id: renderer.get("videoId")?.as_str()?.to_string(),
+        title: renderer.get("title").and_then(text_of).unwrap_or_default(),
+        channel_name: owner.and_then(text_of),
+        channel_id: owner
+            .and_then(|o| o.pointer("/runs/0/navigationEndpoint/browseEndpoint/browseId"))
+            .and_then(|id| id.as_str())
+            .map(String::from),
+        duration_seconds: renderer
+            .get("lengthText")
+            .and_then(text_of)
+            .and_then(|l| parse_length(&l)),
+        view_count: renderer
+            .get("viewCountText")
+            .and_then(text_of)
+            .map(|v| parse_digits(&v).unwrap_or(0)),
+        published_time: renderer.get("publishedTimeText").and_then(text_of),
+        thumbnail_url: renderer.get("thumbnail").and_then(thumbnail_url),
+    })
+}
+
+fn parse_channel(renderer: &Value) -> Option<SearchChannel> {
+    Some(SearchChannel {
+        channel_id: renderer.get("channelId")?.as_str()?.to_string(),
+        title: renderer.get("title").and_then(text_of).unwrap_or_default(),
+        // Channels with a handle show it in `subscriberCountText`, and their
+        // subscribers in `videoCountText`
+        subscribers: [
+            renderer.get("videoCountText"),
+            renderer.get("subscriberCountText"),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(text_of)
+        .find(|t| t.contains("subscriber")),
+        thumbnail_url: renderer.get("thumbnail").and_then(thumbnail_url),
+    })
+}
+
+fn parse_playlist(renderer: &Value) -> Option<SearchPlaylist> {
+    Some(SearchPlaylist {
+        playlist_id: renderer.get("playlistId")?.as_str()?.to_string(),
+        title: renderer.get("title").and_then(text_of).unwrap_or_default(),
+        channel_name: renderer
+            .get("shortBylineText")
+            .or_else(|| renderer.get("longBylineText"))
+            .and_then(text_of),
+        video_count: renderer
+            .get("videoCount")
+            .and_then(|c| c.as_str())
+            .and_then(parse_digits),
+        // One thumbnail object per video shown on the playlist's cover
+        thumbnail_url: renderer.pointer("/thumbnails/0").and_then(thumbnail_url),
+    })
+}
+
+/// The results of a search response. Only the items of the result list are read,
+/// so the shelves mixed into it ("People also watched", Shorts) are left out.
+fn parse_results(response: &Value) -> Vec<SearchResult> {
+    find_all(response, "itemSectionRenderer")
+        .into_iter()
+        .filter_map(|section| section.get("contents")?.as_array())
+        .flatten()
+        .filter_map(|item| {
+            if let Some(video) = item.get("videoRenderer") {
+                parse_video(video).map(SearchResult::Video)
+            } else if let Some(channel) = item.get("channelRenderer") {
+                parse_channel(channel).map(SearchResult::Channel)
+            } else if let Some(playlist) = item.get("playlistRenderer") {
+                parse_playlist(playlist).map(SearchResult::Playlist)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Searches YouTube for `query`. Without `continuation` the first page is returned.
+pub async fn search_youtube(
+    query: String,
+    filters: Option<SearchFilters>,
+    continuation: Option<String>,
+) -> Result<SearchPage, TranscriptError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err(TranscriptError::InvalidInput(
+            "Enter something to search for.".into(),
+        ));
+    }
+
+    let body = match &continuation {
+        Some(token) => serde_json::json!({ "continuation": token }),
+        None => {
+            let mut body = serde_json::json!({ "query": query });
+            if let Some(params) = filter_params(&filters.unwrap_or_default()) {
+                body["params"] = serde_json::json!(params);
+            }
+            body
+        }
+    };
+    let client = build_client()?;
+    let response = post_endpoint(&client, "search", body).await?;
+
+    Ok(SearchPage {
+        query,
+        results: parse_results(&response),
+        continuation: continuation_token(&response),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn encodes_filters_like_the_website() {
+        let filters = |json| serde_json::from_value::<SearchFilters>(json).unwrap();
+
+        assert_eq!(filter_params(&SearchFilters::default()), None);
+        assert_eq!(
+            filter_params(&filters(json!({ "type": "video" }))).as_deref(),
+            Some("EgIQAQ==")
+        );
+        assert_eq!(
+            filter_params(&filters(json!({ "uploadDate": "thisWeek" }))).as_deref(),
+            Some("EgIIAw==")
+        );
+        assert_eq!(
+            filter_params(&filters(
+                json!({ "type": "video", "duration": "long", "uploadDate": "today" })
+            ))
+            .as_deref(),
+            Some("EgYIAhABGAI=")
+        );
+    }
+
+    #[test]
+    fn reads_result_items_but_not_shelves() {
+        let response = json!({ "contents": { "sectionListRenderer": { "contents": [
+            { "itemSectionRenderer": { "contents": [
+                { "videoRenderer": {
+                    "videoId": "dQw4w9WgXcQ",
+                    "title": { "runs": [{ "text": "Cooking 101" }] },
+                    "ownerText": { "runs": [{
+                        "text": "Chef",
+                        "navigationEndpoint": { "browseEndpoint": { "browseId": "UC1" } }
+                    }] },
+                    "lengthText": { "simpleText": "1:02:03" },
+                    "viewCountText": { "simpleText": "1,234 views" },
+                    "publishedTimeText": { "simpleText": "3 weeks ago" },
+                    "thumbnail": { "thumbnails": [{ "url": "small" }, { "url": "large" }] }
+                } },
+                { "shelfRenderer": { "content": { "verticalListRenderer": { "items": [
+                    { "videoRenderer": { "videoId": "jNQXAC9IVRw" } }
+                ] } } } },
+                { "channelRenderer": {
+                    "channelId": "UC2",
+                    "title": { "simpleText": "Chef" },
+                    "videoCountText": { "simpleText": "12K subscribers" }
+                } }
+            ] } }
+        ] } } });
+
+        let results = parse_results(&response);
+        assert_eq!(results.len(), 2);
+        let SearchResult::Video(video) = &results[0] else {
+            panic!("expected a video");
+        };
+        assert_eq!(video.channel_id.as_deref(), Some("UC1"));
+        assert_eq!(video.duration_seconds, Some(3723));
+        assert_eq!(video.view_count, Some(1234));
+        assert_eq!(video.thumbnail_url.as_deref(), Some("large"));
+        let SearchResult::Channel(channel) = &results[1] else {
+            panic!("expected a channel");
+        };
+        assert_eq!(channel.subscribers.as_deref(), Some("12K subscribers"));
+    }
+}
@@ -0,0 +1,36 @@
+//! The commands of [`insighttube_core::action_items`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+pub(crate) use insighttube_core::action_items::*;
+
+#[tauri::command]
+pub async fn extract_action_items(
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<ActionItemOptions>,
+    operation_id: Option<String>,
+) -> Result<ActionItems, TranscriptError> {
+    insighttube_core::action_items::extract_action_items(
+        &db,
+        &operations,
+        video_id,
+        provider,
+        options,
+        operation_id,
+    )
+    .await
+}
+
+#[tauri::command]
+pub fn export_action_items(
+    video_id: String,
+    items: Vec<ActionItem>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    insighttube_core::action_items::export_action_items(video_id, items, path)
+}
@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::activity`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::activity::*;
+
+#[tauri::command]
+pub fn get_activity(
+    db: tauri::State<'_, Database>,
+    filter: Option<ActivityFilter>,
+    page: Option<usize>,
+) -> Result<ActivityPage, TranscriptError> {
+    insighttube_core::activity::get_activity(&db, filter, page)
+}
@@ -0,0 +1,327 @@
+//! A local HTTP API, so scripts and other tools can drive the app.
+//!
+//! When turned on in the settings, a server on `127.0.0.1` answers:
+//!
+//! - `GET /v1/transcripts/{video}`: the transcript, as `fetch_transcript_v2`
+//!   returns it (`?wordTiming=true` for word timings);
+//! - `GET /v1/search?q=…&limit=…`: full-text search over cached transcripts;
+//! - `POST /v1/summaries/{video}`: a summary, with an optional JSON body of
+//!   `provider` and the options of `summarize_transcript`.
+//!
+//! Every request must carry the token from the settings as
+//! `Authorization: Bearer <token>`. Errors are answered with the JSON the commands
+//! fail with and a matching status. The server only listens on the loopback
+//! interface, and sends no CORS headers, so web pages cannot call it either.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::search::SearchResult;
+use crate::settings::ApiServerSettings;
+use crate::summarize::{summarize, SummarizeOptions, Summary};
+use crate::transcript::{load_transcript_with_track, Transcript};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use once_cell::sync::Lazy;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Mutex, PoisonError};
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
+
+const TOKEN_LENGTH: usize = 40;
+
+/// The server while it runs, and the port it listens on.
+struct Running {
+    port: u16,
+    shutdown: CancellationToken,
+}
+
+static SERVER: Lazy<Mutex<Option<Running>>> = Lazy::new(|| Mutex::new(None));
+
+/// A failed request, answered with the error's JSON.
+struct ApiError(TranscriptError);
+
+impl From<TranscriptError> for ApiError {
+    fn from(e: TranscriptError) -> Self {
+        Self(e)
+    }
+}
+
+fn status(error: &TranscriptError) -> StatusCode {
+    match error {
+        TranscriptError::InvalidVideoId(_) | TranscriptError::InvalidInput(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        TranscriptError::VideoUnavailable(_)
+        | TranscriptError::TranscriptsDisabled
+        | TranscriptError::NoTranscript
+        | TranscriptError::EmptyTranscript
+        | TranscriptError::TranslationUnavailable(_) => StatusCode::NOT_FOUND,
+        TranscriptError::PrivateVideo
+        | TranscriptError::AgeRestricted
+        | TranscriptError::MembersOnly
+        | TranscriptError::LoginRequired(_)
+        | TranscriptError::RegionBlocked => StatusCode::FORBIDDEN,
+        TranscriptError::RateLimited | TranscriptError::CaptchaRequired => {
+            StatusCode::TOO_MANY_REQUESTS
+        }
+        TranscriptError::NetworkError(_) | TranscriptError::LlmError(_) => StatusCode::BAD_GATEWAY,
+        TranscriptError::BudgetExceeded(_) => StatusCode::PAYMENT_REQUIRED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (status(&self.0), Json(self.0)).into_response()
+    }
+}
+
+/// Compares in constant time, so the token cannot be guessed from response times.
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    let Some(expected) = crate::settings::current().api_server.token else {
+        return false;
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| !expected.is_empty() && token_matches(&expected, given.trim()))
+}
+
+async fn require_token(request: Request, next: Next) -> Response {
+    if authorized(request.headers()) {
+        next.run(request).await
+    } else {
+        let error = TranscriptError::InvalidInput("Missing or wrong API token.".into());
+        (StatusCode::UNAUTHORIZED, Json(error)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct TranscriptQuery {
+    word_timing: Option<bool>,
+}
+
+async fn transcript(
+    State(app): State<tauri::AppHandle>,
+    Path(video_id): Path<String>,
+    Query(query): Query<TranscriptQuery>,
+) -> Result<Json<Transcript>, ApiError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let db = app.state::<Database>();
+    Ok(Json(
+        load_transcript_with_track(&db, &video_id, query.word_timing, None).await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+async fn search(
+    State(app): State<tauri::AppHandle>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, ApiError> {
+    let db = app.state::<Database>();
+    Ok(Json(crate::search::search(&db, &query.q, query.limit)?))
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct SummaryRequest {
+    /// The preselected provider when unset.
+    provider: Option<ProviderKind>,
+    #[serde(flatten)]
+    options: SummarizeOptions,
+}
+
+async fn summary(
+    State(app): State<tauri::AppHandle>,
+    Path(video_id): Path<String>,
+    body: Option<Json<SummaryRequest>>,
+) -> Result<Json<Summary>, ApiError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let Json(mut request) = body.unwrap_or_default();
+    let settings = crate::settings::current().llm;
+    let provider = request.provider.or(settings.provider).ok_or_else(|| {
+        TranscriptError::InvalidInput("Name a provider, or choose one in the settings.".into())
+    })?;
+    if request.provider.is_none() && request.options.model.is_none() {
+        request.options.model = settings.model;
+    }
+    let db = app.state::<Database>();
+    let llm = llm::provider(&db, provider, request.options.model.clone(), "summary")?;
+
+    let segments = crate::transcript::load_transcript(&db, &video_id, None).await?;
+    let (tldr, key_points) = summarize(llm.as_ref(), &segments, &request.options, None).await?;
+    Ok(Json(Summary {
+        tldr,
+        key_points,
+        provider,
+        model: llm.model().to_string(),
+    }))
+}
+
+fn router(app: tauri::AppHandle) -> Router {
+    Router::new()
+        .route("/v1/transcripts/{video_id}", get(transcript))
+        .route("/v1/search", get(search))
+        .route("/v1/summaries/{video_id}", post(summary))
+        .layer(middleware::from_fn(require_token))
+        .with_state(app)
+}
+
+/// A listener bound for the port new settings move the server to.
+pub(crate) struct Bound {
+    port: u16,
+    listener: std::net::TcpListener,
+}
+
+/// Binds the port `settings` start or move the server on, so a port already in use
+/// is reported before the settings are saved or anything else changes. `None` when
+/// the server keeps its port or stops.
+pub(crate) fn bind(settings: &ApiServerSettings) -> Result<Option<Bound>, TranscriptError> {
+    let running = SERVER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .map(|s| s.port);
+    let port = match settings.enabled.then_some(settings.port) {
+        Some(port) if running != Some(port) => port,
+        _ => return Ok(None),
+    };
+    let listener = std::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+        .and_then(|l| l.set_nonblocking(true).map(|_| l))
+        .map_err(|e| {
+            TranscriptError::InvalidInput(format!(
+                "The local API cannot listen on port {}: {}",
+                port, e
+            ))
+        })?;
+    Ok(Some(Bound { port, listener }))
+}
+
+/// Starts, stops or moves the server to match `settings`, listening on `bound`
+/// from [`bind`] when it starts or moves.
+pub(crate) fn apply(app: &tauri::AppHandle, settings: &ApiServerSettings, bound: Option<Bound>) {
+    let mut server = SERVER.lock().unwrap_or_else(PoisonError::into_inner);
+    let wanted = settings.enabled.then_some(settings.port);
+    if server.as_ref().map(|s| s.port) == wanted {
+        return;
+    }
+    if let Some(running) = server.take() {
+        running.shutdown.cancel();
+    }
+    let Some(Bound { port, listener }) = bound.filter(|b| Some(b.port) == wanted) else {
+        return;
+    };
+
+    let shutdown = CancellationToken::new();
+    let stopped = shutdown.clone();
+    let router = router(app.clone());
+    tauri::async_runtime::spawn(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::warn!(error = %e, "local API not started");
+                return;
+            }
+        };
+        tracing::info!(port, "local API listening");
+        let served = axum::serve(listener, router)
+            .with_graceful_shutdown(async move { stopped.cancelled().await })
+            .await;
+        if let Err(e) = served {
+            tracing::warn!(error = %e, "local API stopped");
+        }
+    });
+    *server = Some(Running { port, shutdown });
+}
+
+/// Replaces the local API's token with a new random one and returns it. Scripts
+/// using the old token are locked out.
+#[tauri::command]
+pub fn regenerate_api_token(app: tauri::AppHandle) -> Result<String, TranscriptError> {
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    crate::settings::update(&app, |s| s.api_server.token = Some(token.clone()))?;
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens_whole() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cret", "s3cre"));
+        assert!(!token_matches("s3cret", "s3creT"));
+    }
+
+    #[test]
+    fn answers_errors_with_matching_statuses() {
+        assert_eq!(
+            status(&TranscriptError::InvalidVideoId("x".into())),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(&TranscriptError::NoTranscript),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(&TranscriptError::RateLimited),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            status(&TranscriptError::DatabaseError("locked".into())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn reports_a_port_in_use_when_binding() {
+        let taken = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let settings = ApiServerSettings {
+            enabled: true,
+            port,
+            token: Some("s3cret".into()),
+        };
+
+        let Err(e) = bind(&settings) else {
+            panic!("bound a port in use");
+        };
+        assert!(e.to_string().contains(&port.to_string()));
+        drop(taken);
+        assert!(bind(&settings).unwrap().is_some_and(|b| b.port == port));
+        let stopped = ApiServerSettings {
+            enabled: false,
+            ..settings
+        };
+        assert!(bind(&stopped).unwrap().is_none());
+    }
+}
@@ -0,0 +1,35 @@
+//! The commands of [`insighttube_core::ask`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+pub(crate) use insighttube_core::ask::*;
+
+/// Answers `question` about `video_id` with `provider`. With `request_id` the answer
+/// is streamed as `llm-chunk` events and can be cancelled.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ask_transcript(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    question: String,
+    provider: ProviderKind,
+    options: Option<AskOptions>,
+    request_id: Option<String>,
+) -> Result<TranscriptAnswer, TranscriptError> {
+    let on_chunk = request_id
+        .as_deref()
+        .map(|id| llm::stream::emitter(&app, id));
+    let answer = insighttube_core::ask::ask_transcript(
+        &db,
+        video_id,
+        question,
+        provider,
+        options,
+        on_chunk.as_deref(),
+    );
+    llm::stream::cancellable(&app, &operations, request_id.as_deref(), answer).await
+}
@@ -0,0 +1,44 @@
+//! The commands of [`insighttube_core::auto_ingest`], and the timer that checks the
+//! subscriptions while the app runs.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::subscriptions;
+pub(crate) use insighttube_core::auto_ingest::*;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Checks the subscriptions now and then on the configured interval, for the
+/// lifetime of the app. A changed interval takes effect after the next check.
+pub(crate) fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut minutes = poll_minutes();
+        let mut ticks = schedule(minutes, Instant::now());
+        loop {
+            ticks.tick().await;
+            let _ = subscriptions::check_all(&app).await;
+            if poll_minutes() != minutes {
+                minutes = poll_minutes();
+                let next = Instant::now() + Duration::from_secs(minutes as u64 * 60);
+                ticks = schedule(minutes, next);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_ingested_summary(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+) -> Result<Option<IngestedSummary>, TranscriptError> {
+    insighttube_core::auto_ingest::get_ingested_summary(&db, video_id)
+}
+
+#[tauri::command]
+pub fn list_ingested_summaries(
+    db: tauri::State<'_, Database>,
+    limit: Option<usize>,
+) -> Result<Vec<IngestedSummary>, TranscriptError> {
+    insighttube_core::auto_ingest::list_ingested_summaries(&db, limit)
+}
@@ -0,0 +1,67 @@
+//! The commands of [`insighttube_core::batch`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::operations::Operations;
+use crate::progress;
+pub(crate) use insighttube_core::batch::*;
+use tokio_util::sync::CancellationToken;
+
+/// Notifies how many transcripts of a batch were fetched, unless it was cancelled.
+fn announce(app: &tauri::AppHandle, results: &[BatchItemResult], cancel: &CancellationToken) {
+    if cancel.is_cancelled() {
+        return;
+    }
+    let fetched = results.iter().filter(|r| r.segments.is_some()).count();
+    crate::notifications::notify(
+        app,
+        "Batch finished",
+        &format!("Fetched {} of {} transcripts.", fetched, results.len()),
+    );
+}
+
+/// Fetches transcripts for several videos with at most `concurrency` requests in
+/// flight. Results are returned in input order, one per input. With `operation_id`,
+/// progress events report how many are done and `cancel_operation` stops the batch.
+#[tauri::command]
+pub async fn fetch_transcripts_batch(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_ids: Vec<String>,
+    concurrency: Option<usize>,
+    operation_id: Option<String>,
+) -> Result<Vec<BatchItemResult>, TranscriptError> {
+    let operation = operations.start(operation_id.as_deref());
+    let progress = progress::emitting(&app, operation_id);
+    let results = fetch_all(&db, video_ids, concurrency, &progress, operation.token()).await;
+    announce(&app, &results, operation.token());
+    Ok(results)
+}
+
+/// Fetches the transcripts of every video of a playlist, listing all its pages
+/// first. Results are in playlist order, one per video.
+#[tauri::command]
+pub async fn fetch_playlist_transcripts(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    playlist_id: String,
+    concurrency: Option<usize>,
+    operation_id: Option<String>,
+) -> Result<Vec<BatchItemResult>, TranscriptError> {
+    let operation = operations.start(operation_id.as_deref());
+    let progress = progress::emitting(&app, operation_id);
+    let results =
+        fetch_playlist(&db, &playlist_id, concurrency, &progress, operation.token()).await?;
+    announce(&app, &results, operation.token());
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn check_transcript_availability(
+    video_ids: Vec<String>,
+    concurrency: Option<usize>,
+) -> Result<Vec<TranscriptAvailability>, TranscriptError> {
+    insighttube_core::batch::check_transcript_availability(video_ids, concurrency).await
+}
@@ -0,0 +1,15 @@
+//! The commands of [`insighttube_core::cache`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::cache::*;
+
+#[tauri::command]
+pub fn clear_transcript_cache(db: tauri::State<'_, Database>) -> Result<u64, TranscriptError> {
+    insighttube_core::cache::clear_transcript_cache(&db)
+}
+
+#[tauri::command]
+pub fn get_cache_stats(db: tauri::State<'_, Database>) -> Result<CacheStats, TranscriptError> {
+    insighttube_core::cache::get_cache_stats(&db)
+}
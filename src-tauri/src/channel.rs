@@ -0,0 +1,13 @@
+//! The commands of [`insighttube_core::channel`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::channel::*;
+
+#[tauri::command]
+pub async fn fetch_channel_videos(
+    channel_id_or_handle: String,
+    limit: Option<usize>,
+    continuation: Option<String>,
+) -> Result<ChannelVideosPage, TranscriptError> {
+    insighttube_core::channel::fetch_channel_videos(channel_id_or_handle, limit, continuation).await
+}
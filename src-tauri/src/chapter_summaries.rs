@@ -0,0 +1,31 @@
+//! The commands of [`insighttube_core::chapter_summaries`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+use crate::progress;
+pub(crate) use insighttube_core::chapter_summaries::*;
+
+#[tauri::command]
+pub async fn summarize_by_chapter(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<ChapterSummaryOptions>,
+    operation_id: Option<String>,
+) -> Result<ChapterOutline, TranscriptError> {
+    let progress = progress::emitting(&app, operation_id.clone());
+    insighttube_core::chapter_summaries::summarize_by_chapter(
+        &db,
+        &operations,
+        video_id,
+        provider,
+        options,
+        operation_id,
+        &progress,
+    )
+    .await
+}
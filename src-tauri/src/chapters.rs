@@ -0,0 +1,9 @@
+//! The commands of [`insighttube_core::chapters`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::chapters::*;
+
+#[tauri::command]
+pub async fn fetch_chapters(video_id: String) -> Result<Vec<Chapter>, TranscriptError> {
+    insighttube_core::chapters::fetch_chapters(video_id).await
+}
@@ -0,0 +1,114 @@
+//! The commands of [`insighttube_core::collections`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+use crate::progress;
+use crate::summarize::SummarizeOptions;
+pub(crate) use insighttube_core::collections::*;
+
+#[tauri::command]
+pub fn create_collection(
+    db: tauri::State<'_, Database>,
+    name: String,
+    description: Option<String>,
+) -> Result<Collection, TranscriptError> {
+    insighttube_core::collections::create_collection(&db, name, description)
+}
+
+#[tauri::command]
+pub fn list_collections(
+    db: tauri::State<'_, Database>,
+) -> Result<Vec<Collection>, TranscriptError> {
+    insighttube_core::collections::list_collections(&db)
+}
+
+#[tauri::command]
+pub fn update_collection(
+    db: tauri::State<'_, Database>,
+    id: i64,
+    name: Option<String>,
+    description: Option<String>,
+) -> Result<Collection, TranscriptError> {
+    insighttube_core::collections::update_collection(&db, id, name, description)
+}
+
+#[tauri::command]
+pub fn delete_collection(db: tauri::State<'_, Database>, id: i64) -> Result<bool, TranscriptError> {
+    insighttube_core::collections::delete_collection(&db, id)
+}
+
+#[tauri::command]
+pub fn add_to_collection(
+    db: tauri::State<'_, Database>,
+    id: i64,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    insighttube_core::collections::add_to_collection(&db, id, video_ids)
+}
+
+#[tauri::command]
+pub fn remove_from_collection(
+    db: tauri::State<'_, Database>,
+    id: i64,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    insighttube_core::collections::remove_from_collection(&db, id, video_ids)
+}
+
+#[tauri::command]
+pub fn list_collection_videos(
+    db: tauri::State<'_, Database>,
+    id: i64,
+) -> Result<Vec<CollectionVideo>, TranscriptError> {
+    insighttube_core::collections::list_collection_videos(&db, id)
+}
+
+#[tauri::command]
+pub fn collections_of_video(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+) -> Result<Vec<Collection>, TranscriptError> {
+    insighttube_core::collections::collections_of_video(&db, video_id)
+}
+
+/// Summarizes every video of a collection, one at a time, then writes an overview
+/// of the whole collection from those summaries. A video that fails to load or
+/// summarize gets an error entry and the rest carry on. With `operation_id`,
+/// progress events report how many are done and `cancel_operation` stops the run.
+#[tauri::command]
+pub async fn summarize_collection(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    id: i64,
+    provider: ProviderKind,
+    options: Option<SummarizeOptions>,
+    operation_id: Option<String>,
+) -> Result<CollectionSummary, TranscriptError> {
+    let collection = get(&db, id)?;
+    let operation = operations.start(operation_id.as_deref());
+    let progress = progress::emitting(&app, operation_id);
+    let result = insighttube_core::collections::summarize_collection(
+        &db,
+        id,
+        provider,
+        options,
+        &progress,
+        operation.token(),
+    )
+    .await;
+    if !operation.token().is_cancelled() {
+        crate::notifications::finished(&app, "Collection summary", &result, |summary| {
+            let summarized = summary.videos.iter().filter(|v| v.summary.is_some());
+            format!(
+                "Summarized {} of {} videos of \"{}\".",
+                summarized.count(),
+                summary.videos.len(),
+                collection.name
+            )
+        });
+    }
+    result
+}
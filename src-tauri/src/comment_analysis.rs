@@ -0,0 +1,31 @@
+//! The commands of [`insighttube_core::comment_analysis`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+use crate::progress;
+pub(crate) use insighttube_core::comment_analysis::*;
+
+#[tauri::command]
+pub async fn analyze_comments(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<CommentAnalysisOptions>,
+    operation_id: Option<String>,
+) -> Result<CommentAnalysis, TranscriptError> {
+    let progress = progress::emitting(&app, operation_id.clone());
+    insighttube_core::comment_analysis::analyze_comments(
+        &db,
+        &operations,
+        video_id,
+        provider,
+        options,
+        operation_id,
+        &progress,
+    )
+    .await
+}
@@ -0,0 +1,13 @@
+//! The commands of [`insighttube_core::comments`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::comments::*;
+
+#[tauri::command]
+pub async fn fetch_comments(
+    video_id: String,
+    sort: Option<CommentSort>,
+    limit: Option<usize>,
+) -> Result<Vec<Comment>, TranscriptError> {
+    insighttube_core::comments::fetch_comments(video_id, sort, limit).await
+}
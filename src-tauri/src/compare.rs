@@ -0,0 +1,34 @@
+//! The commands of [`insighttube_core::compare`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+use crate::progress;
+pub(crate) use insighttube_core::compare::*;
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn compare_videos(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_ids: Vec<String>,
+    question: String,
+    provider: ProviderKind,
+    options: Option<CompareOptions>,
+    operation_id: Option<String>,
+) -> Result<Comparison, TranscriptError> {
+    let progress = progress::emitting(&app, operation_id.clone());
+    insighttube_core::compare::compare_videos(
+        &db,
+        &operations,
+        video_ids,
+        question,
+        provider,
+        options,
+        operation_id,
+        &progress,
+    )
+    .await
+}
@@ -0,0 +1,23 @@
+//! The commands of [`insighttube_core::cookies`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::cookies::*;
+
+/// Imports a cookies.txt or JSON cookie export from `path`, replacing the current jar.
+#[tauri::command]
+pub fn import_cookies(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<CookieStatus, TranscriptError> {
+    insighttube_core::cookies::import_cookies(&crate::settings::data_dir(&app)?, &path)
+}
+
+#[tauri::command]
+pub fn clear_cookies(app: tauri::AppHandle) -> Result<(), TranscriptError> {
+    insighttube_core::cookies::clear_cookies(&crate::settings::data_dir(&app)?)
+}
+
+#[tauri::command]
+pub fn get_cookie_status() -> CookieStatus {
+    insighttube_core::cookies::get_cookie_status()
+}
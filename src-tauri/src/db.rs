@@ -0,0 +1,17 @@
+//! The commands of [`insighttube_core::db`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::db::*;
+
+#[tauri::command]
+pub fn get_db_info(db: tauri::State<'_, Database>) -> Result<DbInfo, TranscriptError> {
+    insighttube_core::db::get_db_info(&db)
+}
+
+#[tauri::command]
+pub fn set_database_encryption(
+    db: tauri::State<'_, Database>,
+    enabled: bool,
+) -> Result<(), TranscriptError> {
+    insighttube_core::db::set_database_encryption(&db, enabled)
+}
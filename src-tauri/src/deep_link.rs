@@ -0,0 +1,156 @@
+//! `insighttube://` links, so a browser bookmarklet or extension can hand the app
+//! the video being watched.
+//!
+//! `insighttube://analyze?v=VIDEO` (or `?url=` with a whole YouTube URL) queues a
+//! transcript job for the video, or a summary job with `&summarize=1`, then brings
+//! the app window to the front. Each link is announced with a `deep-link` event
+//! carrying the video and its job, for the window to open the video. A second
+//! launch by the operating system is turned into a link for the running app by the
+//! single-instance plugin, so links never start a second copy.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::jobs::{self, Job, JobKind, JobQueue};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+const SCHEME: &str = "insighttube";
+const DEEP_LINK_EVENT: &str = "deep-link";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Analyze {
+    video_id: String,
+    summarize: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DeepLinkOpened {
+    video_id: String,
+    /// The queued job; `None` when the same job was already waiting or running.
+    job: Option<Job>,
+}
+
+fn invalid(link: &str) -> TranscriptError {
+    TranscriptError::InvalidInput(format!("Unsupported link \"{}\".", link))
+}
+
+/// Reads an `insighttube://analyze` link.
+fn parse(link: &str) -> Result<Analyze, TranscriptError> {
+    let url = reqwest::Url::parse(link).map_err(|_| invalid(link))?;
+    // `insighttube://analyze?…` has the action as its host, `insighttube:analyze?…`
+    // as its path.
+    let action = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| url.path())
+        .trim_matches('/');
+    if url.scheme() != SCHEME || !action.eq_ignore_ascii_case("analyze") {
+        return Err(invalid(link));
+    }
+
+    let mut video = None;
+    let mut summarize = false;
+    for (name, value) in url.query_pairs() {
+        match name.as_ref() {
+            "v" | "url" => video = Some(value.into_owned()),
+            "summarize" => summarize = matches!(value.as_ref(), "1" | "true"),
+            _ => {}
+        }
+    }
+    let video = video.ok_or_else(|| {
+        TranscriptError::InvalidInput(format!("The link \"{}\" names no video.", link))
+    })?;
+    Ok(Analyze {
+        video_id: crate::video_id::parse(&video)?,
+        summarize,
+    })
+}
+
+/// Shows the app window, restored and in front.
+pub(crate) fn focus(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open(app: &tauri::AppHandle, link: &str) -> Result<(), TranscriptError> {
+    let analyze = parse(link)?;
+    let kind = if analyze.summarize {
+        JobKind::Summary
+    } else {
+        JobKind::Transcript
+    };
+    let job = jobs::enqueue(&app.state::<Database>(), kind, &analyze.video_id)?;
+    app.state::<JobQueue>().wake();
+    let opened = DeepLinkOpened {
+        video_id: analyze.video_id,
+        job,
+    };
+    let _ = app.emit(DEEP_LINK_EVENT, &opened);
+    Ok(())
+}
+
+fn handle<'a>(app: &tauri::AppHandle, links: impl IntoIterator<Item = &'a str>) {
+    for link in links {
+        if let Err(e) = open(app, link) {
+            tracing::warn!(link, error = %e, "deep link ignored");
+        }
+    }
+    focus(app);
+}
+
+/// Handles links opened from now on, and the one the app was launched with.
+pub(crate) fn start(app: &tauri::AppHandle) {
+    let deep_link = app.deep_link();
+    // Installers register the scheme; this covers development builds and AppImages.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = deep_link.register_all() {
+        tracing::warn!(error = %e, "could not register the insighttube:// scheme");
+    }
+
+    let handle_app = app.clone();
+    deep_link.on_open_url(move |event| {
+        handle(&handle_app, event.urls().iter().map(|u| u.as_str()));
+    });
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        handle(app, urls.iter().map(|u| u.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_analyze_links() {
+        let analyze = |video_id: &str, summarize| Analyze {
+            video_id: video_id.into(),
+            summarize,
+        };
+        assert_eq!(
+            parse("insighttube://analyze?v=dQw4w9WgXcQ").unwrap(),
+            analyze("dQw4w9WgXcQ", false)
+        );
+        assert_eq!(
+            parse("insighttube://analyze/?v=dQw4w9WgXcQ&summarize=1").unwrap(),
+            analyze("dQw4w9WgXcQ", true)
+        );
+        assert_eq!(
+            parse("insighttube:analyze?url=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3DdQw4w9WgXcQ%26t%3D42")
+                .unwrap(),
+            analyze("dQw4w9WgXcQ", false)
+        );
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        assert!(parse("insighttube://analyze").is_err());
+        assert!(parse("insighttube://delete?v=dQw4w9WgXcQ").is_err());
+        assert!(parse("https://analyze?v=dQw4w9WgXcQ").is_err());
+        assert!(parse("insighttube://analyze?v=https://example.com/watch").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::diagnostics`].
+
+use crate::error::TranscriptError;
+
+/// Writes a zip to `path` with the recent logs, the redacted settings, the app and
+/// OS versions, and the last failed requests, to attach to a bug report.
+#[tauri::command]
+pub fn export_diagnostics(app: tauri::AppHandle, path: String) -> Result<(), TranscriptError> {
+    insighttube_core::diagnostics::export_diagnostics(
+        &path,
+        app.package_info().version.to_string(),
+        tauri::VERSION,
+    )
+}
@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::diff`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::diff::*;
+
+#[tauri::command]
+pub async fn diff_transcripts(
+    db: tauri::State<'_, Database>,
+    video_id_a: String,
+    video_id_b: String,
+) -> Result<TranscriptDiff, TranscriptError> {
+    insighttube_core::diff::diff_transcripts(&db, video_id_a, video_id_b).await
+}
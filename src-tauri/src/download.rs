@@ -0,0 +1,30 @@
+//! The commands of [`insighttube_core::download`].
+
+use crate::error::TranscriptError;
+use crate::operations::Operations;
+use crate::progress;
+pub(crate) use insighttube_core::download::*;
+
+/// Downloads the best audio-only stream of `video_id` to `path`. Progress events
+/// carry `operation_id`, the video ID when none is given, and `cancel_operation`
+/// with the same ID stops the download.
+#[tauri::command]
+pub async fn download_audio(
+    app: tauri::AppHandle,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<DownloadResult, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let operation_id = operation_id.unwrap_or_else(|| video_id.clone());
+    let progress = progress::emitting(&app, Some(operation_id.clone()));
+    insighttube_core::download::download_audio(
+        &operations,
+        &video_id,
+        path,
+        &operation_id,
+        &progress,
+    )
+    .await
+}
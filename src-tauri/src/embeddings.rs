@@ -0,0 +1,25 @@
+//! The commands of [`insighttube_core::embeddings`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+pub(crate) use insighttube_core::embeddings::*;
+
+#[tauri::command]
+pub async fn embed_transcript(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    provider: ProviderKind,
+) -> Result<EmbeddingStatus, TranscriptError> {
+    insighttube_core::embeddings::embed_transcript(&db, video_id, provider).await
+}
+
+#[tauri::command]
+pub async fn semantic_search(
+    db: tauri::State<'_, Database>,
+    query: String,
+    provider: ProviderKind,
+    options: Option<SemanticSearchOptions>,
+) -> Result<Vec<SemanticResult>, TranscriptError> {
+    insighttube_core::embeddings::semantic_search(&db, query, provider, options).await
+}
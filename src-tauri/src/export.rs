@@ -0,0 +1,31 @@
+//! The commands of [`insighttube_core::export`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::export::*;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+#[tauri::command]
+pub async fn export_transcript(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    format: ExportFormat,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<(), TranscriptError> {
+    insighttube_core::export::export_transcript(&db, video_id, format, path, options).await
+}
+
+/// Copies the transcript of `video_id` to the system clipboard.
+#[tauri::command]
+pub async fn copy_transcript_to_clipboard(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<ClipboardOptions>,
+) -> Result<(), TranscriptError> {
+    let text = insighttube_core::export::clipboard_transcript(&db, video_id, options).await?;
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| TranscriptError::FileError(format!("Could not copy to the clipboard: {}", e)))
+}
@@ -0,0 +1,38 @@
+//! The commands of [`insighttube_core::flashcards`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::ProviderKind;
+use crate::operations::Operations;
+pub(crate) use insighttube_core::flashcards::*;
+
+#[tauri::command]
+pub async fn generate_flashcards(
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    provider: ProviderKind,
+    count: Option<usize>,
+    options: Option<FlashcardOptions>,
+    operation_id: Option<String>,
+) -> Result<Vec<Flashcard>, TranscriptError> {
+    insighttube_core::flashcards::generate_flashcards(
+        &db,
+        &operations,
+        video_id,
+        provider,
+        count,
+        options,
+        operation_id,
+    )
+    .await
+}
+
+#[tauri::command]
+pub fn export_flashcards(
+    video_id: String,
+    cards: Vec<Flashcard>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    insighttube_core::flashcards::export_flashcards(video_id, cards, path)
+}
@@ -0,0 +1,38 @@
+//! The commands of [`insighttube_core::highlights`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::highlights::*;
+
+#[tauri::command]
+pub async fn add_highlight(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    start: f64,
+    end: f64,
+) -> Result<Highlight, TranscriptError> {
+    insighttube_core::highlights::add_highlight(&db, video_id, start, end).await
+}
+
+#[tauri::command]
+pub fn remove_highlight(db: tauri::State<'_, Database>, id: i64) -> Result<bool, TranscriptError> {
+    insighttube_core::highlights::remove_highlight(&db, id)
+}
+
+#[tauri::command]
+pub fn list_highlights(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+) -> Result<Vec<Highlight>, TranscriptError> {
+    insighttube_core::highlights::list_highlights(&db, video_id)
+}
+
+#[tauri::command]
+pub fn export_highlights(
+    db: tauri::State<'_, Database>,
+    video_id: Option<String>,
+    collection_id: Option<i64>,
+    path: String,
+) -> Result<(), TranscriptError> {
+    insighttube_core::highlights::export_highlights(&db, video_id, collection_id, path)
+}
@@ -0,0 +1,45 @@
+//! The commands of [`insighttube_core::history`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::history::*;
+
+#[tauri::command]
+pub fn list_history(
+    db: tauri::State<'_, Database>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, TranscriptError> {
+    insighttube_core::history::list_history(&db, limit, offset)
+}
+
+#[tauri::command]
+pub fn search_history(
+    db: tauri::State<'_, Database>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, TranscriptError> {
+    insighttube_core::history::search_history(&db, query, limit)
+}
+
+#[tauri::command]
+pub fn set_history_pinned(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    pinned: bool,
+) -> Result<bool, TranscriptError> {
+    insighttube_core::history::set_history_pinned(&db, video_id, pinned)
+}
+
+#[tauri::command]
+pub fn delete_history_entries(
+    db: tauri::State<'_, Database>,
+    video_ids: Vec<String>,
+) -> Result<u64, TranscriptError> {
+    insighttube_core::history::delete_history_entries(&db, video_ids)
+}
+
+#[tauri::command]
+pub fn clear_history(db: tauri::State<'_, Database>) -> Result<u64, TranscriptError> {
+    insighttube_core::history::clear_history(&db)
+}
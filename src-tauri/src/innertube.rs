@@ -0,0 +1,15 @@
+//! Keeping the client versions of [`insighttube_core::innertube`] current while the
+//! app runs.
+
+use crate::http;
+pub(crate) use insighttube_core::innertube::*;
+
+/// Brings the client versions up to date in the background, so startup does not
+/// wait for the scrape.
+pub(crate) fn refresh_versions_in_background() {
+    tauri::async_runtime::spawn(async {
+        if let Ok(client) = http::build_client() {
+            versions::refresh(&client).await;
+        }
+    });
+}
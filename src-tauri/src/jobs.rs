@@ -0,0 +1,151 @@
+//! The commands of [`insighttube_core::jobs`], and the workers that run the queue
+//! while the app runs.
+
+use crate::activity;
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::jobs::*;
+use std::sync::atomic::Ordering;
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+const JOB_EVENT: &str = "job-updated";
+
+fn notify(app: &tauri::AppHandle, job: Option<Job>) {
+    if let Some(job) = job {
+        let _ = app.emit(JOB_EVENT, job);
+    }
+}
+
+/// Once the queue has run empty, announces how the jobs since it last did went.
+fn announce_if_drained(app: &tauri::AppHandle, db: &Database, queue: &JobQueue) {
+    if queue.active() > 0 || has_pending(db) {
+        return;
+    }
+    let done = queue.done.swap(0, Ordering::Relaxed);
+    let failed = queue.failed.swap(0, Ordering::Relaxed);
+    let body = match (done, failed) {
+        (0, 0) => return,
+        (done, 0) => format!("{} jobs done.", done),
+        (done, failed) => format!("{} jobs done, {} failed.", done, failed),
+    };
+    crate::notifications::notify(app, "Background jobs finished", &body);
+}
+
+async fn run_claimed(app: tauri::AppHandle, job: Job, cancel: CancellationToken) {
+    let db = app.state::<Database>();
+    let queue = app.state::<JobQueue>();
+    let result = run(&db, &job, &cancel).await;
+    if let Ok(mut running) = queue.running.lock() {
+        running.remove(&job.id);
+    }
+    match &result {
+        Ok(()) => {
+            queue.done.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(TranscriptError::Cancelled) => {}
+        Err(e) => {
+            queue.failed.fetch_add(1, Ordering::Relaxed);
+            let video_id = (job.kind != JobKind::Playlist).then_some(job.input.as_str());
+            activity::failed(&db, video_id, job.kind.task(), e);
+        }
+    }
+    notify(&app, finish(&db, job.id, &result).ok().flatten());
+    announce_if_drained(&app, &db, &queue);
+    queue.wake.notify_one();
+}
+
+/// Requeues interrupted jobs and starts dispatching pending ones, for the lifetime
+/// of the app.
+pub(crate) fn start(app: &tauri::AppHandle) -> Result<(), TranscriptError> {
+    requeue_interrupted(&app.state::<Database>())?;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let db = app.state::<Database>();
+        let queue = app.state::<JobQueue>();
+        loop {
+            while queue.active() < queue.parallelism.load(Ordering::Relaxed) {
+                let Ok(Some(job)) = claim(&db) else { break };
+                let cancel = CancellationToken::new();
+                if let Ok(mut running) = queue.running.lock() {
+                    running.insert(job.id, cancel.clone());
+                }
+                notify(&app, Some(job.clone()));
+                tauri::async_runtime::spawn(run_claimed(app.clone(), job, cancel));
+            }
+            let _ = tokio::time::timeout(POLL_INTERVAL, queue.wake.notified()).await;
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn enqueue_jobs(
+    db: tauri::State<'_, Database>,
+    queue: tauri::State<'_, JobQueue>,
+    kind: JobKind,
+    inputs: Vec<String>,
+) -> Result<Vec<Job>, TranscriptError> {
+    insighttube_core::jobs::enqueue_jobs(&db, &queue, kind, inputs)
+}
+
+#[tauri::command]
+pub fn list_jobs(
+    db: tauri::State<'_, Database>,
+    status: Option<JobStatus>,
+    limit: Option<usize>,
+) -> Result<Vec<Job>, TranscriptError> {
+    insighttube_core::jobs::list_jobs(&db, status, limit)
+}
+
+/// Queues a failed or cancelled job again.
+#[tauri::command]
+pub fn retry_job(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    queue: tauri::State<'_, JobQueue>,
+    id: i64,
+) -> Result<Job, TranscriptError> {
+    let job = set_status(
+        &db,
+        id,
+        JobStatus::Pending,
+        None,
+        &[JobStatus::Failed, JobStatus::Cancelled],
+    )?
+    .ok_or_else(|| {
+        TranscriptError::InvalidInput("Only failed or cancelled jobs can be retried.".into())
+    })?;
+    notify(&app, Some(job.clone()));
+    queue.wake.notify_one();
+    Ok(job)
+}
+
+/// Cancels a pending or running job. Returns whether there was one to cancel.
+#[tauri::command]
+pub fn cancel_job(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    queue: tauri::State<'_, JobQueue>,
+    id: i64,
+) -> Result<bool, TranscriptError> {
+    let token = queue.running.lock().ok().and_then(|r| r.get(&id).cloned());
+    if let Some(token) = token {
+        // The worker marks the job cancelled once it stops
+        token.cancel();
+        return Ok(true);
+    }
+    let job = set_status(&db, id, JobStatus::Cancelled, None, &[JobStatus::Pending])?;
+    let cancelled = job.is_some();
+    notify(&app, job);
+    Ok(cancelled)
+}
+
+/// Sets how many jobs run at the same time.
+#[tauri::command]
+pub fn set_job_parallelism(
+    app: tauri::AppHandle,
+    parallelism: usize,
+) -> Result<(), TranscriptError> {
+    crate::settings::update(&app, |s| s.job_parallelism = parallelism).map(|_| ())
+}
@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::keywords`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::keywords::*;
+
+#[tauri::command]
+pub async fn extract_keywords(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<KeywordOptions>,
+) -> Result<Vec<Keyword>, TranscriptError> {
+    insighttube_core::keywords::extract_keywords(&db, video_id, options).await
+}
@@ -1,14 +1,232 @@
+mod action_items;
+mod activity;
+mod api_server;
+mod ask;
+mod auto_ingest;
+mod batch;
+mod cache;
+mod channel;
+mod chapter_summaries;
+mod chapters;
+mod collections;
+mod comment_analysis;
+mod comments;
+mod compare;
+mod cookies;
+mod db;
+mod deep_link;
+mod diagnostics;
+mod diff;
+mod download;
+mod embeddings;
+mod export;
+mod flashcards;
+mod highlights;
+mod history;
+mod innertube;
+mod jobs;
+mod keywords;
+mod llm;
+mod logging;
+mod metadata;
+mod metrics;
+mod notes;
+mod notifications;
+mod notion;
+mod obsidian;
+mod operations;
+mod outline;
+mod playlist;
+mod progress;
+mod prompt;
+mod proxy;
+mod punctuate;
+mod quick_add;
+mod quotes;
+mod rate_limit;
+mod readwise;
+mod related;
+mod report;
+mod search;
+mod secrets;
+mod settings;
+mod stats;
+mod storage;
+mod subscriptions;
+mod summarize;
 mod transcript;
+mod translate;
+mod video_list;
+mod webhook;
+mod youtube_search;
+
+use insighttube_core::{error, http, video_id};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+    // Registered first: a second launch, such as one opening an insighttube:// link,
+    // hands its link to this instance and exits.
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            deep_link::focus(app)
+        }));
+    }
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
+            logging::init(&data_dir);
+            app.manage(db::Database::open(&data_dir.join("insighttube.db"))?);
+            app.manage(operations::Operations::default());
+            app.manage(jobs::JobQueue::default());
+
+            let store = app.store("settings.json")?;
+            let mut legacy = store.get("app_settings");
+            settings::load(app.handle(), &data_dir, legacy.as_ref());
+            cookies::load(&data_dir);
+            secrets::load(&data_dir);
+            if let Some(legacy) = legacy.as_mut() {
+                if secrets::migrate_settings(legacy) {
+                    store.set("app_settings", legacy.clone());
+                    let _ = store.save();
+                }
+            }
+            innertube::refresh_versions_in_background();
+            jobs::start(app.handle())?;
+            auto_ingest::start(app.handle());
+            deep_link::start(app.handle());
+            quick_add::start(app.handle());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                quick_add::dropped(window.app_handle(), paths);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             transcript::fetch_transcript,
-            transcript::fetch_video_info
+            transcript::fetch_transcript_v2,
+            transcript::fetch_transcript_paragraphs,
+            transcript::fetch_transcript_with_fallback,
+            transcript::is_local_transcription_available,
+            transcript::list_available_transcripts,
+            transcript::fetch_translated_transcript,
+            transcript::fetch_transcript_with_metadata,
+            transcript::fetch_video_info,
+            metadata::fetch_video_metadata,
+            chapters::fetch_chapters,
+            cache::clear_transcript_cache,
+            cache::get_cache_stats,
+            batch::fetch_transcripts_batch,
+            batch::fetch_playlist_transcripts,
+            batch::check_transcript_availability,
+            playlist::fetch_playlist_videos,
+            channel::fetch_channel_videos,
+            rate_limit::get_rate_limit,
+            rate_limit::set_rate_limit,
+            proxy::set_proxy,
+            proxy::test_proxy_connection,
+            cookies::import_cookies,
+            cookies::clear_cookies,
+            cookies::get_cookie_status,
+            export::export_transcript,
+            export::copy_transcript_to_clipboard,
+            search::search_transcripts,
+            search::search_in_transcript,
+            download::download_audio,
+            secrets::set_secret,
+            secrets::delete_secret,
+            secrets::get_api_key_status,
+            summarize::summarize_transcript,
+            llm::ollama::set_ollama_config,
+            llm::ollama::list_local_models,
+            llm::list_models,
+            ask::ask_transcript,
+            prompt::complete_prompt,
+            embeddings::embed_transcript,
+            embeddings::semantic_search,
+            keywords::extract_keywords,
+            quotes::extract_quotes,
+            translate::translate_transcript,
+            operations::cancel_operation,
+            jobs::enqueue_jobs,
+            jobs::list_jobs,
+            jobs::retry_job,
+            jobs::cancel_job,
+            jobs::set_job_parallelism,
+            settings::get_settings,
+            settings::update_settings,
+            history::list_history,
+            history::search_history,
+            history::set_history_pinned,
+            history::delete_history_entries,
+            history::clear_history,
+            collections::create_collection,
+            collections::list_collections,
+            collections::update_collection,
+            collections::delete_collection,
+            collections::add_to_collection,
+            collections::remove_from_collection,
+            collections::list_collection_videos,
+            collections::collections_of_video,
+            collections::summarize_collection,
+            notes::create_note,
+            notes::update_note,
+            notes::delete_note,
+            notes::list_notes,
+            highlights::add_highlight,
+            highlights::remove_highlight,
+            highlights::list_highlights,
+            highlights::export_highlights,
+            comments::fetch_comments,
+            comment_analysis::analyze_comments,
+            youtube_search::search_youtube,
+            related::fetch_related_videos,
+            subscriptions::subscribe_channel,
+            subscriptions::subscribe_playlist,
+            subscriptions::unsubscribe,
+            subscriptions::set_subscription_auto_ingest,
+            subscriptions::list_subscriptions,
+            subscriptions::list_subscription_videos,
+            subscriptions::check_subscriptions,
+            auto_ingest::get_ingested_summary,
+            auto_ingest::list_ingested_summaries,
+            logging::get_recent_logs,
+            logging::set_log_level,
+            diagnostics::export_diagnostics,
+            metrics::get_metrics,
+            diff::diff_transcripts,
+            stats::transcript_stats,
+            punctuate::punctuate_transcript,
+            chapter_summaries::summarize_by_chapter,
+            outline::generate_outline,
+            flashcards::generate_flashcards,
+            flashcards::export_flashcards,
+            action_items::extract_action_items,
+            action_items::export_action_items,
+            compare::compare_videos,
+            report::export_report,
+            obsidian::export_to_obsidian,
+            notion::export_to_notion,
+            readwise::sync_readwise,
+            webhook::test_webhook,
+            api_server::regenerate_api_token,
+            video_list::import_video_list,
+            db::get_db_info,
+            db::set_database_encryption,
+            storage::get_storage_usage,
+            activity::get_activity,
+            llm::usage::get_llm_usage
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
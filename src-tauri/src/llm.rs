@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::llm`], and streaming its completions to the
+//! frontend.
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::llm::*;
+
+pub(crate) mod ollama;
+pub(crate) mod stream;
+pub(crate) mod usage;
+
+#[tauri::command]
+pub async fn list_models(provider: ProviderKind) -> Result<Vec<ModelInfo>, TranscriptError> {
+    insighttube_core::llm::list_models(provider).await
+}
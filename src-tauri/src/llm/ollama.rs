@@ -0,0 +1,19 @@
+//! The commands of [`insighttube_core::llm::ollama`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::llm::ollama::*;
+
+#[tauri::command]
+pub fn set_ollama_config(
+    app: tauri::AppHandle,
+    config: Option<OllamaConfig>,
+) -> Result<(), TranscriptError> {
+    crate::settings::update(&app, |s| s.ollama = config.unwrap_or_default()).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn list_local_models(
+    base_url: Option<String>,
+) -> Result<Vec<LocalModel>, TranscriptError> {
+    insighttube_core::llm::ollama::list_local_models(base_url).await
+}
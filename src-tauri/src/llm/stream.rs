@@ -0,0 +1,64 @@
+//! Streaming completions to the frontend as `llm-chunk` events, and cancelling them.
+//!
+//! A command that streams takes a `request_id` chosen by the frontend. Every piece
+//! of generated text is emitted with that ID, followed by a final `done` event, and
+//! `cancel_operation` with the same ID aborts the command.
+
+use super::OnChunk;
+use crate::error::TranscriptError;
+use crate::operations::Operations;
+use serde::Serialize;
+use std::future::Future;
+use tauri::Emitter;
+
+const CHUNK_EVENT: &str = "llm-chunk";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LlmChunk<'a> {
+    request_id: &'a str,
+    delta: &'a str,
+    done: bool,
+}
+
+/// A callback emitting each delta of `request_id` as an `llm-chunk` event.
+pub(crate) fn emitter(app: &tauri::AppHandle, request_id: &str) -> Box<OnChunk<'static>> {
+    let app = app.clone();
+    let request_id = request_id.to_string();
+    Box::new(move |delta: &str| {
+        let _ = app.emit(
+            CHUNK_EVENT,
+            LlmChunk {
+                request_id: &request_id,
+                delta,
+                done: false,
+            },
+        );
+    })
+}
+
+/// Runs `task`, letting `cancel_operation(request_id)` abort it. Without a request
+/// ID the task simply runs to completion. Otherwise the stream is closed with a
+/// `done` event once the task ends.
+pub(crate) async fn cancellable<T>(
+    app: &tauri::AppHandle,
+    operations: &Operations,
+    request_id: Option<&str>,
+    task: impl Future<Output = Result<T, TranscriptError>>,
+) -> Result<T, TranscriptError> {
+    let Some(request_id) = request_id else {
+        return task.await;
+    };
+
+    let result = operations.start(Some(request_id)).run(task).await;
+
+    let _ = app.emit(
+        CHUNK_EVENT,
+        LlmChunk {
+            request_id,
+            delta: "",
+            done: true,
+        },
+    );
+    result
+}
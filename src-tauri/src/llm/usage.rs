@@ -0,0 +1,13 @@
+//! The commands of [`insighttube_core::llm::usage`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::llm::usage::*;
+
+#[tauri::command]
+pub fn get_llm_usage(
+    db: tauri::State<'_, Database>,
+    period: Option<UsagePeriod>,
+) -> Result<LlmUsage, TranscriptError> {
+    insighttube_core::llm::usage::get_llm_usage(&db, period)
+}
@@ -0,0 +1,15 @@
+//! The commands of [`insighttube_core::logging`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::logging::*;
+
+#[tauri::command]
+pub fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, TranscriptError> {
+    insighttube_core::logging::get_recent_logs(lines)
+}
+
+/// Sets how detailed the logs are, from `error` to `trace`.
+#[tauri::command]
+pub fn set_log_level(app: tauri::AppHandle, level: LogLevel) -> Result<(), TranscriptError> {
+    crate::settings::update(&app, |s| s.log_level = level).map(|_| ())
+}
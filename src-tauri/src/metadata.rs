@@ -0,0 +1,9 @@
+//! The commands of [`insighttube_core::metadata`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::metadata::*;
+
+#[tauri::command]
+pub async fn fetch_video_metadata(video_id: String) -> Result<VideoMetadata, TranscriptError> {
+    insighttube_core::metadata::fetch_video_metadata(video_id).await
+}
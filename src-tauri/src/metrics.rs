@@ -0,0 +1,8 @@
+//! The commands of [`insighttube_core::metrics`].
+
+pub(crate) use insighttube_core::metrics::*;
+
+#[tauri::command]
+pub fn get_metrics() -> Vec<ClientMetrics> {
+    insighttube_core::metrics::get_metrics()
+}
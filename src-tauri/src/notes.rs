@@ -0,0 +1,38 @@
+//! The commands of [`insighttube_core::notes`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::notes::*;
+
+#[tauri::command]
+pub fn create_note(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    offset: f64,
+    text: String,
+) -> Result<Note, TranscriptError> {
+    insighttube_core::notes::create_note(&db, video_id, offset, text)
+}
+
+#[tauri::command]
+pub fn update_note(
+    db: tauri::State<'_, Database>,
+    id: i64,
+    text: Option<String>,
+    offset: Option<f64>,
+) -> Result<Note, TranscriptError> {
+    insighttube_core::notes::update_note(&db, id, text, offset)
+}
+
+#[tauri::command]
+pub fn delete_note(db: tauri::State<'_, Database>, id: i64) -> Result<bool, TranscriptError> {
+    insighttube_core::notes::delete_note(&db, id)
+}
+
+#[tauri::command]
+pub fn list_notes(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+) -> Result<Vec<Note>, TranscriptError> {
+    insighttube_core::notes::list_notes(&db, video_id)
+}
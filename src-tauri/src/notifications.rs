@@ -0,0 +1,41 @@
+//! Desktop notifications for long-running work that finishes or fails.
+//!
+//! They are sent from the backend, which knows when batches, transcriptions and
+//! summaries end, even when nothing in the frontend is waiting on them (queued
+//! jobs, auto-ingestion). Nothing is sent while one of the app's windows has
+//! focus, as the outcome is on screen already, nor when the `notifications`
+//! setting is off.
+
+use crate::error::TranscriptError;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+fn app_has_focus(app: &tauri::AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+/// Shows a notification, unless turned off or the app has focus. Failing to show
+/// one is not worth surfacing.
+pub(crate) fn notify(app: &tauri::AppHandle, title: &str, body: &str) {
+    if !crate::settings::current().notifications || app_has_focus(app) {
+        return;
+    }
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+/// Announces the outcome of `task`: a result as described by `success`, or the
+/// error. Cancelled work is not announced, since the user stopped it.
+pub(crate) fn finished<T>(
+    app: &tauri::AppHandle,
+    task: &str,
+    result: &Result<T, TranscriptError>,
+    success: impl FnOnce(&T) -> String,
+) {
+    match result {
+        Ok(value) => notify(app, &format!("{} finished", task), &success(value)),
+        Err(TranscriptError::Cancelled) => {}
+        Err(e) => notify(app, &format!("{} failed", task), &e.to_string()),
+    }
+}
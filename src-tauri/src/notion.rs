@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::notion`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::notion::*;
+
+#[tauri::command]
+pub async fn export_to_notion(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<NotionOptions>,
+) -> Result<String, TranscriptError> {
+    insighttube_core::notion::export_to_notion(&db, video_id, options).await
+}
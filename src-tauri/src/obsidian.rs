@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::obsidian`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::obsidian::*;
+
+#[tauri::command]
+pub async fn export_to_obsidian(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<ObsidianOptions>,
+) -> Result<String, TranscriptError> {
+    insighttube_core::obsidian::export_to_obsidian(&db, video_id, options).await
+}
@@ -0,0 +1,8 @@
+//! The commands of [`insighttube_core::operations`].
+
+pub(crate) use insighttube_core::operations::*;
+
+#[tauri::command]
+pub fn cancel_operation(operations: tauri::State<'_, Operations>, operation_id: String) -> bool {
+    insighttube_core::operations::cancel_operation(&operations, operation_id)
+}
@@ -0,0 +1,15 @@
+//! The commands of [`insighttube_core::outline`].
+
+use crate::chapters::Chapter;
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::outline::*;
+
+#[tauri::command]
+pub async fn generate_outline(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<OutlineOptions>,
+) -> Result<Vec<Chapter>, TranscriptError> {
+    insighttube_core::outline::generate_outline(&db, video_id, options).await
+}
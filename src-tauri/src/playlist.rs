@@ -0,0 +1,12 @@
+//! The commands of [`insighttube_core::playlist`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::playlist::*;
+
+#[tauri::command]
+pub async fn fetch_playlist_videos(
+    playlist_id: String,
+    continuation: Option<String>,
+) -> Result<PlaylistPage, TranscriptError> {
+    insighttube_core::playlist::fetch_playlist_videos(playlist_id, continuation).await
+}
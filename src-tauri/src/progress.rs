@@ -0,0 +1,19 @@
+//! Progress of long-running commands, reported as `progress` events.
+//!
+//! Commands that take a while accept an `operation_id` chosen by the frontend and
+//! tag every event with it.
+
+pub(crate) use insighttube_core::progress::*;
+use std::sync::Arc;
+use tauri::Emitter;
+
+const PROGRESS_EVENT: &str = "progress";
+
+/// Reports the progress of `operation_id` as `progress` events.
+pub(crate) fn emitting(app: &tauri::AppHandle, operation_id: Option<String>) -> Progress {
+    let app = app.clone();
+    let sink = move |event: &ProgressEvent| {
+        let _ = app.emit(PROGRESS_EVENT, event);
+    };
+    Progress::new(Arc::new(sink), operation_id)
+}
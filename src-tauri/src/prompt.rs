@@ -0,0 +1,26 @@
+//! The commands of [`insighttube_core::prompt`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+pub(crate) use insighttube_core::prompt::*;
+
+/// Completes `request` with `provider`. With `request_id` the text is streamed as
+/// `llm-chunk` events and can be cancelled.
+#[tauri::command]
+pub async fn complete_prompt(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    provider: ProviderKind,
+    request: PromptRequest,
+    request_id: Option<String>,
+) -> Result<String, TranscriptError> {
+    let on_chunk = request_id
+        .as_deref()
+        .map(|id| llm::stream::emitter(&app, id));
+    let text =
+        insighttube_core::prompt::complete_prompt(&db, provider, request, on_chunk.as_deref());
+    llm::stream::cancellable(&app, &operations, request_id.as_deref(), text).await
+}
@@ -0,0 +1,19 @@
+//! The commands of [`insighttube_core::proxy`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::proxy::*;
+
+#[tauri::command]
+pub fn set_proxy(
+    app: tauri::AppHandle,
+    config: Option<ProxyConfig>,
+) -> Result<(), TranscriptError> {
+    crate::settings::update(&app, |s| s.proxy = config).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn test_proxy_connection(
+    config: Option<ProxyConfig>,
+) -> Result<ProxyTestResult, TranscriptError> {
+    insighttube_core::proxy::test_proxy_connection(config).await
+}
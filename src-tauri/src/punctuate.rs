@@ -0,0 +1,25 @@
+//! The commands of [`insighttube_core::punctuate`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::operations::Operations;
+use crate::transcript::TranscriptSegment;
+pub(crate) use insighttube_core::punctuate::*;
+
+#[tauri::command]
+pub async fn punctuate_transcript(
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    options: Option<PunctuateOptions>,
+    operation_id: Option<String>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    insighttube_core::punctuate::punctuate_transcript(
+        &db,
+        &operations,
+        video_id,
+        options,
+        operation_id,
+    )
+    .await
+}
@@ -0,0 +1,167 @@
+//! Videos offered for one-click ingestion: YouTube links copied to the clipboard
+//! and lists of them dropped on the window.
+//!
+//! With `watch_clipboard` on, the clipboard is read every second; a newly copied
+//! YouTube link is announced with a `clipboard-video` event. A `.txt` file dropped
+//! on the window is read for one URL or ID per line, and the videos found are
+//! announced with a `videos-dropped` event. Nothing is queued here: the window
+//! offers the videos and enqueues the ones taken with `enqueue_jobs` (or the whole
+//! list with `import_video_list`).
+
+use crate::error::TranscriptError;
+use crate::jobs::JobKind;
+use crate::video_list;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::Emitter;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+const CLIPBOARD_EVENT: &str = "clipboard-video";
+const DROP_EVENT: &str = "videos-dropped";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Clipboard text longer than this is not taken for a link.
+const MAX_LINK_LEN: usize = 512;
+/// Largest dropped list read.
+const MAX_LIST_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ClipboardVideo {
+    video_id: String,
+    /// The link as copied.
+    url: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct DroppedVideos {
+    path: String,
+    /// Videos of the list, in order and without repeats.
+    video_ids: Vec<String>,
+    /// Lines that were neither blank nor a video.
+    invalid_lines: usize,
+}
+
+/// The video of `text` when it is a single YouTube link. Bare IDs are left out, as
+/// any copied 11-letter word would pass for one.
+fn copied_video(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.len() > MAX_LINK_LEN
+        || text.contains(char::is_whitespace)
+        || !text.to_ascii_lowercase().contains("youtu")
+    {
+        return None;
+    }
+    crate::video_id::parse(text).ok()
+}
+
+/// The videos of a list of one URL or ID per line, and how many lines were not one.
+fn listed_videos(text: &str) -> (Vec<String>, usize) {
+    let mut videos: Vec<String> = Vec::new();
+    let mut invalid = 0;
+    for entry in video_list::entries(text, JobKind::Transcript, None) {
+        match entry.id {
+            Ok(id) if !videos.contains(&id) => videos.push(id),
+            Ok(_) => {}
+            Err(_) => invalid += 1,
+        }
+    }
+    (videos, invalid)
+}
+
+fn read_list(path: &Path) -> Result<DroppedVideos, TranscriptError> {
+    let error = |e: std::io::Error| {
+        TranscriptError::FileError(format!("Could not read \"{}\": {}", path.display(), e))
+    };
+    let size = std::fs::metadata(path).map_err(error)?.len();
+    if size > MAX_LIST_BYTES {
+        return Err(TranscriptError::InvalidInput(format!(
+            "\"{}\" is too large for a list of videos.",
+            path.display()
+        )));
+    }
+    let text = std::fs::read_to_string(path).map_err(error)?;
+    let (video_ids, invalid_lines) = listed_videos(&text);
+    Ok(DroppedVideos {
+        path: path.display().to_string(),
+        video_ids,
+        invalid_lines,
+    })
+}
+
+/// Announces the videos of the `.txt` files among `paths` dropped on the window.
+pub(crate) fn dropped(app: &tauri::AppHandle, paths: &[PathBuf]) {
+    let lists = paths
+        .iter()
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("txt")));
+    for path in lists {
+        match read_list(path) {
+            Ok(dropped) if !dropped.video_ids.is_empty() => {
+                let _ = app.emit(DROP_EVENT, &dropped);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "dropped list not read"),
+        }
+    }
+}
+
+/// Watches the clipboard for as long as the app runs, while `watch_clipboard` is on.
+pub(crate) fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // What the clipboard held at the last look; `None` while not watching, so
+        // a link copied before the watch was turned on is not announced.
+        let mut last: Option<String> = None;
+        let mut ticks = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticks.tick().await;
+            if !crate::settings::current().watch_clipboard {
+                last = None;
+                continue;
+            }
+            let text = app.clipboard().read_text().unwrap_or_default();
+            if last.as_ref() == Some(&text) {
+                continue;
+            }
+            let announce = last.is_some();
+            last = Some(text.clone());
+            if let Some(video_id) = copied_video(&text).filter(|_| announce) {
+                let copied = ClipboardVideo {
+                    video_id,
+                    url: text.trim().to_string(),
+                };
+                let _ = app.emit(CLIPBOARD_EVENT, &copied);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_only_single_youtube_links_from_the_clipboard() {
+        assert_eq!(
+            copied_video(" https://youtu.be/dQw4w9WgXcQ?t=42\n").as_deref(),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(copied_video("dQw4w9WgXcQ"), None);
+        assert_eq!(
+            copied_video("see https://youtu.be/dQw4w9WgXcQ and more"),
+            None
+        );
+        assert_eq!(copied_video("https://youtube.com/@channel"), None);
+    }
+
+    #[test]
+    fn reads_lists_of_videos() {
+        let list = "# cooking\nhttps://www.youtube.com/watch?v=dQw4w9WgXcQ\n\n\
+                    9bZkp7q19f0\nhttps://youtu.be/dQw4w9WgXcQ\nnot a video\n";
+        assert_eq!(
+            listed_videos(list),
+            (vec!["dQw4w9WgXcQ".into(), "9bZkp7q19f0".into()], 1)
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::quotes`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::quotes::*;
+
+#[tauri::command]
+pub async fn extract_quotes(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<QuoteOptions>,
+) -> Result<Vec<Quote>, TranscriptError> {
+    insighttube_core::quotes::extract_quotes(&db, video_id, options).await
+}
@@ -0,0 +1,17 @@
+//! The commands of [`insighttube_core::rate_limit`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::rate_limit::*;
+
+#[tauri::command]
+pub fn get_rate_limit() -> RateLimitConfig {
+    insighttube_core::rate_limit::get_rate_limit()
+}
+
+#[tauri::command]
+pub fn set_rate_limit(
+    app: tauri::AppHandle,
+    config: RateLimitConfig,
+) -> Result<(), TranscriptError> {
+    crate::settings::update(&app, |s| s.rate_limit = config).map(|_| ())
+}
@@ -0,0 +1,13 @@
+//! The commands of [`insighttube_core::readwise`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::readwise::*;
+
+#[tauri::command]
+pub async fn sync_readwise(
+    db: tauri::State<'_, Database>,
+    video_id: Option<String>,
+) -> Result<ReadwiseSync, TranscriptError> {
+    insighttube_core::readwise::sync_readwise(&db, video_id).await
+}
@@ -0,0 +1,9 @@
+//! The commands of [`insighttube_core::related`].
+
+use crate::error::TranscriptError;
+use crate::youtube_search::SearchVideo;
+
+#[tauri::command]
+pub async fn fetch_related_videos(video_id: String) -> Result<Vec<SearchVideo>, TranscriptError> {
+    insighttube_core::related::fetch_related_videos(video_id).await
+}
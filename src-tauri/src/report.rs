@@ -0,0 +1,15 @@
+//! The commands of [`insighttube_core::report`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::report::*;
+
+#[tauri::command]
+pub async fn export_report(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    path: String,
+    options: Option<ReportOptions>,
+) -> Result<(), TranscriptError> {
+    insighttube_core::report::export_report(&db, video_id, path, options).await
+}
@@ -0,0 +1,24 @@
+//! The commands of [`insighttube_core::search`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::search::*;
+
+#[tauri::command]
+pub fn search_transcripts(
+    db: tauri::State<'_, Database>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, TranscriptError> {
+    insighttube_core::search::search_transcripts(&db, query, limit)
+}
+
+#[tauri::command]
+pub async fn search_in_transcript(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<TranscriptMatch>, TranscriptError> {
+    insighttube_core::search::search_in_transcript(&db, video_id, query, options).await
+}
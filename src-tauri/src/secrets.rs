@@ -0,0 +1,19 @@
+//! The commands of [`insighttube_core::secrets`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::secrets::*;
+
+#[tauri::command]
+pub fn set_secret(service: KeyedService, value: String) -> Result<(), TranscriptError> {
+    insighttube_core::secrets::set_secret(service, value)
+}
+
+#[tauri::command]
+pub fn delete_secret(service: KeyedService) -> Result<(), TranscriptError> {
+    insighttube_core::secrets::delete_secret(service)
+}
+
+#[tauri::command]
+pub fn get_api_key_status() -> Vec<KeyedService> {
+    insighttube_core::secrets::get_api_key_status()
+}
@@ -0,0 +1,100 @@
+//! Saving the settings of [`insighttube_core::settings`] from the app, and applying
+//! them to its state.
+//!
+//! Each change is saved and then broadcast as a `settings-changed` event carrying
+//! the full settings, so every open window sees the same values.
+
+use crate::api_server;
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::jobs::JobQueue;
+use crate::storage;
+pub(crate) use insighttube_core::settings::*;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+
+const SETTINGS_EVENT: &str = "settings-changed";
+
+/// Pushes `settings` to the modules that keep their own copy, the local API
+/// listening on `bound` from [`api_server::bind`].
+fn apply(
+    settings: &Settings,
+    app: &tauri::AppHandle,
+    bound: Option<api_server::Bound>,
+) -> Result<(), TranscriptError> {
+    settings.apply_headless()?;
+    app.state::<JobQueue>()
+        .set_parallelism(settings.job_parallelism);
+    if let Err(e) = storage::enforce(&app.state::<Database>(), None) {
+        tracing::warn!(error = %e, "cache limits not enforced");
+    }
+    api_server::apply(app, &settings.api_server, bound);
+    Ok(())
+}
+
+/// The app's data directory, where the settings, cookies and database are kept.
+pub(crate) fn data_dir(app: &tauri::AppHandle) -> Result<PathBuf, TranscriptError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| TranscriptError::FileError(format!("Could not locate app data: {}", e)))
+}
+
+/// Loads and applies the saved settings, falling back to `legacy` (the frontend's
+/// stored settings) on first run. Invalid values are replaced with their defaults
+/// so a bad file cannot keep the app from starting.
+pub(crate) fn load(app: &tauri::AppHandle, data_dir: &Path, legacy: Option<&Value>) {
+    let settings = read_saved(data_dir)
+        .or_else(|| legacy.map(from_legacy).filter(|s| s.validate().is_ok()))
+        .unwrap_or_default();
+    // A port in use does not keep the other settings from applying
+    let bound = api_server::bind(&settings.api_server).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "local API not started");
+        None
+    });
+    let _ = apply(&settings, app, bound);
+    replace(settings);
+}
+
+/// Applies, saves and broadcasts `settings`.
+fn store(app: &tauri::AppHandle, mut settings: Settings) -> Result<Settings, TranscriptError> {
+    settings.validate()?;
+    settings.job_parallelism = settings
+        .job_parallelism
+        .clamp(1, crate::jobs::MAX_PARALLELISM);
+    // Before anything is saved, so a port in use leaves the settings as they were
+    let bound = api_server::bind(&settings.api_server)?;
+    let contents = serde_json::to_string_pretty(&settings).unwrap_or_default();
+    std::fs::write(data_dir(app)?.join(SETTINGS_FILE), contents)
+        .map_err(|e| TranscriptError::FileError(format!("Could not save settings: {}", e)))?;
+    apply(&settings, app, bound)?;
+    replace(settings.clone());
+    let _ = app.emit(SETTINGS_EVENT, &settings);
+    Ok(settings)
+}
+
+/// Changes the settings through `change` and stores the result.
+pub(crate) fn update(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&mut Settings),
+) -> Result<Settings, TranscriptError> {
+    let mut settings = current();
+    change(&mut settings);
+    store(app, settings)
+}
+
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    current()
+}
+
+/// Updates the settings with `patch`, a partial settings object such as
+/// `{ "rateLimit": { "burst": 10 } }`, and returns the new settings.
+#[tauri::command]
+pub fn update_settings(app: tauri::AppHandle, patch: Value) -> Result<Settings, TranscriptError> {
+    let mut settings = serde_json::to_value(current()).unwrap_or_default();
+    merge(&mut settings, patch);
+    let settings = serde_json::from_value(settings)
+        .map_err(|e| TranscriptError::InvalidInput(format!("Invalid settings: {}", e)))?;
+    store(&app, settings)
+}
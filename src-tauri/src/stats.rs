@@ -0,0 +1,14 @@
+//! The commands of [`insighttube_core::stats`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::stats::*;
+
+#[tauri::command]
+pub async fn transcript_stats(
+    db: tauri::State<'_, Database>,
+    video_id: String,
+    options: Option<StatsOptions>,
+) -> Result<TranscriptStats, TranscriptError> {
+    insighttube_core::stats::transcript_stats(&db, video_id, options).await
+}
@@ -0,0 +1,10 @@
+//! The commands of [`insighttube_core::storage`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::storage::*;
+
+#[tauri::command]
+pub fn get_storage_usage(db: tauri::State<'_, Database>) -> Result<StorageUsage, TranscriptError> {
+    insighttube_core::storage::get_storage_usage(&db)
+}
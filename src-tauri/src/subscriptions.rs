@@ -0,0 +1,79 @@
+//! The commands of [`insighttube_core::subscriptions`], and the `subscription-uploads`
+//! event announcing new videos.
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::jobs::JobQueue;
+pub(crate) use insighttube_core::subscriptions::*;
+use tauri::{Emitter, Manager};
+
+const UPLOADS_EVENT: &str = "subscription-uploads";
+
+/// Checks every subscription for new videos and announces those found.
+pub(crate) async fn check_all(app: &tauri::AppHandle) -> Result<Vec<NewUploads>, TranscriptError> {
+    let found = insighttube_core::subscriptions::check_all(
+        &app.state::<Database>(),
+        &app.state::<JobQueue>(),
+    )
+    .await?;
+    for uploads in &found {
+        let _ = app.emit(UPLOADS_EVENT, uploads);
+    }
+    Ok(found)
+}
+
+#[tauri::command]
+pub async fn subscribe_channel(
+    db: tauri::State<'_, Database>,
+    channel: String,
+) -> Result<Subscription, TranscriptError> {
+    insighttube_core::subscriptions::subscribe_channel(&db, channel).await
+}
+
+#[tauri::command]
+pub async fn subscribe_playlist(
+    db: tauri::State<'_, Database>,
+    playlist: String,
+) -> Result<Subscription, TranscriptError> {
+    insighttube_core::subscriptions::subscribe_playlist(&db, playlist).await
+}
+
+#[tauri::command]
+pub fn unsubscribe(db: tauri::State<'_, Database>, id: String) -> Result<bool, TranscriptError> {
+    insighttube_core::subscriptions::unsubscribe(&db, id)
+}
+
+#[tauri::command]
+pub fn set_subscription_auto_ingest(
+    db: tauri::State<'_, Database>,
+    id: String,
+    enabled: bool,
+) -> Result<Subscription, TranscriptError> {
+    insighttube_core::subscriptions::set_subscription_auto_ingest(&db, id, enabled)
+}
+
+#[tauri::command]
+pub fn list_subscriptions(
+    db: tauri::State<'_, Database>,
+) -> Result<Vec<Subscription>, TranscriptError> {
+    insighttube_core::subscriptions::list_subscriptions(&db)
+}
+
+#[tauri::command]
+pub fn list_subscription_videos(
+    db: tauri::State<'_, Database>,
+    subscription_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SubscriptionVideo>, TranscriptError> {
+    insighttube_core::subscriptions::list_subscription_videos(&db, subscription_id, limit)
+}
+
+/// Checks every subscription for new videos now, instead of waiting for the next
+/// scheduled check. Returns the videos found, which are announced as events and
+/// auto-ingested as well.
+#[tauri::command]
+pub async fn check_subscriptions(
+    app: tauri::AppHandle,
+) -> Result<Vec<NewUploads>, TranscriptError> {
+    check_all(&app).await
+}
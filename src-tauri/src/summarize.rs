@@ -0,0 +1,50 @@
+//! The commands of [`insighttube_core::summarize`].
+
+use crate::activity::{self, ActivityKind};
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::llm::{self, ProviderKind};
+use crate::operations::Operations;
+pub(crate) use insighttube_core::summarize::*;
+use serde_json::json;
+
+/// Summarizes the transcript of `video_id` with `provider`. With `request_id` the
+/// summary is streamed as `llm-chunk` events and can be cancelled.
+#[tauri::command]
+pub async fn summarize_transcript(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    provider: ProviderKind,
+    options: Option<SummarizeOptions>,
+    request_id: Option<String>,
+) -> Result<Summary, TranscriptError> {
+    let video_id = crate::video_id::parse(&video_id)?;
+    let on_chunk = request_id
+        .as_deref()
+        .map(|id| llm::stream::emitter(&app, id));
+    let summary = insighttube_core::summarize::summarize_transcript(
+        &db,
+        &video_id,
+        provider,
+        options,
+        on_chunk.as_deref(),
+    );
+    let result = llm::stream::cancellable(&app, &operations, request_id.as_deref(), summary).await;
+    crate::notifications::finished(&app, "Summary", &result, |summary| summary.tldr.clone());
+    activity::finished(
+        &db,
+        ActivityKind::SummaryGenerated,
+        Some(&video_id),
+        "Summarizing a video",
+        &result,
+        |summary| {
+            (
+                format!("Summarized with {}", summary.model),
+                json!({ "provider": summary.provider, "model": summary.model }),
+            )
+        },
+    );
+    result
+}
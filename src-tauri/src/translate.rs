@@ -0,0 +1,27 @@
+//! The commands of [`insighttube_core::translate`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::operations::Operations;
+use crate::transcript::TranscriptSegment;
+pub(crate) use insighttube_core::translate::*;
+
+#[tauri::command]
+pub async fn translate_transcript(
+    db: tauri::State<'_, Database>,
+    operations: tauri::State<'_, Operations>,
+    video_id: String,
+    target_lang: String,
+    engine: Option<TranslationEngine>,
+    operation_id: Option<String>,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    insighttube_core::translate::translate_transcript(
+        &db,
+        &operations,
+        video_id,
+        target_lang,
+        engine,
+        operation_id,
+    )
+    .await
+}
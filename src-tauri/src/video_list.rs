@@ -0,0 +1,16 @@
+//! The commands of [`insighttube_core::video_list`].
+
+use crate::db::Database;
+use crate::error::TranscriptError;
+use crate::jobs::{JobKind, JobQueue};
+pub(crate) use insighttube_core::video_list::*;
+
+#[tauri::command]
+pub fn import_video_list(
+    db: tauri::State<'_, Database>,
+    queue: tauri::State<'_, JobQueue>,
+    path: String,
+    kind: Option<JobKind>,
+) -> Result<VideoListImport, TranscriptError> {
+    insighttube_core::video_list::import_video_list(&db, &queue, path, kind)
+}
@@ -0,0 +1,8 @@
+//! The commands of [`insighttube_core::webhook`].
+
+use crate::error::TranscriptError;
+
+#[tauri::command]
+pub async fn test_webhook() -> Result<u16, TranscriptError> {
+    insighttube_core::webhook::test_webhook().await
+}
@@ -0,0 +1,13 @@
+//! The commands of [`insighttube_core::youtube_search`].
+
+use crate::error::TranscriptError;
+pub(crate) use insighttube_core::youtube_search::*;
+
+#[tauri::command]
+pub async fn search_youtube(
+    query: String,
+    filters: Option<SearchFilters>,
+    continuation: Option<String>,
+) -> Result<SearchPage, TranscriptError> {
+    insighttube_core::youtube_search::search_youtube(query, filters, continuation).await
+}
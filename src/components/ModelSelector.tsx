@@ -5,7 +5,8 @@ import { listModels, getDefaultModels } from "../services/ai";
 
 interface ModelSelectorProps {
   provider: AIProvider;
-  apiKey: string;
+  /** Whether an API key for `provider` is stored, so its models can be listed. */
+  hasApiKey: boolean;
   selectedModel: string;
   onModelChange: (modelId: string) => void;
   disabled?: boolean;
@@ -13,7 +14,7 @@ interface ModelSelectorProps {
 
 export default function ModelSelector({
   provider,
-  apiKey,
+  hasApiKey,
   selectedModel,
   onModelChange,
   disabled = false,
@@ -24,14 +25,14 @@ export default function ModelSelector({
   const [fetched, setFetched] = useState(false);
 
   const fetchModels = useCallback(async () => {
-    if (!apiKey) {
+    if (!hasApiKey) {
       setModels(getDefaultModels(provider));
       setFetched(false);
       return;
     }
     setLoading(true);
     try {
-      const result = await listModels(provider, apiKey);
+      const result = await listModels(provider);
       setModels(result.length > 0 ? result : getDefaultModels(provider));
       setFetched(result.length > 0);
     } catch {
@@ -40,7 +41,7 @@ export default function ModelSelector({
     } finally {
       setLoading(false);
     }
-  }, [provider, apiKey]);
+  }, [provider, hasApiKey]);
 
   useEffect(() => {
     fetchModels();
@@ -61,11 +62,11 @@ export default function ModelSelector({
         <div className="model-selector-label">
           <Cpu size={14} />
           <span>Model</span>
-          {!fetched && apiKey && (
+          {!fetched && hasApiKey && (
             <span className="model-hint">(defaults)</span>
           )}
         </div>
-        {apiKey && (
+        {hasApiKey && (
           <button
             className="model-refresh-btn"
             onClick={(e) => {
@@ -5,8 +5,8 @@ import VideoInput from "../components/VideoInput";
 import ModelSelector from "../components/ModelSelector";
 import { getTranscript, transcriptToText, extractVideoId, getVideoThumbnail, getVideoInfo } from "../services/transcript";
 import { generateQuiz, getDefaultModelForProvider } from "../services/ai";
-import { getSettings, saveSettings, getVideoSessionByVideoId, saveVideoSession, getVideoSessions } from "../services/storage";
-import { Quiz, AppSettings, VideoSession } from "../types";
+import { getSettings, saveSettings, getVideoSessionByVideoId, saveVideoSession, getVideoSessions, getApiKeyStatus } from "../services/storage";
+import { Quiz, AppSettings, KeyedService, VideoSession } from "../types";
 
 type Stage = "idle" | "transcript" | "info" | "generating" | "done";
 
@@ -18,23 +18,15 @@ export default function HomePage() {
   const [stage, setStage] = useState<Stage>("idle");
   const [thumbnail, setThumbnail] = useState<string | null>(null);
   const [recentSessions, setRecentSessions] = useState<VideoSession[]>([]);
+  const [keyedServices, setKeyedServices] = useState<KeyedService[]>([]);
 
   useEffect(() => {
     getSettings().then(setSettings);
+    getApiKeyStatus().then(setKeyedServices);
     getVideoSessions().then((sessions) => setRecentSessions(sessions.slice(0, 6)));
   }, []);
 
-  const hasApiKey = settings
-    ? settings.selectedProvider === "openai"
-      ? !!settings.openaiApiKey
-      : !!settings.geminiApiKey
-    : false;
-
-  const currentApiKey = settings
-    ? settings.selectedProvider === "openai"
-      ? settings.openaiApiKey
-      : settings.geminiApiKey
-    : "";
+  const hasApiKey = !!settings && keyedServices.includes(settings.selectedProvider);
 
   const handleModelChange = (modelId: string) => {
     if (!settings) return;
@@ -80,16 +72,10 @@ export default function HomePage() {
 
       // Step 2: Generate quiz
       setStage("generating");
-      const apiKey =
-        settings.selectedProvider === "openai"
-          ? settings.openaiApiKey
-          : settings.geminiApiKey;
-
       const model = settings.selectedModel || getDefaultModelForProvider(settings.selectedProvider);
 
       const questions = await generateQuiz(
         settings.selectedProvider,
-        apiKey,
         transcriptText,
         settings.questionCount,
         model
@@ -178,7 +164,7 @@ export default function HomePage() {
         {settings && (
           <ModelSelector
             provider={settings.selectedProvider}
-            apiKey={currentApiKey}
+            hasApiKey={hasApiKey}
             selectedModel={settings.selectedModel}
             onModelChange={handleModelChange}
             disabled={isLoading}
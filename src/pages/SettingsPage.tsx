@@ -1,20 +1,35 @@
 import { useState, useEffect } from "react";
-import { Save, Eye, EyeOff, CheckCircle, Key, Hash } from "lucide-react";
-import { getSettings, saveSettings } from "../services/storage";
-import { AppSettings, DEFAULT_SETTINGS } from "../types";
+import { Save, Eye, EyeOff, CheckCircle, Key, Hash, Globe } from "lucide-react";
+import { deleteApiKey, getApiKeyStatus, getSettings, saveSettings, setApiKey } from "../services/storage";
+import {
+  clearCookies,
+  getCookieStatus,
+  importCookies,
+  testProxyConnection,
+} from "../services/transcript";
+import { AIProvider, AppSettings, CookieStatus, DEFAULT_SETTINGS, KeyedService, ProxySettings } from "../types";
 
 export default function SettingsPage() {
   const [settings, setSettings] = useState<AppSettings>(DEFAULT_SETTINGS);
   const [showOpenAI, setShowOpenAI] = useState(false);
   const [showGemini, setShowGemini] = useState(false);
+  const [keyedServices, setKeyedServices] = useState<KeyedService[]>([]);
+  const [newKeys, setNewKeys] = useState<Record<AIProvider, string>>({ openai: "", gemini: "" });
+  const [keyError, setKeyError] = useState<string | null>(null);
   const [saved, setSaved] = useState(false);
   const [loading, setLoading] = useState(true);
+  const [proxyStatus, setProxyStatus] = useState<string | null>(null);
+  const [cookiePath, setCookiePath] = useState("");
+  const [cookieStatus, setCookieStatus] = useState<CookieStatus | null>(null);
+  const [cookieError, setCookieError] = useState<string | null>(null);
 
   useEffect(() => {
     getSettings().then((s) => {
       setSettings(s);
       setLoading(false);
     });
+    getCookieStatus().then(setCookieStatus).catch(() => setCookieStatus(null));
+    getApiKeyStatus().then(setKeyedServices);
   }, []);
 
   const handleSave = async () => {
@@ -30,6 +45,68 @@ export default function SettingsPage() {
     setSettings((prev) => ({ ...prev, [key]: value }));
   };
 
+  const updateNewKey = (service: AIProvider, value: string) => {
+    setNewKeys((prev) => ({ ...prev, [service]: value }));
+    setKeyError(null);
+  };
+
+  // Keys go straight to the keychain; they are never part of the saved settings.
+  const handleSaveKey = async (service: AIProvider) => {
+    setKeyError(null);
+    try {
+      await setApiKey(service, newKeys[service]);
+      setNewKeys((prev) => ({ ...prev, [service]: "" }));
+      setKeyedServices(await getApiKeyStatus());
+    } catch (err) {
+      setKeyError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleRemoveKey = async (service: AIProvider) => {
+    setKeyError(null);
+    try {
+      await deleteApiKey(service);
+      setKeyedServices(await getApiKeyStatus());
+    } catch (err) {
+      setKeyError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const updateProxy = (key: keyof ProxySettings, value: string) => {
+    setSettings((prev) => ({ ...prev, proxy: { ...prev.proxy, [key]: value } }));
+    setProxyStatus(null);
+  };
+
+  const handleTestProxy = async () => {
+    setProxyStatus("Testing...");
+    try {
+      const result = await testProxyConnection(settings.proxy);
+      setProxyStatus(`Connected (HTTP ${result.status}, ${result.latencyMs} ms)`);
+    } catch (err) {
+      setProxyStatus(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleImportCookies = async () => {
+    setCookieError(null);
+    try {
+      setCookieStatus(await importCookies(cookiePath.trim()));
+      setCookiePath("");
+    } catch (err) {
+      setCookieError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleClearCookies = async () => {
+    setCookieError(null);
+    try {
+      await clearCookies();
+      setCookieStatus({ cookieCount: 0, signedIn: false });
+    } catch (err) {
+      setCookieError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
   if (loading) {
     return (
       <div className="page settings-page">
@@ -47,7 +124,7 @@ export default function SettingsPage() {
       <div className="page-header">
         <h1>Settings</h1>
         <p className="page-subtitle">
-          Configure your AI provider and API keys. Keys are stored locally on your device.
+          Configure your AI provider and API keys. Keys are kept in your system keychain.
         </p>
       </div>
 
@@ -65,9 +142,9 @@ export default function SettingsPage() {
               <input
                 type={showOpenAI ? "text" : "password"}
                 className="form-input"
-                value={settings.openaiApiKey}
-                onChange={(e) => updateSetting("openaiApiKey", e.target.value)}
-                placeholder="sk-..."
+                value={newKeys.openai}
+                onChange={(e) => updateNewKey("openai", e.target.value)}
+                placeholder={keyedServices.includes("openai") ? "Enter a new key to replace the saved one" : "sk-..."}
               />
               <button
                 className="toggle-visibility"
@@ -78,22 +155,38 @@ export default function SettingsPage() {
               </button>
             </div>
             <span className="form-hint">
-              Get your key from{" "}
+              {keyedServices.includes("openai") ? "A key is saved. " : ""}Get your key from{" "}
               <a href="https://platform.openai.com/api-keys" target="_blank" rel="noreferrer">
                 platform.openai.com
               </a>
             </span>
           </div>
 
+          <div className="form-group">
+            <button
+              className="btn btn-secondary"
+              onClick={() => handleSaveKey("openai")}
+              disabled={!newKeys.openai.trim()}
+              type="button"
+            >
+              Save OpenAI Key
+            </button>
+            {keyedServices.includes("openai") && (
+              <button className="btn btn-secondary" onClick={() => handleRemoveKey("openai")} type="button">
+                Remove OpenAI Key
+              </button>
+            )}
+          </div>
+
           <div className="form-group">
             <label className="form-label">Google Gemini API Key</label>
             <div className="input-with-toggle">
               <input
                 type={showGemini ? "text" : "password"}
                 className="form-input"
-                value={settings.geminiApiKey}
-                onChange={(e) => updateSetting("geminiApiKey", e.target.value)}
-                placeholder="AIza..."
+                value={newKeys.gemini}
+                onChange={(e) => updateNewKey("gemini", e.target.value)}
+                placeholder={keyedServices.includes("gemini") ? "Enter a new key to replace the saved one" : "AIza..."}
               />
               <button
                 className="toggle-visibility"
@@ -104,12 +197,29 @@ export default function SettingsPage() {
               </button>
             </div>
             <span className="form-hint">
-              Get your key from{" "}
+              {keyedServices.includes("gemini") ? "A key is saved. " : ""}Get your key from{" "}
               <a href="https://aistudio.google.com/apikey" target="_blank" rel="noreferrer">
                 aistudio.google.com
               </a>
             </span>
           </div>
+
+          <div className="form-group">
+            <button
+              className="btn btn-secondary"
+              onClick={() => handleSaveKey("gemini")}
+              disabled={!newKeys.gemini.trim()}
+              type="button"
+            >
+              Save Gemini Key
+            </button>
+            {keyedServices.includes("gemini") && (
+              <button className="btn btn-secondary" onClick={() => handleRemoveKey("gemini")} type="button">
+                Remove Gemini Key
+              </button>
+            )}
+            {keyError && <span className="form-hint">{keyError}</span>}
+          </div>
         </section>
 
         {/* Quiz Settings */}
@@ -140,6 +250,95 @@ export default function SettingsPage() {
             </div>
           </div>
         </section>
+
+        {/* Network */}
+        <section className="settings-section">
+          <div className="section-header">
+            <Globe size={20} />
+            <h2>Network</h2>
+          </div>
+
+          <div className="form-group">
+            <label className="form-label">Proxy URL</label>
+            <input
+              type="text"
+              className="form-input"
+              value={settings.proxy.url}
+              onChange={(e) => updateProxy("url", e.target.value)}
+              placeholder="socks5://127.0.0.1:1080"
+            />
+            <span className="form-hint">
+              HTTP or SOCKS5 proxy used for all YouTube requests. Leave empty to connect directly.
+            </span>
+          </div>
+
+          <div className="form-group">
+            <label className="form-label">Proxy Username</label>
+            <input
+              type="text"
+              className="form-input"
+              value={settings.proxy.username}
+              onChange={(e) => updateProxy("username", e.target.value)}
+              placeholder="Optional"
+            />
+          </div>
+
+          <div className="form-group">
+            <label className="form-label">Proxy Password</label>
+            <input
+              type="password"
+              className="form-input"
+              value={settings.proxy.password}
+              onChange={(e) => updateProxy("password", e.target.value)}
+              placeholder="Optional"
+            />
+          </div>
+
+          <div className="form-group">
+            <button
+              className="btn btn-secondary"
+              onClick={handleTestProxy}
+              disabled={!settings.proxy.url.trim()}
+              type="button"
+            >
+              Test Connection
+            </button>
+            {proxyStatus && <span className="form-hint">{proxyStatus}</span>}
+          </div>
+
+          <div className="form-group">
+            <label className="form-label">YouTube Cookies</label>
+            <input
+              type="text"
+              className="form-input"
+              value={cookiePath}
+              onChange={(e) => setCookiePath(e.target.value)}
+              placeholder="/path/to/cookies.txt"
+            />
+            <span className="form-hint">
+              {cookieStatus?.signedIn
+                ? `Signed in (${cookieStatus.cookieCount} cookies). Age-restricted and members-only videos are available.`
+                : "Import a cookies.txt or JSON export from youtube.com to fetch age-restricted and members-only videos."}
+            </span>
+            {cookieError && <span className="form-hint">{cookieError}</span>}
+          </div>
+
+          <div className="form-group">
+            <button
+              className="btn btn-secondary"
+              onClick={handleImportCookies}
+              disabled={!cookiePath.trim()}
+              type="button"
+            >
+              Import Cookies
+            </button>
+            {cookieStatus && cookieStatus.cookieCount > 0 && (
+              <button className="btn btn-secondary" onClick={handleClearCookies} type="button">
+                Remove Cookies
+              </button>
+            )}
+          </div>
+        </section>
       </div>
 
       <div className="settings-footer">
@@ -95,10 +95,6 @@ export default function StudyPage() {
       setGeneratingTabs((prev) => new Set(prev).add(type));
       setError(null);
       try {
-        const apiKey =
-          settings.selectedProvider === "openai"
-            ? settings.openaiApiKey
-            : settings.geminiApiKey;
         const model =
           settings.selectedModel ||
           getDefaultModelForProvider(settings.selectedProvider);
@@ -106,7 +102,6 @@ export default function StudyPage() {
         const result = await generateStudyMaterial(
           type,
           settings.selectedProvider,
-          apiKey,
           session.transcript,
           model
         );
@@ -140,10 +135,6 @@ export default function StudyPage() {
     setGeneratingTabs((prev) => new Set(prev).add("quizHistory"));
     setError(null);
     try {
-      const apiKey =
-        settings.selectedProvider === "openai"
-          ? settings.openaiApiKey
-          : settings.geminiApiKey;
       const model =
         settings.selectedModel ||
         getDefaultModelForProvider(settings.selectedProvider);
@@ -152,7 +143,6 @@ export default function StudyPage() {
 
       const questions = await generateQuiz(
         settings.selectedProvider,
-        apiKey,
         session.transcript,
         qCount,
         model
@@ -219,10 +209,6 @@ export default function StudyPage() {
     }
 
     try {
-      const apiKey =
-        settings.selectedProvider === "openai"
-          ? settings.openaiApiKey
-          : settings.geminiApiKey;
       const model =
         settings.selectedModel ||
         getDefaultModelForProvider(settings.selectedProvider);
@@ -240,7 +226,6 @@ export default function StudyPage() {
 
       const finalContent = await streamChatWithVideo(
         settings.selectedProvider,
-        apiKey,
         session.transcript,
         updatedMessages,
         model,
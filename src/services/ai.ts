@@ -1,6 +1,55 @@
-import OpenAI from "openai";
-import { GoogleGenAI } from "@google/genai";
-import { AIProvider, AIModel, QuizQuestion, StudyMaterials, Flashcard, MindMapNode, StudyMaterialType, ChatMessage } from "../types";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { AIProvider, AIModel, QuizQuestion, StudyMaterials, Flashcard, MindMapNode, StudyMaterialType, ChatMessage, LlmChunk } from "../types";
+import { toError } from "./transcript";
+
+/* ---- Completions ---- */
+
+interface PromptRequest {
+  /** The feature the prompt is for, which its usage is recorded against. */
+  feature: "quiz" | "studyMaterial" | "chat";
+  system: string;
+  prompt: string;
+  json: boolean;
+  model: string;
+}
+
+/**
+ * Completes a prompt in the backend, which calls the provider with the API key
+ * from the keychain. With `onChunk` the text is streamed, each call getting the
+ * text so far; aborting `signal` cancels the request and keeps that text.
+ */
+async function complete(
+  provider: AIProvider,
+  request: PromptRequest,
+  onChunk?: (accumulated: string) => void,
+  signal?: AbortSignal
+): Promise<string> {
+  if (!onChunk) {
+    return invoke<string>("complete_prompt", { provider, request });
+  }
+
+  const requestId = crypto.randomUUID();
+  let full = "";
+  const unlisten = await listen<LlmChunk>("llm-chunk", ({ payload }) => {
+    if (payload.requestId !== requestId || payload.done) return;
+    full += payload.delta;
+    onChunk(full);
+  });
+  const cancel = () => {
+    invoke("cancel_operation", { operationId: requestId }).catch(() => {});
+  };
+  signal?.addEventListener("abort", cancel);
+  try {
+    return await invoke<string>("complete_prompt", { provider, request, requestId });
+  } catch (err) {
+    if (signal?.aborted) return full;
+    throw err;
+  } finally {
+    signal?.removeEventListener("abort", cancel);
+    unlisten();
+  }
+}
 
 /* ---- Error Helpers ---- */
 
@@ -9,7 +58,7 @@ function parseAIError(err: unknown, provider: AIProvider): string {
   // Already a clean string
   if (typeof err === "string") return err;
 
-  const raw = err instanceof Error ? err.message : String(err);
+  const raw = toError(err).message;
 
   // Try to extract JSON error body (Gemini often returns raw JSON)
   try {
@@ -93,13 +142,12 @@ Return JSON in this exact format:
   ]
 }`;
 
-function buildPrompt(transcript: string, count: number): string {
-  const systemPrompt = QUIZ_SYSTEM_PROMPT.replace("{count}", String(count));
+function transcriptPrompt(transcript: string): string {
   const trimmed =
     transcript.length > 15000
       ? transcript.substring(0, 15000) + "... [transcript truncated]"
       : transcript;
-  return `${systemPrompt}\n\nTRANSCRIPT:\n${trimmed}`;
+  return `TRANSCRIPT:\n${trimmed}`;
 }
 
 /** Fisher-Yates shuffle options so the correct answer isn't always in the same slot */
@@ -142,71 +190,21 @@ function parseQuizResponse(text: string): QuizQuestion[] {
   return shuffleOptions(questions);
 }
 
-async function generateWithOpenAI(
-  apiKey: string,
-  transcript: string,
-  count: number,
-  model: string
-): Promise<QuizQuestion[]> {
-  const client = new OpenAI({
-    apiKey,
-    dangerouslyAllowBrowser: true,
-  });
-
-  const response = await client.chat.completions.create({
-    model,
-    messages: [
-      {
-        role: "user",
-        content: buildPrompt(transcript, count),
-      },
-    ],
-    temperature: 0.7,
-    response_format: { type: "json_object" },
-  });
-
-  const content = response.choices[0]?.message?.content;
-  if (!content) throw new Error("No response from OpenAI");
-
-  return parseQuizResponse(content);
-}
-
-async function generateWithGemini(
-  apiKey: string,
-  transcript: string,
-  count: number,
-  model: string
-): Promise<QuizQuestion[]> {
-  const ai = new GoogleGenAI({ apiKey });
-
-  const response = await ai.models.generateContent({
-    model,
-    contents: buildPrompt(transcript, count),
-    config: {
-      temperature: 0.7,
-      responseMimeType: "application/json",
-    },
-  });
-
-  const content = response.text;
-  if (!content) throw new Error("No response from Gemini");
-
-  return parseQuizResponse(content);
-}
-
 export async function generateQuiz(
   provider: AIProvider,
-  apiKey: string,
   transcript: string,
   questionCount: number,
   model: string
 ): Promise<QuizQuestion[]> {
   try {
-    if (provider === "openai") {
-      return await generateWithOpenAI(apiKey, transcript, questionCount, model);
-    } else {
-      return await generateWithGemini(apiKey, transcript, questionCount, model);
-    }
+    const content = await complete(provider, {
+      feature: "quiz",
+      system: QUIZ_SYSTEM_PROMPT.replace("{count}", String(questionCount)),
+      prompt: transcriptPrompt(transcript),
+      json: true,
+      model,
+    });
+    return parseQuizResponse(content);
   } catch (err) {
     throw new Error(parseAIError(err, provider));
   }
@@ -214,53 +212,11 @@ export async function generateQuiz(
 
 /* ---- Model Listing ---- */
 
-const OPENAI_CHAT_PREFIXES = ["gpt-4", "gpt-3.5", "o1", "o3", "o4", "chatgpt"];
-
-async function listOpenAIModels(apiKey: string): Promise<AIModel[]> {
-  const client = new OpenAI({ apiKey, dangerouslyAllowBrowser: true });
-  const page = await client.models.list();
-  const models: AIModel[] = [];
-  for await (const m of page) {
-    const id = m.id;
-    if (OPENAI_CHAT_PREFIXES.some((p) => id.startsWith(p))) {
-      models.push({ id, name: id, provider: "openai" });
-    }
-  }
-  models.sort((a, b) => a.id.localeCompare(b.id));
-  return models;
-}
-
-async function listGeminiModels(apiKey: string): Promise<AIModel[]> {
-  const ai = new GoogleGenAI({ apiKey });
-  const pager = await ai.models.list({ config: { pageSize: 100 } });
-  const models: AIModel[] = [];
-  for (const m of pager.page) {
-    const id = m.name ?? "";
-    const shortId = id.replace(/^models\//, "");
-    const displayName = m.displayName ?? shortId;
-    // Only include generative models (not embeddings, etc.)
-    const supported = m.supportedActions ?? [];
-    const canGenerate =
-      supported.includes("generateContent") ||
-      shortId.startsWith("gemini");
-    if (canGenerate) {
-      models.push({ id: shortId, name: displayName, provider: "gemini" });
-    }
-  }
-  models.sort((a, b) => a.name.localeCompare(b.name));
-  return models;
-}
-
-export async function listModels(
-  provider: AIProvider,
-  apiKey: string
-): Promise<AIModel[]> {
+/** Lists the models `provider` offers, asked in the backend with the stored API key. */
+export async function listModels(provider: AIProvider): Promise<AIModel[]> {
   try {
-    if (provider === "openai") {
-      return await listOpenAIModels(apiKey);
-    } else {
-      return await listGeminiModels(apiKey);
-    }
+    const models = await invoke<{ id: string; name: string }[]>("list_models", { provider });
+    return models.map((m) => ({ ...m, provider }));
   } catch (err) {
     console.error(`Failed to list ${provider} models:`, err);
     return getDefaultModels(provider);
@@ -362,65 +318,6 @@ RULES:
 - Return ONLY the markdown text, no JSON wrapping`,
 };
 
-function buildStudyPrompt(type: StudyMaterialType, transcript: string): string {
-  const trimmed =
-    transcript.length > 15000
-      ? transcript.substring(0, 15000) + "... [transcript truncated]"
-      : transcript;
-  return `${STUDY_PROMPTS[type]}\n\nTRANSCRIPT:\n${trimmed}`;
-}
-
-async function generateContentWithOpenAI(
-  apiKey: string,
-  prompt: string,
-  model: string,
-  jsonMode: boolean
-): Promise<string> {
-  const client = new OpenAI({ apiKey, dangerouslyAllowBrowser: true });
-  const response = await client.chat.completions.create({
-    model,
-    messages: [{ role: "user", content: prompt }],
-    temperature: 0.7,
-    ...(jsonMode ? { response_format: { type: "json_object" } } : {}),
-  });
-  const content = response.choices[0]?.message?.content;
-  if (!content) throw new Error("No response from OpenAI");
-  return content;
-}
-
-async function generateContentWithGemini(
-  apiKey: string,
-  prompt: string,
-  model: string,
-  jsonMode: boolean
-): Promise<string> {
-  const ai = new GoogleGenAI({ apiKey });
-  const response = await ai.models.generateContent({
-    model,
-    contents: prompt,
-    config: {
-      temperature: 0.7,
-      ...(jsonMode ? { responseMimeType: "application/json" } : {}),
-    },
-  });
-  const content = response.text;
-  if (!content) throw new Error("No response from Gemini");
-  return content;
-}
-
-async function generateContent(
-  provider: AIProvider,
-  apiKey: string,
-  prompt: string,
-  model: string,
-  jsonMode: boolean
-): Promise<string> {
-  if (provider === "openai") {
-    return generateContentWithOpenAI(apiKey, prompt, model, jsonMode);
-  }
-  return generateContentWithGemini(apiKey, prompt, model, jsonMode);
-}
-
 function cleanMarkdown(text: string): string {
   return text.replace(/^```(?:markdown|md)?\s*/m, "").replace(/```\s*$/m, "").trim();
 }
@@ -428,14 +325,17 @@ function cleanMarkdown(text: string): string {
 export async function generateStudyMaterial(
   type: StudyMaterialType,
   provider: AIProvider,
-  apiKey: string,
   transcript: string,
   model: string
 ): Promise<Partial<StudyMaterials>> {
   try {
-    const prompt = buildStudyPrompt(type, transcript);
-    const jsonMode = type === "mindMap" || type === "flashcards";
-    const raw = await generateContent(provider, apiKey, prompt, model, jsonMode);
+    const raw = await complete(provider, {
+      feature: "studyMaterial",
+      system: STUDY_PROMPTS[type],
+      prompt: transcriptPrompt(transcript),
+      json: type === "mindMap" || type === "flashcards",
+      model,
+    });
 
     switch (type) {
       case "summary":
@@ -476,115 +376,49 @@ Answer the student's questions clearly and accurately based on the video content
 VIDEO TRANSCRIPT:
 `;
 
+/** The conversation so far, as one prompt ending where the assistant replies. */
+function chatPrompt(messages: ChatMessage[]): string {
+  let prompt = "";
+  for (const m of messages) {
+    prompt += `${m.role === "user" ? "Student" : "Assistant"}: ${m.content}\n\n`;
+  }
+  return prompt + "Assistant:";
+}
+
 export async function chatWithVideo(
   provider: AIProvider,
-  apiKey: string,
   transcript: string,
   messages: ChatMessage[],
   model: string
 ): Promise<string> {
-  try {
-    const systemContent = CHAT_SYSTEM_PROMPT + transcript;
-
-    if (provider === "openai") {
-      const client = new OpenAI({ apiKey, dangerouslyAllowBrowser: true });
-      const response = await client.chat.completions.create({
-        model,
-        messages: [
-          { role: "system", content: systemContent },
-          ...messages.map((m) => ({
-            role: m.role as "user" | "assistant",
-            content: m.content,
-          })),
-        ],
-        temperature: 0.7,
-      });
-      const content = response.choices[0]?.message?.content;
-      if (!content) throw new Error("No response from OpenAI");
-      return content;
-    } else {
-      const ai = new GoogleGenAI({ apiKey });
-      // Build conversation as a single prompt for Gemini
-      let prompt = systemContent + "\n\n";
-      for (const m of messages) {
-        prompt += `${m.role === "user" ? "Student" : "Assistant"}: ${m.content}\n\n`;
-      }
-      prompt += "Assistant:";
-
-      const response = await ai.models.generateContent({
-        model,
-        contents: prompt,
-        config: { temperature: 0.7 },
-      });
-      const content = response.text;
-      if (!content) throw new Error("No response from Gemini");
-      return content;
-    }
-  } catch (err) {
-    throw new Error(parseAIError(err, provider));
-  }
+  return streamChatWithVideo(provider, transcript, messages, model);
 }
 
 /** Streaming version of chatWithVideo – calls onChunk with accumulated text */
 export async function streamChatWithVideo(
   provider: AIProvider,
-  apiKey: string,
   transcript: string,
   messages: ChatMessage[],
   model: string,
-  onChunk: (accumulated: string) => void,
+  onChunk?: (accumulated: string) => void,
   signal?: AbortSignal
 ): Promise<string> {
   try {
-    const systemContent = CHAT_SYSTEM_PROMPT + transcript;
-    let full = "";
-
-    if (provider === "openai") {
-      const client = new OpenAI({ apiKey, dangerouslyAllowBrowser: true });
-      const stream = await client.chat.completions.create({
-        model,
-        messages: [
-          { role: "system", content: systemContent },
-          ...messages.map((m) => ({
-            role: m.role as "user" | "assistant",
-            content: m.content,
-          })),
-        ],
-        temperature: 0.7,
-        stream: true,
-      });
-      for await (const chunk of stream) {
-        if (signal?.aborted) break;
-        const delta = chunk.choices[0]?.delta?.content;
-        if (delta) {
-          full += delta;
-          onChunk(full);
-        }
-      }
-    } else {
-      const ai = new GoogleGenAI({ apiKey });
-      let prompt = systemContent + "\n\n";
-      for (const m of messages) {
-        prompt += `${m.role === "user" ? "Student" : "Assistant"}: ${m.content}\n\n`;
-      }
-      prompt += "Assistant:";
-
-      const response = await ai.models.generateContentStream({
+    const full = await complete(
+      provider,
+      {
+        feature: "chat",
+        system: CHAT_SYSTEM_PROMPT + transcript,
+        prompt: chatPrompt(messages),
+        json: false,
         model,
-        contents: prompt,
-        config: { temperature: 0.7 },
-      });
-      for await (const chunk of response) {
-        if (signal?.aborted) break;
-        const delta = chunk.text;
-        if (delta) {
-          full += delta;
-          onChunk(full);
-        }
-      }
+      },
+      onChunk,
+      signal
+    );
+    if (!full && !signal?.aborted) {
+      throw new Error(`No response from ${provider === "openai" ? "OpenAI" : "Gemini"}`);
     }
-
-    if (!full) throw new Error(`No response from ${provider === "openai" ? "OpenAI" : "Gemini"}`);
     return full;
   } catch (err) {
     if (signal?.aborted) return "";
@@ -1,5 +1,7 @@
+import { invoke } from "@tauri-apps/api/core";
 import { load } from "@tauri-apps/plugin-store";
-import { AppSettings, DEFAULT_SETTINGS, VideoSession, QuizResult, TodoItem, Note, Reminder, ChatSession } from "../types";
+import { AppSettings, DEFAULT_SETTINGS, KeyedService, VideoSession, QuizResult, TodoItem, Note, Reminder, ChatSession } from "../types";
+import { toError } from "./transcript";
 
 const STORE_NAME = "settings.json";
 const DATA_STORE_NAME = "data.json";
@@ -41,15 +43,47 @@ export async function saveSettings(settings: AppSettings): Promise<void> {
   const store = await getStore();
   await store.set(SETTINGS_KEY, settings);
   await store.save();
+  // The backend reads the proxy at startup; push changes so they apply right away.
+  await invoke("set_proxy", {
+    config: settings.proxy.url.trim() ? settings.proxy : null,
+  });
+  await invoke("set_ollama_config", {
+    config: {
+      baseUrl: settings.ollama.baseUrl,
+      model: settings.ollama.model || null,
+      embeddingModel: settings.ollama.embeddingModel || null,
+    },
+  });
 }
 
-export async function getApiKey(
-  provider: "openai" | "gemini"
-): Promise<string> {
-  const settings = await getSettings();
-  return provider === "openai"
-    ? settings.openaiApiKey
-    : settings.geminiApiKey;
+/* ---- API Keys ---- */
+
+/**
+ * Services with an API key stored. The keys live in the OS keychain, and the
+ * backend never hands them back to the webview.
+ */
+export async function getApiKeyStatus(): Promise<KeyedService[]> {
+  try {
+    return await invoke<KeyedService[]>("get_api_key_status");
+  } catch {
+    return [];
+  }
+}
+
+export async function setApiKey(service: KeyedService, value: string): Promise<void> {
+  try {
+    await invoke("set_secret", { service, value });
+  } catch (err) {
+    throw toError(err);
+  }
+}
+
+export async function deleteApiKey(service: KeyedService): Promise<void> {
+  try {
+    await invoke("delete_secret", { service });
+  } catch (err) {
+    throw toError(err);
+  }
 }
 
 /* ---- Video Sessions ---- */
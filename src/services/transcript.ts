@@ -1,5 +1,17 @@
 import { invoke } from "@tauri-apps/api/core";
-import { TranscriptSegment, VideoInfo } from "../types";
+import { BackendError, CookieStatus, LocalModel, ProxySettings, ProxyTestResult, TranscriptSegment, VideoInfo } from "../types";
+
+/**
+ * Converts a rejected `invoke` value into an `Error`, keeping the backend's
+ * error kind available as `kind`.
+ */
+export function toError(err: unknown): Error & Partial<Pick<BackendError, "kind">> {
+  if (err && typeof err === "object" && "message" in err) {
+    const backend = err as BackendError;
+    return Object.assign(new Error(backend.message), { kind: backend.kind });
+  }
+  return new Error(typeof err === "string" ? err : "An unexpected error occurred");
+}
 
 /**
  * Fetches the transcript for a YouTube video by calling the Rust backend.
@@ -14,9 +26,14 @@ export async function getTranscript(
     throw new Error("Invalid YouTube URL or video ID.");
   }
 
-  const segments = await invoke<TranscriptSegment[]>("fetch_transcript", {
-    videoId,
-  });
+  let segments: TranscriptSegment[];
+  try {
+    segments = await invoke<TranscriptSegment[]>("fetch_transcript", {
+      videoId,
+    });
+  } catch (err) {
+    throw toError(err);
+  }
 
   if (!segments || segments.length === 0) {
     throw new Error(
@@ -27,6 +44,51 @@ export async function getTranscript(
   return segments;
 }
 
+/** Sends a test request to YouTube through `proxy` without saving it. */
+export async function testProxyConnection(
+  proxy: ProxySettings
+): Promise<ProxyTestResult> {
+  try {
+    return await invoke<ProxyTestResult>("test_proxy_connection", {
+      config: proxy,
+    });
+  } catch (err) {
+    throw toError(err);
+  }
+}
+
+/** Lists the models pulled into the Ollama server at `baseUrl` (the configured one by default). */
+export async function listLocalModels(baseUrl?: string): Promise<LocalModel[]> {
+  try {
+    return await invoke<LocalModel[]>("list_local_models", {
+      baseUrl: baseUrl?.trim() || null,
+    });
+  } catch (err) {
+    throw toError(err);
+  }
+}
+
+/** Imports a cookies.txt or JSON cookie export so requests are made signed in. */
+export async function importCookies(path: string): Promise<CookieStatus> {
+  try {
+    return await invoke<CookieStatus>("import_cookies", { path });
+  } catch (err) {
+    throw toError(err);
+  }
+}
+
+export async function clearCookies(): Promise<void> {
+  try {
+    await invoke("clear_cookies");
+  } catch (err) {
+    throw toError(err);
+  }
+}
+
+export async function getCookieStatus(): Promise<CookieStatus> {
+  return invoke<CookieStatus>("get_cookie_status");
+}
+
 export function transcriptToText(segments: TranscriptSegment[]): string {
   return segments.map((s) => s.text).join(" ");
 }
@@ -3,6 +3,29 @@ export interface TranscriptSegment {
   duration: number;
   offset: number;
   lang: string;
+  /** Probable speaker, from 0; only set when `detectSpeakers` is on. */
+  speaker?: number;
+  /** Filtered words the segment contains, when the content filter flags them. */
+  flagged?: string[];
+}
+
+export type CaptionKind = "manual" | "auto";
+
+/** Clean-up stages `fetch_transcript` can run over the text, in the order given. */
+export type NormalizeStage = "stripFillers" | "fixCapitalization" | "expandNumerals" | "collapseRepeats";
+
+/** `fetch_transcript_v2`: the segments with the details of their caption track. */
+export interface Transcript {
+  videoId: string;
+  languageCode: string;
+  languageName: string;
+  kind: CaptionKind;
+  isAutoGenerated: boolean;
+  /** Machine-translated by YouTube from a track in another language. */
+  isTranslated: boolean;
+  /** Watch page of the video. */
+  sourceUrl: string;
+  segments: TranscriptSegment[];
 }
 
 export interface QuizQuestion {
@@ -74,6 +97,38 @@ export interface VideoSession {
   latestQuiz?: Quiz;
 }
 
+/** Error shape returned by failing Rust commands. */
+export interface BackendError {
+  kind:
+    | "invalidVideoId"
+    | "invalidInput"
+    | "videoUnavailable"
+    | "captchaRequired"
+    | "transcriptsDisabled"
+    | "noTranscript"
+    | "emptyTranscript"
+    | "translationUnavailable"
+    | "rateLimited"
+    | "regionBlocked"
+    | "privateVideo"
+    | "ageRestricted"
+    | "membersOnly"
+    | "loginRequired"
+    | "liveNow"
+    | "upcoming"
+    | "liveCaptionsPending"
+    | "commentsDisabled"
+    | "networkError"
+    | "parseError"
+    | "databaseError"
+    | "fileError"
+    | "transcriptionError"
+    | "llmError"
+    | "budgetExceeded"
+    | "cancelled";
+  message: string;
+}
+
 export interface VideoInfo {
   title: string;
   author: string;
@@ -81,24 +136,611 @@ export interface VideoInfo {
 
 /* ---- Settings ---- */
 
+export interface ProxySettings {
+  /** http://, https://, socks5:// or socks5h:// URL; empty disables the proxy. */
+  url: string;
+  username: string;
+  password: string;
+}
+
+export interface ProxyTestResult {
+  status: number;
+  latencyMs: number;
+}
+
+export interface CookieStatus {
+  cookieCount: number;
+  signedIn: boolean;
+}
+
+export interface OllamaSettings {
+  /** Empty means http://localhost:11434. */
+  baseUrl: string;
+  model: string;
+  /** Used for semantic search and questions; empty means nomic-embed-text. */
+  embeddingModel: string;
+}
+
+export interface LocalModel {
+  name: string;
+  size: number;
+  modifiedAt: string;
+}
+
+/** Payload of `llm-chunk` events emitted while a streamed completion runs. */
+export interface LlmChunk {
+  requestId: string;
+  delta: string;
+  /** Set on the last event of a request, which carries no text. */
+  done: boolean;
+}
+
+/** Payload of `progress` events emitted by long-running commands. */
+export interface ProgressEvent {
+  operationId: string;
+  stage:
+    | "listing"
+    | "fetching"
+    | "downloading"
+    | "decoding"
+    | "transcribing"
+    | "summarizing"
+    | "analyzing";
+  /** 0–100 within the stage; null while the total is unknown. */
+  percent: number | null;
+  message: string | null;
+}
+
+export type JobKind = "transcript" | "playlist" | "summary";
+export type JobStatus = "pending" | "running" | "done" | "failed" | "cancelled";
+
+/** A background ingestion job; `job-updated` events carry one as it changes. */
+export interface Job {
+  id: number;
+  kind: JobKind;
+  input: string;
+  status: JobStatus;
+  attempts: number;
+  error: string | null;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export type ListLineStatus = "queued" | "alreadyQueued" | "duplicate" | "invalid";
+
+/** A line of a list read by `import_video_list`, and what became of it. */
+export interface ListLine {
+  line: number;
+  input: string;
+  status: ListLineStatus;
+  /** The video (or playlist) the line names. */
+  id: string | null;
+  jobId: number | null;
+  error: string | null;
+}
+
+export interface VideoListImport {
+  /** Jobs queued by the import. */
+  queued: number;
+  lines: ListLine[];
+}
+
+/** A caption track of a video, as `list_available_transcripts` lists them. */
+export interface CaptionTrack {
+  languageCode: string;
+  languageName: string;
+  kind: CaptionKind;
+  isAutoGenerated: boolean;
+  isTranslatable: boolean;
+}
+
+/** What `check_transcript_availability` found for one input, without fetching captions. */
+export interface TranscriptAvailability {
+  input: string;
+  videoId: string | null;
+  available: boolean;
+  /** Caption tracks in every language offered. */
+  tracks: CaptionTrack[];
+  durationSeconds: number | null;
+  /** Why the transcript cannot be fetched. */
+  error: BackendError | null;
+}
+
+/** Payload of `clipboard-video` events: a YouTube link just copied, to offer ingesting. */
+export interface ClipboardVideoEvent {
+  videoId: string;
+  url: string;
+}
+
+/** Payload of `videos-dropped` events: the videos of a `.txt` list dropped on the window. */
+export interface VideosDroppedEvent {
+  path: string;
+  videoIds: string[];
+  /** Lines that were neither blank nor a video. */
+  invalidLines: number;
+}
+
+/** Payload of `deep-link` events, sent for each `insighttube://analyze` link opened. */
+export interface DeepLinkEvent {
+  videoId: string;
+  /** Null when the same job was already queued. */
+  job: Job | null;
+}
+
+export type ActivityKind = "videoIngested" | "summaryGenerated" | "exportCompleted" | "error";
+
+/** An entry of the activity feed (`get_activity`). */
+export interface Activity {
+  id: number;
+  kind: ActivityKind;
+  videoId: string | null;
+  message: string;
+  /** Facts particular to the kind, such as the path of an export or the kind of an error. */
+  details: Record<string, unknown>;
+  createdAt: number;
+}
+
+/** Which activity `get_activity` lists; everything when empty. */
+export interface ActivityFilter {
+  kinds?: ActivityKind[];
+  videoId?: string | null;
+  /** Seconds since the epoch. */
+  since?: number | null;
+  until?: number | null;
+}
+
+export interface ActivityPage {
+  /** Newest first, 50 at most. */
+  entries: Activity[];
+  page: number;
+  hasMore: boolean;
+}
+
+export type InnertubeClient = "android" | "ios" | "web" | "mweb" | "webEmbedded" | "tvEmbedded";
+
+/** How one Innertube client has fared since the app started (`get_metrics`). */
+export interface ClientMetrics {
+  client: InnertubeClient;
+  attempts: number;
+  successes: number;
+  /** CAPTCHAs and rate limiting included. */
+  failures: number;
+  captchas: number;
+  rateLimited: number;
+  /** From 0 to 1. */
+  successRate: number;
+  averageLatencyMs: number;
+}
+
+/** Backend preferences (`get_settings`); `settings-changed` events carry them after every change. */
+export interface BackendSettings {
+  preferredLanguages: string[];
+  /** Which captions win when a language has both. */
+  preferredCaptionKind: CaptionKind;
+  /** Label segments with the probable speaker, guessed from `>>` markers and pauses. */
+  detectSpeakers: boolean;
+  /** Masks or flags the bundled profanity list (`profanity`) and `words`, matched whole. */
+  contentFilter: { mode: "off" | "mask" | "flag"; profanity: boolean; words: string[] };
+  /** Innertube clients tried in order until one lists captions for a video. */
+  innertubeClients: InnertubeClient[];
+  /** Try the clients that have listed captions most reliably first. */
+  adaptiveClientOrder: boolean;
+  /** User-Agents rotated through, one per video fetched; bundled browsers when empty. */
+  userAgents: string[];
+  /** A PO token to send as is, or the base URL of a server that mints them. */
+  poToken: { token: string | null; providerUrl: string | null };
+  proxy: ProxySettings | null;
+  rateLimit: { requestsPerMinute: number; burst: number };
+  /** Seconds; the watch page and caption fetches have their own totals. */
+  timeouts: {
+    connectSecs: number;
+    readSecs: number;
+    totalSecs: number;
+    watchPageSecs: number;
+    captionsSecs: number;
+  };
+  cacheTtlHours: number;
+  /** Largest watch page or caption file read, decompressed, in megabytes. */
+  maxResponseMb: number;
+  /** Least recently used transcripts are dropped past these limits; null for none. */
+  storage: { maxCacheMb: number | null; maxTranscripts: number | null };
+  llm: { provider: "openai" | "anthropic" | "gemini" | "ollama" | null; model: string | null };
+  ollama: OllamaSettings;
+  /** Budgets are US dollars per calendar month; null or absent for no limit. */
+  llmUsage: {
+    monthlyBudget: number | null;
+    providerBudgets: Partial<Record<"openai" | "anthropic" | "gemini" | "ollama", number>>;
+    /** Prices in US dollars per million tokens, for models missing from the built-in list. */
+    prices: { model: string; input: number; output: number }[];
+  };
+  export: {
+    format: "srt" | "vtt" | "markdown" | "json" | "ndjson" | "csv";
+    clipboardStyle: "plain" | "timestamped" | "markdown";
+  };
+  /** Where `export_to_obsidian` writes notes; `folder` is inside the vault. */
+  obsidian: { vaultPath: string | null; folder: string; tags: string[] };
+  /** Link to, or ID of, the page `export_to_notion` creates pages under. */
+  notion: { parentPage: string | null };
+  jobParallelism: number;
+  /** How often subscriptions are checked for new videos; at least 5. */
+  subscriptionPollMinutes: number;
+  /** Transcripts, and summaries unless turned off, of new subscription videos. */
+  /**
+   * Stages of the pipeline background jobs ingest videos with, after fetching and
+   * parsing. Indexing embeds the transcript, and needs `store` on.
+   */
+  ingest: {
+    normalize: boolean;
+    normalizeStages: NormalizeStage[];
+    store: boolean;
+    index: boolean;
+    /** Summary jobs summarize either way. */
+    summarize: boolean;
+  };
+  autoIngest: {
+    enabled: boolean;
+    summarize: boolean;
+    /** The preselected provider and model when null. */
+    provider: "openai" | "anthropic" | "gemini" | "ollama" | null;
+    model: string | null;
+  };
+  /**
+   * Posted to with the results when a background job ingests a video; with a secret,
+   * requests are signed in `X-InsightTube-Signature` (`test_webhook` sends a ping).
+   */
+  webhook: { url: string | null; secret: string | null };
+  /** HTTP API on 127.0.0.1 for scripts; requests carry `token` (`regenerate_api_token`) as a bearer token. */
+  apiServer: { enabled: boolean; port: number; token: string | null };
+  /** Desktop notifications when batches, transcriptions, summaries and jobs end. */
+  notifications: boolean;
+  /** Announce YouTube links copied to the clipboard with `clipboard-video` events. */
+  watchClipboard: boolean;
+  /** How detailed the diagnostic logs are (`set_log_level`, `get_recent_logs`). */
+  logLevel: "error" | "warn" | "info" | "debug" | "trace";
+}
+
+/** The local database (`get_db_info`). */
+export interface DbInfo {
+  path: string;
+  /** Migrations applied; below `latestVersion` only while the app upgrades it. */
+  schemaVersion: number;
+  latestVersion: number;
+  sizeBytes: number;
+  sqliteVersion: string;
+  /** Whether this build can encrypt the database (`set_database_encryption`). */
+  encryptionAvailable: boolean;
+  encrypted: boolean;
+  tables: { name: string; rows: number }[];
+}
+
+/** Disk space the app takes (`get_storage_usage`), in bytes unless noted. */
+export interface StorageUsage {
+  /** Videos with a cached transcript. */
+  transcripts: number;
+  transcriptBytes: number;
+  searchIndexBytes: number;
+  embeddingBytes: number;
+  otherBytes: number;
+  databaseBytes: number;
+  logBytes: number;
+}
+
+/** A fetched video in the "recently analyzed" view (`list_history`, `search_history`). */
+export interface HistoryEntry {
+  videoId: string;
+  title: string;
+  channelName: string;
+  channelId: string;
+  languages: string[];
+  pinned: boolean;
+  fetchCount: number;
+  firstFetchedAt: number;
+  lastFetchedAt: number;
+}
+
+/** A user-defined group of saved videos. */
+export interface Collection {
+  id: number;
+  name: string;
+  description: string;
+  videoCount: number;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface CollectionVideo {
+  videoId: string;
+  /** From the history; null for videos not in it. */
+  title: string | null;
+  channelName: string | null;
+  addedAt: number;
+}
+
+/** A note anchored to a moment of a video (`list_notes`). */
+export interface TranscriptNote {
+  id: number;
+  videoId: string;
+  /** Seconds into the video. */
+  offset: number;
+  text: string;
+  createdAt: number;
+  updatedAt: number;
+}
+
+/** A highlighted transcript passage, with its text as it read when marked. */
+export interface Highlight {
+  id: number;
+  videoId: string;
+  start: number;
+  end: number;
+  text: string;
+  createdAt: number;
+}
+
+/** Outcome of `sync_readwise`; highlights already in Readwise are not sent again. */
+export interface ReadwiseSync {
+  synced: number;
+  alreadySynced: number;
+}
+
+export type CommentSort = "top" | "newest";
+
+/** A comment thread from `fetch_comments`, or one of its replies. */
+export interface VideoComment {
+  commentId: string;
+  author: string;
+  authorChannelId: string | null;
+  text: string;
+  likeCount: number;
+  /** As YouTube shows it, such as "2 days ago". */
+  publishedTime: string;
+  isPinned: boolean;
+  isCreator: boolean;
+  replyCount: number;
+  replies: VideoComment[];
+}
+
+/** Sentiment and recurring themes of a video's comments (`analyze_comments`). */
+export interface CommentAnalysis {
+  videoId: string;
+  commentCount: number;
+  sentiment: { positive: number; neutral: number; negative: number };
+  themes: { theme: string; description: string; commentCount: number }[];
+  provider: "openai" | "anthropic" | "gemini" | "ollama";
+  model: string;
+  analyzedAt: number;
+}
+
+/** Filters of `search_youtube`; omitted ones are not applied. */
+export interface YouTubeSearchFilters {
+  type?: "video" | "channel" | "playlist";
+  duration?: "short" | "medium" | "long";
+  uploadDate?: "lastHour" | "today" | "thisWeek" | "thisMonth" | "thisYear";
+}
+
+/** A video found by `search_youtube`, or suggested by `fetch_related_videos`. */
+export interface YouTubeVideo {
+  videoId: string;
+  title: string;
+  channelName: string | null;
+  channelId: string | null;
+  durationSeconds: number | null;
+  viewCount: number | null;
+  publishedTime: string | null;
+  thumbnailUrl: string | null;
+}
+
+export type YouTubeSearchResult =
+  | ({ type: "video" } & YouTubeVideo)
+  | {
+      type: "channel";
+      channelId: string;
+      title: string;
+      subscribers: string | null;
+      thumbnailUrl: string | null;
+    }
+  | {
+      type: "playlist";
+      playlistId: string;
+      title: string;
+      channelName: string | null;
+      videoCount: number | null;
+      thumbnailUrl: string | null;
+    };
+
+export interface YouTubeSearchPage {
+  query: string;
+  results: YouTubeSearchResult[];
+  continuation: string | null;
+}
+
+export interface Subscription {
+  /** The channel ID or playlist ID. */
+  id: string;
+  kind: "channel" | "playlist";
+  title: string;
+  /** Whether new videos are auto-ingested, when auto-ingestion is on. */
+  autoIngest: boolean;
+  addedAt: number;
+  lastCheckedAt: number | null;
+}
+
+export interface SubscriptionVideo {
+  videoId: string;
+  subscriptionId: string;
+  title: string;
+  /** RFC 3339 upload time from the subscription feed. */
+  publishedAt: string;
+  discoveredAt: number;
+}
+
+/** Payload of `subscription-uploads` events: new videos of one subscription. */
+export interface NewUploads {
+  subscriptionId: string;
+  title: string;
+  videos: SubscriptionVideo[];
+}
+
+/** A summary made by auto-ingestion (`get_ingested_summary`, `list_ingested_summaries`). */
+export interface IngestedSummary {
+  videoId: string;
+  summary: {
+    tldr: string;
+    /** `timestamp` is in seconds. */
+    keyPoints: { text: string; timestamp: number }[];
+    provider: "openai" | "anthropic" | "gemini" | "ollama";
+    model: string;
+  };
+  summarizedAt: number;
+}
+
+/** One video's side of a diff block; empty where the other side's text would go. */
+export interface DiffSide {
+  text: string;
+  /** Seconds from the start of the video. */
+  start: number;
+  end: number;
+}
+
+export interface DiffBlock {
+  kind: "added" | "removed" | "changed";
+  a: DiffSide;
+  b: DiffSide;
+}
+
+/** Sentence-level comparison of two videos (`diff_transcripts`). */
+export interface TranscriptDiff {
+  videoIdA: string;
+  videoIdB: string;
+  /** From 0 to 1. */
+  similarity: number;
+  unchangedSentences: number;
+  blocks: DiffBlock[];
+}
+
+/** Reading-time and speech-rate statistics of a transcript (`transcript_stats`). Times are in seconds. */
+export interface TranscriptStats {
+  wordCount: number;
+  uniqueWords: number;
+  duration: number;
+  wordsPerMinute: number;
+  speechRate: { start: number; end: number; words: number; wordsPerMinute: number }[];
+  /** Longest first. */
+  longestGaps: { start: number; end: number; duration: number }[];
+  readingMinutes: number;
+}
+
+/** Result of `summarize_by_chapter`: one summary per chapter, in order. */
+export interface ChapterOutline {
+  videoId: string;
+  chapters: {
+    title: string;
+    /** Seconds from the start of the video. */
+    startTime: number;
+    endTime: number;
+    /** Empty for a chapter without speech. */
+    tldr: string;
+    keyPoints: { text: string; timestamp: number }[];
+  }[];
+  provider: "openai" | "anthropic" | "gemini" | "ollama";
+  model: string;
+  summarizedAt: number;
+}
+
+/** A question and answer pair from `generate_flashcards`, tied to a moment of the video. */
+export interface TimedFlashcard {
+  question: string;
+  answer: string;
+  /** Seconds from the start of the video, where the answer is given. */
+  timestamp: number;
+}
+
+/** One entry of `extract_action_items`; `export_action_items` takes them back as edited. */
+export interface ActionItem {
+  kind: "action" | "takeaway";
+  text: string;
+  /** Seconds from the start of the video. */
+  timestamp: number;
+  done: boolean;
+}
+
+/** A point of `compare_videos`, with what each cited video says on it. */
+export interface ComparedPoint {
+  point: string;
+  /** `timestamp` is in seconds. */
+  citations: { videoId: string; timestamp: number; claim: string }[];
+}
+
+/** Result of `compare_videos`. */
+export interface Comparison {
+  question: string;
+  overview: string;
+  agreements: ComparedPoint[];
+  disagreements: ComparedPoint[];
+  /** The summary each video was compared by; `timestamp` is in seconds. */
+  videos: { videoId: string; tldr: string; keyPoints: { text: string; timestamp: number }[] }[];
+  provider: "openai" | "anthropic" | "gemini" | "ollama";
+  model: string;
+}
+
+export type UsagePeriod = "today" | "week" | "month" | "all";
+
+export interface UsageTotals {
+  calls: number;
+  inputTokens: number;
+  outputTokens: number;
+  /** Estimated US dollars. */
+  cost: number;
+  /** Calls to models of unknown price, which `cost` leaves out. */
+  unpricedCalls: number;
+}
+
+export interface UsageBreakdown extends UsageTotals {
+  provider: "openai" | "anthropic" | "gemini" | "ollama";
+  model: string;
+  /** The feature that made the calls, such as "summary" or "question". */
+  operation: string;
+}
+
+export interface LlmBudget {
+  /** null for the budget covering all hosted providers. */
+  provider: "openai" | "anthropic" | "gemini" | "ollama" | null;
+  limit: number;
+  /** Spent this calendar month. */
+  spent: number;
+}
+
+/** Language model usage over a period (`get_llm_usage`). */
+export interface LlmUsage {
+  period: UsagePeriod;
+  since: number;
+  total: UsageTotals;
+  /** By provider, model and operation, most expensive first. */
+  breakdown: UsageBreakdown[];
+  budgets: LlmBudget[];
+}
+
+/** A service whose API key the backend keeps in the keychain (`set_secret`). */
+export type KeyedService = "openai" | "anthropic" | "gemini" | "deepl" | "notion" | "readwise";
+
 export interface AppSettings {
-  openaiApiKey: string;
-  geminiApiKey: string;
   selectedProvider: AIProvider;
   selectedModel: string;
   openaiModel: string;
   geminiModel: string;
   questionCount: number;
+  proxy: ProxySettings;
+  ollama: OllamaSettings;
 }
 
 export const DEFAULT_SETTINGS: AppSettings = {
-  openaiApiKey: "",
-  geminiApiKey: "",
   selectedProvider: "gemini",
   selectedModel: "gemini-2.5-flash",
   openaiModel: "gpt-4.1-nano",
   geminiModel: "gemini-2.5-flash",
   questionCount: 10,
+  proxy: { url: "", username: "", password: "" },
+  ollama: { baseUrl: "", model: "", embeddingModel: "" },
 };
 
 /* ---- Todo ---- */